@@ -12,6 +12,7 @@ use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId};
 //Bring into scope the functions and data as necessary for Bourg
 use fdm_library::bourg::common::vector::Vector;
 use fdm_library::bourg::common::quaternion::Quaternion;
+use fdm_library::bourg::common::real::Float;
 use fdm_library::bourg::fdm::structures::DataFDM;
 use fdm_library::bourg::fdm::structures::PointMass;
 use fdm_library::bourg::fdm::calc_loads::calc_airplane_loads;
@@ -19,12 +20,14 @@ use fdm_library::bourg::fdm::mass_properties::calc_airplane_mass_properties;
 use fdm_library::bourg::fdm::keypresses::*;
 
 //Bring into scope the functions and structures as necessary for Palmer
-use fdm_library::palmer::fdm::plane_right_hand_side::plane_rhs;
+use fdm_library::palmer::fdm::plane_right_hand_side::plane_derivatives;
+use fdm_library::palmer::fdm::equations_of_motion::VELOCITY_STATES;
+use fdm_library::integrator::{Integrator, IntegrationMethod};
 use fdm_library::palmer::fdm::structures::DataFDM as OtherDataFDM;
 use fdm_library::palmer::fdm::structures::PerformanceData;
 
 
-fn bourg(fps: f32, frames: usize) 
+fn bourg(fps: Float, frames: usize) 
 {
     let dt = 1.0 / fps;
 
@@ -120,6 +123,7 @@ fn bourg(fps: f32, frames: usize)
 fn palmer(fps: f64, frames: usize)
 {
     let dt = 1.0 / fps;
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
 
     //Create airplane
     let mut fdm = OtherDataFDM{
@@ -159,34 +163,12 @@ fn palmer(fps: f64, frames: usize)
     for _ in 0..frames //frame #
     {
 
-        let mut qcopy = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-        let mut dq1 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-        let mut dq2 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-        let mut dq3 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-        let mut dq4 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-
         //Retrieve value of dependent variable
         let mut q = fdm.q.clone();
 
-        //Get the static time variable DT
-        let ds = dt;
-
-        // Compute the four Runge-Kutta steps, The return 
-        // value of plane_right_hand_side method is an array
-        // of delta-q values for each of the four steps.
-        plane_rhs(&mut fdm, &mut q, &mut qcopy,  &ds, 0.0, &mut dq1);
-        plane_rhs(&mut fdm, &mut q, &mut dq1,    &ds, 0.5, &mut dq2);
-        plane_rhs(&mut fdm, &mut q, &mut dq2,    &ds, 0.5, &mut dq3);
-        plane_rhs(&mut fdm, &mut q, &mut dq3,    &ds, 1.0, &mut dq4);
-
-        //  Update the dependent and independent variable values
-        //  at the new dependent variable location and store the
-        //  values in the ODE object arrays.
-        for i in 0..6
-        {
-            q[i] = q[i] + (dq1[i] + 2.0 * dq2[i] + 2.0 * dq3[i] + dq4[i]) / 6.0;
-            fdm.q[i] = q[i];
-        }
+        //Advance the ODEs one time step with 4th-order Runge-Kutta
+        integrator.step(0.0, &mut q, dt, &VELOCITY_STATES, |_, y, dydt| plane_derivatives(&mut fdm, y, dydt));
+        fdm.q = q;
 
         //Calculate airspeed
         fdm.airspeed = (fdm.q[0] * fdm.q[0] + fdm.q[2] * fdm.q[2] + fdm.q[4] * fdm.q[4]).sqrt();
@@ -16,26 +16,54 @@ use specs::prelude::*;
 //Main loop
 use std::{thread, time};
 
+//nalgebra vector type
+use nalgebra::Vector3;
+
 //Import Component modules
 use fdm_library::bourg::fdm::structures::KeyboardState;
+use fdm_library::bourg::fdm::structures::StateHistory;
+use fdm_library::bourg::fdm::structures::AutopilotState;
 use fdm_library::bourg::fdm::structures::DataFDM;
 use fdm_library::bourg::fdm::structures::PointMass;
+use fdm_library::bourg::fdm::structures::LandingGear;
+use fdm_library::bourg::fdm::structures::Actuators;
+use fdm_library::bourg::fdm::structures::InitialConditions;
 use fdm_library::flightgear::FGNetFDM;
+use fdm_library::flight_plan::{FlightPlan, Waypoint};
+use fdm_library::approach::{Approach, Runway};
+use fdm_library::actuator::{Actuator, ActuatorDynamics};
+use fdm_library::engine::{PistonEngine, PistonEngineData};
+use fdm_library::propeller::{Propeller, PropellerData};
+use fdm_library::fuel::{FuelSystem, FuelTank};
+use fdm_library::sensors::{Sensors, SensorSuiteData};
+use fdm_library::estimator::{Estimator, EstimatorData};
+use fdm_library::air_data::{AirDataComputer, AirDataComputerData};
+use fdm_library::envelope::{EnvelopeMonitor, EnvelopeLimits};
+use fdm_library::crash::{CrashMonitor, CrashLimits, SpawnedEngines};
 
 //Import Resources
 use fdm_library::bourg::resources::delta_time::DeltaTime;
 use fdm_library::bourg::resources::max_thrust::MaxThrust;
 use fdm_library::bourg::resources::delta_thrust::DeltaThrust;
+use fdm_library::integrator::{Integrator, IntegrationMethod};
+use fdm_library::scheduler::Scheduler;
 
 //Import Systems
 use fdm_library::bourg::systems::system_flight_control::FlightControl;
+use fdm_library::bourg::systems::system_flight_plan::FlightPlanGuidance;
+use fdm_library::bourg::systems::system_approach::Autoland;
+use fdm_library::bourg::systems::system_autopilot::Autopilot;
 use fdm_library::bourg::systems::system_equations_of_motion::EquationsOfMotion;
+use fdm_library::bourg::systems::system_envelope::EnvelopeMonitoring;
+use fdm_library::bourg::systems::system_crash::CrashDetection;
 use fdm_library::bourg::systems::system_make_packet::MakePacket;
 use fdm_library::bourg::systems::system_send_packet::SendPacket;
 
 //Import Vector, Matrix, Quaternion, math utilities module if required for setup
 use fdm_library::bourg::common::vector::Vector;
 use fdm_library::bourg::common::quaternion::Quaternion;
+use fdm_library::navigation::Geodetic;
+use fdm_library::bourg::common::real::{Real, Float};
 
 //Import calculate mass properties function for use when the airplane Entity is created
 use fdm_library::bourg::fdm::mass_properties::calc_airplane_mass_properties;
@@ -48,32 +76,53 @@ fn main()
     //Register the Components to the world
     world.register::<DataFDM>();
     world.register::<KeyboardState>();
+    world.register::<StateHistory>();
+    world.register::<AutopilotState>();
+    world.register::<FlightPlan>();
+    world.register::<Approach>();
+    world.register::<Actuators>();
+    world.register::<InitialConditions>();
+    world.register::<PistonEngine>();
+    world.register::<Propeller>();
     world.register::<FGNetFDM>();
 
     //Choose frame rate, which will calculate delta time
-    let frame_rate: f32 = 30.0;
-    let dt: f32 = 1.0 / frame_rate; //seconds
+    let frame_rate: Float = 30.0;
+    let dt: Float = 1.0 / frame_rate; //seconds
 
     //Add dt as a SPECS resource
     world.insert(DeltaTime(dt));
 
-    //Choose max thrust potential and increment amount
-    let max_thrust: f32 = 3000.0;
-    let d_thrust: f32 = 100.0;
+    //Choose max thrust potential and how fast the thrust keys change it, per second
+    let max_thrust: Float = 3000.0;
+    let d_thrust: Float = 3000.0;
 
     //Add max_thrust and d_thrust as resources
     world.insert(MaxThrust(max_thrust));
     world.insert(DeltaThrust(d_thrust));
 
+    //Choose the integration method. Semi-implicit Euler matches Bourg's original update order,
+    //RungeKutta4 or DormandPrince45 are more stable at low frame rates
+    world.insert(Integrator::new(IntegrationMethod::SemiImplicitEuler));
+
+    //Run the physics at 240 Hz, and poll the keyboard and send packets at the frame rate.
+    //The main loop tells the scheduler how much real time has passed each frame
+    world.insert(Scheduler::new(240.0, frame_rate.to_f64(), frame_rate.to_f64()));
+
     //Create variable to keep track of time elapsed
-    let mut current_time: f32 = 0.0;
+    let mut current_time: Float = 0.0;
     let mut current_frame_main: usize = 0;
 
     //Create a dispatcher to manage system execution
     let mut dispatcher = DispatcherBuilder::new()
     .with(FlightControl, "flightcontrol", &[])
-    .with(EquationsOfMotion, "EOM", &["flightcontrol"])
-    .with(MakePacket, "makepacket", &["EOM"])
+    .with(FlightPlanGuidance, "flightplan", &["flightcontrol"])
+    .with(Autoland, "approach", &["flightplan"])
+    .with(Autopilot, "autopilot", &["approach"])
+    .with(EquationsOfMotion, "EOM", &["autopilot"])
+    .with(EnvelopeMonitoring, "envelope", &["EOM"])
+    .with(CrashDetection, "crash", &["EOM"])
+    .with(MakePacket, "makepacket", &["crash"])
     .with(SendPacket, "sendpacket", &["makepacket"])
     .build();
     dispatcher.setup(&mut world);
@@ -92,20 +141,55 @@ fn main()
             PointMass{f_mass: 2.62, v_d_coords: Vector::new(3.03, -2.5, 3.0), v_local_inertia: Vector::new(0.837, 0.385, 1.206), f_incidence: 0.0, f_dihedral: 0.0, f_area: 10.8, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
             PointMass{f_mass: 2.93, v_d_coords: Vector::new(2.25, 0.0, 5.0), v_local_inertia: Vector::new(1.262, 1.942, 0.718), f_incidence: 0.0, f_dihedral: 90.0, f_area: 12.0, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
             PointMass{f_mass: 31.8, v_d_coords: Vector::new(15.25, 0.0, 1.5), v_local_inertia: Vector::new(66.30, 861.9, 861.9), f_incidence: 0.0, f_dihedral: 0.0, f_area: 84.0, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) }
-        ], ..Default::default()};
+        ],
+        //Tricycle landing gear, the mains just behind the CG and the nose wheel well ahead of it
+        gear: vec![
+            LandingGear{v_d_coords: Vector::new(12.0, 4.0, -2.0), f_stiffness: 5000.0, f_damping: 800.0, f_rolling_friction: 0.02, f_brake_friction: 0.5, f_side_friction: 0.8, ..Default::default()},
+            LandingGear{v_d_coords: Vector::new(12.0, -4.0, -2.0), f_stiffness: 5000.0, f_damping: 800.0, f_rolling_friction: 0.02, f_brake_friction: 0.5, f_side_friction: 0.8, ..Default::default()},
+            LandingGear{v_d_coords: Vector::new(18.0, 0.0, -2.0), f_stiffness: 3000.0, f_damping: 250.0, f_rolling_friction: 0.02, f_brake_friction: 0.0, f_side_friction: 0.8, ..Default::default()},
+        ],
+        //Two wing tanks of 100 kg of avgas feeding together, their fuel is part of the mass properties as it burns
+        fuel: FuelSystem::new(vec![
+            FuelTank::new(Vector3::new(14.5, 8.0, 2.5), 100.0, 0),
+            FuelTank::new(Vector3::new(14.5, -8.0, 2.5), 100.0, 0),
+        ]), ..Default::default()};
 
     //Calculate mass properties on this airplane
     calc_airplane_mass_properties(&mut myairplane);
 
-    //Create an airplane Entity and populate its DataFDM Component with the mass properties computed
+    //Control surface servos and the engine spooling up and down, the surfaces start centred and the thrust at 500
+    let mut actuators = Actuators{
+        elevator: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -20.0, 20.0, 60.0),
+        aileron: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -20.0, 20.0, 60.0),
+        rudder: Actuator::new(ActuatorDynamics::SecondOrder(15.0, 0.7), -20.0, 20.0, 40.0),
+        thrust: Actuator::new(ActuatorDynamics::FirstOrder(1.0), 0.0, max_thrust.to_f64(), f64::INFINITY),
+        elevator_flap: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -1.0, 1.0, 4.0),
+        aileron_flap: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -1.0, 1.0, 4.0),
+    };
+    actuators.thrust.reset(500.0);
+
+    //600 hp radial engine, already running, turning a clockwise 2.7 m propeller on the nose. The thrust keys move its throttle,
+    //and R, T and Y work the mixture and starter. The propeller's torque, spin and P-factor pull the airplane to the left
+    let mut engine = PistonEngine::new(PistonEngineData::new(447000.0, 2250.0, 0.0221));
+    engine.start();
+    let propeller = Propeller::new(PropellerData{ inertia: 8.0, ..PropellerData::fixed_pitch(2.7, Vector3::new(26.0, 0.0, 1.5)) });
+
+    //Envelope of the airplane, the monitor logs the exceedances and the angle of attack protection is on
+    let mut envelope = EnvelopeMonitor::new(EnvelopeLimits::new(25.0, 90.0));
+    envelope.protection = true;
+
+    //Crashes on the ground freeze the airplane, which is reset to where it started 5 seconds later
+    let mut crash = CrashMonitor::new(CrashLimits::default());
+    crash.reset_delay = Some(5.0);
+
+    //Populate the airplane's DataFDM with the mass properties computed
     //Additionally, define the starting flight values and position
-    let _plane = world.create_entity()
-    .with(DataFDM{
+    let fdm = DataFDM{
         //Define initial flight parameters
         //Wpafb runway geodetic coordinates at 2000 ft (609 meters) above sea level, ground level is at 248 meters elevation
-        //Note: make the origin and start position the same, origin will remain constant throughout simulation
-        lla_origin: Vector{x: 39.826, y: -84.045, z: 609.0},
-        v_position: Vector{x: 39.826 , y: -84.045, z: 609.0},
+        //The earth space position starts at the same point, 2000 ft up
+        position: Geodetic::from_degrees(39.826, -84.045, 609.0),
+        v_position: Vector{x: 0.0, y: 0.0, z: 2000.0},
         
         v_velocity: Vector{x: 60.0, y: 0.0, z: 0.0},
         f_speed: 60.0,
@@ -117,13 +201,27 @@ fn main()
         mass: myairplane.mass,
         m_inertia: myairplane.m_inertia,
         m_inertia_inverse: myairplane.m_inertia_inverse,
+        v_cg: myairplane.v_cg,
 
-        //Copy over the defined PointMass elements
+        //Copy over the defined PointMass elements, landing gear and fuel tanks
         element: myairplane.element,
+        gear: myairplane.gear,
+        fuel: myairplane.fuel,
+
+        //Ground level in earth space, 248 meters
+        f_ground_elevation: 813.7,
+
+        //The right wing stalls a degree before the left one, so a stall drops the right wing
+        f_stall_asymmetry: 1.0,
 
         //Everything else is zero to begin
         ..Default::default()
-    })
+    };
+
+    //Create an airplane Entity with Components, it is reset to its initial conditions after a crash
+    let _plane = world.create_entity()
+    .with(InitialConditions { fdm: fdm.clone(), engines: SpawnedEngines { engine: Some(engine), propeller: Some(propeller.clone()), jet: None } })
+    .with(fdm)
     .with(KeyboardState{
         thrust_up: false,
         thrust_down: false,
@@ -135,7 +233,45 @@ fn main()
         pitch_down: false,
         flaps_down: false,
         zero_flaps: false,
+        ap_altitude_hold: false,
+        ap_vertical_speed: false,
+        ap_heading_hold: false,
+        ap_airspeed_hold: false,
+        ap_wing_leveler: false,
+        ap_disengage: false,
+        ap_flight_plan: false,
+        ap_approach: false,
+        mixture_rich: false,
+        mixture_lean: false,
+        engine_start: false,
+    })
+    .with(StateHistory{
+        ..Default::default()
+    })
+    .with(AutopilotState::default())
+    //Racetrack east of the base at 2000 ft, press F to let the flight plan drive the autopilot
+    .with(FlightPlan{
+        repeat: true,
+        ..FlightPlan::new(vec![
+            Waypoint::from_degrees(39.826, -83.98, 609.0),
+            Waypoint::from_degrees(39.80, -83.98, 609.0),
+            Waypoint::from_degrees(39.80, -84.045, 609.0),
+            Waypoint::from_degrees(39.826, -84.045, 609.0),
+        ])
     })
+    //Runway 36 south of the base, press G to fly the approach and land
+    .with(Approach{
+        flare_height: 15.0,
+        ..Approach::new(Runway::new(Geodetic::from_degrees(39.79, -84.045, 248.0), 0.0, 2500.0), 40.0)
+    })
+    .with(actuators)
+    .with(engine)
+    .with(propeller)
+    .with(Sensors::new(SensorSuiteData::default()))
+    .with(Estimator::new(EstimatorData::from_sensors(&SensorSuiteData::default())))
+    .with(AirDataComputer::new(AirDataComputerData::default()))
+    .with(envelope)
+    .with(crash)
     .with(FGNetFDM{
         ..Default::default()
     })
@@ -144,12 +280,19 @@ fn main()
     //Create time type with the desired DT in milliseconds
     let timestep = time::Duration::from_millis((dt * 1000.0) as u64);
 
+    //Time the previous frame started
+    let mut last_frame = time::Instant::now();
+
     //Main simulation loop
     loop 
     {
         //Get current time
         let start = time::Instant::now();
 
+        //Advance the scheduler by the real time elapsed since the last frame
+        world.write_resource::<Scheduler>().advance(start.duration_since(last_frame).as_secs_f64());
+        last_frame = start;
+
         //Increment time count
         current_time = current_time + dt;
         current_frame_main = current_frame_main + 1;
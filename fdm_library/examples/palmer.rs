@@ -19,16 +19,39 @@ use std::{thread, time};
 
 //Import Component modules
 use fdm_library::palmer::fdm::structures::KeyboardState;
+use fdm_library::palmer::fdm::structures::StateHistory;
+use fdm_library::palmer::fdm::structures::AutopilotState;
 use fdm_library::palmer::fdm::structures::DataFDM;
 use fdm_library::palmer::fdm::structures::PerformanceData;
+use fdm_library::palmer::fdm::structures::ControlLimits;
+use fdm_library::palmer::fdm::structures::Actuators;
+use fdm_library::palmer::fdm::structures::InitialConditions;
 use fdm_library::flightgear::FGNetFDM;
+use fdm_library::flight_plan::{FlightPlan, Waypoint};
+use fdm_library::approach::{Approach, Runway};
+use fdm_library::actuator::{Actuator, ActuatorDynamics};
+use fdm_library::engine::{PistonEngine, PistonEngineData};
+use fdm_library::fuel::{FuelSystem, FuelTank};
+use fdm_library::sensors::{Sensors, SensorSuiteData};
+use fdm_library::estimator::{Estimator, EstimatorData};
+use fdm_library::air_data::{AirDataComputer, AirDataComputerData};
+use fdm_library::envelope::{EnvelopeMonitor, EnvelopeLimits};
+use fdm_library::crash::{CrashMonitor, CrashLimits, SpawnedEngines};
+use fdm_library::navigation::Geodetic;
 
 //Import Resources
 use fdm_library::palmer::resources::delta_time::DeltaTime;
+use fdm_library::integrator::{Integrator, IntegrationMethod};
+use fdm_library::scheduler::Scheduler;
 
 //Import Systems
 use fdm_library::palmer::systems::system_flight_control::FlightControl;
+use fdm_library::palmer::systems::system_flight_plan::FlightPlanGuidance;
+use fdm_library::palmer::systems::system_approach::Autoland;
+use fdm_library::palmer::systems::system_autopilot::Autopilot;
 use fdm_library::palmer::systems::system_equations_of_motion::EquationsOfMotion;
+use fdm_library::palmer::systems::system_envelope::EnvelopeMonitoring;
+use fdm_library::palmer::systems::system_crash::CrashDetection;
 use fdm_library::palmer::systems::system_make_packet::MakePacket;
 use fdm_library::palmer::systems::system_send_packet::SendPacket;
 
@@ -41,6 +64,13 @@ fn main()
     //Register Components in the world
     world.register::<DataFDM>();
     world.register::<KeyboardState>();
+    world.register::<StateHistory>();
+    world.register::<AutopilotState>();
+    world.register::<FlightPlan>();
+    world.register::<Approach>();
+    world.register::<Actuators>();
+    world.register::<InitialConditions>();
+    world.register::<PistonEngine>();
     world.register::<FGNetFDM>();
 
     //Choose frame rate, which will calculate delta time
@@ -50,6 +80,13 @@ fn main()
     //Add dt as a SPECS resource
     world.insert(DeltaTime(dt)); 
 
+    //Choose the integration method (Euler, SemiImplicitEuler, RungeKutta4, DormandPrince45)
+    world.insert(Integrator::new(IntegrationMethod::RungeKutta4));
+
+    //Run the physics at 240 Hz, and poll the keyboard and send packets at the frame rate.
+    //The main loop tells the scheduler how much real time has passed each frame
+    world.insert(Scheduler::new(240.0, frame_rate, frame_rate));
+
     //Create variable to keep track of time elapsed
     let mut current_time = 0.0;
     let mut current_frame_main: usize = 0;
@@ -57,15 +94,31 @@ fn main()
     //Create dispatcher of the Systems
     let mut dispatcher = DispatcherBuilder::new()
     .with(FlightControl, "flightcontrol", &[])
-    .with(EquationsOfMotion, "EOM", &["flightcontrol"])
-    .with(MakePacket, "makepacket", &["EOM"])
+    .with(FlightPlanGuidance, "flightplan", &["flightcontrol"])
+    .with(Autoland, "approach", &["flightplan"])
+    .with(Autopilot, "autopilot", &["approach"])
+    .with(EquationsOfMotion, "EOM", &["autopilot"])
+    .with(EnvelopeMonitoring, "envelope", &["EOM"])
+    .with(CrashDetection, "crash", &["EOM"])
+    .with(MakePacket, "makepacket", &["crash"])
     .with(SendPacket, "sendpacket", &["makepacket"])
     .build();
     dispatcher.setup(&mut world);
 
-    //Create airplane Entity with Components
-    let _plane = world.create_entity()
-    .with(DataFDM{
+    //160 hp engine turning the propeller at 2400 RPM, idling on the runway. R, T and Y work the mixture and starter
+    let mut engine = PistonEngine::new(PistonEngineData::new(119310.0, 2400.0, 0.0059));
+    engine.start();
+
+    //Envelope of the airplane with the angle of attack limit at Cl(max), the monitor logs the exceedances and the protection is on
+    let mut envelope = EnvelopeMonitor::new(EnvelopeLimits{ max_alpha: 16.0, ..EnvelopeLimits::new(25.0, 80.0) });
+    envelope.protection = true;
+
+    //Crashes on the ground freeze the airplane, which is reset to where it started 5 seconds later
+    let mut crash = CrashMonitor::new(CrashLimits::default());
+    crash.reset_delay = Some(5.0);
+
+    //Starting state of the airplane
+    let fdm = DataFDM{
         //Starting position and origin in geodetic coordinates
         //Wpafb runway latitude/longitude/altitude. Ground level is 248.0 meters elevation
         //Note: make the origin and start position the same, origin will remain constant throughout simulation
@@ -74,14 +127,26 @@ fn main()
        
         bank: 0.0, //bank angle degrees (-20 - 20)
         alpha: 0.0,//angle of attack degrees (-16 - 20)
+        bank_rate: 0.0, //rates the bank and angle of attack changed at, set once it flies
+        alpha_rate: 0.0,
+        flown: None,
         throttle: 0.0, //throttle percentage (0 - 1)
         flap: 0.0,  //flap deflection amount degrees (20 or 40)
+        shaft_power: None, //power of the engine, set once it runs
+        propeller_thrust: None, //thrust of the propeller, set once it turns
+        jet_thrust: None, //thrust of a jet engine, set once it runs
         airspeed: 0.0,
 
         climb_angle: 0.0,
         heading_angle: 0.0,
         climb_rate: 0.0,
         q: vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0], //will store ODE results
+
+        stall_margin: 0.0, //angle of attack left before the wing stalls, set once it flies
+        stall_warning: 0.0,
+
+        on_ground: true, //parked on the runway
+        touchdown_rate: 0.0,
         
         mass_properties: PerformanceData{
             wing_area: 16.2,            //  wing wetted area, m^2
@@ -94,15 +159,37 @@ fn main()
             alpha_cl_max: 16.0,         //  alpha at Cl(max)
             cdp: 0.034,                 //  parasitic drag coefficient
             eff: 0.77,                  //  induced drag efficiency coefficient
-            mass: 1114.0,               //  airplane mass, kg
+            mass: 1114.0,               //  airplane mass with full tanks, kg
             engine_power: 119310.0,     //  peak engine power, W
             engine_rps: 40.0,           //  engine turnover rate, rev/s
             prop_diameter: 1.905,       //  propeller diameter, m
             a: 1.83,                    //  propeller efficiency curve fit coefficient
             b:-1.32,                    //  propeller efficiency curve fit coefficient
-        }
+        },
 
-    })
+        control_limits: ControlLimits{
+            alpha_min: -16.0,           //  angle of attack limits, deg
+            alpha_max: 20.0,
+            bank_max: 20.0,             //  bank limit either way, deg
+            flap_detents: vec![0.0, 20.0, 40.0], //  flap settings, deg
+            flap_cl: vec![0.0, 0.25, 0.5],       //  lift coefficient the flaps add at each setting
+            alpha_rate: 30.0,           //  how fast the keys move the controls, deg/s
+            bank_rate: 30.0,
+            throttle_rate: 1.5,         //  full throttle in 2/3 of a second
+        },
+
+        //Two wing tanks of 75 kg of avgas feeding together, the engine stops once they run dry
+        fuel: FuelSystem::new(vec![
+            FuelTank::new(Vector3::new(0.0, 2.5, 0.0), 75.0, 0),
+            FuelTank::new(Vector3::new(0.0, -2.5, 0.0), 75.0, 0),
+        ]),
+
+    };
+
+    //Create an airplane Entity with Components, it is reset to its initial conditions after a crash
+    let _plane = world.create_entity()
+    .with(InitialConditions { fdm: fdm.clone(), engines: SpawnedEngines { engine: Some(engine), ..SpawnedEngines::default() } })
+    .with(fdm)
     .with(KeyboardState{
         throttle_up: false,
         throttle_down: false,
@@ -112,7 +199,46 @@ fn main()
         bank_left: false,
         flaps_down: false,
         zero_flaps: false,
+        ap_altitude_hold: false,
+        ap_vertical_speed: false,
+        ap_heading_hold: false,
+        ap_airspeed_hold: false,
+        ap_wing_leveler: false,
+        ap_disengage: false,
+        ap_flight_plan: false,
+        ap_approach: false,
+        mixture_rich: false,
+        mixture_lean: false,
+        engine_start: false,
     })
+    .with(StateHistory{
+        ..Default::default()
+    })
+    .with(AutopilotState::default())
+    //Survey pattern north of the base 300 meters above the ground, press F to let the flight plan drive the autopilot
+    .with(FlightPlan::new(vec![
+        Waypoint::from_degrees(39.84, -84.045, 548.0).with_airspeed(50.0),
+        Waypoint::from_degrees(39.84, -84.03, 548.0),
+        Waypoint::from_degrees(39.826, -84.03, 548.0),
+        Waypoint::from_degrees(39.826, -84.015, 548.0),
+        Waypoint::from_degrees(39.84, -84.015, 548.0),
+    ]))
+    //Runway 36 south of the base, press G to fly the approach and land
+    .with(Approach::new(Runway::new(Geodetic::from_degrees(39.79, -84.045, 248.0), 0.0, 2500.0), 40.0))
+    //The airplane pitches and rolls at a limited rate, the engine spools up and the flaps run out slowly.
+    //Everything starts at zero like the controls above
+    .with(Actuators{
+        alpha: Actuator::new(ActuatorDynamics::SecondOrder(6.0, 0.8), -16.0, 20.0, 10.0),
+        bank: Actuator::new(ActuatorDynamics::FirstOrder(0.3), -45.0, 45.0, 15.0),
+        throttle: Actuator::new(ActuatorDynamics::FirstOrder(0.5), 0.0, 1.0, f64::INFINITY),
+        flap: Actuator::new(ActuatorDynamics::Instant, 0.0, 40.0, 5.0),
+    })
+    .with(engine)
+    .with(Sensors::new(SensorSuiteData::default()))
+    .with(Estimator::new(EstimatorData::from_sensors(&SensorSuiteData::default())))
+    .with(AirDataComputer::new(AirDataComputerData::default()))
+    .with(envelope)
+    .with(crash)
     .with(FGNetFDM{
         ..Default::default()
     })
@@ -121,12 +247,19 @@ fn main()
     //Create time type with the dt in milliseconds
     let timestep = time::Duration::from_millis((dt * 1000.0) as u64);
 
+    //Time the previous frame started
+    let mut last_frame = time::Instant::now();
+
     //Loop simulation
     loop 
     {
         //Get current time
         let start = time::Instant::now();
 
+        //Advance the scheduler by the real time elapsed since the last frame
+        world.write_resource::<Scheduler>().advance(start.duration_since(last_frame).as_secs_f64());
+        last_frame = start;
+
         //Increment time count
         current_time = current_time + dt;
         current_frame_main = current_frame_main + 1;
@@ -0,0 +1,171 @@
+//To run unit tests for the actuator module from the command line:
+//cargo test --lib actuator
+
+//Actuator dynamics shared by both models. The inputs and the autopilot set the command of a control channel,
+//and its actual position follows it through a first-order lag or a second-order servo, within rate and position limits.
+//Each model has an Actuators Component with one Actuator per control channel, configured per aircraft.
+//Positions are in the units of the channel (degrees for surfaces, the thrust or throttle units for the engine)
+
+//How the actual position responds to the command
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ActuatorDynamics
+{
+    //The position goes straight to the command, only the rate limit slows it down
+    Instant,
+    //First-order lag with a time constant (s)
+    FirstOrder(f64),
+    //Second-order servo with a natural frequency (rad/s) and a damping ratio
+    SecondOrder(f64, f64),
+}
+
+//One control channel: its dynamics, limits, the latest command and the actual position
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Actuator
+{
+    pub dynamics: ActuatorDynamics,
+    pub min: f64, //position limits
+    pub max: f64,
+    pub rate_limit: f64, //largest rate of travel, units/s
+    pub command: f64, //latest command, as given. It is held within the position limits when the actuator moves
+    pub position: f64, //actual position
+    pub rate: f64, //actual rate, units/s
+}
+
+impl Default for Actuator
+{
+    //An actuator without lag or limits, the position always equals the command
+    fn default() -> Actuator
+    {
+        Actuator::new(ActuatorDynamics::Instant, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY)
+    }
+}
+
+impl Actuator
+{
+    pub fn new(dynamics: ActuatorDynamics, min: f64, max: f64, rate_limit: f64) -> Actuator
+    {
+        Actuator { dynamics, min, max, rate_limit, command: 0.0, position: 0.0, rate: 0.0 }
+    }
+
+    //Put the actuator at rest at a position, which also becomes the command
+    pub fn reset(&mut self, position: f64)
+    {
+        self.command = position;
+        self.position = position.clamp(self.min, self.max);
+        self.rate = 0.0;
+    }
+
+    //Move the actuator towards a command for dt seconds and return the actual position
+    pub fn update(&mut self, command: f64, dt: f64) -> f64
+    {
+        self.command = command;
+        if dt <= 0.0
+        {
+            return self.position;
+        }
+
+        let target = command.clamp(self.min, self.max);
+        let rate = match self.dynamics
+        {
+            ActuatorDynamics::Instant => (target - self.position) / dt,
+            //Exact step of the lag, so it holds for any dt
+            ActuatorDynamics::FirstOrder(time_constant) if time_constant > 0.0 => (target - self.position) * (1.0 - (-dt / time_constant).exp()) / dt,
+            ActuatorDynamics::FirstOrder(_) => (target - self.position) / dt,
+            //Semi-implicit Euler step, stable while natural_frequency * dt stays well below one
+            ActuatorDynamics::SecondOrder(natural_frequency, damping) =>
+            {
+                let acceleration = natural_frequency * natural_frequency * (target - self.position) - 2.0 * damping * natural_frequency * self.rate;
+                self.rate + acceleration * dt
+            }
+        };
+
+        self.rate = rate.clamp(-self.rate_limit, self.rate_limit);
+        self.position += self.rate * dt;
+
+        //The actuator stops at its limits
+        if self.position < self.min || self.position > self.max
+        {
+            self.position = self.position.clamp(self.min, self.max);
+            self.rate = 0.0;
+        }
+
+        self.position
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn instant_test()
+    {
+        //Without limits the position follows the command
+        let mut actuator = Actuator::default();
+        assert_eq!(actuator.update(16.0, 0.01), 16.0);
+        assert_eq!(actuator.update(-3.0, 0.01), -3.0);
+
+        //The rate limit turns a step into a ramp and the position limits hold
+        let mut actuator = Actuator::new(ActuatorDynamics::Instant, -20.0, 20.0, 50.0);
+        assert_eq!(actuator.update(16.0, 0.1), 5.0);
+        assert_eq!(actuator.rate, 50.0);
+        for _ in 0..10
+        {
+            actuator.update(30.0, 0.1);
+        }
+        assert_eq!(actuator.position, 20.0);
+        assert_eq!(actuator.command, 30.0);
+
+        //No time, no movement
+        assert_eq!(actuator.update(0.0, 0.0), 20.0);
+    }
+
+    #[test]
+    fn first_order_test()
+    {
+        //After one time constant the lag has covered 63% of a step, whatever the step size
+        for steps in [1, 10, 240]
+        {
+            let mut actuator = Actuator::new(ActuatorDynamics::FirstOrder(0.5), -1.0, 1.0, f64::INFINITY);
+            for _ in 0..steps
+            {
+                actuator.update(1.0, 0.5 / steps as f64);
+            }
+            assert!((actuator.position - (1.0 - (-1.0_f64).exp())).abs() < 1e-9);
+        }
+
+        //A rate limit slower than the lag sets the travel time
+        let mut actuator = Actuator::new(ActuatorDynamics::FirstOrder(0.05), 0.0, 1.0, 0.5);
+        actuator.update(1.0, 0.2);
+        assert!((actuator.position - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn second_order_test()
+    {
+        //A damped servo settles on the command and overshoots a little with a damping ratio of 0.5
+        let mut actuator = Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.5), -25.0, 25.0, f64::INFINITY);
+        let mut peak: f64 = 0.0;
+        for _ in 0..480
+        {
+            peak = peak.max(actuator.update(10.0, 1.0 / 240.0));
+        }
+        assert!((actuator.position - 10.0).abs() < 1e-3);
+        assert!(peak > 11.0 && peak < 12.0);
+
+        //Limited to 60 deg/s it needs at least half a second for 30 degrees
+        let mut actuator = Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -25.0, 25.0, 60.0);
+        actuator.reset(-15.0);
+        for _ in 0..119
+        {
+            actuator.update(15.0, 1.0 / 240.0);
+            assert!(actuator.rate <= 60.0);
+        }
+        assert!(actuator.position < 15.0);
+
+        //Reset puts the actuator at rest within its limits
+        actuator.reset(40.0);
+        assert_eq!((actuator.command, actuator.position, actuator.rate), (40.0, 25.0, 0.0));
+    }
+}
@@ -0,0 +1,284 @@
+//To run unit tests for the air_data module from the command line:
+//cargo test --lib air_data
+
+//Air data computer shared by both models. From the static air around the airplane and the relative wind in body axes
+//it gives the instrument values a pilot or an autopilot reads: indicated and calibrated airspeed, true airspeed, Mach,
+//the pressure altitude, the altimeter reading at its setting, the density altitude, a vertical speed lagged like a VSI,
+//and the angle of attack and sideslip. The static port reads a fraction of the impact pressure high, so the indicated
+//airspeed and altitude read low, and the calibrated airspeed and pressure altitude are those values corrected.
+//Each model's air_data module finds the relative wind and the atmosphere from its DataFDM.
+//Aerospace axes and SI units: the body axes are x forward, y right, z down, m, m/s, rad and Pa
+
+//SPECS
+use specs::prelude::*;
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//Standard atmosphere and its altitudes
+use crate::atmosphere::{Atmosphere, SEA_LEVEL_PRESSURE, pressure_altitude, altimeter_altitude, density_altitude};
+
+//Pitot-static pressures
+use crate::sensors::{impact_pressure, calibrated_airspeed};
+
+//FGNetFDM packet structure
+use crate::flightgear::FGNetFDM;
+
+//Feet in a meter
+use crate::navigation::FEET_PER_METER;
+
+//Meters per second in a knot, for FlightGear
+pub const METERS_PER_SECOND_PER_KNOT: f64 = 0.514444;
+
+//Data of the air data computer, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AirDataComputerData
+{
+    pub altimeter_setting: f64, //Pa, the sea level pressure the altimeter is set to
+    pub vsi_time_constant: f64, //s, lag of the vertical speed
+    pub static_error: f64, //static pressure error as a fraction of the impact pressure
+}
+
+impl Default for AirDataComputerData
+{
+    //Standard setting and a small static error
+    fn default() -> Self
+    {
+        AirDataComputerData { altimeter_setting: SEA_LEVEL_PRESSURE, vsi_time_constant: 2.0, static_error: 0.01 }
+    }
+}
+
+//Outputs of the air data computer
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct AirData
+{
+    pub indicated_airspeed: f64, //m/s
+    pub calibrated_airspeed: f64, //m/s
+    pub true_airspeed: f64, //m/s
+    pub mach: f64,
+    pub pressure_altitude: f64, //m
+    pub indicated_altitude: f64, //m, the altimeter reading at its setting
+    pub density_altitude: f64, //m
+    pub vertical_speed: f64, //m/s, up
+    pub alpha: f64, //rad
+    pub beta: f64, //rad
+    pub static_pressure: f64, //Pa
+    pub impact_pressure: f64, //Pa
+}
+
+//Air data computer, with its latest outputs once it has run
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AirDataComputer
+{
+    pub data: AirDataComputerData,
+    pub outputs: Option<AirData>,
+}
+
+impl Component for AirDataComputer
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl AirDataComputer
+{
+    //A computer with no outputs until its first update
+    pub fn new(data: AirDataComputerData) -> AirDataComputer
+    {
+        AirDataComputer { data, outputs: None }
+    }
+
+    //Clear the outputs when the airplane is put somewhere else, so the vertical speed starts again from the next update
+    //instead of lagging from where it was
+    pub fn reset(&mut self)
+    {
+        self.outputs = None;
+    }
+
+    //Update the outputs dt seconds on in the static air around the airplane with the relative wind (m/s) in body axes.
+    //The vertical speed is the rate of change of the pressure altitude through a first-order lag
+    pub fn update(&mut self, air: &Atmosphere, relative_wind: &Vector3<f64>, dt: f64)
+    {
+        let data = self.data;
+        let true_airspeed = relative_wind.norm();
+        let (alpha, beta) = flow_angles(relative_wind);
+
+        //The static port reads high by a fraction of the impact pressure, and the pitot minus static low by as much
+        let impact = impact_pressure(air, true_airspeed);
+        let static_error = data.static_error * impact;
+        let altitude = pressure_altitude(air.pressure);
+
+        let vertical_speed = match self.outputs
+        {
+            Some(last) if dt > 0.0 =>
+            {
+                let rate = (altitude - last.pressure_altitude) / dt;
+                last.vertical_speed + (rate - last.vertical_speed) * (1.0 - (-dt / data.vsi_time_constant).exp())
+            }
+            Some(last) => last.vertical_speed,
+            None => 0.0,
+        };
+
+        self.outputs = Some(AirData
+        {
+            indicated_airspeed: calibrated_airspeed((impact - static_error).max(0.0)),
+            calibrated_airspeed: calibrated_airspeed(impact),
+            true_airspeed,
+            mach: true_airspeed / air.speed_of_sound(),
+            pressure_altitude: altitude,
+            indicated_altitude: altimeter_altitude(air.pressure + static_error, data.altimeter_setting),
+            density_altitude: density_altitude(air.density),
+            vertical_speed,
+            alpha,
+            beta,
+            static_pressure: air.pressure,
+            impact_pressure: impact,
+        });
+    }
+}
+
+//Angle of attack and sideslip (rad) of the relative wind in body axes, none without a wind
+pub fn flow_angles(relative_wind: &Vector3<f64>) -> (f64, f64)
+{
+    let speed = relative_wind.norm();
+    if speed < 1e-6
+    {
+        return (0.0, 0.0);
+    }
+    (relative_wind.z.atan2(relative_wind.x), (relative_wind.y / speed).clamp(-1.0, 1.0).asin())
+}
+
+//Load the air data into the FlightGear packet, the airspeed in knots and the climb rate in ft/s, in network byte order
+pub fn load_air_data(air_data: &AirData, fgnet: &mut FGNetFDM)
+{
+    let vcas = (air_data.calibrated_airspeed / METERS_PER_SECOND_PER_KNOT) as f32;
+    let climb_rate = (air_data.vertical_speed * FEET_PER_METER) as f32;
+
+    fgnet.vcas = f32::from_be_bytes(vcas.to_ne_bytes());
+    fgnet.climb_rate = f32::from_be_bytes(climb_rate.to_ne_bytes());
+    fgnet.alpha = f32::from_be_bytes((air_data.alpha as f32).to_ne_bytes());
+    fgnet.beta = f32::from_be_bytes((air_data.beta as f32).to_ne_bytes());
+}
+
+//Print the air data, this is called once per frame
+pub fn print_air_data(computer: &AirDataComputer)
+{
+    if let Some(air_data) = computer.outputs
+    {
+        println!("IAS/CAS (kt):       {} {}", air_data.indicated_airspeed / METERS_PER_SECOND_PER_KNOT, air_data.calibrated_airspeed / METERS_PER_SECOND_PER_KNOT);
+        println!("TAS (kt):           {}", air_data.true_airspeed / METERS_PER_SECOND_PER_KNOT);
+        println!("Mach:               {}", air_data.mach);
+        println!("Altimeter (ft):     {}", air_data.indicated_altitude * FEET_PER_METER);
+        println!("Density alt (ft):   {}", air_data.density_altitude * FEET_PER_METER);
+        println!("VSI (ft/min):       {}", air_data.vertical_speed * FEET_PER_METER * 60.0);
+        println!("Alpha/beta (deg):   {} {}", air_data.alpha.to_degrees(), air_data.beta.to_degrees());
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Computer without a static error
+    fn exact() -> AirDataComputer
+    {
+        AirDataComputer::new(AirDataComputerData { static_error: 0.0, ..AirDataComputerData::default() })
+    }
+
+    #[test]
+    fn airspeed_test()
+    {
+        //At sea level the calibrated airspeed is the true airspeed
+        let mut computer = exact();
+        computer.update(&Atmosphere::standard(0.0), &Vector3::new(60.0, 0.0, 0.0), 0.01);
+        let air_data = computer.outputs.unwrap();
+        assert!((air_data.calibrated_airspeed - 60.0).abs() < 1e-6);
+        assert_eq!(air_data.indicated_airspeed, air_data.calibrated_airspeed);
+
+        //Higher up it reads low, nearly with the square root of the density ratio at low Mach
+        let air = Atmosphere::standard(3000.0);
+        computer.update(&air, &Vector3::new(60.0, 0.0, 0.0), 0.01);
+        let air_data = computer.outputs.unwrap();
+        let equivalent = 60.0 * (air.density / Atmosphere::standard(0.0).density).sqrt();
+        assert!(air_data.calibrated_airspeed < 60.0);
+        assert!((air_data.calibrated_airspeed - equivalent).abs() < 0.2, "{}", air_data.calibrated_airspeed);
+        assert!((air_data.mach - 60.0 / air.speed_of_sound()).abs() < 1e-12);
+
+        //The static error makes the indicated airspeed read low
+        let mut computer = AirDataComputer::new(AirDataComputerData::default());
+        computer.update(&air, &Vector3::new(60.0, 0.0, 0.0), 0.01);
+        let air_data = computer.outputs.unwrap();
+        assert!(air_data.indicated_airspeed < air_data.calibrated_airspeed);
+        assert!(air_data.calibrated_airspeed - air_data.indicated_airspeed < 1.0);
+    }
+
+    #[test]
+    fn altitude_test()
+    {
+        //At the standard setting the altimeter reads the pressure altitude, at a lower setting it reads lower
+        let air = Atmosphere::standard(1500.0);
+        let mut computer = exact();
+        computer.update(&air, &Vector3::new(50.0, 0.0, 0.0), 0.01);
+        let air_data = computer.outputs.unwrap();
+        assert!((air_data.pressure_altitude - 1500.0).abs() < 1e-6);
+        assert!((air_data.indicated_altitude - 1500.0).abs() < 1e-6);
+        assert!((air_data.density_altitude - 1500.0).abs() < 1e-3);
+
+        //About 27 ft for each hPa below standard
+        computer.data.altimeter_setting = SEA_LEVEL_PRESSURE - 1000.0;
+        computer.update(&air, &Vector3::new(50.0, 0.0, 0.0), 0.01);
+        let drop = 1500.0 - computer.outputs.unwrap().indicated_altitude;
+        assert!((drop * FEET_PER_METER - 270.0).abs() < 15.0, "{}", drop);
+    }
+
+    #[test]
+    fn vertical_speed_test()
+    {
+        //Climbing at 5 m/s the VSI starts at nothing and lags the climb, after a time constant it shows 63% of it
+        let mut computer = exact();
+        let time_constant = computer.data.vsi_time_constant;
+        let steps = (time_constant * 100.0).round() as usize;
+        for step in 0..=steps
+        {
+            let altitude = 1000.0 + 5.0 * step as f64 / 100.0;
+            computer.update(&Atmosphere::standard(altitude), &Vector3::new(50.0, 0.0, 0.0), 0.01);
+        }
+        let vertical_speed = computer.outputs.unwrap().vertical_speed;
+        assert!((vertical_speed - 5.0 * (1.0 - (-1.0_f64).exp())).abs() < 0.05, "{}", vertical_speed);
+
+        //And then settles on it
+        for step in steps + 1..steps * 6
+        {
+            let altitude = 1000.0 + 5.0 * step as f64 / 100.0;
+            computer.update(&Atmosphere::standard(altitude), &Vector3::new(50.0, 0.0, 0.0), 0.01);
+        }
+        assert!((computer.outputs.unwrap().vertical_speed - 5.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn flow_angles_test()
+    {
+        //The wind from below the nose is a positive angle of attack, from the right a positive sideslip
+        let (alpha, beta) = flow_angles(&Vector3::new(50.0, 0.0, 50.0_f64 * 5.0_f64.to_radians().tan()));
+        assert!((alpha - 5.0_f64.to_radians()).abs() < 1e-12);
+        assert_eq!(beta, 0.0);
+        let (alpha, beta) = flow_angles(&Vector3::new(50.0 * 3.0_f64.to_radians().cos(), 50.0 * 3.0_f64.to_radians().sin(), 0.0));
+        assert!(alpha.abs() < 1e-12);
+        assert!((beta - 3.0_f64.to_radians()).abs() < 1e-12);
+        assert_eq!(flow_angles(&Vector3::zeros()), (0.0, 0.0));
+    }
+
+    #[test]
+    fn packet_test()
+    {
+        //FlightGear takes knots and ft/s
+        let air_data = AirData { calibrated_airspeed: 51.4444, vertical_speed: 1.0, alpha: 0.1, beta: -0.02, ..AirData::default() };
+        let mut fgnet = FGNetFDM::default();
+        load_air_data(&air_data, &mut fgnet);
+        let read = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+        assert!((read(fgnet.vcas) - 100.0).abs() < 1e-3);
+        assert!((read(fgnet.climb_rate) - 3.28084).abs() < 1e-5);
+        assert_eq!(read(fgnet.alpha), 0.1);
+        assert_eq!(read(fgnet.beta), -0.02);
+    }
+}
@@ -0,0 +1,335 @@
+//To run unit tests for the approach module from the command line:
+//cargo test --lib approach
+
+//ILS style approach and autoland shared by both models. The runway is given by its threshold, course and
+//glide slope, and the localizer and glide slope deviations are computed from the geodetic position the way
+//the instruments would show them. The guidance flies the centreline and the glide path, flares to a gentle
+//touchdown and reports where and how hard the airplane landed. Each model's approach module turns the
+//guidance into autopilot modes and controls in its own units. Distances are meters, speeds m/s and angles degrees
+
+//SPECS
+use specs::prelude::*;
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//Great circle routines
+use crate::navigation::{Geodetic, destination, cross_track, along_track};
+
+//Runway the approach is flown to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Runway
+{
+    pub threshold: Geodetic, //landing threshold on the centreline, the altitude is the runway elevation
+    pub course: f64, //deg, clockwise from north, direction of landing
+    pub length: f64, //m, from the threshold to the far end, where the localizer antenna is
+    pub glide_slope: f64, //deg, angle of the glide path
+    pub threshold_crossing_height: f64, //m, height of the glide path over the threshold
+}
+
+impl Runway
+{
+    //Runway with a 3 degree glide path crossing the threshold at 15 m
+    pub fn new(threshold: Geodetic, course: f64, length: f64) -> Runway
+    {
+        Runway { threshold, course, length, glide_slope: 3.0, threshold_crossing_height: 15.0 }
+    }
+
+    //Far end of the runway on the centreline
+    pub fn end(&self) -> Geodetic
+    {
+        destination(&self.threshold, self.course.to_radians(), self.length)
+    }
+
+    //Distance past the threshold where the glide path meets the runway
+    pub fn glide_path_origin(&self) -> f64
+    {
+        self.threshold_crossing_height / self.glide_slope.to_radians().tan()
+    }
+}
+
+//Deviations from the centreline and glide path, positive right of the centreline and above the path
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Deviations
+{
+    pub localizer: f64, //m
+    pub localizer_angle: f64, //deg, seen from the localizer antenna
+    pub glide_slope: f64, //m
+    pub glide_slope_angle: f64, //deg, seen from the glide path origin
+    pub distance_from_threshold: f64, //m along the centreline, negative before the threshold
+    pub height: f64, //m above the runway
+}
+
+//Deviations of a position from the approach to a runway
+pub fn deviations(runway: &Runway, position: &Geodetic) -> Deviations
+{
+    let end = runway.end();
+    let cross = cross_track(&runway.threshold, &end, position);
+    let along = along_track(&runway.threshold, &end, position);
+    let height = position.altitude - runway.threshold.altitude;
+
+    //The glide path rises from its origin on the runway at the glide slope angle
+    let tan_glide_slope = runway.glide_slope.to_radians().tan();
+    let to_origin = runway.glide_path_origin() - along;
+    let to_antenna = runway.length - along;
+
+    Deviations
+    {
+        localizer: cross,
+        localizer_angle: cross.atan2(to_antenna).to_degrees(),
+        glide_slope: height - to_origin * tan_glide_slope,
+        glide_slope_angle: height.atan2(to_origin).to_degrees() - runway.glide_slope,
+        distance_from_threshold: along,
+        height,
+    }
+}
+
+//Phases of the approach
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ApproachPhase
+{
+    Off,
+    Approach, //coupled to the localizer and glide slope
+    Flare, //the sink rate is eased off close to the runway, the thrust stays at its approach setting
+    Rollout, //on the runway, thrust idle and brakes on
+    Stopped,
+}
+
+//Where and how the airplane touched down
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Touchdown
+{
+    pub distance_from_threshold: f64, //m
+    pub centreline_deviation: f64, //m, positive right
+    pub vertical_speed: f64, //m/s, negative down
+    pub ground_speed: f64, //m/s
+}
+
+//Output of the guidance, the targets for the autopilot in the current phase
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ApproachGuidance
+{
+    pub phase: ApproachPhase,
+    pub heading: f64, //deg, clockwise from north
+    pub vertical_speed: f64, //m/s, positive up
+    pub airspeed: f64, //m/s
+}
+
+//Approach to a runway and the phase it is flown in, with the latest deviations and the touchdown once landed
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Approach
+{
+    pub runway: Runway,
+    pub airspeed: f64, //m/s, approach speed held down to the flare
+    pub intercept_distance: f64, //m, the centreline is intercepted aiming at a point this far ahead on it
+    pub max_intercept: f64, //deg, largest intercept angle to the centreline
+    pub glide_slope_gain: f64, //1/s, extra sink rate per meter above the glide path
+    pub flare_height: f64, //m, height of the wheels above the runway where the flare starts
+    pub touchdown_rate: f64, //m/s, sink rate the flare aims for at touchdown
+    pub flare_lead: f64, //s, the flare works on the height predicted this far ahead, to make up for the lag of the autopilot
+    pub stop_speed: f64, //m/s, ground speed below which the rollout is over
+    pub phase: ApproachPhase,
+    pub deviations: Deviations, //latest deviations
+    pub touchdown: Option<Touchdown>,
+}
+
+impl Component for Approach
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl Approach
+{
+    pub fn new(runway: Runway, airspeed: f64) -> Approach
+    {
+        Approach
+        {
+            runway,
+            airspeed,
+            intercept_distance: 1000.0,
+            max_intercept: 30.0,
+            glide_slope_gain: 0.2,
+            flare_height: 6.0,
+            touchdown_rate: 0.3,
+            flare_lead: 0.5,
+            stop_speed: 1.0,
+            phase: ApproachPhase::Off,
+            deviations: Deviations::default(),
+            touchdown: None,
+        }
+    }
+
+    //The approach is flying the airplane
+    pub fn is_engaged(&self) -> bool
+    {
+        !matches!(self.phase, ApproachPhase::Off | ApproachPhase::Stopped)
+    }
+
+    //Engage the approach, a previous touchdown is forgotten
+    pub fn engage(&mut self)
+    {
+        self.phase = ApproachPhase::Approach;
+        self.touchdown = None;
+    }
+
+    //Fly an engaged approach again from its start and forget the deviations, for an airplane put back in the air
+    pub fn reset(&mut self)
+    {
+        if self.is_engaged()
+        {
+            self.engage();
+        }
+        self.deviations = Deviations::default();
+    }
+
+    //Guidance from a position, whose altitude is that of the wheels, and the NED ground velocity (m/s).
+    //touchdown is the vertical speed at which the wheels met the runway once they are on it.
+    //None is returned when the approach is not engaged or the airplane has stopped
+    pub fn guide(&mut self, position: &Geodetic, v_ned: &Vector3<f64>, touchdown: Option<f64>) -> Option<ApproachGuidance>
+    {
+        if !self.is_engaged()
+        {
+            return None;
+        }
+
+        self.deviations = deviations(&self.runway, position);
+        let d = self.deviations;
+        let ground_speed = (v_ned.x * v_ned.x + v_ned.y * v_ned.y).sqrt();
+
+        //Phase changes
+        if self.phase == ApproachPhase::Approach && d.height < self.flare_height
+        {
+            self.phase = ApproachPhase::Flare;
+        }
+        if let Some(vertical_speed) = touchdown
+        {
+            if self.touchdown.is_none()
+            {
+                self.touchdown = Some(Touchdown
+                {
+                    distance_from_threshold: d.distance_from_threshold,
+                    centreline_deviation: d.localizer,
+                    vertical_speed,
+                    ground_speed,
+                });
+                self.phase = ApproachPhase::Rollout;
+            }
+        }
+        if self.phase == ApproachPhase::Rollout && ground_speed < self.stop_speed
+        {
+            self.phase = ApproachPhase::Stopped;
+            return None;
+        }
+
+        //Lateral: aim at a point ahead on the centreline
+        let intercept = d.localizer.atan2(self.intercept_distance).to_degrees().clamp(-self.max_intercept, self.max_intercept);
+        let heading = (self.runway.course - intercept).rem_euclid(360.0);
+
+        //Vertical: the sink rate of the glide path at the current ground speed, corrected toward the path.
+        //Below the path the airplane holds its altitude until the path comes down to meet it
+        let glide_path_rate = ground_speed * self.runway.glide_slope.to_radians().tan();
+        let vertical_speed = match self.phase
+        {
+            ApproachPhase::Approach => (-glide_path_rate - self.glide_slope_gain * d.glide_slope).clamp(-2.0 * glide_path_rate, 0.0),
+            _ =>
+            {
+                //Exponential flare, starting from the glide path sink rate at the flare height and
+                //easing down to the touchdown rate
+                let entry_rate = (self.airspeed * self.runway.glide_slope.to_radians().tan() - self.touchdown_rate).max(0.1);
+                let time_constant = self.flare_height / entry_rate;
+                let height = (d.height - v_ned.z * self.flare_lead).max(0.0);
+                -height / time_constant - self.touchdown_rate
+            }
+        };
+
+        Some(ApproachGuidance { phase: self.phase, heading, vertical_speed, airspeed: self.airspeed })
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Runway 36 at Wright-Patterson, 2000 m long, elevation 248 m
+    fn runway() -> Runway
+    {
+        Runway::new(Geodetic::from_degrees(39.80, -84.05, 248.0), 0.0, 2000.0)
+    }
+
+    #[test]
+    fn deviations_test()
+    {
+        let runway = runway();
+        let tan_glide_slope = 3.0_f64.to_radians().tan();
+
+        //On the glide path 5 km before the threshold
+        let before = destination(&runway.threshold, 180.0_f64.to_radians(), 5000.0);
+        let on_path = Geodetic { altitude: 248.0 + 15.0 + 5000.0 * tan_glide_slope, ..before };
+        let d = deviations(&runway, &on_path);
+        assert!((d.distance_from_threshold + 5000.0).abs() < 1.0);
+        assert!(d.localizer.abs() < 0.1 && d.localizer_angle.abs() < 1.0e-3);
+        assert!(d.glide_slope.abs() < 0.1 && d.glide_slope_angle.abs() < 1.0e-3);
+
+        //100 m east and 30 m high, right of the centreline and above the path
+        let high_right = Geodetic { altitude: on_path.altitude + 30.0, ..destination(&before, 90.0_f64.to_radians(), 100.0) };
+        let d = deviations(&runway, &high_right);
+        assert!((d.localizer - 100.0).abs() < 0.5);
+        assert!((d.localizer_angle - 100.0_f64.atan2(7000.0).to_degrees()).abs() < 0.01);
+        assert!((d.glide_slope - 30.0).abs() < 0.5);
+        assert!(d.glide_slope_angle > 0.0);
+
+        //Over the threshold the path is at the threshold crossing height
+        let d = deviations(&runway, &Geodetic { altitude: 248.0 + 15.0, ..runway.threshold });
+        assert!(d.glide_slope.abs() < 1.0e-6 && d.height == 15.0);
+    }
+
+    #[test]
+    fn guidance_test()
+    {
+        let runway = runway();
+        let mut approach = Approach::new(runway, 35.0);
+        let velocity = Vector3::new(35.0, 0.0, 0.0);
+        let before = destination(&runway.threshold, 180.0_f64.to_radians(), 5000.0);
+        let path_altitude = 248.0 + 15.0 + 5000.0 * 3.0_f64.to_radians().tan();
+
+        //Nothing until engaged
+        assert_eq!(approach.guide(&before, &velocity, None), None);
+        approach.engage();
+
+        //On the path: the runway course and the glide path sink rate
+        let g = approach.guide(&Geodetic { altitude: path_altitude, ..before }, &velocity, None).unwrap();
+        assert_eq!(g.phase, ApproachPhase::Approach);
+        assert!(g.heading.min(360.0 - g.heading) < 1.0e-3);
+        assert!((g.vertical_speed + 35.0 * 3.0_f64.to_radians().tan()).abs() < 0.01);
+
+        //Right of the centreline it turns left, below the path it holds the altitude
+        let right = destination(&before, 90.0_f64.to_radians(), 300.0);
+        let g = approach.guide(&Geodetic { altitude: path_altitude - 50.0, ..right }, &velocity, None).unwrap();
+        assert!(g.heading > 330.0 && g.heading < 360.0);
+        assert_eq!(g.vertical_speed, 0.0);
+
+        //Close to the runway it flares, and the sink rate eases off toward the touchdown rate
+        let over = Geodetic { altitude: 248.0 + 3.0, ..runway.threshold };
+        let g = approach.guide(&over, &velocity, None).unwrap();
+        assert_eq!(g.phase, ApproachPhase::Flare);
+        assert!(g.vertical_speed < -0.3 && g.vertical_speed > -35.0 * 3.0_f64.to_radians().tan());
+
+        //Touchdown is reported once, then the rollout runs until the airplane stops
+        let landed = destination(&runway.threshold, 0.0, 400.0);
+        let g = approach.guide(&Geodetic { altitude: 248.0, ..landed }, &velocity, Some(-0.4)).unwrap();
+        assert_eq!(g.phase, ApproachPhase::Rollout);
+        let touchdown = approach.touchdown.unwrap();
+        assert!((touchdown.distance_from_threshold - 400.0).abs() < 1.0);
+        assert!(touchdown.centreline_deviation.abs() < 0.1);
+        assert_eq!(touchdown.vertical_speed, -0.4);
+        assert_eq!(touchdown.ground_speed, 35.0);
+
+        approach.guide(&Geodetic { altitude: 248.0, ..landed }, &Vector3::new(20.0, 0.0, 0.0), Some(0.0));
+        assert_eq!(approach.touchdown, Some(touchdown));
+        assert_eq!(approach.guide(&landed, &Vector3::new(0.5, 0.0, 0.0), Some(0.0)), None);
+        assert_eq!(approach.phase, ApproachPhase::Stopped);
+        assert!(!approach.is_engaged());
+    }
+}
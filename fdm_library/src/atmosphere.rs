@@ -0,0 +1,168 @@
+//To run unit tests for the atmosphere module from the command line:
+//cargo test --lib atmosphere
+
+//International Standard Atmosphere shared by both models: the troposphere with its temperature lapse rate
+//and the isothermal layer above the tropopause, up to 20 km. SI units: m, Pa, K and kg/m^3
+
+pub const SEA_LEVEL_PRESSURE: f64 = 101325.0; //Pa
+pub const SEA_LEVEL_TEMPERATURE: f64 = 288.15; //K
+pub const SEA_LEVEL_DENSITY: f64 = 1.225; //kg/m^3
+pub const GAS_CONSTANT: f64 = 287.053; //J/(kg K), dry air
+pub const LAPSE_RATE: f64 = 0.0065; //K/m, troposphere
+pub const TROPOPAUSE: f64 = 11000.0; //m
+pub const HEAT_CAPACITY_RATIO: f64 = 1.4; //dry air
+const GRAVITY: f64 = 9.80665; //m/s^2
+
+//Static air at an altitude
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Atmosphere
+{
+    pub pressure: f64, //Pa
+    pub temperature: f64, //K
+    pub density: f64, //kg/m^3
+}
+
+impl Atmosphere
+{
+    //Standard atmosphere at a geopotential altitude (m). Below sea level the troposphere is extended
+    pub fn standard(altitude: f64) -> Atmosphere
+    {
+        let altitude = altitude.min(20000.0);
+        let exponent = GRAVITY / (GAS_CONSTANT * LAPSE_RATE);
+
+        let (pressure, temperature) = if altitude <= TROPOPAUSE
+        {
+            let temperature = SEA_LEVEL_TEMPERATURE - LAPSE_RATE * altitude;
+            (SEA_LEVEL_PRESSURE * (temperature / SEA_LEVEL_TEMPERATURE).powf(exponent), temperature)
+        }
+        else
+        {
+            let temperature = SEA_LEVEL_TEMPERATURE - LAPSE_RATE * TROPOPAUSE;
+            let tropopause_pressure = SEA_LEVEL_PRESSURE * (temperature / SEA_LEVEL_TEMPERATURE).powf(exponent);
+            (tropopause_pressure * (-GRAVITY * (altitude - TROPOPAUSE) / (GAS_CONSTANT * temperature)).exp(), temperature)
+        };
+
+        Atmosphere { pressure, temperature, density: pressure / (GAS_CONSTANT * temperature) }
+    }
+
+    //Speed of sound (m/s)
+    pub fn speed_of_sound(&self) -> f64
+    {
+        (HEAT_CAPACITY_RATIO * GAS_CONSTANT * self.temperature).sqrt()
+    }
+}
+
+//Pressure altitude (m), the standard altitude of a static pressure (Pa), as a barometric altimeter set to 1013.25 hPa reads it
+pub fn pressure_altitude(pressure: f64) -> f64
+{
+    let exponent = GRAVITY / (GAS_CONSTANT * LAPSE_RATE);
+    let tropopause_temperature = SEA_LEVEL_TEMPERATURE - LAPSE_RATE * TROPOPAUSE;
+    let tropopause_pressure = SEA_LEVEL_PRESSURE * (tropopause_temperature / SEA_LEVEL_TEMPERATURE).powf(exponent);
+
+    if pressure >= tropopause_pressure
+    {
+        (SEA_LEVEL_TEMPERATURE / LAPSE_RATE) * (1.0 - (pressure / SEA_LEVEL_PRESSURE).powf(1.0 / exponent))
+    }
+    else
+    {
+        TROPOPAUSE + GAS_CONSTANT * tropopause_temperature / GRAVITY * (tropopause_pressure / pressure).ln()
+    }
+}
+
+//Altitude (m) a barometric altimeter reads at a static pressure (Pa) with its altimeter setting (Pa), the standard
+//troposphere's pressure scale starting from the setting. Set to 1013.25 hPa it reads the pressure altitude
+pub fn altimeter_altitude(pressure: f64, setting: f64) -> f64
+{
+    let exponent = GRAVITY / (GAS_CONSTANT * LAPSE_RATE);
+    (SEA_LEVEL_TEMPERATURE / LAPSE_RATE) * (1.0 - (pressure / setting).powf(1.0 / exponent))
+}
+
+//Density altitude (m), the standard altitude of an air density (kg/m^3)
+pub fn density_altitude(density: f64) -> f64
+{
+    let exponent = GRAVITY / (GAS_CONSTANT * LAPSE_RATE) - 1.0;
+    let sea_level = Atmosphere::standard(0.0);
+    let tropopause = Atmosphere::standard(TROPOPAUSE);
+
+    if density >= tropopause.density
+    {
+        (SEA_LEVEL_TEMPERATURE / LAPSE_RATE) * (1.0 - (density / sea_level.density).powf(1.0 / exponent))
+    }
+    else
+    {
+        TROPOPAUSE + GAS_CONSTANT * tropopause.temperature / GRAVITY * (tropopause.density / density).ln()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn standard_test()
+    {
+        //Sea level
+        let sea_level = Atmosphere::standard(0.0);
+        assert_eq!(sea_level.pressure, SEA_LEVEL_PRESSURE);
+        assert_eq!(sea_level.temperature, SEA_LEVEL_TEMPERATURE);
+        assert!((sea_level.density - SEA_LEVEL_DENSITY).abs() < 1e-4);
+
+        //Table values at 5 km, the tropopause and 15 km geopotential altitude
+        let table = [(5000.0, 54020.0, 255.65, 0.7361), (11000.0, 22632.0, 216.65, 0.3639), (15000.0, 12045.0, 216.65, 0.1937)];
+        for (altitude, pressure, temperature, density) in table
+        {
+            let air = Atmosphere::standard(altitude);
+            assert!((air.pressure - pressure).abs() < 5.0, "{:?}", air);
+            assert!((air.temperature - temperature).abs() < 1e-9);
+            assert!((air.density - density).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn speed_of_sound_test()
+    {
+        //340.3 m/s at sea level and 295.1 m/s in the isothermal layer
+        assert!((Atmosphere::standard(0.0).speed_of_sound() - 340.3).abs() < 0.05);
+        assert!((Atmosphere::standard(15000.0).speed_of_sound() - 295.1).abs() < 0.05);
+    }
+
+    #[test]
+    fn pressure_altitude_test()
+    {
+        //The inverse of the standard pressure in both layers
+        for altitude in [-200.0, 0.0, 1500.0, 11000.0, 15000.0]
+        {
+            assert!((pressure_altitude(Atmosphere::standard(altitude).pressure) - altitude).abs() < 1e-6, "{}", altitude);
+        }
+    }
+
+    #[test]
+    fn altimeter_altitude_test()
+    {
+        //Set to standard the altimeter reads the pressure altitude, and about 8.3 m more for each hPa the setting goes up
+        for altitude in [0.0, 1500.0, 5000.0]
+        {
+            let pressure = Atmosphere::standard(altitude).pressure;
+            assert!((altimeter_altitude(pressure, SEA_LEVEL_PRESSURE) - altitude).abs() < 1e-6);
+        }
+        let pressure = Atmosphere::standard(300.0).pressure;
+        let per_hpa = altimeter_altitude(pressure, SEA_LEVEL_PRESSURE + 100.0) - altimeter_altitude(pressure, SEA_LEVEL_PRESSURE);
+        assert!((per_hpa - 8.3).abs() < 0.2, "{}", per_hpa);
+    }
+
+    #[test]
+    fn density_altitude_test()
+    {
+        //The inverse of the standard density in both layers
+        for altitude in [0.0, 1500.0, 11000.0, 15000.0]
+        {
+            assert!((density_altitude(Atmosphere::standard(altitude).density) - altitude).abs() < 1e-6, "{}", altitude);
+        }
+
+        //20 degrees above standard at 1500 m the air is as thin as it is about 720 m higher up, 120 ft a degree
+        let standard = Atmosphere::standard(1500.0);
+        let hot = standard.pressure / (GAS_CONSTANT * (standard.temperature + 20.0));
+        assert!((density_altitude(hot) - 2220.0).abs() < 50.0, "{}", density_altitude(hot));
+    }
+}
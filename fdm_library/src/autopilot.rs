@@ -0,0 +1,202 @@
+//To run unit tests for the autopilot module from the command line:
+//cargo test --lib autopilot
+
+//Pieces of the autopilot shared by both models: the PID loop and the modes.
+//Each model has its own autopilot module with the cascaded loops, gains and Autopilot System.
+//Targets are in the units of the model (Bourg: ft and ft/s, Palmer: m and m/s), headings in degrees
+
+//Gains and output limits of one PID loop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PidGains
+{
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    pub output_min: f64,
+    pub output_max: f64,
+}
+
+impl PidGains
+{
+    pub fn new(kp: f64, ki: f64, kd: f64, output_min: f64, output_max: f64) -> PidGains
+    {
+        PidGains { kp, ki, kd, output_min, output_max }
+    }
+}
+
+//PID loop with the derivative taken on the measurement, so a setpoint change does not kick the output,
+//and anti-windup: the integrator stops when the output is saturated and the error would drive it further
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pid
+{
+    pub gains: PidGains,
+    pub integral: f64, //integral term, in output units
+    previous_measurement: Option<f64>,
+}
+
+impl Pid
+{
+    pub fn new(gains: PidGains) -> Pid
+    {
+        Pid { gains, integral: 0.0, previous_measurement: None }
+    }
+
+    //Forget the history, the next output starts from the integral term.
+    //A loop without integral action could never move away from it, so its integral term is zeroed instead
+    pub fn reset(&mut self, integral: f64)
+    {
+        self.integral = if self.gains.ki != 0.0 { integral.clamp(self.gains.output_min, self.gains.output_max) } else { 0.0 };
+        self.previous_measurement = None;
+    }
+
+    pub fn update(&mut self, setpoint: f64, measurement: f64, dt: f64) -> f64
+    {
+        let error = setpoint - measurement;
+
+        let derivative = match self.previous_measurement
+        {
+            Some(previous) if dt > 0.0 => -(measurement - previous) / dt,
+            _ => 0.0,
+        };
+        self.previous_measurement = Some(measurement);
+
+        let unsaturated = self.gains.kp * error + self.integral + self.gains.ki * error * dt + self.gains.kd * derivative;
+        let output = unsaturated.clamp(self.gains.output_min, self.gains.output_max);
+
+        //Only integrate while it helps to bring the output back inside its limits
+        let winding_up = (unsaturated > self.gains.output_max && error > 0.0) || (unsaturated < self.gains.output_min && error < 0.0);
+        if !winding_up
+        {
+            self.integral = (self.integral + self.gains.ki * error * dt).clamp(self.gains.output_min, self.gains.output_max);
+        }
+
+        output
+    }
+}
+
+//Pitch axis modes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum VerticalMode
+{
+    Off,
+    AltitudeHold(f64), //target altitude
+    VerticalSpeed(f64), //target climb rate, positive up
+}
+
+//Roll axis modes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LateralMode
+{
+    Off,
+    WingLeveler,
+    HeadingHold(f64), //target heading, degrees clockwise from north
+}
+
+//Thrust modes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpeedMode
+{
+    Off,
+    AirspeedHold(f64), //target airspeed
+}
+
+//Engaged modes, one per axis
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutopilotModes
+{
+    pub vertical: VerticalMode,
+    pub lateral: LateralMode,
+    pub speed: SpeedMode,
+    pub coupled: bool, //the approach coupler sets the targets, the vertical speed is then held on the approach loop
+}
+
+impl Default for AutopilotModes
+{
+    fn default() -> Self
+    {
+        AutopilotModes { vertical: VerticalMode::Off, lateral: LateralMode::Off, speed: SpeedMode::Off, coupled: false }
+    }
+}
+
+impl AutopilotModes
+{
+    pub fn is_engaged(&self) -> bool
+    {
+        *self != AutopilotModes::default()
+    }
+}
+
+//Difference between two headings in degrees, wrapped to -180..180
+pub fn heading_error(target: f64, heading: f64) -> f64
+{
+    let mut error = (target - heading) % 360.0;
+    if error > 180.0
+    {
+        error -= 360.0;
+    }
+    if error < -180.0
+    {
+        error += 360.0;
+    }
+    error
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn pid_test()
+    {
+        //Proportional and integral terms
+        let mut pid = Pid::new(PidGains::new(2.0, 1.0, 0.0, -10.0, 10.0));
+        assert_eq!(pid.update(1.0, 0.0, 0.5), 2.5);
+        assert_eq!(pid.integral, 0.5);
+
+        //Derivative on the measurement: a setpoint step gives no kick, a moving measurement is damped
+        let mut pid = Pid::new(PidGains::new(0.0, 0.0, 1.0, -10.0, 10.0));
+        assert_eq!(pid.update(0.0, 0.0, 0.1), 0.0);
+        assert_eq!(pid.update(5.0, 0.0, 0.1), 0.0);
+        assert!((pid.update(5.0, 0.1, 0.1) + 1.0).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn anti_windup_test()
+    {
+        //A large error held for a long time saturates the output without winding up the integrator
+        let mut pid = Pid::new(PidGains::new(1.0, 1.0, 0.0, -1.0, 1.0));
+        for _ in 0..1000
+        {
+            assert_eq!(pid.update(100.0, 0.0, 0.1), 1.0);
+        }
+        assert!(pid.integral <= 1.0);
+
+        //As soon as the error changes sign the output comes off the limit
+        assert!(pid.update(0.0, 2.0, 0.1) < 0.0);
+    }
+
+    #[test]
+    fn bumpless_reset_test()
+    {
+        //Resetting to the current control value gives the same output for zero error
+        let mut pid = Pid::new(PidGains::new(3.0, 0.5, 0.2, 0.0, 3000.0));
+        pid.reset(1200.0);
+        assert_eq!(pid.update(150.0, 150.0, 0.1), 1200.0);
+
+        let mut proportional = Pid::new(PidGains::new(3.0, 0.0, 0.0, -10.0, 10.0));
+        proportional.reset(5.0);
+        assert_eq!(proportional.update(1.0, 1.0, 0.1), 0.0);
+    }
+
+    #[test]
+    fn heading_error_test()
+    {
+        assert_eq!(heading_error(10.0, 350.0), 20.0);
+        assert_eq!(heading_error(350.0, 10.0), -20.0);
+        assert_eq!(heading_error(90.0, 45.0), 45.0);
+        assert_eq!(heading_error(-170.0, 170.0), 20.0);
+        assert!(!AutopilotModes::default().is_engaged());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod real;
 pub mod vector;
 pub mod matrix;
 pub mod quaternion;
 pub mod math_utils;
-pub mod constants;
\ No newline at end of file
+pub mod constants;
+pub mod interop;
\ No newline at end of file
@@ -1,7 +1,9 @@
+use crate::bourg::common::real::Float;
+
 // numerical constants
-pub static TOL: f32 = 0.000000000000001; // float type tolerance 
-pub static PI: f32 = 3.14159265359;
+pub static TOL: Float = 0.000000000000001; // float type tolerance 
+pub static PI: Float = 3.14159265359;
 
 // physical constants
-pub static G: f32 = -32.174; // acceleration due to gravity, ft/s^2
-pub static RHO: f32 = 0.0023769; // density of air at sea level, slugs/ft^3
\ No newline at end of file
+pub static G: Float = -32.174; // acceleration due to gravity, ft/s^2
+pub static RHO: Float = 0.0023769; // density of air at sea level, slugs/ft^3
\ No newline at end of file
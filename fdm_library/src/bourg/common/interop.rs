@@ -0,0 +1,118 @@
+//To run unit tests for the interop module from the command line:
+//cargo test --lib interop
+
+//Conversions between the Bourg math types and the nalgebra types used by the Palmer model,
+//coord_transforms and the navigation module. Both sides must have the same precision, use cast() first
+//to change it, for example Vector3::<f64>::from(v.cast::<f64>())
+
+use nalgebra as na;
+use na::RealField;
+
+use crate::bourg::common::vector::Vec3;
+use crate::bourg::common::matrix::Mat3;
+use crate::bourg::common::quaternion::Quat;
+use crate::bourg::common::real::Real;
+
+//Vector <---> Vector3
+impl<T: Real + na::Scalar> From<Vec3<T>> for na::Vector3<T>
+{
+    fn from(v: Vec3<T>) -> Self
+    {
+        na::Vector3::new(v.x, v.y, v.z)
+    }
+}
+
+impl<T: Real + na::Scalar> From<na::Vector3<T>> for Vec3<T>
+{
+    fn from(v: na::Vector3<T>) -> Self
+    {
+        Vec3::new(v.x, v.y, v.z)
+    }
+}
+
+//Matrix <---> Matrix3
+impl<T: Real + na::Scalar> From<Mat3<T>> for na::Matrix3<T>
+{
+    fn from(m: Mat3<T>) -> Self
+    {
+        na::Matrix3::new(m.e11, m.e12, m.e13,
+                         m.e21, m.e22, m.e23,
+                         m.e31, m.e32, m.e33)
+    }
+}
+
+impl<T: Real + na::Scalar> From<na::Matrix3<T>> for Mat3<T>
+{
+    fn from(m: na::Matrix3<T>) -> Self
+    {
+        Mat3::new(m[(0, 0)], m[(0, 1)], m[(0, 2)],
+                  m[(1, 0)], m[(1, 1)], m[(1, 2)],
+                  m[(2, 0)], m[(2, 1)], m[(2, 2)])
+    }
+}
+
+//Quaternion <---> UnitQuaternion. nalgebra normalizes the quaternion on the way in
+impl<T: Real + RealField> From<Quat<T>> for na::UnitQuaternion<T>
+{
+    fn from(q: Quat<T>) -> Self
+    {
+        na::UnitQuaternion::from_quaternion(na::Quaternion::new(q.n, q.v.x, q.v.y, q.v.z))
+    }
+}
+
+impl<T: Real + RealField> From<na::UnitQuaternion<T>> for Quat<T>
+{
+    fn from(q: na::UnitQuaternion<T>) -> Self
+    {
+        Quat::new(q.w, q.i, q.j, q.k)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bourg::common::vector::Vector64;
+    use crate::bourg::common::matrix::Matrix64;
+    use crate::bourg::common::quaternion::Quaternion64;
+
+    #[test]
+    fn vector_round_trip_test()
+    {
+        let v = Vector64::new(1.0, -2.0, 3.5);
+        let n: na::Vector3<f64> = v.into();
+        assert_eq!(n, na::Vector3::new(1.0, -2.0, 3.5));
+        assert_eq!(Vector64::from(n), v);
+    }
+
+    #[test]
+    fn matrix_round_trip_test()
+    {
+        let m = Matrix64::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 10.0);
+        let n: na::Matrix3<f64> = m.into();
+        assert_eq!(n[(0, 1)], 2.0);
+        assert_eq!(n[(2, 2)], 10.0);
+        assert_eq!(Matrix64::from(n), m);
+
+        //Products agree
+        let v = Vector64::new(0.5, -1.0, 2.0);
+        assert_eq!(Vector64::from(n * na::Vector3::from(v)), m * v);
+    }
+
+    #[test]
+    fn quaternion_rotation_test()
+    {
+        //Both types rotate vectors the same way
+        let q = Quaternion64::from_euler_zyx(0.3, -0.2, 1.2);
+        let u: na::UnitQuaternion<f64> = q.into();
+        let v = Vector64::new(1.0, 2.0, 3.0);
+
+        let a = Quaternion64::qvrotate(&q, &v);
+        let b = Vector64::from(u * na::Vector3::from(v));
+        assert!((a - b).magnitude() < 1.0e-12);
+
+        let back = Quaternion64::from(u);
+        assert!((back.n - q.n).abs() < 1.0e-15 && (back.v - q.v).magnitude() < 1.0e-15);
+    }
+}
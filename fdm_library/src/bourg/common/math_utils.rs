@@ -1,13 +1,12 @@
-
 //Helper functions for degrees <---> radians conversion (Bourg's implementation)
-pub fn deg_to_rad(deg: f32) -> f32
+use crate::bourg::common::real::Real;
+
+pub fn deg_to_rad<T: Real>(deg: T) -> T
 {
-    let pi: f32 = 3.14159265359;
-	deg * pi / 180.0
+	deg * T::BOURG_PI / T::from_f64(180.0)
 }
 
-pub fn rad_to_deg(rad: f32) -> f32
+pub fn rad_to_deg<T: Real>(rad: T) -> T
 {	
-    let pi: f32 = 3.14159265359;
-	rad * 180.0 / pi
+	rad * T::from_f64(180.0) / T::BOURG_PI
 }
\ No newline at end of file
@@ -1,29 +1,35 @@
 //To run the unit tests for the Matrix module from the command line: 
 //cargo test --lib matrix
 
-use crate::bourg::common::vector::Vector;
+use crate::bourg::common::vector::Vec3;
+use crate::bourg::common::real::{Real, Float};
 
 //Operator overloads
-use std::ops::{Mul};
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign, Index, IndexMut};
 
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
-pub struct Matrix
+pub struct Mat3<T>
 {
     // elements eij: i -> row, j -> column
-    pub e11: f32, pub e12: f32, pub e13: f32,
-    pub e21: f32, pub e22: f32, pub e23: f32,
-    pub e31: f32, pub e32: f32, pub e33: f32,
+    pub e11: T, pub e12: T, pub e13: T,
+    pub e21: T, pub e22: T, pub e23: T,
+    pub e31: T, pub e32: T, pub e33: T,
 }
 
+//Matrix in the precision of the Bourg model, and fixed precision versions
+pub type Matrix = Mat3<Float>;
+pub type Matrix32 = Mat3<f32>;
+pub type Matrix64 = Mat3<f64>;
+
 
 //Multiplication by vector
-impl Mul<Vector> for Matrix
+impl<T: Real> Mul<Vec3<T>> for Mat3<T>
 {
-    type Output = Vector;
+    type Output = Vec3<T>;
 
-    fn mul(self, u: Vector) -> Vector 
+    fn mul(self, u: Vec3<T>) -> Vec3<T> 
     {
-        let vector = Vector { 
+        let vector = Vec3 { 
             x: self.e11*u.x + self.e12*u.y + self.e13*u.z,
             y: self.e21*u.x + self.e22*u.y + self.e23*u.z,
             z: self.e31*u.x + self.e32*u.y + self.e33*u.z,
@@ -34,15 +40,140 @@ impl Mul<Vector> for Matrix
     }
 }
 
+//Matrix multiplication
+impl<T: Real> Mul for Mat3<T>
+{
+    type Output = Self;
+
+    fn mul(self, m: Self) -> Self 
+    {
+        Mat3 {
+            e11: self.e11*m.e11 + self.e12*m.e21 + self.e13*m.e31,
+            e12: self.e11*m.e12 + self.e12*m.e22 + self.e13*m.e32,
+            e13: self.e11*m.e13 + self.e12*m.e23 + self.e13*m.e33,
+
+            e21: self.e21*m.e11 + self.e22*m.e21 + self.e23*m.e31,
+            e22: self.e21*m.e12 + self.e22*m.e22 + self.e23*m.e32,
+            e23: self.e21*m.e13 + self.e22*m.e23 + self.e23*m.e33,
+
+            e31: self.e31*m.e11 + self.e32*m.e21 + self.e33*m.e31,
+            e32: self.e31*m.e12 + self.e32*m.e22 + self.e33*m.e32,
+            e33: self.e31*m.e13 + self.e32*m.e23 + self.e33*m.e33,
+        }
+    }
+}
+
+//Multiplication by scalar
+impl<T: Real> Mul<T> for Mat3<T>
+{
+    type Output = Self;
+
+    fn mul(self, s: T) -> Self 
+    {
+        self.map(|e| e * s)
+    }
+}
+
+//Division by scalar
+impl<T: Real> Div<T> for Mat3<T>
+{
+    type Output = Self;
+
+    fn div(self, s: T) -> Self 
+    {
+        self.map(|e| e / s)
+    }
+}
+
+//Matrix addition
+impl<T: Real> Add for Mat3<T>
+{
+    type Output = Self;
+
+    fn add(self, m: Self) -> Self 
+    {
+        self.zip(&m, |a, b| a + b)
+    }
+}
 
-impl Matrix
+//Matrix subtraction
+impl<T: Real> Sub for Mat3<T>
 {
+    type Output = Self;
 
-    pub fn new( r1c1: f32,  r1c2: f32,  r1c3: f32, 
-                r2c1: f32,  r2c2: f32,  r2c3: f32, 
-                r3c1: f32,  r3c2: f32,  r3c3: f32) -> Matrix
+    fn sub(self, m: Self) -> Self 
     {
-        Matrix { 
+        self.zip(&m, |a, b| a - b)
+    }
+}
+
+//Reverse
+impl<T: Real> Neg for Mat3<T>
+{
+    type Output = Self;
+
+    fn neg(self) -> Self 
+    {
+        self.map(|e| -e)
+    }
+}
+
+impl<T: Real> AddAssign for Mat3<T>
+{
+    fn add_assign(&mut self, m: Self) 
+    {
+        *self = *self + m;
+    }
+}
+
+impl<T: Real> SubAssign for Mat3<T>
+{
+    fn sub_assign(&mut self, m: Self) 
+    {
+        *self = *self - m;
+    }
+}
+
+//Element access by (row, column), both starting at 0
+impl<T: Real> Index<(usize, usize)> for Mat3<T>
+{
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T 
+    {
+        match (row, col)
+        {
+            (0, 0) => &self.e11, (0, 1) => &self.e12, (0, 2) => &self.e13,
+            (1, 0) => &self.e21, (1, 1) => &self.e22, (1, 2) => &self.e23,
+            (2, 0) => &self.e31, (2, 1) => &self.e32, (2, 2) => &self.e33,
+            _ => panic!("Matrix index out of range: ({}, {})", row, col),
+        }
+    }
+}
+
+impl<T: Real> IndexMut<(usize, usize)> for Mat3<T>
+{
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T 
+    {
+        match (row, col)
+        {
+            (0, 0) => &mut self.e11, (0, 1) => &mut self.e12, (0, 2) => &mut self.e13,
+            (1, 0) => &mut self.e21, (1, 1) => &mut self.e22, (1, 2) => &mut self.e23,
+            (2, 0) => &mut self.e31, (2, 1) => &mut self.e32, (2, 2) => &mut self.e33,
+            _ => panic!("Matrix index out of range: ({}, {})", row, col),
+        }
+    }
+}
+
+
+impl<T: Real> Mat3<T>
+{
+
+    pub fn new( r1c1: T,  r1c2: T,  r1c3: T, 
+                r2c1: T,  r2c2: T,  r2c3: T, 
+                r3c1: T,  r3c2: T,  r3c3: T) -> Mat3<T>
+    {
+        Mat3 { 
             e11: r1c1,
             e12: r1c2,
             e13: r1c3,
@@ -56,21 +187,147 @@ impl Matrix
 
     }
 
-    pub fn inverse(&self) -> Matrix
+    pub fn identity() -> Mat3<T>
+    {
+        Mat3::diagonal(&Vec3::new(T::ONE, T::ONE, T::ONE))
+    }
+
+    pub fn diagonal(d: &Vec3<T>) -> Mat3<T>
+    {
+        Mat3::new(d.x, T::ZERO, T::ZERO,
+                  T::ZERO, d.y, T::ZERO,
+                  T::ZERO, T::ZERO, d.z)
+    }
+
+    //Build from three row or column vectors
+    pub fn from_rows(r1: &Vec3<T>, r2: &Vec3<T>, r3: &Vec3<T>) -> Mat3<T>
+    {
+        Mat3::new(r1.x, r1.y, r1.z,
+                  r2.x, r2.y, r2.z,
+                  r3.x, r3.y, r3.z)
+    }
+
+    pub fn from_columns(c1: &Vec3<T>, c2: &Vec3<T>, c3: &Vec3<T>) -> Mat3<T>
+    {
+        Mat3::from_rows(c1, c2, c3).transpose()
+    }
+
+    //Rotation matrices about the x, y and z axes, angle in radians (right hand rule)
+    pub fn rotation_x(angle: T) -> Mat3<T>
+    {
+        let (s, c) = (angle.sin(), angle.cos());
+        Mat3::new(T::ONE, T::ZERO, T::ZERO,
+                  T::ZERO, c, -s,
+                  T::ZERO, s, c)
+    }
+
+    pub fn rotation_y(angle: T) -> Mat3<T>
+    {
+        let (s, c) = (angle.sin(), angle.cos());
+        Mat3::new(c, T::ZERO, s,
+                  T::ZERO, T::ONE, T::ZERO,
+                  -s, T::ZERO, c)
+    }
+
+    pub fn rotation_z(angle: T) -> Mat3<T>
     {
-        let mut d: f32 = self.e11*self.e22*self.e33 -
+        let (s, c) = (angle.sin(), angle.cos());
+        Mat3::new(c, -s, T::ZERO,
+                  s, c, T::ZERO,
+                  T::ZERO, T::ZERO, T::ONE)
+    }
+
+    //Rotation from body to earth space for roll, pitch and yaw in radians, applied in yaw-pitch-roll order
+    pub fn rotation_from_euler(roll: T, pitch: T, yaw: T) -> Mat3<T>
+    {
+        Mat3::rotation_z(yaw) * Mat3::rotation_y(pitch) * Mat3::rotation_x(roll)
+    }
+
+    //Inertia tensor of a point mass at position r, used with the parallel axis theorem
+    pub fn point_mass_inertia(mass: T, r: &Vec3<T>) -> Mat3<T>
+    {
+        let ixy = mass * (r.x * r.y);
+        let ixz = mass * (r.x * r.z);
+        let iyz = mass * (r.y * r.z);
+        Mat3::new(mass * (r.y * r.y + r.z * r.z), -ixy, -ixz,
+                  -ixy, mass * (r.z * r.z + r.x * r.x), -iyz,
+                  -ixz, -iyz, mass * (r.x * r.x + r.y * r.y))
+    }
+
+    pub fn transpose(&self) -> Mat3<T>
+    {
+        Mat3::new(self.e11, self.e21, self.e31,
+                  self.e12, self.e22, self.e32,
+                  self.e13, self.e23, self.e33)
+    }
+
+    pub fn determinant(&self) -> T
+    {
+        self.e11*self.e22*self.e33 -
+        self.e11*self.e32*self.e23 +
+        self.e21*self.e32*self.e13 -
+        self.e21*self.e12*self.e33 +
+        self.e31*self.e12*self.e23 -
+        self.e31*self.e22*self.e13
+    }
+
+    //Rows and columns as vectors, index starting at 0
+    pub fn row(&self, i: usize) -> Vec3<T>
+    {
+        Vec3::new(self[(i, 0)], self[(i, 1)], self[(i, 2)])
+    }
+
+    pub fn column(&self, j: usize) -> Vec3<T>
+    {
+        Vec3::new(self[(0, j)], self[(1, j)], self[(2, j)])
+    }
+
+    //Inverse that returns None for a singular matrix instead of dividing by 1. The determinant is checked against
+    //the rounding error it carries at the size of the matrix, so a matrix singular to the precision of T is singular
+    pub fn try_inverse(&self) -> Option<Mat3<T>>
+    {
+        let norm = (self.row(0) * self.row(0) + self.row(1) * self.row(1) + self.row(2) * self.row(2)).sqrt();
+        if self.determinant().abs() <= T::EPSILON * norm * norm * norm
+        {
+            None
+        }
+        else
+        {
+            Some(self.inverse())
+        }
+    }
+
+    //Apply a function to every element
+    fn map<F: Fn(T) -> T>(&self, f: F) -> Mat3<T>
+    {
+        Mat3::new(f(self.e11), f(self.e12), f(self.e13),
+                  f(self.e21), f(self.e22), f(self.e23),
+                  f(self.e31), f(self.e32), f(self.e33))
+    }
+
+    //Combine the elements of two matrices
+    fn zip<F: Fn(T, T) -> T>(&self, m: &Mat3<T>, f: F) -> Mat3<T>
+    {
+        Mat3::new(f(self.e11, m.e11), f(self.e12, m.e12), f(self.e13, m.e13),
+                  f(self.e21, m.e21), f(self.e22, m.e22), f(self.e23, m.e23),
+                  f(self.e31, m.e31), f(self.e32, m.e32), f(self.e33, m.e33))
+    }
+
+    pub fn inverse(&self) -> Mat3<T>
+    {
+        let mut d: T = self.e11*self.e22*self.e33 -
                     self.e11*self.e32*self.e23 +
                     self.e21*self.e32*self.e13 -
                     self.e21*self.e12*self.e33 +
                     self.e31*self.e12*self.e23 -
                     self.e31*self.e22*self.e13;
     
-        if d == 0.0 
+        if d == T::ZERO 
         {
-                d = 1.0;
+                d = T::ONE;
         }
     
-        let matrix = Matrix
+        let matrix = Mat3
         {
 
             e11: (self.e22*self.e33-self.e23*self.e32)/d,
@@ -87,6 +344,15 @@ impl Matrix
         return matrix;           
     }
 
+    //Convert to another precision
+    pub fn cast<U: Real>(&self) -> Mat3<U>
+    {
+        let c = |e: T| U::from_f64(e.to_f64());
+        Mat3::new(c(self.e11), c(self.e12), c(self.e13),
+                  c(self.e21), c(self.e22), c(self.e23),
+                  c(self.e31), c(self.e32), c(self.e33))
+    }
+
 }
 
 #[cfg(test)]
@@ -95,6 +361,10 @@ mod tests
 
     use super::*;
 
+    //These results are exact for single precision
+    type Matrix = Mat3<f32>;
+    type Vector = Vec3<f32>;
+
     //MATRIX TESTS
     #[test]
     fn m_inverse_test1()
@@ -113,13 +383,13 @@ mod tests
     #[test]
     fn m_inverse_test2()
     { 
-        let m = Matrix::new(2549.629150390625, -0.0, 166.91925048828125, 
-                            -0.0, 2024.4990234375, -0.0, 
-                            166.91925048828125, -0.0, 4414.73388671875).inverse();
+        let m = Matrix::new(2549.6292, -0.0, 166.91925, 
+                            -0.0, 2024.499, -0.0, 
+                            166.91925, -0.0, 4414.734).inverse();
 
-        let equal = Matrix::new(0.0003931871615350246429443359375, -0.0, -1.48662438732571899890899658203125e-05,
-                                -0.0, 0.0004939493373967707157135009765625, -0.0,
-                                -1.48662438732571899890899658203125e-05, -0.0, 0.0002270762925036251544952392578125);
+        let equal = Matrix::new(0.00039318716, -0.0, -1.4866244e-5,
+                                -0.0, 0.00049394934, -0.0,
+                                -1.4866244e-5, -0.0, 0.00022707629);
         assert_eq!(m, equal);
     }
 
@@ -138,15 +408,124 @@ mod tests
     #[test]
     fn m_multiply_by_vec_test2()
     {  
-        let m = Matrix::new(2549.629150390625, -0.0, 166.91925048828125, 
-                            -0.0, 2024.4990234375, -0.0, 
-                            166.91925048828125, -0.0, 4414.73388671875);
+        let m = Matrix::new(2549.6292, -0.0, 166.91925, 
+                            -0.0, 2024.499, -0.0, 
+                            166.91925, -0.0, 4414.734);
 
-        let v = m * Vector::new(0.000029893242754042148590087890625, 0.063622482120990753173828125, -0.000000184518285095691680908203125);
+        let v = m * Vector::new(0.000029893243, 0.06362248, -0.00000018451829);
 
-        let equal = Vector::new(0.076185882091522216796875, 128.80364990234375, 0.00417515868321061134338379);
+        let equal = Vector::new(0.07618588, 128.80365, 0.0041751587);
         assert_eq!(v, equal);
 
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn m_inverse_double_precision_test1()
+    {
+        let m = Matrix64::new(2549.629150390625, -0.0, 166.91925048828125, 
+                              -0.0, 2024.4990234375, -0.0, 
+                              166.91925048828125, -0.0, 4414.73388671875);
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        let u = m.inverse() * (m * v);
+
+        assert!((u - v).magnitude() < 1.0e-12);
+    }
+
+    #[test]
+    fn m_multiply_by_matrix_test1()
+    {
+        let m = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        let n = Matrix::new(9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0);
+
+        let equal = Matrix::new(30.0, 24.0, 18.0,
+                                84.0, 69.0, 54.0,
+                                138.0, 114.0, 90.0);
+        assert_eq!(m * n, equal);
+        assert_eq!(m * Matrix::identity(), m);
+    }
+
+    #[test]
+    fn m_transpose_determinant_test1()
+    {
+        let m = Matrix::new(2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0);
+
+        assert_eq!(m.transpose(), Matrix::new(2.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0));
+        assert_eq!(m.determinant(), 1.0);
+        assert_eq!(m.transpose().determinant(), m.determinant());
+        assert_eq!(m.row(1), Vector::new(1.0, 1.0, 1.0));
+        assert_eq!(m.column(0), Vector::new(2.0, 1.0, 0.0));
+        assert_eq!(Matrix::from_columns(&m.column(0), &m.column(1), &m.column(2)), m);
+    }
+
+    #[test]
+    fn m_add_subtract_test1()
+    {
+        let m = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        let mut n = m + Matrix::identity();
+        assert_eq!(n, Matrix::new(2.0, 2.0, 3.0, 4.0, 6.0, 6.0, 7.0, 8.0, 10.0));
+
+        n -= m;
+        assert_eq!(n, Matrix::identity());
+        assert_eq!(m * 2.0, m + m);
+        assert_eq!((m * 2.0) / 2.0, m);
+        assert_eq!(-m + m, Matrix::default());
+    }
+
+    #[test]
+    fn m_index_test1()
+    {
+        let mut m = Matrix::identity();
+        m[(0, 2)] = 4.0;
+        assert_eq!(m.e13, 4.0);
+        assert_eq!(m[(1, 1)], 1.0);
+        assert_eq!(m[(2, 0)], 0.0);
+    }
+
+    #[test]
+    fn m_try_inverse_test1()
+    {
+        let singular = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        assert_eq!(singular.try_inverse(), None);
+
+        let m = Matrix::new(2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0);
+        assert_eq!(m.try_inverse(), Some(m.inverse()));
+        assert_eq!(m * m.inverse(), Matrix::identity());
+    }
+
+    #[test]
+    fn m_try_inverse_test2()
+    {
+        //A point mass has no inertia about the line to it, in single precision rounding leaves its inertia matrix
+        //a determinant far from zero, which is still singular at the size of its elements
+        let point = Matrix::point_mass_inertia(150.0, &Vector::new(1.7, -2.3, 0.9));
+        assert!(point.determinant().abs() > 1.0);
+        assert_eq!(point.try_inverse(), None);
+
+        //With the inertia of the rest of an airplane on top it has an inverse
+        let airplane = point + Matrix::diagonal(&Vector::new(1285.0, 1825.0, 2667.0));
+        let product = airplane.try_inverse().unwrap() * airplane;
+        for i in 0..3
+        {
+            for j in 0..3
+            {
+                assert!((product[(i, j)] - Matrix::identity()[(i, j)]).abs() < 1e-5, "{:?}", product);
+            }
+        }
+    }
+
+    #[test]
+    fn m_rotation_test1()
+    {
+        //90 degrees about z takes x onto y
+        let r = Matrix64::rotation_z(std::f64::consts::FRAC_PI_2);
+        let v = r * Vec3::new(1.0, 0.0, 0.0);
+        assert!((v - Vec3::new(0.0, 1.0, 0.0)).magnitude() < 1.0e-15);
+
+        //Rotation matrices are orthogonal with determinant 1
+        let r = Matrix64::rotation_from_euler(0.3, -0.2, 1.1);
+        assert!((r.determinant() - 1.0).abs() < 1.0e-12);
+        let e = r * r.transpose() - Matrix64::identity();
+        assert!(e.e11.abs() + e.e12.abs() + e.e13.abs() + e.e22.abs() + e.e23.abs() + e.e33.abs() < 1.0e-12);
+    }
+
+}
@@ -1,33 +1,39 @@
 //To run unit tests for the Quaternion module from the command line: 
 //cargo test --lib quaternion
 
-use crate::bourg::common::vector::Vector;
+use crate::bourg::common::vector::Vec3;
+use crate::bourg::common::matrix::Mat3;
+use crate::bourg::common::real::{Real, Float};
 use crate::bourg::common::math_utils::rad_to_deg;
 use crate::bourg::common::math_utils::deg_to_rad;
-use crate::bourg::common::constants::PI;
 
 //Operator overloads
 use std::ops::{Add, Sub, Div, Mul};
 
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
-pub struct Quaternion
+pub struct Quat<T>
 {
-    pub n: f32, //scalar part
-    pub v: Vector, //vector part v.x, v.y, v.z
+    pub n: T, //scalar part
+    pub v: Vec3<T>, //vector part v.x, v.y, v.z
 }
 
+//Quaternion in the precision of the Bourg model, and fixed precision versions
+pub type Quaternion = Quat<Float>;
+pub type Quaternion32 = Quat<f32>;
+pub type Quaternion64 = Quat<f64>;
+
 
 //Quaternion addition
-impl Add for Quaternion
+impl<T: Real> Add for Quat<T>
 {
     type Output = Self;
 
     fn add(self, q2: Self) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n: self.n + q2.n,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.v.x + q2.v.x,
                 y: self.v.y + q2.v.y,
                 z: self.v.z + q2.v.z,
@@ -39,16 +45,16 @@ impl Add for Quaternion
 }
 
 //Quaternion subtraction
-impl Sub for Quaternion
+impl<T: Real> Sub for Quat<T>
 {
     type Output = Self;
 
     fn sub(self, q2: Self) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n: self.n + q2.n,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.v.x - q2.v.x,
                 y: self.v.y - q2.v.y,
                 z: self.v.z - q2.v.z,
@@ -61,16 +67,16 @@ impl Sub for Quaternion
 
 
 //Quaternion multiplcation
-impl Mul for Quaternion
+impl<T: Real> Mul for Quat<T>
 {
     type Output = Self;
 
     fn mul(self, q2: Self) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n:  self.n*q2.n - self.v.x*q2.v.x - self.v.y*q2.v.y - self.v.z*q2.v.z,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.n*q2.v.x + self.v.x*q2.n + self.v.y*q2.v.z - self.v.z*q2.v.y,
                 y: self.n*q2.v.y + self.v.y*q2.n + self.v.z*q2.v.x - self.v.x*q2.v.z,
                 z: self.n*q2.v.z + self.v.z*q2.n + self.v.x*q2.v.y - self.v.y*q2.v.x,
@@ -82,16 +88,16 @@ impl Mul for Quaternion
 }
 
 //Quaternion multiplcation by scalar
-impl Mul<f32> for Quaternion
+impl<T: Real> Mul<T> for Quat<T>
 {
     type Output = Self;
 
-    fn mul(self, scalar: f32) -> Self 
+    fn mul(self, scalar: T) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n: self.n * scalar,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.v.x * scalar,
                 y: self.v.y * scalar,
                 z: self.v.z * scalar,
@@ -104,16 +110,16 @@ impl Mul<f32> for Quaternion
 
 
 //Quaternion division by scalar
-impl Div<f32> for Quaternion
+impl<T: Real> Div<T> for Quat<T>
 {
     type Output = Self;
 
-    fn div(self, scalar: f32) -> Self 
+    fn div(self, scalar: T) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n: self.n / scalar,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.v.x / scalar,
                 y: self.v.y / scalar,
                 z: self.v.z / scalar,
@@ -125,16 +131,16 @@ impl Div<f32> for Quaternion
 }
 
 //Quaternion multiplcation by Vector
-impl Mul<Vector> for Quaternion
+impl<T: Real> Mul<Vec3<T>> for Quat<T>
 {
     type Output = Self;
 
-    fn mul(self, v: Vector) -> Self 
+    fn mul(self, v: Vec3<T>) -> Self 
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n:  -(self.v.x*v.x + self.v.y*v.y + self.v.z*v.z),
             
-            v:  Vector {
+            v:  Vec3 {
                 x: self.n*v.x + self.v.y*v.z - self.v.z*v.y,
                 y: self.n*v.y + self.v.z*v.x - self.v.x*v.z,
                 z: self.n*v.z + self.v.x*v.y - self.v.y*v.x,
@@ -148,32 +154,32 @@ impl Mul<Vector> for Quaternion
 
 
 
-impl Quaternion
+impl<T: Real> Quat<T>
 {
-    pub fn new(n: f32, x: f32, y: f32, z: f32) -> Quaternion
+    pub fn new(n: T, x: T, y: T, z: T) -> Quat<T>
     {
-        Quaternion { 
+        Quat { 
             
             n: n,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: x,
                 y: y,
                 z: z,
             } }
     }
 
-    pub fn magnitude(&self) -> f32
+    pub fn magnitude(&self) -> T
     {
         return (self.n * self.n + self.v.x * self.v.x + self.v.y * self.v.y + self.v.z * self.v.z).sqrt();
     }
 
-    pub fn conjugate(q: &Quaternion) -> Quaternion
+    pub fn conjugate(q: &Quat<T>) -> Quat<T>
     {
-        let quat = Quaternion { 
+        let quat = Quat { 
             n: q.n,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: -q.v.x, 
                 y: -q.v.y,
                 z: -q.v.z,
@@ -183,15 +189,15 @@ impl Quaternion
     }
 
     
-    pub fn qvrotate(q: &Quaternion, v: &Vector) -> Vector
+    pub fn qvrotate(q: &Quat<T>, v: &Vec3<T>) -> Vec3<T>
     {
 
-        let q2 = Quaternion::conjugate(&q);
+        let q2 = Quat::conjugate(&q);
 
-        let q1 = Quaternion { //multiply quaternion by vector
+        let q1 = Quat { //multiply quaternion by vector
             n:  -(q.v.x*v.x + q.v.y*v.y + q.v.z*v.z),
             
-            v:  Vector {
+            v:  Vec3 {
                 x: q.n*v.x + q.v.y*v.z - q.v.z*v.y,
                 y: q.n*v.y + q.v.z*v.x - q.v.x*v.z,
                 z: q.n*v.z + q.v.x*v.y - q.v.y*v.x,
@@ -199,17 +205,17 @@ impl Quaternion
         };   
 
 
-        let t2 = Quaternion { //multiply quaternions
+        let t2 = Quat { //multiply quaternions
             n:  q1.n*q2.n - q1.v.x*q2.v.x - q1.v.y*q2.v.y - q1.v.z*q2.v.z,
             
-            v:  Vector {
+            v:  Vec3 {
                 x: q1.n*q2.v.x + q1.v.x*q2.n + q1.v.y*q2.v.z - q1.v.z*q2.v.y,
                 y: q1.n*q2.v.y + q1.v.y*q2.n + q1.v.z*q2.v.x - q1.v.x*q2.v.z,
                 z: q1.n*q2.v.z + q1.v.z*q2.n + q1.v.x*q2.v.y - q1.v.y*q2.v.x,
             }
         };   
 
-        let vector = Vector { 
+        let vector = Vec3 { 
             x: t2.v.x,
             y: t2.v.y,
             z: t2.v.z,
@@ -218,45 +224,50 @@ impl Quaternion
         return vector;
     }
 
-    pub fn make_euler_from_q(q: &Quaternion) -> Vector
+    //Euler angles in degrees for the rotation sequence yaw (z), pitch (y), roll (x) about the model's own axes,
+    //x forward, y left, z up. A positive pitch about the left axis is nose down and yaw is measured from the x axis.
+    //Use to_euler_zyx on an NED orientation (bourg::fdm::frames::attitude_ned) for aerospace angles
+    pub fn make_euler_from_q(q: &Quat<T>) -> Vec3<T>
     {
-        let q00: f64 = (q.n * q.n) as f64;
-        let q11: f64 = (q.v.x * q.v.x) as f64;
-        let q22: f64 = (q.v.y * q.v.y) as f64;
-        let q33: f64 = (q.v.z * q.v.z) as f64;
+        let q00: f64 = (q.n * q.n).to_f64();
+        let q11: f64 = (q.v.x * q.v.x).to_f64();
+        let q22: f64 = (q.v.y * q.v.y).to_f64();
+        let q33: f64 = (q.v.z * q.v.z).to_f64();
+        let two = T::from_f64(2.0);
         
         let r11: f64 = q00 + q11 - q22 - q33;
-        let r21: f64 = (2.0 * (q.v.x*q.v.y + q.n*q.v.z) ) as f64;
-        let r31: f64 = (2.0 * (q.v.x*q.v.z - q.n*q.v.y) ) as f64;
-        let r32: f64 = (2.0 * (q.v.y*q.v.z + q.n*q.v.x) ) as f64;
+        let r21: f64 = (two * (q.v.x*q.v.y + q.n*q.v.z) ).to_f64();
+        let r31: f64 = (two * (q.v.x*q.v.z - q.n*q.v.y) ).to_f64();
+        let r32: f64 = (two * (q.v.y*q.v.z + q.n*q.v.x) ).to_f64();
         let r33: f64 = q00 - q11 - q22 + q33;
         
-        let mut u: Vector = Default::default();
+        let mut u: Vec3<T> = Default::default();
         let tmp: f64 = r31.abs();
         if tmp > 0.999999
         {
-            let r12: f64 = (2.0 * (q.v.x*q.v.y - q.n*q.v.z) ) as f64;
-            let r13: f64 = (2.0 * (q.v.x*q.v.z + q.n*q.v.y) ) as f64;
+            let r12: f64 = (two * (q.v.x*q.v.y - q.n*q.v.z) ).to_f64();
+            let r13: f64 = (two * (q.v.x*q.v.z + q.n*q.v.y) ).to_f64();
         
-            u.x = rad_to_deg(0.0_f32); //roll
-            u.y = rad_to_deg ((-(PI as f64/2.0) * r31/tmp) as f32); //pitch
-            u.z = rad_to_deg(-r12.atan2(-r31*r13) as f32) ; //yaw
+            u.x = rad_to_deg(T::ZERO); //roll
+            u.y = rad_to_deg(T::from_f64(-(T::BOURG_PI.to_f64()/2.0) * r31/tmp)); //pitch
+            u.z = rad_to_deg(T::from_f64(-r12.atan2(-r31*r13))) ; //yaw
             return u;
         }
         
-        u.x = rad_to_deg( (r32.atan2(r33)) as f32); //roll
-        u.y = rad_to_deg( (-r31.asin()) as f32) ; //pitch
-        u.z = rad_to_deg( (r21.atan2(r11)) as f32 ); //yaw
+        u.x = rad_to_deg(T::from_f64(r32.atan2(r33))); //roll
+        u.y = rad_to_deg(T::from_f64(-r31.asin())) ; //pitch
+        u.z = rad_to_deg(T::from_f64(r21.atan2(r11))); //yaw
         return u;
 
     }
 
-    pub fn make_q_from_euler(x: f64, y: f64, z: f64) -> Quaternion
+    //Euler angles are roll, pitch and yaw in degrees, same rotation sequence as make_euler_from_q
+    pub fn make_q_from_euler(x: T, y: T, z: T) -> Quat<T>
     {
-        let mut q: Quaternion = Default::default();
-        let roll: f64 = deg_to_rad(x as f32) as f64;
-        let pitch: f64 = deg_to_rad(y as f32) as f64;
-        let yaw: f64 = deg_to_rad(z as f32) as f64;
+        let mut q: Quat<T> = Default::default();
+        let roll: f64 = deg_to_rad(x).to_f64();
+        let pitch: f64 = deg_to_rad(y).to_f64();
+        let yaw: f64 = deg_to_rad(z).to_f64();
 
         let cyaw: f64 = (0.5 * yaw).cos();
         let cpitch: f64 = (0.5 * pitch).cos();
@@ -272,13 +283,223 @@ impl Quaternion
         let syawcpitch: f64 = syaw * cpitch;
     
     
-        q.n = (cyawcpitch * croll + syawspitch * sroll) as f32;
-        q.v.x = (cyawcpitch * sroll - syawspitch * croll) as f32;
-        q.v.y = (cyawspitch * croll + syawcpitch * sroll) as f32;
-        q.v.z = (syawcpitch * croll - cyawspitch * sroll) as f32;
+        q.n = T::from_f64(cyawcpitch * croll + syawspitch * sroll);
+        q.v.x = T::from_f64(cyawcpitch * sroll - syawspitch * croll);
+        q.v.y = T::from_f64(cyawspitch * croll + syawcpitch * sroll);
+        q.v.z = T::from_f64(syawcpitch * croll - cyawspitch * sroll);
 
         return q;
     }
+
+    //Quaternion dot product
+    pub fn dot(&self, q: &Quat<T>) -> T
+    {
+        self.n * q.n + self.v * q.v
+    }
+
+    //Unit quaternion in the same direction, the identity if the magnitude is zero
+    pub fn normalized(&self) -> Quat<T>
+    {
+        let mag = self.magnitude();
+        if mag <= T::TOL
+        {
+            return Quat::identity();
+        }
+        *self / mag
+    }
+
+    pub fn identity() -> Quat<T>
+    {
+        Quat::new(T::ONE, T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    //Rotation of angle radians about an axis (right hand rule), the axis does not need to be unit length
+    pub fn from_axis_angle(axis: &Vec3<T>, angle: T) -> Quat<T>
+    {
+        let mut u = *axis;
+        u.normalize();
+        let half = angle * T::from_f64(0.5);
+        Quat { n: half.cos(), v: u * half.sin() }
+    }
+
+    //Axis and angle (radians, 0 to 2 pi) of a unit quaternion. The axis is x for a zero rotation
+    pub fn to_axis_angle(&self) -> (Vec3<T>, T)
+    {
+        let s = self.v.magnitude();
+        if s <= T::TOL
+        {
+            return (Vec3::new(T::ONE, T::ZERO, T::ZERO), T::ZERO);
+        }
+        (self.v / s, T::from_f64(2.0) * s.atan2(self.n))
+    }
+
+    //Exponential map: unit quaternion for a rotation vector (axis times angle in radians)
+    pub fn exp_map(theta: &Vec3<T>) -> Quat<T>
+    {
+        let angle = theta.magnitude();
+        if angle < T::from_f64(1.0e-6)
+        {
+            //Taylor series, avoids dividing by a tiny angle
+            let q = Quat { n: T::ONE - angle * angle / T::from_f64(8.0), v: *theta * T::from_f64(0.5) };
+            return q.normalized();
+        }
+        let half = angle * T::from_f64(0.5);
+        Quat { n: half.cos(), v: *theta * (half.sin() / angle) }
+    }
+
+    //Logarithmic map: rotation vector of a unit quaternion, taking the shorter way round
+    pub fn log_map(&self) -> Vec3<T>
+    {
+        let q = if self.n < T::ZERO { *self * -T::ONE } else { *self };
+        let (axis, angle) = q.to_axis_angle();
+        axis * angle
+    }
+
+    //Advance an orientation by a body space angular velocity (rad/s) held constant for dt seconds.
+    //This is the exact solution of qdot = 0.5 * q * w, so the result stays a unit quaternion
+    pub fn integrate(&self, omega: &Vec3<T>, dt: T) -> Quat<T>
+    {
+        *self * Quat::exp_map(&(*omega * dt))
+    }
+
+    //Spherical linear interpolation between unit quaternions along the shorter arc, t from 0 to 1
+    pub fn slerp(q1: &Quat<T>, q2: &Quat<T>, t: T) -> Quat<T>
+    {
+        let mut q2 = *q2;
+        let mut d = q1.dot(&q2);
+        if d < T::ZERO
+        {
+            q2 = q2 * -T::ONE;
+            d = -d;
+        }
+
+        //Nearly parallel, linear interpolation is accurate and avoids dividing by sin(0)
+        if d > T::from_f64(0.9995)
+        {
+            return (*q1 * (T::ONE - t) + q2 * t).normalized();
+        }
+
+        let sin_theta = (T::ONE - d * d).sqrt();
+        let theta = sin_theta.atan2(d);
+        let w1 = ((T::ONE - t) * theta).sin() / sin_theta;
+        let w2 = (t * theta).sin() / sin_theta;
+        q1.weighted_sum(w1, &q2, w2)
+    }
+
+    fn weighted_sum(&self, w1: T, q: &Quat<T>, w2: T) -> Quat<T>
+    {
+        Quat::new(self.n * w1 + q.n * w2,
+                  self.v.x * w1 + q.v.x * w2,
+                  self.v.y * w1 + q.v.y * w2,
+                  self.v.z * w1 + q.v.z * w2)
+    }
+
+    //Shortest rotation taking the direction of u onto the direction of v
+    pub fn from_two_vectors(u: &Vec3<T>, v: &Vec3<T>) -> Quat<T>
+    {
+        let mut a = *u;
+        let mut b = *v;
+        a.normalize();
+        b.normalize();
+        let d = a * b;
+
+        if d < T::from_f64(-0.999999)
+        {
+            //Opposite directions, turn half way round any axis perpendicular to u
+            let mut axis = Vec3::crossproduct(&Vec3::new(T::ONE, T::ZERO, T::ZERO), &a);
+            if axis.magnitude() < T::from_f64(1.0e-6)
+            {
+                axis = Vec3::crossproduct(&Vec3::new(T::ZERO, T::ONE, T::ZERO), &a);
+            }
+            return Quat::from_axis_angle(&axis, T::PI);
+        }
+
+        Quat { n: T::ONE + d, v: Vec3::crossproduct(&a, &b) }.normalized()
+    }
+
+    //Direction cosine matrix of a unit quaternion. It rotates vectors the same way as qvrotate,
+    //so for the airplane orientation it takes body space into earth space
+    pub fn to_rotation_matrix(&self) -> Mat3<T>
+    {
+        let two = T::from_f64(2.0);
+        let (n, x, y, z) = (self.n, self.v.x, self.v.y, self.v.z);
+        Mat3::new(T::ONE - two * (y*y + z*z), two * (x*y - n*z), two * (x*z + n*y),
+                  two * (x*y + n*z), T::ONE - two * (x*x + z*z), two * (y*z - n*x),
+                  two * (x*z - n*y), two * (y*z + n*x), T::ONE - two * (x*x + y*y))
+    }
+
+    //Unit quaternion from a rotation matrix (Shepperd's method, picks the best conditioned element)
+    pub fn from_rotation_matrix(m: &Mat3<T>) -> Quat<T>
+    {
+        let one = T::ONE;
+        let two = T::from_f64(2.0);
+        let quarter = T::from_f64(0.25);
+        let trace = m.e11 + m.e22 + m.e33;
+
+        let q = if trace > T::ZERO
+        {
+            let s = (trace + one).sqrt() * two;
+            Quat::new(quarter * s, (m.e32 - m.e23) / s, (m.e13 - m.e31) / s, (m.e21 - m.e12) / s)
+        }
+        else if m.e11 > m.e22 && m.e11 > m.e33
+        {
+            let s = (one + m.e11 - m.e22 - m.e33).sqrt() * two;
+            Quat::new((m.e32 - m.e23) / s, quarter * s, (m.e12 + m.e21) / s, (m.e13 + m.e31) / s)
+        }
+        else if m.e22 > m.e33
+        {
+            let s = (one + m.e22 - m.e11 - m.e33).sqrt() * two;
+            Quat::new((m.e13 - m.e31) / s, (m.e12 + m.e21) / s, quarter * s, (m.e23 + m.e32) / s)
+        }
+        else
+        {
+            let s = (one + m.e33 - m.e11 - m.e22).sqrt() * two;
+            Quat::new((m.e21 - m.e12) / s, (m.e13 + m.e31) / s, (m.e23 + m.e32) / s, quarter * s)
+        };
+
+        q.normalized()
+    }
+
+    //Euler angles in radians for the intrinsic rotation sequence yaw (z), then pitch (y'), then roll (x''),
+    //returned as x = roll, y = pitch, z = yaw. At +/- 90 degrees pitch the roll is set to zero
+    pub fn to_euler_zyx(&self) -> Vec3<T>
+    {
+        let m = self.to_rotation_matrix();
+        let mut sin_pitch = -m.e31;
+        if sin_pitch > T::ONE
+        {
+            sin_pitch = T::ONE;
+        }
+        if sin_pitch < -T::ONE
+        {
+            sin_pitch = -T::ONE;
+        }
+
+        if sin_pitch.abs() > T::from_f64(0.999999)
+        {
+            //Gimbal lock, only the difference of yaw and roll is defined
+            let pitch = T::PI * T::from_f64(0.5) * sin_pitch;
+            let yaw = (-m.e12).atan2(m.e22);
+            return Vec3::new(T::ZERO, pitch, yaw);
+        }
+
+        Vec3::new(m.e32.atan2(m.e33), sin_pitch.asin(), m.e21.atan2(m.e11))
+    }
+
+    //Unit quaternion for roll, pitch and yaw in radians, same rotation sequence as to_euler_zyx
+    pub fn from_euler_zyx(roll: T, pitch: T, yaw: T) -> Quat<T>
+    {
+        let x = Vec3::new(T::ONE, T::ZERO, T::ZERO);
+        let y = Vec3::new(T::ZERO, T::ONE, T::ZERO);
+        let z = Vec3::new(T::ZERO, T::ZERO, T::ONE);
+        Quat::from_axis_angle(&z, yaw) * Quat::from_axis_angle(&y, pitch) * Quat::from_axis_angle(&x, roll)
+    }
+
+    //Convert to another precision
+    pub fn cast<U: Real>(&self) -> Quat<U>
+    {
+        Quat {n: U::from_f64(self.n.to_f64()), v: self.v.cast()}
+    }
 }
 
 
@@ -287,6 +508,11 @@ mod tests
 {
     use super::*;
 
+    //These results are exact for single precision
+    type Quaternion = Quat<f32>;
+    type Vector = Vec3<f32>;
+    use crate::bourg::common::vector::Vector64;
+
     //QUATERNION TESTS
 
     #[test]
@@ -303,7 +529,7 @@ mod tests
     { 
         let q = Quaternion::new(8.1, 15.25, 0.1, 2.89);
         
-        let equal = 17.5081310272216796875;
+        let equal = 17.508131;
         assert_eq!(q.magnitude(), equal);
     }
         
@@ -325,7 +551,7 @@ mod tests
         let q2 = Quaternion::new(0.20345166, -0.35279512, -0.034243274, -0.91267216);
         q1 = q1 + q2;
 
-        let equal = Quaternion::new(1.201631069183349609375, -0.375548690557479858398438, -0.0742167681455612182617188, -0.873655140399932861328125);
+        let equal = Quaternion::new(1.2016311, -0.3755487, -0.07421677, -0.87365514);
         assert_eq!(q1, equal);
     }
 
@@ -347,7 +573,7 @@ mod tests
         let mut q = Quaternion::new(0.99817944, -0.022753572, -0.039973494, 0.03901701);
         q = q * 0.016666668;
 
-        let equal = Quaternion::new(0.0166363250464200973510742, -0.000379226228687912225723267, -0.000666224921587854623794556, 0.00065028353128582239151001);
+        let equal = Quaternion::new(0.016636325, -0.00037922623, -0.0006662249, 0.00065028353);
         assert_eq!(q, equal);
     }
 
@@ -357,7 +583,7 @@ mod tests
         let mut q = Quaternion::new(2.0, 1.0, 2.0, 3.0);
         q = q / 3.0;
 
-        let equal = Quaternion::new(0.666666686534881591796875, 0.333333343267440795898438, 0.666666686534881591796875, 1.0);
+        let equal = Quaternion::new(0.6666667, 0.33333334, 0.6666667, 1.0);
         assert_eq!(q, equal);
     }
 
@@ -367,7 +593,7 @@ mod tests
         let mut q = Quaternion::new(0.99817944, -0.022753572, -0.039973494, 0.03901701);
         q = q / 0.016666668;
 
-        let equal = Quaternion::new(59.8907623291015625,-1.36521422863006591796875, -2.3984096050262451171875, 2.3410205841064453125);
+        let equal = Quaternion::new(59.890762,-1.3652142, -2.3984096, 2.3410206);
         assert_eq!(q, equal);
     }
         
@@ -387,7 +613,7 @@ mod tests
         let q1 = Quaternion::new(0.3199454, 0.04186167, -0.2620119, -0.9095231);
         let q2 = Quaternion::conjugate(&q1);
 
-        let equal = Quaternion::new(0.319945394992828369140625, -0.0418616682291030883789062, 0.26201188564300537109375, 0.90952312946319580078125);
+        let equal = Quaternion::new(0.3199454, -0.04186167, 0.2620119, 0.9095231);
         assert_eq!(q2, equal);
     }
 
@@ -409,7 +635,7 @@ mod tests
         let q2 = Quaternion::new(0.3199454, 0.04186167, -0.2620119, -0.9095231);
         q1 = q1 * q2;
 
-        let equal = Quaternion::new(0.345328778028488159179688, 0.0810852870345115661621094, -0.293385803699493408203125, -0.8877489566802978515625);
+        let equal = Quaternion::new(0.34532878, 0.08108529, -0.2933858, -0.88774896);
         assert_eq!(q1, equal);
     }
 
@@ -431,7 +657,7 @@ mod tests
         let v = Vector::new(127.105736, -13.1427, -10.31398);
         q1 = q1 * v;
 
-        let equal = Quaternion::new(2.7691707611083984375, 127.79940032958984375, -8.39416790008544921875, -4.91529941558837890625);
+        let equal = Quaternion::new(2.7691708, 127.7994, -8.394168, -4.9152994);
         assert_eq!(q1, equal);
     }
 
@@ -453,7 +679,7 @@ mod tests
         let mut v = Vector::new(127.105736, -13.1427, -10.31398);
         v = Quaternion::qvrotate(&q1, &v);
 
-        let equal = Vector::new(128.1537475585937, -3.393682956695556640625, 0.285190761089324951171875);
+        let equal = Vector::new(128.15375, -3.393683, 0.28519076);
         assert_eq!(v, equal);
     }
 
@@ -462,16 +688,16 @@ mod tests
     {
         let q = Quaternion::make_q_from_euler(5.0, 7.0, 10.0);
 
-        let equal = Quaternion::new(0.993622303009033203125,  0.0380566865205764770507812, 0.0645529404282569885253906, 0.0842576175928115844726562);
+        let equal = Quaternion::new(0.9936223,  0.038056687, 0.06455294, 0.08425762);
         assert_eq!(q, equal);
     }
 
     #[test]
     fn q_make_quaternion_from_euler_angles_test2()
     {
-        let q = Quaternion::make_q_from_euler(-2.790951251983642578125, -4.475101947784423828125, 4.585966587066650390625);
+        let q = Quaternion::make_q_from_euler(-2.7909513, -4.475102, 4.5859666);
 
-        let equal = Quaternion::new(0.998179376125335693359375, -0.0227535702288150787353516, -0.0399734899401664733886719, 0.0390170104801654815673828);
+        let equal = Quaternion::new(0.9981794, -0.02275357, -0.03997349, 0.03901701);
         assert_eq!(q, equal);
     }
 
@@ -481,7 +707,7 @@ mod tests
         let q = Quaternion::new(2.0, 1.0, 2.0, 3.0);
         let v = Quaternion::make_euler_from_q(&q);
 
-        let equal = Vector::new(0.0, 90.0, 15.9453945159912109375);
+        let equal = Vector::new(0.0, 90.0, 15.9453945);
         assert_eq!(v, equal);
     }
 
@@ -491,7 +717,7 @@ mod tests
         let q = Quaternion::new(0.99817944, -0.022753572, -0.039973494,0.03901701);
         let v = Quaternion::make_euler_from_q(&q);
 
-        let equal = Vector::new(-2.790951251983642578125, -4.475101947784423828125, 4.585966587066650390625);
+        let equal = Vector::new(-2.7909513, -4.475102, 4.5859666);
         assert_eq!(v, equal);
     }
 
@@ -501,7 +727,7 @@ mod tests
         let q = Quaternion::new(0.3199454, 0.04186167, -0.2620119, -0.9095231);
         let v = Quaternion::make_euler_from_q(&q);
 
-        let equal = Vector::new(30.3658618927001953125, -5.25052165985107421875, -142.664825439453125);
+        let equal = Vector::new(30.365862, -5.2505217, -142.66483);
         assert_eq!(v, equal);
     }
 
@@ -511,7 +737,115 @@ mod tests
         let q = Quaternion::new(0.20345166, -0.35279512, -0.034243274, -0.91267216);
         let v = Quaternion::make_euler_from_q(&q);
 
-        let equal = Vector::new(-6.178070545196533203125, -41.140392303466796875, -152.5458831787109375);
+        let equal = Vector::new(-6.1780705, -41.140392, -152.54588);
         assert_eq!(v, equal);
     }
-}
\ No newline at end of file
+
+    //Largest element difference between two quaternions
+    fn q_distance(a: &Quaternion64, b: &Quaternion64) -> f64
+    {
+        (a.n - b.n).abs().max((a.v.x - b.v.x).abs()).max((a.v.y - b.v.y).abs()).max((a.v.z - b.v.z).abs())
+    }
+
+    #[test]
+    fn q_rotation_matrix_test1()
+    {
+        //The DCM rotates vectors the same way as qvrotate and converts back to the same quaternion
+        let q = Quaternion64::from_euler_zyx(0.4, -0.3, 2.5);
+        let v = Vector64::new(1.0, -2.0, 0.5);
+        let m = q.to_rotation_matrix();
+
+        assert!((m * v - Quaternion64::qvrotate(&q, &v)).magnitude() < 1.0e-12);
+        assert!(q_distance(&Quaternion64::from_rotation_matrix(&m), &q) < 1.0e-12);
+
+        //All four branches of Shepperd's method
+        for axis in [Vector64::new(1.0, 0.0, 0.0), Vector64::new(0.0, 1.0, 0.0), Vector64::new(0.0, 0.0, 1.0)].iter()
+        {
+            let q = Quaternion64::from_axis_angle(axis, 3.0);
+            assert!(q_distance(&Quaternion64::from_rotation_matrix(&q.to_rotation_matrix()), &q) < 1.0e-12);
+        }
+    }
+
+    #[test]
+    fn q_axis_angle_test1()
+    {
+        let q = Quaternion64::from_axis_angle(&Vector64::new(0.0, 0.0, 2.0), std::f64::consts::FRAC_PI_2);
+        let v = Quaternion64::qvrotate(&q, &Vector64::new(1.0, 0.0, 0.0));
+        assert!((v - Vector64::new(0.0, 1.0, 0.0)).magnitude() < 1.0e-15);
+
+        let (axis, angle) = q.to_axis_angle();
+        assert!((axis - Vector64::new(0.0, 0.0, 1.0)).magnitude() < 1.0e-15);
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() < 1.0e-15);
+
+        let theta = Vector64::new(0.1, -0.2, 0.3);
+        assert!((Quaternion64::exp_map(&theta).log_map() - theta).magnitude() < 1.0e-15);
+    }
+
+    #[test]
+    fn q_slerp_test1()
+    {
+        let z = Vector64::new(0.0, 0.0, 1.0);
+        let q1 = Quaternion64::from_axis_angle(&z, 0.2);
+        let q2 = Quaternion64::from_axis_angle(&z, 1.4);
+
+        //Constant angular rate along the arc
+        let q = Quaternion64::slerp(&q1, &q2, 0.25);
+        assert!(q_distance(&q, &Quaternion64::from_axis_angle(&z, 0.5)) < 1.0e-15);
+        assert!(q_distance(&Quaternion64::slerp(&q1, &q2, 0.0), &q1) < 1.0e-15);
+        assert!(q_distance(&Quaternion64::slerp(&q1, &q2, 1.0), &q2) < 1.0e-15);
+
+        //-q2 is the same rotation, slerp takes the shorter way
+        let q = Quaternion64::slerp(&q1, &(q2 * -1.0), 0.25);
+        assert!(q_distance(&q, &Quaternion64::from_axis_angle(&z, 0.5)) < 1.0e-15);
+    }
+
+    #[test]
+    fn q_from_two_vectors_test1()
+    {
+        let u = Vector64::new(1.0, 2.0, 0.5);
+        let v = Vector64::new(-3.0, 0.1, 2.0);
+        let q = Quaternion64::from_two_vectors(&u, &v);
+        let mut w = Quaternion64::qvrotate(&q, &u);
+        w.normalize();
+        let mut v_unit = v;
+        v_unit.normalize();
+        assert!((w - v_unit).magnitude() < 1.0e-12);
+
+        //Opposite vectors
+        let q = Quaternion64::from_two_vectors(&u, &-u);
+        assert!((Quaternion64::qvrotate(&q, &u) + u).magnitude() < 1.0e-12);
+    }
+
+    #[test]
+    fn q_integrate_test1()
+    {
+        //Spin at 1 rad/s about body z for 1000 small steps, the result has unit length without renormalizing
+        let omega = Vector64::new(0.0, 0.0, 1.0);
+        let mut q = Quaternion64::from_euler_zyx(0.1, 0.2, 0.3);
+        let start = q;
+        for _ in 0..1000
+        {
+            q = q.integrate(&omega, 0.001);
+        }
+
+        assert!((q.magnitude() - 1.0).abs() < 1.0e-12);
+        assert!(q_distance(&q, &(start * Quaternion64::from_axis_angle(&omega, 1.0))) < 1.0e-12);
+    }
+
+    #[test]
+    fn q_euler_zyx_test1()
+    {
+        let q = Quaternion64::from_euler_zyx(0.3, -0.4, 2.0);
+        let e = q.to_euler_zyx();
+        assert!((e - Vector64::new(0.3, -0.4, 2.0)).magnitude() < 1.0e-12);
+
+        //Same sequence as Bourg's degree functions
+        let q = Quaternion64::make_q_from_euler(10.0, 20.0, 30.0);
+        let e = q.to_euler_zyx();
+        let b = Quaternion64::make_euler_from_q(&q);
+        assert!((e.x.to_degrees() - b.x).abs() < 1.0e-6);
+        assert!((e.y.to_degrees() - b.y).abs() < 1.0e-6);
+        assert!((e.z.to_degrees() - b.z).abs() < 1.0e-6);
+    }
+
+}
@@ -0,0 +1,78 @@
+//Floating point types the Vector, Matrix and Quaternion types can be built on.
+//The Bourg model uses the Float alias, which is f32 by default and f64 with the "bourg_f64" feature:
+//cargo build --features bourg_f64
+
+use std::fmt::Debug;
+
+//Operator overloads
+use std::ops::{Add, Sub, Div, Mul, Neg, AddAssign, SubAssign, MulAssign, DivAssign};
+
+//Precision of the Bourg model
+#[cfg(not(feature = "bourg_f64"))]
+pub type Float = f32;
+#[cfg(feature = "bourg_f64")]
+pub type Float = f64;
+
+pub trait Real: Copy + Clone + Debug + Default + PartialEq + PartialOrd
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self>
+    + AddAssign + SubAssign + MulAssign + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const PI: Self;
+    const BOURG_PI: Self; //Bourg's value of pi, 3.14159265359, used by the degree conversions to match the C++ reference
+    const TOL: Self; //float type tolerance
+    const EPSILON: Self; //machine epsilon, the gap between 1 and the next number of the type
+
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn asin(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+}
+
+impl Real for f32
+{
+    const ZERO: f32 = 0.0;
+    const ONE: f32 = 1.0;
+    const PI: f32 = std::f32::consts::PI;
+    #[allow(clippy::approx_constant, clippy::excessive_precision)] //Bourg's value of pi, kept on purpose to match the C++ reference
+    const BOURG_PI: f32 = 3.14159265359;
+    const TOL: f32 = 0.000000000000001;
+    const EPSILON: f32 = f32::EPSILON;
+
+    fn from_f64(x: f64) -> f32 { x as f32 }
+    fn to_f64(self) -> f64 { self as f64 }
+
+    fn sqrt(self) -> f32 { f32::sqrt(self) }
+    fn abs(self) -> f32 { f32::abs(self) }
+    fn sin(self) -> f32 { f32::sin(self) }
+    fn cos(self) -> f32 { f32::cos(self) }
+    fn asin(self) -> f32 { f32::asin(self) }
+    fn atan2(self, other: f32) -> f32 { f32::atan2(self, other) }
+}
+
+impl Real for f64
+{
+    const ZERO: f64 = 0.0;
+    const ONE: f64 = 1.0;
+    const PI: f64 = std::f64::consts::PI;
+    #[allow(clippy::approx_constant)] //Bourg's value of pi, kept on purpose to match the C++ reference
+    const BOURG_PI: f64 = 3.14159265359;
+    const TOL: f64 = 0.000000000000001;
+    const EPSILON: f64 = f64::EPSILON;
+
+    fn from_f64(x: f64) -> f64 { x }
+    fn to_f64(self) -> f64 { self }
+
+    fn sqrt(self) -> f64 { f64::sqrt(self) }
+    fn abs(self) -> f64 { f64::abs(self) }
+    fn sin(self) -> f64 { f64::sin(self) }
+    fn cos(self) -> f64 { f64::cos(self) }
+    fn asin(self) -> f64 { f64::asin(self) }
+    fn atan2(self, other: f64) -> f64 { f64::atan2(self, other) }
+}
@@ -1,21 +1,26 @@
 //To run unit tests for the Vector module from the command line:
 //cargo test --lib vector
 
-use crate::bourg::common::constants::TOL;
+use crate::bourg::common::real::{Real, Float};
 
 //Operator overloads
-use std::ops::{Add, Sub, Div, Mul, Neg};
+use std::ops::{Add, Sub, Div, Mul, Neg, AddAssign, SubAssign, MulAssign, DivAssign, Index, IndexMut};
 
 #[derive(Debug, Copy, Clone, Default, PartialEq)]
-pub struct Vector
+pub struct Vec3<T>
 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
+//Vector in the precision of the Bourg model, and fixed precision versions
+pub type Vector = Vec3<Float>;
+pub type Vector32 = Vec3<f32>;
+pub type Vector64 = Vec3<f64>;
+
 //Vector addition
-impl Add for Vector 
+impl<T: Real> Add for Vec3<T> 
 {
     type Output = Self;
 
@@ -26,7 +31,7 @@ impl Add for Vector
 }
 
 //Vector substraction
-impl Sub for Vector 
+impl<T: Real> Sub for Vec3<T> 
 {
     type Output = Self;
 
@@ -37,40 +42,40 @@ impl Sub for Vector
 }
 
 //Dotproduct (Vector multiplication)
-impl Mul for Vector 
+impl<T: Real> Mul for Vec3<T> 
 {
-    type Output = f32;
+    type Output = T;
 
-    fn mul(self, v: Self) -> f32 
+    fn mul(self, v: Self) -> T 
     {
         self.x*v.x + self.y*v.y + self.z*v.z
     }
 }
 
 //Division by scalar
-impl Div<f32> for Vector 
+impl<T: Real> Div<T> for Vec3<T> 
 {
     type Output = Self;
 
-    fn div(self, scalar: f32) -> Self 
+    fn div(self, scalar: T) -> Self 
     {
         Self {x: self.x / scalar, y: self.y / scalar, z: self.z / scalar}
     }
 }
 
 //Multiplication by scalar
-impl Mul<f32> for Vector 
+impl<T: Real> Mul<T> for Vec3<T> 
 {
     type Output = Self;
 
-    fn mul(self, scalar: f32) -> Self 
+    fn mul(self, scalar: T) -> Self 
     {
         Self {x: self.x * scalar, y: self.y * scalar, z: self.z * scalar}
     }
 }
 
 //Reverse
-impl Neg for Vector 
+impl<T: Real> Neg for Vec3<T> 
 {
     type Output = Self;
 
@@ -80,48 +85,133 @@ impl Neg for Vector
     }
 }
 
+//Multiplication by scalar on the left, scalar * Vector
+impl Mul<Vec3<f32>> for f32 
+{
+    type Output = Vec3<f32>;
+
+    fn mul(self, v: Vec3<f32>) -> Vec3<f32> 
+    {
+        v * self
+    }
+}
+
+impl Mul<Vec3<f64>> for f64 
+{
+    type Output = Vec3<f64>;
+
+    fn mul(self, v: Vec3<f64>) -> Vec3<f64> 
+    {
+        v * self
+    }
+}
+
+//Compound assignment
+impl<T: Real> AddAssign for Vec3<T> 
+{
+    fn add_assign(&mut self, other: Self) 
+    {
+        *self = *self + other;
+    }
+}
+
+impl<T: Real> SubAssign for Vec3<T> 
+{
+    fn sub_assign(&mut self, other: Self) 
+    {
+        *self = *self - other;
+    }
+}
+
+impl<T: Real> MulAssign<T> for Vec3<T> 
+{
+    fn mul_assign(&mut self, scalar: T) 
+    {
+        *self = *self * scalar;
+    }
+}
 
-impl Vector
+impl<T: Real> DivAssign<T> for Vec3<T> 
 {
-    pub fn new(x: f32, y: f32, z: f32) -> Vector
+    fn div_assign(&mut self, scalar: T) 
     {
-        Vector {x: x, y: y, z: z}
+        *self = *self / scalar;
     }
+}
+
+//Component access, v[0] = x, v[1] = y, v[2] = z
+impl<T: Real> Index<usize> for Vec3<T> 
+{
+    type Output = T;
 
-    pub fn magnitude(&self) -> f32
+    fn index(&self, i: usize) -> &T 
+    {
+        match i
+        {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector index out of range: {}", i),
+        }
+    }
+}
+
+impl<T: Real> IndexMut<usize> for Vec3<T> 
+{
+    fn index_mut(&mut self, i: usize) -> &mut T 
+    {
+        match i
+        {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vector index out of range: {}", i),
+        }
+    }
+}
+
+
+impl<T: Real> Vec3<T>
+{
+    pub fn new(x: T, y: T, z: T) -> Vec3<T>
+    {
+        Vec3 {x: x, y: y, z: z}
+    }
+
+    pub fn magnitude(&self) -> T
     {
         return (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
     }
 
     pub fn normalize(&mut self)
     {
-        let mut m: f32 = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
-        if m <= TOL
+        let mut m: T = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if m <= T::TOL
         {
-            m = 1.0;
+            m = T::ONE;
         }
         self.x /= m;
         self.y /= m;
         self.z /= m;
         
-        if self.x.abs() < TOL 
+        if self.x.abs() < T::TOL 
         {
-            self.x = 0.0;
+            self.x = T::ZERO;
         }
-        if self.y.abs() < TOL 
+        if self.y.abs() < T::TOL 
         {
-            self.y = 0.0;
+            self.y = T::ZERO;
         }
-        if self.z.abs() < TOL
+        if self.z.abs() < T::TOL
         {
-            self.z = 0.0;
+            self.z = T::ZERO;
         }
     
     }
 
-    pub fn crossproduct(u: &Vector, v: &Vector) -> Vector
+    pub fn crossproduct(u: &Vec3<T>, v: &Vec3<T>) -> Vec3<T>
     {
-        let vector = Vector { 
+        let vector = Vec3 { 
             x:  u.y*v.z - u.z*v.y,
             y: -u.x*v.z + u.z*v.x,
             z:  u.x*v.y - u.y*v.x,
@@ -129,6 +219,34 @@ impl Vector
 
         return vector;
     }
+
+    //Dot and cross product as methods
+    pub fn dot(&self, v: &Vec3<T>) -> T
+    {
+        *self * *v
+    }
+
+    pub fn cross(&self, v: &Vec3<T>) -> Vec3<T>
+    {
+        Vec3::crossproduct(self, v)
+    }
+
+    //Component-wise multiplication and division
+    pub fn component_mul(&self, v: &Vec3<T>) -> Vec3<T>
+    {
+        Vec3 {x: self.x * v.x, y: self.y * v.y, z: self.z * v.z}
+    }
+
+    pub fn component_div(&self, v: &Vec3<T>) -> Vec3<T>
+    {
+        Vec3 {x: self.x / v.x, y: self.y / v.y, z: self.z / v.z}
+    }
+
+    //Convert to another precision
+    pub fn cast<U: Real>(&self) -> Vec3<U>
+    {
+        Vec3 {x: U::from_f64(self.x.to_f64()), y: U::from_f64(self.y.to_f64()), z: U::from_f64(self.z.to_f64())}
+    }
 }
 
 
@@ -137,26 +255,29 @@ mod tests
 {
     use super::*;
 
+    //These results are exact for single precision
+    type Vector = Vec3<f32>;
+
     //VECTOR TESTS
     #[test]
     fn v_magnitude_test1() 
     {
         let v = Vector::new(1.0, 1.0, 1.0);
-        assert_eq!(v.magnitude(), 1.73205077648162841796875);
+        assert_eq!(v.magnitude(), 1.7320508);
     }
 
     #[test]
     fn v_magnitude_test2()
     {
         let v = Vector::new(1.0, 2.0, 3.0);
-        assert_eq!(v.magnitude(), 3.7416574954986572265625);
+        assert_eq!(v.magnitude(), 3.7416575);
     }
 
     #[test]
     fn v_magnitude_test3()
     {
-        let v = Vector::new(63.73516082763671875, -0.004545502364635467529296875, -8.11302661895751953125);
-        assert_eq!(v.magnitude(), 64.24945068359375);
+        let v = Vector::new(63.73516, -0.0045455024, -8.113027);
+        assert_eq!(v.magnitude(), 64.24945);
     }
 
 
@@ -166,7 +287,7 @@ mod tests
         let mut v = Vector::new(1.0, 1.0, 1.0);
         v.normalize();
 
-        let equal = Vector::new(0.57735025882720947265625, 0.57735025882720947265625, 0.57735025882720947265625);
+        let equal = Vector::new(0.57735026, 0.57735026, 0.57735026);
         assert_eq!(v, equal);
     }
 
@@ -176,7 +297,7 @@ mod tests
         let mut v = Vector::new(3.0, 3.0, 3.0);
         v.normalize();
 
-        let equal = Vector::new(0.577350318431854248046875, 0.577350318431854248046875,0.577350318431854248046875);
+        let equal = Vector::new(0.5773503, 0.5773503,0.5773503);
         assert_eq!(v, equal);
     }
 
@@ -186,17 +307,17 @@ mod tests
         let mut v = Vector::new(1.0, 2.0, 3.0);
         v.normalize();
 
-        let equal = Vector::new(0.267261236906051635742188, 0.534522473812103271484375, 0.80178368091583251953125);
+        let equal = Vector::new(0.26726124, 0.5345225, 0.8017837);
         assert_eq!(v, equal);
     }
 
     #[test]
     fn v_normalize_test4()
     {
-        let mut v = Vector::new(-0.061048544943332672119140625, 0.0, 0.998134791851043701171875);
+        let mut v = Vector::new(-0.061048545, 0.0, 0.9981348);
         v.normalize();
 
-        let equal = Vector::new(-0.0610485486686229705810546875, 0.0, 0.9981348514556884765625);
+        let equal = Vector::new(-0.06104855, 0.0, 0.99813485);
         assert_eq!(v, equal);
     }
 
@@ -224,17 +345,17 @@ mod tests
     #[test]
     fn v_add_test2()
     {
-        let v = Vector::new(0.000291615608148276805877685546875,
-            -0.001196154276840388774871826171875,
-            0.0007719345740042626857757568359375);
+        let v = Vector::new(0.0002916156,
+            -0.0011961543,
+            0.0007719346);
 
-        let u = Vector::new(0.0000101931509561836719512939453125,
-            -0.005297116935253143310546875,
-            0.000000784755684435367584228515625);
+        let u = Vector::new(0.000010193151,
+            -0.005297117,
+            0.0000007847557);
 
         let uv = v + u;
 
-        let equal = Vector::new(0.000301808759104460477828979, -0.00649327132850885391235352, 0.000772719329688698053359985);
+        let equal = Vector::new(0.00030180876, -0.0064932713, 0.00077271933);
 
 
         assert_eq!(uv, equal);
@@ -254,17 +375,17 @@ mod tests
     #[test]
     fn v_subtract_test2()
     {
-        let v = Vector::new(0.000291615608148276805877685546875,
-            -0.001196154276840388774871826171875,
-            0.0007719345740042626857757568359375);
+        let v = Vector::new(0.0002916156,
+            -0.0011961543,
+            0.0007719346);
 
-        let u = Vector::new(0.0000101931509561836719512939453125,
-            -0.005297116935253143310546875,
-            0.000000784755684435367584228515625);
+        let u = Vector::new(0.000010193151,
+            -0.005297117,
+            0.0000007847557);
 
         let uv = v - u;
 
-        let equal = Vector::new(0.000281422457192093133926392, 0.00410096254199743270874023, 0.000771149818319827318191528);
+        let equal = Vector::new(0.00028142246, 0.0041009625, 0.0007711498);
 
 
         assert_eq!(uv, equal);
@@ -289,19 +410,19 @@ mod tests
     fn v_cross_product_test2()
     {
         
-        let v = Vector::new( 1.01931509561836719512939453125e-05,
-                            -0.005297116935253143310546875,
-                            7.84755684435367584228515625e-07);
+        let v = Vector::new( 1.0193151e-5,
+                            -0.005297117,
+                            7.847557e-7);
 
-        let u = Vector::new(1.81550502777099609375,
+        let u = Vector::new(1.815505,
                         0.0,
-                        -0.6772263050079345703125);
+                        -0.6772263);
 
         let uv = Vector::crossproduct(&v, &u);
 
-        let equal = Vector::new(0.0035873469896614551544189453125,
-                            8.327797331730835139751434326171875e-06,
-                            0.009616942144930362701416015625);
+        let equal = Vector::new(0.003587347,
+                            8.327797e-6,
+                            0.009616942);
                             
         assert_eq!(uv, equal);
 
@@ -324,15 +445,15 @@ mod tests
     fn v_dot_product_test2()
     {
         
-        let v = Vector::new(-0.9932043552398681640625,
-            -3.16645900966250337660312652587890625e-06,
-            0.116383351385593414306640625);
+        let v = Vector::new(-0.99320436,
+            -3.166459e-6,
+            0.11638335);
 
         let u = Vector::new(0.0, 0.0, 1.0);
 
         let uv = v * u;
 
-        assert_eq!(uv, 0.116383351385593414306640625);
+        assert_eq!(uv, 0.11638335);
     }
 
     #[test]
@@ -351,9 +472,61 @@ mod tests
         let v = Vector::new(1.0, 2.0, 3.0);
         let u = v / 3.0;
 
-        let equal = Vector::new(0.333333343267440795898438, 0.666666686534881591796875, 1.0);
+        let equal = Vector::new(0.33333334, 0.6666667, 1.0);
         assert_eq!(u, equal);
     }
 
+    #[test]
+    fn v_double_precision_test1()
+    {
+        let v = Vector64::new(1.0, 2.0, 3.0);
+        assert_eq!(v.magnitude(), 14.0_f64.sqrt());
+        assert_eq!(Vector64::crossproduct(&v, &Vector64::new(0.0, 0.0, 1.0)), Vector64::new(2.0, -1.0, 0.0));
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn v_cast_test1()
+    {
+        let v = Vector::new(0.1, 0.2, 0.3);
+        let u: Vector64 = v.cast();
+        assert_eq!(u.cast::<f32>(), v);
+        assert_eq!(u.x, 0.1_f32 as f64);
+    }
+
+    #[test]
+    fn v_assign_ops_test1()
+    {
+        let mut v = Vector::new(1.0, 2.0, 3.0);
+        v += Vector::new(1.0, 1.0, 1.0);
+        v -= Vector::new(0.0, 1.0, 2.0);
+        v *= 3.0;
+        v /= 2.0;
+
+        let equal = Vector::new(3.0, 3.0, 3.0);
+        assert_eq!(v, equal);
+        assert_eq!(2.0 * v, v * 2.0);
+    }
+
+    #[test]
+    fn v_methods_test1()
+    {
+        let v = Vector::new(1.0, 0.0, 0.0);
+        let u = Vector::new(0.0, 1.0, 1.0);
+
+        assert_eq!(v.cross(&u), Vector::crossproduct(&v, &u));
+        assert_eq!(v.dot(&u), v * u);
+        assert_eq!(Vector::new(1.0, 2.0, 3.0).component_mul(&Vector::new(2.0, 3.0, 4.0)), Vector::new(2.0, 6.0, 12.0));
+        assert_eq!(Vector::new(2.0, 6.0, 12.0).component_div(&Vector::new(2.0, 3.0, 4.0)), Vector::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn v_index_test1()
+    {
+        let mut v = Vector::new(1.0, 2.0, 3.0);
+        v[2] = 5.0;
+        assert_eq!(v[0], 1.0);
+        assert_eq!(v[1], 2.0);
+        assert_eq!(v.z, 5.0);
+    }
+
+}
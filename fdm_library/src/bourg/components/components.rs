@@ -7,6 +7,10 @@ use specs::prelude::*;
 //Import structures to make into Components
 use crate::bourg::fdm::structures::KeyboardState;
 use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::structures::StateHistory;
+use crate::bourg::fdm::structures::AutopilotState;
+use crate::bourg::fdm::structures::Actuators;
+use crate::bourg::fdm::structures::InitialConditions;
 //use crate::flightgear::FGNetFDM;
 
 //Component holding the state of the airplane
@@ -21,6 +25,30 @@ impl Component for KeyboardState
     type Storage = VecStorage<Self>;
 }
 
+//Component holding the last two physics states for interpolated output
+impl Component for StateHistory
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the autopilot modes and loops
+impl Component for AutopilotState
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the actuators of the control channels
+impl Component for Actuators
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the state a crashed airplane is reset to
+impl Component for InitialConditions
+{
+    type Storage = VecStorage<Self>;
+}
+
 // //Component containing the FGNetFDM structure to be sent to FlightGear
 // impl Component for FGNetFDM
 // {
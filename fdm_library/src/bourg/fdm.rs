@@ -6,5 +6,25 @@ pub mod keypresses;
 pub mod make_packet;
 pub mod flight_control;
 pub mod send_packet;
+pub mod interpolation;
+pub mod frames;
+pub mod trim;
+pub mod linearize;
+pub mod autopilot;
+pub mod flight_plan;
+pub mod approach;
+pub mod landing_gear;
+pub mod actuators;
+pub mod engine;
+pub mod propeller;
+pub mod jet;
+pub mod powerplant;
+pub mod fuel;
+pub mod sensors;
+pub mod estimator;
+pub mod air_data;
+pub mod envelope;
+pub mod crash;
+pub mod physics_step;
 
 pub mod structures;
\ No newline at end of file
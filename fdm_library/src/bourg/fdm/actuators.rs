@@ -0,0 +1,85 @@
+//Functions to move the control surfaces, the elevator and aileron flaps and the thrust through their actuators
+
+//DataFDM and Actuators structs
+use crate::bourg::fdm::structures::{DataFDM, Actuators};
+
+//Continuous controls of the airplane
+use crate::bourg::fdm::trim::SurfaceControls;
+
+impl Actuators
+{
+    //Put every actuator at rest at the current controls of the airplane, so nothing moves when they are engaged
+    pub fn reset(&mut self, fdm: &DataFDM)
+    {
+        let controls = SurfaceControls::from_fdm(fdm);
+        self.elevator.reset(controls.elevator);
+        self.aileron.reset(controls.aileron);
+        self.rudder.reset(controls.rudder);
+        self.thrust.reset(controls.thrust);
+        let (elevator_flap, aileron_flap) = flap_commands(fdm);
+        self.elevator_flap.reset(elevator_flap);
+        self.aileron_flap.reset(aileron_flap);
+    }
+}
+
+//Elevator and aileron flap switches set on the airplane by the pitch and roll keys.
+//The roll keys switch the aileron flaps opposite ways, and the aileron is the right one
+fn flap_commands(fdm: &DataFDM) -> (f64, f64)
+{
+    (fdm.element[4].i_flap as f64, 0.5 * (fdm.element[3].i_flap - fdm.element[0].i_flap) as f64)
+}
+
+//Set the elevator and aileron flap switches on the airplane, each at the switch nearest to the flap position
+fn apply_flaps(fdm: &mut DataFDM, elevator: f64, aileron: f64)
+{
+    let switch = |position: f64| position.round().clamp(-1.0, 1.0) as i32;
+    fdm.element[4].i_flap = switch(elevator);
+    fdm.element[5].i_flap = switch(elevator);
+    fdm.element[0].i_flap = switch(-aileron);
+    fdm.element[3].i_flap = switch(aileron);
+}
+
+//Take the controls on the airplane as the commands, move the actuators for dt seconds and
+//put their actual positions on the airplane for the physics step
+pub fn actuate(fdm: &mut DataFDM, actuators: &mut Actuators, dt: f64)
+{
+    let command = SurfaceControls::from_fdm(fdm);
+    SurfaceControls
+    {
+        thrust: actuators.thrust.update(command.thrust, dt),
+        elevator: actuators.elevator.update(command.elevator, dt),
+        aileron: actuators.aileron.update(command.aileron, dt),
+        rudder: actuators.rudder.update(command.rudder, dt),
+        ..command
+    }.apply(fdm);
+
+    let (elevator_flap, aileron_flap) = flap_commands(fdm);
+    let elevator_flap = actuators.elevator_flap.update(elevator_flap, dt);
+    let aileron_flap = actuators.aileron_flap.update(aileron_flap, dt);
+    apply_flaps(fdm, elevator_flap, aileron_flap);
+}
+
+//Put the commands back on the airplane after the physics step, so the inputs and the autopilot keep working on them
+pub fn restore_commands(fdm: &mut DataFDM, actuators: &Actuators)
+{
+    SurfaceControls
+    {
+        thrust: actuators.thrust.command,
+        elevator: actuators.elevator.command,
+        aileron: actuators.aileron.command,
+        rudder: actuators.rudder.command,
+        ..SurfaceControls::from_fdm(fdm)
+    }.apply(fdm);
+    apply_flaps(fdm, actuators.elevator_flap.command, actuators.aileron_flap.command);
+}
+
+//Print the commanded and actual positions, this is called once per frame
+pub fn print_actuators(actuators: &Actuators)
+{
+    println!("Elevator (deg):   {} commanded, {} actual", actuators.elevator.command, actuators.elevator.position);
+    println!("Aileron (deg):    {} commanded, {} actual", actuators.aileron.command, actuators.aileron.position);
+    println!("Rudder (deg):     {} commanded, {} actual", actuators.rudder.command, actuators.rudder.position);
+    println!("Thrust:           {} commanded, {} actual", actuators.thrust.command, actuators.thrust.position);
+    println!("Elevator flap:    {} commanded, {} actual", actuators.elevator_flap.command, actuators.elevator_flap.position);
+    println!("Aileron flap:     {} commanded, {} actual", actuators.aileron_flap.command, actuators.aileron_flap.position);
+}
@@ -0,0 +1,30 @@
+//Functions to run the air data computer on the airplane
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM struct
+use crate::bourg::fdm::structures::DataFDM;
+
+//Feet in the model's units
+use crate::bourg::fdm::frames::FEET_PER_METER;
+use crate::bourg::common::real::Real;
+
+//Air data computer and standard atmosphere
+use crate::air_data::AirDataComputer;
+use crate::atmosphere::Atmosphere;
+
+//Relative wind (m/s) in the aerospace body axes, as the velocity of the airplane through the air. The air is still,
+//so it is the body velocity. Body space is x forward, y left, z up, the aerospace axes are x forward, y right, z down
+pub fn relative_wind(fdm: &DataFDM) -> Vector3<f64>
+{
+    let velocity = &fdm.v_velocity_body;
+    Vector3::new(velocity.x.to_f64(), -velocity.y.to_f64(), -velocity.z.to_f64()) / FEET_PER_METER
+}
+
+//Update the air data computer dt seconds on in the air around the airplane
+pub fn run_air_data(fdm: &DataFDM, computer: &mut AirDataComputer, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.altitude);
+    computer.update(&air, &relative_wind(fdm), dt);
+}
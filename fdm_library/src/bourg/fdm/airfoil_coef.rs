@@ -1,19 +1,53 @@
+//Precision of the Bourg model
+use crate::bourg::common::real::Float;
+
 //Functions to collect airfoil performance data:
 //lift and drag coefficient data is given for a set of discrete attack angles, 
 //so then linear interpolation is used to determine the coefficients for the 
 //attack angle that falls between the discrete angles.
 
+//Share of its lift a wing element stalling early loses, and the degrees past its early stall angle over which it loses it
+pub const EARLY_STALL_LIFT_LOSS: Float = 0.3;
+pub const STALL_ONSET_BAND: Float = 2.0;
+
+//Given the status of the flaps, return the stall angle of the cambered airfoil,
+//the attack angle of its highest lift coefficient.
+pub fn stall_angle(flaps: i32) -> Float
+{
+    if flaps == 1 //flaps up
+    {
+        20.0
+    }
+    else
+    {
+        16.0
+    }
+}
+
+//Return the stall angle of the symmetric airfoil, either way.
+pub fn rudder_stall_angle() -> Float
+{
+    16.0
+}
+
+//Given the attack angle of an element stalling before the others and its early stall angle,
+//return the share of the lift coefficient it keeps.
+pub fn early_stall_lift(angle: Float, early_stall: Float) -> Float
+{
+    1.0 - EARLY_STALL_LIFT_LOSS * ((angle - early_stall) / STALL_ONSET_BAND).clamp(0.0, 1.0)
+}
+
 //Given the angle of attack and status of the flaps,
 //return lift angle coefficient for camabred airfoil with 
 //plain trailing-edge (+/- 15 degree inflation).
-pub fn lift_coefficient(angle: f32, flaps: i32) -> f32
+pub fn lift_coefficient(angle: Float, flaps: i32) -> Float
 {
     let clf0 = vec![-0.54, -0.2, 0.2, 0.57, 0.92, 1.21, 1.43, 1.4, 1.0];
     let clfd = vec![0.0, 0.45, 0.85, 1.02, 1.39, 1.65, 1.75, 1.38, 1.17];
     let clfu = vec![-0.74, -0.4, 0.0, 0.27, 0.63, 0.92, 1.03, 1.1, 0.78];
     let a = vec![-8.0, -4.0, 0.0, 4.0, 8.0, 12.0, 16.0, 20.0, 24.0];
 
-    let mut cl: f32 = 0.0;
+    let mut cl: Float = 0.0;
 
     for i in 0..8  
     {
@@ -44,14 +78,14 @@ pub fn lift_coefficient(angle: f32, flaps: i32) -> f32
 //given angle of attack and flap status, 
 //return drag coefficient for cambered airfoil with 
 //plain trailing-edge flap (+/- 15 degree deflection).
-pub fn drag_coefficient(angle: f32, flaps: i32) -> f32
+pub fn drag_coefficient(angle: Float, flaps: i32) -> Float
 {
     let cdf0 = vec![0.01, 0.0074, 0.004, 0.009, 0.013, 0.023, 0.05, 0.12, 0.21];
     let cdfd = vec![0.0065, 0.0043, 0.0055, 0.0153, 0.0221, 0.0391, 0.1, 0.195, 0.3];
     let cdfu = vec![0.005, 0.0043, 0.0055, 0.02601, 0.03757, 0.06647, 0.13, 0.1, 0.25];
     let a = vec![-8.0, -4.0, 0.0, 4.0, 8.0, 12.0, 16.0, 20.0, 24.0];
 
-    let mut cd: f32 = 0.75; //0.5 in book but 0.75 in actual code
+    let mut cd: Float = 0.75; //0.5 in book but 0.75 in actual code
 
     for i in 0..8  
     {
@@ -84,13 +118,13 @@ pub fn drag_coefficient(angle: f32, flaps: i32) -> f32
 //does not include flaps.
 //Given attack angle, return lift coefficient for a symmetric (no camber) 
 //airfoil without flaps.
-pub fn rudder_lift_coefficient(angle: f32) -> f32
+pub fn rudder_lift_coefficient(angle: Float) -> Float
 {
     let clf0 = vec![0.16, 0.456, 0.736, 0.968, 1.144, 1.12, 0.8];
     let a = vec![0.0, 4.0, 8.0, 12.0, 16.0, 20.0, 24.0];
 
-    let mut cl: f32 = 0.0;
-    let aa: f32 = angle.abs();
+    let mut cl: Float = 0.0;
+    let aa: Float = angle.abs();
 
     for i in 0..6 
     {
@@ -110,13 +144,13 @@ pub fn rudder_lift_coefficient(angle: f32) -> f32
 
 //Given attack angle, return drag coefficient for a symmetric (no camber) 
 //airfoil without flaps.
-pub fn rudder_drag_coefficient(angle: f32) -> f32
+pub fn rudder_drag_coefficient(angle: Float) -> Float
 {
     let cdf0 = vec![0.0032, 0.0072, 0.0104, 0.0184, 0.04, 0.096, 0.168];
     let a = vec![0.0, 4.0, 8.0, 12.0, 16.0, 20.0, 24.0];
 
-    let mut cd: f32 = 0.75; //0.5 in book
-    let aa: f32 = angle.abs();
+    let mut cd: Float = 0.75; //0.5 in book
+    let aa: Float = angle.abs();
 
     for i in 0..6  
     {
@@ -0,0 +1,110 @@
+//Approach and autoland coupling for the Bourg airplane. The approach guidance becomes the targets of the
+//heading hold, vertical speed and airspeed hold modes, converted to feet. On the runway the wings are held
+//level with the thrust off and the brakes on
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Frames, units and quaternion
+use crate::bourg::fdm::frames::{earth_to_ned, FEET_PER_METER};
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Real;
+
+//Approach, position and modes
+use crate::approach::{Approach, ApproachPhase};
+use crate::navigation::Geodetic;
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode, AutopilotModes};
+
+//The approach key engages the approach. Until the flare, flying the roll axis by hand or the disengage key
+//lets go of the approach, after that the airplane is committed to land
+pub fn handle_approach_input(approach: &mut Approach, ap: &AutopilotState, keystate: &KeyboardState)
+{
+    if keystate.ap_approach
+    {
+        approach.engage();
+    }
+    else if approach.phase == ApproachPhase::Approach && !matches!(ap.modes.lateral, LateralMode::HeadingHold(_))
+    {
+        approach.phase = ApproachPhase::Off;
+    }
+}
+
+//Height (ft) of the lowest landing gear contact point above the ground, or of the CG without landing gear
+pub fn wheel_height(fdm: &DataFDM) -> f64
+{
+    let depth = fdm.gear.iter()
+        .map(|gear| -Quaternion::qvrotate(&fdm.q_orientation, &gear.v_cg_coords).z.to_f64())
+        .fold(0.0, f64::max);
+    (fdm.v_position.z - fdm.f_ground_elevation).to_f64() - depth
+}
+
+//Measurements the approach is guided on
+struct Measurements
+{
+    position: Geodetic, //the altitude is that of the wheels
+    v_ned: Vector3<f64>, //m/s
+    touchdown: Option<f64>, //m/s, vertical speed of the touchdown once on the ground
+}
+
+fn measure(fdm: &DataFDM, approach: &Approach) -> Measurements
+{
+    //The ground is taken to be the runway elevation, and the height is that of the wheels
+    Measurements
+    {
+        position: Geodetic { altitude: approach.runway.threshold.altitude + wheel_height(fdm) / FEET_PER_METER, ..fdm.position },
+        v_ned: earth_to_ned(&fdm.v_velocity),
+        touchdown: if fdm.on_ground { Some(fdm.f_touchdown_rate.to_f64() / FEET_PER_METER) } else { None },
+    }
+}
+
+//Set the autopilot targets and controls for the phase of the approach
+pub fn fly_approach(fdm: &mut DataFDM, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    let m = measure(fdm, approach);
+    couple(fdm, &m, approach, ap);
+}
+
+//Fly the approach on the measurements of another state of the airplane, such as its navigation estimate,
+//and set the controls on the airplane
+pub fn fly_approach_on(state: &DataFDM, fdm: &mut DataFDM, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    couple(fdm, &measure(state, approach), approach, ap);
+}
+
+fn couple(fdm: &mut DataFDM, m: &Measurements, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    if !approach.is_engaged()
+    {
+        ap.modes.coupled = false;
+        return;
+    }
+
+    match approach.guide(&m.position, &m.v_ned, m.touchdown)
+    {
+        Some(guidance) => match guidance.phase
+        {
+            ApproachPhase::Approach | ApproachPhase::Flare =>
+            {
+                ap.modes.lateral = LateralMode::HeadingHold(guidance.heading);
+                ap.modes.coupled = true;
+                ap.modes.vertical = VerticalMode::VerticalSpeed(guidance.vertical_speed * FEET_PER_METER);
+                //The thrust is held at its approach setting through the flare and closed on touchdown
+                ap.modes.speed = if guidance.phase == ApproachPhase::Flare { SpeedMode::Off } else { SpeedMode::AirspeedHold(guidance.airspeed * FEET_PER_METER) };
+                fdm.f_brake = 0.0;
+            }
+            _ =>
+            {
+                ap.modes.lateral = LateralMode::WingLeveler;
+                ap.modes.coupled = false;
+                ap.modes.vertical = VerticalMode::Off;
+                ap.modes.speed = SpeedMode::Off;
+                fdm.thrustforce = 0.0;
+                fdm.f_brake = 1.0;
+            }
+        },
+        None => ap.modes = AutopilotModes::default(),
+    }
+}
@@ -0,0 +1,186 @@
+//Autopilot for the Bourg airplane, called by the Autopilot System between the input and the equations of motion.
+//The cascaded loops drive the continuous controls used by the trim: the horizontal tail and outer wing
+//incidences and the thrust. The rudder is left to the pilot
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Continuous controls and attitude
+use crate::bourg::fdm::trim::SurfaceControls;
+use crate::bourg::fdm::frames::attitude_ned;
+use crate::bourg::common::real::Real;
+
+//Shared modes
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode, AutopilotModes, heading_error};
+
+use std::mem::discriminant;
+
+//Measurements used by the loops
+struct Measurements
+{
+    altitude: f64, //ft
+    vertical_speed: f64, //ft/s
+    airspeed: f64, //ft/s
+    roll: f64, //deg
+    pitch: f64, //deg
+    heading: f64, //deg, clockwise from north
+}
+
+fn measure(fdm: &DataFDM) -> Measurements
+{
+    let attitude = attitude_ned(&fdm.q_orientation);
+    Measurements
+    {
+        altitude: fdm.v_position.z.to_f64(),
+        vertical_speed: fdm.v_velocity.z.to_f64(),
+        airspeed: fdm.v_velocity.magnitude().to_f64(),
+        roll: attitude.x.to_f64().to_degrees(),
+        pitch: attitude.y.to_f64().to_degrees(),
+        heading: attitude.z.to_f64().to_degrees(),
+    }
+}
+
+//Engage and disengage modes from the autopilot keys. A mode engages at the current value of what it holds,
+//and flying an axis by hand (pitch, roll or thrust keys) disengages the modes on that axis
+pub fn handle_autopilot_input(fdm: &DataFDM, ap: &mut AutopilotState, keystate: &KeyboardState)
+{
+    let m = measure(fdm);
+
+    if keystate.pitch_up || keystate.pitch_down
+    {
+        ap.modes.vertical = VerticalMode::Off;
+    }
+    if keystate.roll_left || keystate.roll_right
+    {
+        ap.modes.lateral = LateralMode::Off;
+    }
+    if keystate.thrust_up || keystate.thrust_down
+    {
+        ap.modes.speed = SpeedMode::Off;
+    }
+
+    if keystate.ap_disengage
+    {
+        ap.modes = AutopilotModes::default();
+    }
+    if keystate.ap_altitude_hold
+    {
+        ap.modes.vertical = VerticalMode::AltitudeHold(m.altitude);
+    }
+    else if keystate.ap_vertical_speed
+    {
+        ap.modes.vertical = VerticalMode::VerticalSpeed(m.vertical_speed);
+    }
+    if keystate.ap_heading_hold
+    {
+        ap.modes.lateral = LateralMode::HeadingHold(m.heading);
+    }
+    else if keystate.ap_wing_leveler
+    {
+        ap.modes.lateral = LateralMode::WingLeveler;
+    }
+    if keystate.ap_airspeed_hold
+    {
+        ap.modes.speed = SpeedMode::AirspeedHold(m.airspeed);
+    }
+}
+
+//Run the engaged loops for dt seconds and set the controls. The thrust is kept within max_thrust.
+//When a mode is disengaged its surfaces stay where the autopilot left them, like a trim setting
+pub fn autopilot(fdm: &mut DataFDM, ap: &mut AutopilotState, dt: f64, max_thrust: f64)
+{
+    let m = measure(fdm);
+    run_loops(fdm, &m, ap, dt, max_thrust);
+}
+
+//Run the engaged loops on the measurements of another state of the airplane, such as its navigation estimate,
+//and set the controls on the airplane
+pub fn autopilot_on(state: &DataFDM, fdm: &mut DataFDM, ap: &mut AutopilotState, dt: f64, max_thrust: f64)
+{
+    run_loops(fdm, &measure(state), ap, dt, max_thrust);
+}
+
+impl AutopilotState
+{
+    //Start every loop again from the controls of a state the airplane is put back to, as if the modes had just been engaged
+    pub fn reset(&mut self, fdm: &DataFDM)
+    {
+        start_loops(self, &measure(fdm), &SurfaceControls::from_fdm(fdm), [true; 3]);
+    }
+}
+
+//Start the loops of the vertical, lateral and speed axes that are due from the measurements and controls,
+//and keep the modes they run with
+fn start_loops(ap: &mut AutopilotState, m: &Measurements, controls: &SurfaceControls, [vertical, lateral, speed]: [bool; 3])
+{
+    if vertical
+    {
+        ap.altitude.reset(m.vertical_speed);
+        ap.vertical_speed.reset(m.pitch);
+        ap.approach_vertical_speed.reset(m.pitch);
+        ap.pitch.reset(controls.elevator);
+    }
+    if lateral
+    {
+        ap.heading.reset(m.roll);
+        ap.bank.reset(controls.aileron);
+    }
+    if speed
+    {
+        ap.airspeed.reset(controls.thrust);
+    }
+    ap.engaged = ap.modes;
+}
+
+fn run_loops(fdm: &mut DataFDM, m: &Measurements, ap: &mut AutopilotState, dt: f64, max_thrust: f64)
+{
+    if !ap.modes.is_engaged() && !ap.engaged.is_engaged()
+    {
+        return;
+    }
+
+    let mut controls = SurfaceControls::from_fdm(fdm);
+
+    //A newly engaged mode starts its loops from the current controls so nothing jumps
+    let vertical = discriminant(&ap.modes.vertical) != discriminant(&ap.engaged.vertical) || ap.modes.coupled != ap.engaged.coupled;
+    let lateral = discriminant(&ap.modes.lateral) != discriminant(&ap.engaged.lateral);
+    let speed = discriminant(&ap.modes.speed) != discriminant(&ap.engaged.speed);
+    start_loops(ap, m, &controls, [vertical, lateral, speed]);
+
+    //Pitch axis: altitude -> climb rate -> pitch -> elevator. The approach coupler's climb rate runs on its own loop
+    let vertical_speed = if ap.modes.coupled { &mut ap.approach_vertical_speed } else { &mut ap.vertical_speed };
+    let pitch_command = match ap.modes.vertical
+    {
+        VerticalMode::AltitudeHold(altitude) =>
+        {
+            let climb_rate = ap.altitude.update(altitude, m.altitude, dt);
+            Some(vertical_speed.update(climb_rate, m.vertical_speed, dt))
+        }
+        VerticalMode::VerticalSpeed(climb_rate) => Some(vertical_speed.update(climb_rate, m.vertical_speed, dt)),
+        VerticalMode::Off => None,
+    };
+    if let Some(pitch) = pitch_command
+    {
+        controls.elevator = ap.pitch.update(pitch, m.pitch, dt);
+    }
+
+    //Roll axis: heading -> bank -> aileron
+    let bank_command = match ap.modes.lateral
+    {
+        LateralMode::HeadingHold(heading) => Some(ap.heading.update(heading_error(heading, m.heading), 0.0, dt)),
+        LateralMode::WingLeveler => Some(0.0),
+        LateralMode::Off => None,
+    };
+    if let Some(bank) = bank_command
+    {
+        controls.aileron = ap.bank.update(bank, m.roll, dt);
+    }
+
+    //Thrust: airspeed -> thrust
+    if let SpeedMode::AirspeedHold(airspeed) = ap.modes.speed
+    {
+        controls.thrust = ap.airspeed.update(airspeed, m.airspeed, dt).min(max_thrust);
+    }
+
+    controls.apply(fdm);
+}
@@ -10,6 +10,7 @@ use crate::bourg::common::math_utils::rad_to_deg;
 //Vector, quaternion modules
 use crate::bourg::common::vector::Vector;
 use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Float;
 
 //Constants
 use crate::bourg::common::constants::RHO;
@@ -20,6 +21,10 @@ use crate::bourg::fdm::airfoil_coef::rudder_drag_coefficient;
 use crate::bourg::fdm::airfoil_coef::rudder_lift_coefficient;
 use crate::bourg::fdm::airfoil_coef::drag_coefficient;
 use crate::bourg::fdm::airfoil_coef::lift_coefficient;
+use crate::bourg::fdm::airfoil_coef::{stall_angle, rudder_stall_angle, early_stall_lift};
+
+//Ground reaction
+use crate::bourg::fdm::landing_gear::calc_gear_loads;
 
 pub fn calc_airplane_loads(fdm: &mut DataFDM)
 {
@@ -30,9 +35,17 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
     fdm.v_forces = Vector::new(0.0, 0.0, 0.0);
     fdm.v_moments = Vector::new(0.0, 0.0, 0.0);
 
-    //Define thrust vector, which acts through the plane's center of gravity
+    //Define thrust vector, which acts through the plane's center of gravity. An engine model gives the thrust when there is one,
+    //and mounted engines give theirs along their own thrust lines instead
     let mut thrust = Vector::new(1.0, 0.0, 0.0);
-    thrust = thrust * fdm.thrustforce;
+    if fdm.engines.is_empty()
+    {
+        thrust = thrust * fdm.engine_thrust.unwrap_or(fdm.thrustforce);
+    }
+    else
+    {
+        thrust = Vector::new(0.0, 0.0, 0.0);
+    }
 
     //Calculate forces and moments in body space
     let mut v_drag_vector = Vector::new(0.0, 0.0, 0.0);
@@ -45,8 +58,8 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
     {
         if i == 6 //Tail rudder. It is a special case because it can rotate, so the normal vector is recalculated
         {
-            let inc: f32 = deg_to_rad(fdm.element[i].f_incidence);
-            let di: f32 = deg_to_rad(fdm.element[i].f_dihedral);
+            let inc: Float = deg_to_rad(fdm.element[i].f_incidence);
+            let di: Float = deg_to_rad(fdm.element[i].f_dihedral);
             fdm.element[i].v_normal = Vector::new(inc.sin(),
                                                  inc.cos() * di.sin(), 
                                                  inc.cos() * di.cos());
@@ -58,7 +71,7 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
         let v_local_velocity = fdm.v_velocity_body + vtmp;
 
         //Calculate local air speed
-        let f_local_speed: f32 = v_local_velocity.magnitude(); 
+        let f_local_speed: Float = v_local_velocity.magnitude(); 
 
         //Find the direction that drag will act. it will be in line with the relative velocity but going in the opposite direction
         if f_local_speed > 1.0
@@ -85,7 +98,27 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
             _tmp = -1.0;
         }
 
-        let f_attack_angle: f32 = rad_to_deg(_tmp.asin());
+        let f_attack_angle: Float = rad_to_deg(_tmp.asin());
+
+        //Stall margin of the element, the attack angle left before it stalls. With an asymmetry the wing elements on one side
+        //stall early, the right wing when it is positive and the left one when it is negative, and lose some of their lift
+        //past their early stall angle, so the airplane drops that wing
+        let mut f_stall = if i == 6 { rudder_stall_angle() } else { stall_angle(fdm.element[i].i_flap) };
+        let mut f_lift_share: Float = 1.0;
+        if i <= 3
+        {
+            let right_wing = fdm.element[i].v_cg_coords.y < 0.0;
+            let early = if right_wing { fdm.f_stall_asymmetry.max(0.0) } else { (-fdm.f_stall_asymmetry).max(0.0) };
+            if early > 0.0
+            {
+                f_stall -= early;
+                f_lift_share = early_stall_lift(f_attack_angle, f_stall);
+            }
+        }
+        if i <= 6
+        {
+            fdm.f_stall_margins[i] = if i == 6 { f_stall - f_attack_angle.abs() } else { f_stall - f_attack_angle };
+        }
 
         //Determine lift and drag force on the element. Rho is defined as 0.0023769, which is density of air at sea level, slugs/ft^3
         _tmp = 0.5 * RHO * f_local_speed * f_local_speed * fdm.element[i].f_area;   
@@ -102,13 +135,13 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
         }
         else
         {
-            _v_resultant = (v_lift_vector * lift_coefficient(f_attack_angle, fdm.element[i].i_flap) + v_drag_vector * drag_coefficient(f_attack_angle, fdm.element[i].i_flap)) * _tmp;
+            _v_resultant = (v_lift_vector * (lift_coefficient(f_attack_angle, fdm.element[i].i_flap) * f_lift_share) + v_drag_vector * drag_coefficient(f_attack_angle, fdm.element[i].i_flap)) * _tmp;
         }
 
-        //Check for stall. If the coefficient of lift is 0, stall is occuring.
+        //Check for stall. A wing element is stalling past its stall angle, or when the coefficient of lift is 0 beyond the data.
         if i <= 3
         {
-            if lift_coefficient(f_attack_angle, fdm.element[i].i_flap) == 0.0
+            if fdm.f_stall_margins[i] < 0.0 || lift_coefficient(f_attack_angle, fdm.element[i].i_flap) == 0.0
             {
                 fdm.stalling = true; 
             }
@@ -122,8 +155,23 @@ pub fn calc_airplane_loads(fdm: &mut DataFDM)
         mb = mb + vtmp;
      }
 
-    //Add thrust
+    //Add thrust, and the moments of a propeller when there is one
     fb = fb + thrust;
+    mb += fdm.v_propeller_moment;
+
+    //Add the thrust of each mounted engine along its thrust line, its moment about the CG and the moments of its propeller
+    for engine in fdm.engines.iter()
+    {
+        let v_thrust = engine.v_direction * engine.f_thrust;
+        fb += v_thrust;
+        mb += engine.v_cg_coords.cross(&v_thrust);
+        mb += engine.v_propeller_moment;
+    }
+
+    //Add the ground reaction on the landing gear
+    let (gear_force, gear_moment) = calc_gear_loads(fdm);
+    fb += gear_force;
+    mb += gear_moment;
 
     //Convert forces from model space to earth space. rotates the vector by the unit Quaternion (QVRotate function)
      fdm.v_forces = Quaternion::qvrotate(&fdm.q_orientation, &fb);
@@ -0,0 +1,60 @@
+//Functions to find how the airplane touches the ground for the crash monitor and to reset it after a crash
+
+//DataFDM, StateHistory, Actuators, AutopilotState and InitialConditions structs
+use crate::bourg::fdm::structures::{DataFDM, StateHistory, Actuators, AutopilotState, InitialConditions};
+
+//Attitude, feet and poses
+use crate::bourg::fdm::frames::{attitude_ned, FEET_PER_METER};
+use crate::bourg::fdm::interpolation::snapshot;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Real;
+
+//Ground contact
+use crate::crash::GroundContact;
+
+//Contact of the airplane with the ground, none when it is clear of it. The gear touches the ground through its legs,
+//the airframe when an element is below the ground, which is how an airplane without gear meets it. The sink rate
+//is the one the gear touched down at, or the vertical speed when only the airframe is touching
+pub fn ground_contact(fdm: &DataFDM) -> Option<GroundContact>
+{
+    let airframe = fdm.element.iter().any(|element|
+    {
+        let height = fdm.v_position.z + Quaternion::qvrotate(&fdm.q_orientation, &element.v_cg_coords).z;
+        height < fdm.f_ground_elevation
+    });
+    if !fdm.on_ground && !airframe
+    {
+        return None;
+    }
+
+    let vertical_speed = if fdm.on_ground { fdm.f_touchdown_rate } else { fdm.v_velocity.z };
+    let attitude = attitude_ned(&fdm.q_orientation);
+
+    Some(GroundContact
+    {
+        sink_rate: -vertical_speed.to_f64() / FEET_PER_METER,
+        bank: attitude.x.to_f64().to_degrees(),
+        pitch: attitude.y.to_f64().to_degrees(),
+        airframe,
+    })
+}
+
+//Put a crashed airplane back to its initial conditions, with its output poses, actuators and autopilot loops at rest
+//there. What else it carries is reset after it with reset_equipment
+pub fn reset_airplane(fdm: &mut DataFDM, initial: &InitialConditions, history: Option<&mut StateHistory>, actuators: Option<&mut Actuators>, autopilot: Option<&mut AutopilotState>)
+{
+    *fdm = initial.fdm.clone();
+    if let Some(history) = history
+    {
+        history.previous = snapshot(fdm);
+        history.current = history.previous;
+    }
+    if let Some(actuators) = actuators
+    {
+        actuators.reset(fdm);
+    }
+    if let Some(autopilot) = autopilot
+    {
+        autopilot.reset(fdm);
+    }
+}
@@ -0,0 +1,47 @@
+//Functions to run a piston engine on the airplane
+
+//DataFDM and KeyboardState structs
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState};
+
+//Precision of the Bourg model
+use crate::bourg::common::real::{Real, Float};
+
+//Engine and the air it runs in
+use crate::engine::PistonEngine;
+use crate::atmosphere::Atmosphere;
+
+//Mixture travel per second while a mixture key is held
+pub const MIXTURE_RATE: f64 = 0.5;
+
+//Apply the mixture and starter keys. This runs at the input rate, dt seconds after the previous input
+pub fn handle_engine_input(engine: &mut PistonEngine, keystate: &KeyboardState, dt: f64)
+{
+    if keystate.mixture_rich
+    {
+        engine.mixture = (engine.mixture + MIXTURE_RATE * dt).min(1.0);
+    }
+    else if keystate.mixture_lean
+    {
+        engine.mixture = (engine.mixture - MIXTURE_RATE * dt).max(0.0);
+    }
+    if keystate.engine_start && !engine.running
+    {
+        engine.start();
+    }
+}
+
+//Run the engine for dt seconds. The thrust lever, thrustforce as a fraction of max_thrust, is the throttle.
+//Without a Propeller the thrust is max_thrust scaled by the fraction of the rated power the engine gives
+pub fn run_engine(fdm: &mut DataFDM, engine: &mut PistonEngine, max_thrust: Float, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.altitude);
+    let load = engine.default_load(air.density);
+    engine.update(throttle(fdm, max_thrust), &air, load, dt);
+    fdm.engine_thrust = Some(max_thrust * Float::from_f64(engine.power / engine.data.max_power));
+}
+
+//Throttle setting (0 - 1) of the thrust lever
+pub fn throttle(fdm: &DataFDM, max_thrust: Float) -> f64
+{
+    if max_thrust > 0.0 { (fdm.thrustforce / max_thrust).to_f64() } else { 0.0 }
+}
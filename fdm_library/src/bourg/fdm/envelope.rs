@@ -0,0 +1,97 @@
+//Functions to find the state the envelope limits are checked against and to protect the airplane from them
+
+//DataFDM struct
+use crate::bourg::fdm::structures::DataFDM;
+
+//Relative wind, attitude and continuous controls
+use crate::bourg::fdm::air_data::relative_wind;
+use crate::bourg::fdm::frames::attitude_ned;
+use crate::bourg::fdm::trim::SurfaceControls;
+
+//Gravity
+use crate::bourg::common::constants::G;
+use crate::bourg::common::vector::Vector;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::{Real, Float};
+
+//Envelope monitor and air data
+use crate::envelope::{EnvelopeState, EnvelopeMonitor};
+use crate::air_data::flow_angles;
+use crate::sensors::{impact_pressure, calibrated_airspeed};
+use crate::atmosphere::Atmosphere;
+
+//Elevator incidence (deg) the angle of attack protection takes off per second for each degree past its limit,
+//and gives back per second for each degree below it
+pub const ALPHA_PROTECTION_GAIN: f64 = 20.0;
+
+//State of the airplane for the envelope monitor. The load factor is the acceleration over the last physics step
+//without gravity along the body z axis, so it is 1 in level flight
+pub fn envelope_state(fdm: &DataFDM) -> EnvelopeState
+{
+    let wind = relative_wind(fdm);
+    let air = Atmosphere::standard(fdm.position.altitude);
+    let (alpha, _) = flow_angles(&wind);
+
+    let specific_force_earth = fdm.v_acceleration - Vector::new(0.0, 0.0, G);
+    let specific_force_body = Quaternion::qvrotate(&Quaternion::conjugate(&fdm.q_orientation), &specific_force_earth);
+    let attitude = attitude_ned(&fdm.q_orientation);
+
+    EnvelopeState
+    {
+        airspeed: calibrated_airspeed(impact_pressure(&air, wind.norm())),
+        load_factor: -(specific_force_body.z / G).to_f64(),
+        alpha: alpha.to_degrees(),
+        bank: attitude.x.to_f64().to_degrees(),
+        pitch: attitude.y.to_f64().to_degrees(),
+        airborne: !fdm.on_ground,
+    }
+}
+
+//Pitch command the protection took over for a physics step
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PitchCommand
+{
+    elevator: f64, //deg
+    i_flap: i32,
+}
+
+//Clip the pitch control for a physics step of dt seconds when the monitor protects the airplane, and return the command
+//it took over. Past the protected angle of attack the nose down elevator the protection holds builds up, and below it
+//it winds back off, so the protection lets go once the airplane has regained the margin. While it holds any, the
+//elevator key is let go. The command is put back after the step with restore_pitch_command
+pub fn protect(fdm: &mut DataFDM, monitor: &EnvelopeMonitor, dt: f64) -> Option<PitchCommand>
+{
+    let protection = match monitor.alpha_protection()
+    {
+        Some(limit) if !fdm.on_ground =>
+        {
+            let (alpha, _) = flow_angles(&relative_wind(fdm));
+            (fdm.f_alpha_protection.to_f64() + ALPHA_PROTECTION_GAIN * (alpha.to_degrees() - limit) * dt).max(0.0)
+        }
+        _ => 0.0,
+    };
+    fdm.f_alpha_protection = Float::from_f64(protection);
+    if protection <= 0.0
+    {
+        return None;
+    }
+
+    let mut controls = SurfaceControls::from_fdm(fdm);
+    let command = PitchCommand { elevator: controls.elevator, i_flap: fdm.element[4].i_flap };
+    if command.i_flap > 0
+    {
+        fdm.element[4].i_flap = 0;
+        fdm.element[5].i_flap = 0;
+    }
+    controls.elevator -= protection;
+    controls.apply(fdm);
+    Some(command)
+}
+
+//Put the pitch command the protection took over back on the airplane after the physics step
+pub fn restore_pitch_command(fdm: &mut DataFDM, command: &PitchCommand)
+{
+    fdm.element[4].i_flap = command.i_flap;
+    fdm.element[5].i_flap = command.i_flap;
+    SurfaceControls { elevator: command.elevator, ..SurfaceControls::from_fdm(fdm) }.apply(fdm);
+}
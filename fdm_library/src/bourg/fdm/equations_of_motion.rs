@@ -1,8 +1,5 @@
 //This function computes the equations of motion
 
-//Coordinate transforms
-use coord_transforms::prelude::*;
-
 //Get Component data needed to perform the System operations
 use crate::bourg::fdm::structures::KeyboardState;
 use crate::bourg::fdm::structures::DataFDM;
@@ -10,14 +7,27 @@ use crate::bourg::fdm::structures::DataFDM;
 //Get keyboard flight control functions
 use crate::bourg::fdm::keypresses::*;
 use crate::bourg::fdm::calc_loads::calc_airplane_loads;
+use crate::bourg::fdm::landing_gear::update_gear_contact;
 
 //Vector, Matrix, Quaternion, math utilities, constants
 use crate::bourg::common::vector::Vector;
-use crate::bourg::common::quaternion::Quaternion;
-use crate::bourg::common::math_utils::deg_to_rad;
-use crate::bourg::common::math_utils::rad_to_deg;
+use crate::bourg::common::quaternion::{Quaternion, Quaternion64};
+use crate::bourg::common::vector::Vector64;
+use crate::bourg::common::real::{Real, Float};
+
+//Geodetic navigation
+use crate::navigation::{Geodetic, geodetic_rates, wrap_longitude};
+use crate::bourg::fdm::frames::{earth_to_ned, attitude_ned};
+
+//ODE integrator
+use crate::integrator::Integrator;
 
-pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f32, d_thrust: f32, max_thrust: f32)
+//Stall warning
+use crate::stall::smooth_warning;
+
+//Apply the keyboard states to the control surfaces and thrust. This runs at the input rate, dt seconds after
+//the previous input. The thrust changes by d_thrust per second within 0 and max_thrust
+pub fn handle_input(mut fdm: &mut DataFDM, keystate: &KeyboardState, d_thrust: Float, max_thrust: Float, dt: Float)
 {
 
     //Reset/zero the elevators, rudders, and ailerons every loop
@@ -31,11 +41,13 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f32, d_thrust: f
     //Thrust states
     if fdm.thrustforce < max_thrust && keystate.thrust_up == true
     {
-        thrust_up(&mut fdm, d_thrust);
+        thrust_up(&mut fdm, d_thrust * dt);
+        fdm.thrustforce = fdm.thrustforce.min(max_thrust);
     }   
     else if fdm.thrustforce > 0.0 && keystate.thrust_down == true
     {
-        thrust_down(&mut fdm, d_thrust);
+        thrust_down(&mut fdm, d_thrust * dt);
+        fdm.thrustforce = fdm.thrustforce.max(0.0);
     }
 
     //Rudder States
@@ -77,55 +89,31 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f32, d_thrust: f
     { 
         zero_flaps(&mut fdm);
     } 
+}
 
-
-    //Calculate all of the forces and moments on the airplane
-    calc_airplane_loads(&mut fdm);
-
-    //Calculate acceleration of airplane in earth space
-    let ae = fdm.v_forces / fdm.mass;
-
-    //Calculate velocity of airplane in earth space
-    fdm.v_velocity = fdm.v_velocity + ae * dt; 
-
-
-    //Calculate position of airplane in earth space
-
-    //Create WGS84 ellipsoid
-    let ellipsoid = geo_ellipsoid::geo_ellipsoid::new(geo_ellipsoid::WGS84_SEMI_MAJOR_AXIS_METERS, geo_ellipsoid::WGS84_FLATTENING);
-
-    //Take lat/lon origin and put into naglebra vector, and convert the lat/lon degrees to radians
-    let origin = Vector3::new(deg_to_rad(fdm.lla_origin.x) as f64, deg_to_rad(fdm.lla_origin.y) as f64, fdm.lla_origin.z as f64);
-
-    //Load x/y/z velocities into a nalgebra vector representing the displacement, convert from feet to meters
-    let d = Vector3::new(fdm.v_velocity.x as f64 / 3.281, fdm.v_velocity.y as f64 / 3.281, fdm.v_velocity.z as f64 / 3.281);
-
-    //Take East North Up cartesian coordinate displacement and calculate a new lat/lon/alt with respect to the origin
-    let enu2lla = geo::enu2lla(&origin, &d, &ellipsoid);
-
-    //Put the enu2lla results into the native custom vector type and convert lat/lon radians displaced to degrees 
-    let enu2lla_converted = Vector::new(rad_to_deg(enu2lla.x as f32), rad_to_deg(enu2lla.y as f32), enu2lla.z as f32);
-
-    //Subtract the enu2lla results by the origin position get the displacement for the frame
-    let displacement = enu2lla_converted - fdm.lla_origin;
-
-    //Update position by adding old position and displacement with respect to time
-    fdm.v_position = fdm.v_position + displacement * dt; 
-
-
-    //Calculate angular velocity of airplane in body space
-    fdm.v_angular_velocity = fdm.v_angular_velocity + ((fdm.m_inertia_inverse * 
-        (fdm.v_moments - Vector::crossproduct(&fdm.v_angular_velocity, &(fdm.m_inertia * fdm.v_angular_velocity)))) * dt);
-
-    //Calculate the new rotation Quaternion
-    fdm.q_orientation = fdm.q_orientation + (fdm.q_orientation * fdm.v_angular_velocity) * (0.5 * dt);
-
-    //Now normalize the orientation Quaternion (make into unit Quaternion)
-    let mag = fdm.q_orientation.magnitude();
-    if mag != 0.0
+//Advance the airplane state by one physics time step
+pub fn eom(fdm: &mut DataFDM, dt: Float, integrator: &Integrator)
+{
+    //Pack the state, advance it with the configured integrator, then unpack it again.
+    //The orientation at the start of the step is the reference for the rotation vector states
+    let q_start = fdm.q_orientation;
+    let v_velocity = fdm.v_velocity;
+    let vertical_speed = fdm.v_velocity.z;
+    let mut y = save_state(fdm);
+    integrator.step(0.0, &mut y, dt.to_f64(), &RATE_STATES, |_, state, dydt| derivatives(fdm, &q_start, state, dydt));
+    load_state(fdm, &q_start, &y);
+
+    //Mean acceleration over the step. The forces are those of the last load evaluation only
+    fdm.v_acceleration = (fdm.v_velocity - v_velocity) / dt;
+
+    //Weight on wheels, from the gear compression of the state the step ended on. The vertical speed
+    //at the start of the step the gear first touched is kept as the touchdown rate
+    let on_ground = update_gear_contact(fdm);
+    if on_ground && !fdm.on_ground
     {
-        fdm.q_orientation = fdm.q_orientation / mag;
+        fdm.f_touchdown_rate = vertical_speed;
     }
+    fdm.on_ground = on_ground;
 
     //Calculate the velocity in body space
     fdm.v_velocity_body = Quaternion::qvrotate(&Quaternion::conjugate(&fdm.q_orientation), &fdm.v_velocity);
@@ -138,16 +126,103 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f32, d_thrust: f
     fdm.v_euler_angles.x = euler.x; 
     fdm.v_euler_angles.y = euler.y;
     fdm.v_euler_angles.z = euler.z;
-    
-    //Print some relevant data
-    println!("Roll:             {}", fdm.v_euler_angles.x);
-    println!("Pitch:            {}", -fdm.v_euler_angles.y);
-    println!("Yaw:              {}", fdm.v_euler_angles.z);
+
+    //Move the stall warning towards the one of the wing element closest to its stall, from the last load evaluation
+    let margin = fdm.f_stall_margins[..4].iter().fold(Float::MAX, |margin, element| margin.min(*element));
+    fdm.f_stall_warning = Float::from_f64(smooth_warning(fdm.f_stall_warning.to_f64(), margin.to_f64(), dt.to_f64()));
+}
+
+//Print some relevant data, this is called once per frame rather than every physics sub-step
+pub fn print_state(fdm: &DataFDM)
+{
+    let attitude = attitude_ned(&fdm.q_orientation);
+    println!("Roll:             {}", attitude.x.to_f64().to_degrees());
+    println!("Pitch:            {}", attitude.y.to_f64().to_degrees());
+    println!("Heading:          {}", attitude.z.to_f64().to_degrees());
     println!("Position x:       {}", fdm.v_position.x);
     println!("Position y:       {}", fdm.v_position.y);
     println!("Position z:       {}", fdm.v_position.z);
+    println!("Latitude:         {}", fdm.position.latitude_deg());
+    println!("Longitude:        {}", fdm.position.longitude_deg());
+    println!("Altitude (m):     {}", fdm.position.altitude);
     println!("Airspeed (knots): {}", fdm.f_speed/1.688); //convert to knots
     println!("Thrust:           {}", fdm.thrustforce);
+    println!("Stall warning:    {}", fdm.f_stall_warning);
+}
+
+
+//State vector layout used by the integrator:
+//[0..3] velocity (earth space), [3..6] position (earth space), [6..9] angular velocity (body space),
+//[9..12] rotation vector (body space) from the orientation at the start of the step,
+//[12..15] geodetic latitude, longitude (radians) and altitude (meters).
+//The orientation is q_start * exp(rotation vector), so it stays a unit quaternion without renormalizing
+
+//Velocity and angular velocity are advanced first by semi-implicit Euler, which matches Bourg's update order
+pub const RATE_STATES: [usize; 6] = [0, 1, 2, 6, 7, 8];
+
+//Copy the airplane state into a state vector for the integrator
+pub fn save_state(fdm: &DataFDM) -> Vec<f64>
+{
+    vec![fdm.v_velocity.x.to_f64(), fdm.v_velocity.y.to_f64(), fdm.v_velocity.z.to_f64(),
+         fdm.v_position.x.to_f64(), fdm.v_position.y.to_f64(), fdm.v_position.z.to_f64(),
+         fdm.v_angular_velocity.x.to_f64(), fdm.v_angular_velocity.y.to_f64(), fdm.v_angular_velocity.z.to_f64(),
+         0.0, 0.0, 0.0,
+         fdm.position.latitude, fdm.position.longitude, fdm.position.altitude]
+}
+
+//Copy a state vector back into the airplane state, q_start is the orientation the rotation vector is measured from
+pub fn load_state(fdm: &mut DataFDM, q_start: &Quaternion, y: &[f64])
+{
+    fdm.v_velocity = Vector::new(Float::from_f64(y[0]), Float::from_f64(y[1]), Float::from_f64(y[2]));
+    fdm.v_position = Vector::new(Float::from_f64(y[3]), Float::from_f64(y[4]), Float::from_f64(y[5]));
+    fdm.v_angular_velocity = Vector::new(Float::from_f64(y[6]), Float::from_f64(y[7]), Float::from_f64(y[8]));
+    //The rotation is composed in double precision so single precision rounding does not build up in the magnitude
+    let q: Quaternion64 = q_start.cast();
+    fdm.q_orientation = (q * Quaternion64::exp_map(&Vector64::new(y[9], y[10], y[11]))).cast();
+    fdm.position = Geodetic::new(y[12], wrap_longitude(y[13]), y[14]);
+}
+
+//Derivative function for the integrator: loads the state, calculates the forces and moments and returns the state rates
+pub fn derivatives(fdm: &mut DataFDM, q_start: &Quaternion, y: &[f64], dydt: &mut [f64])
+{
+    load_state(fdm, q_start, y);
+
+    //Calculate the velocity in body space, it is needed to find the loads
+    fdm.v_velocity_body = Quaternion::qvrotate(&Quaternion::conjugate(&fdm.q_orientation), &fdm.v_velocity);
+
+    //Calculate all of the forces and moments on the airplane
+    calc_airplane_loads(fdm);
+
+    //Calculate acceleration of airplane in earth space
+    let ae = fdm.v_forces / fdm.mass;
 
+    //Rate of change of the geodetic position, the earth velocity is converted to NED in meters per second
+    let lla_rates = geodetic_rates(&fdm.position, &earth_to_ned(&fdm.v_velocity));
+
+    //Calculate angular acceleration of airplane in body space
+    let alpha = fdm.m_inertia_inverse * 
+        (fdm.v_moments - Vector::crossproduct(&fdm.v_angular_velocity, &(fdm.m_inertia * fdm.v_angular_velocity)));
+
+    //Rate of change of the rotation vector (Bortz equation, terms beyond second order are dropped)
+    let theta = Vector64::new(y[9], y[10], y[11]);
+    let omega: Vector64 = fdm.v_angular_velocity.cast();
+    let theta_cross_omega = theta.cross(&omega);
+    let theta_dot = omega + theta_cross_omega * 0.5 + theta.cross(&theta_cross_omega) * (1.0 / 12.0);
+
+    let rates = [ae.x, ae.y, ae.z,
+                 fdm.v_velocity.x, fdm.v_velocity.y, fdm.v_velocity.z,
+                 alpha.x, alpha.y, alpha.z];
+
+    for (dy, rate) in dydt.iter_mut().zip(rates.iter())
+    {
+        *dy = rate.to_f64();
+    }
 
-}
\ No newline at end of file
+    //The attitude and geodetic rates stay in double precision
+    dydt[9] = theta_dot.x;
+    dydt[10] = theta_dot.y;
+    dydt[11] = theta_dot.z;
+    dydt[12] = lla_rates.x;
+    dydt[13] = lla_rates.y;
+    dydt[14] = lla_rates.z;
+}
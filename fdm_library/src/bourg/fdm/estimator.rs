@@ -0,0 +1,56 @@
+//Functions to fly the guidance on the navigation estimate of the airplane
+
+//DataFDM struct
+use crate::bourg::fdm::structures::DataFDM;
+
+//Frame conversions from the estimate's NED
+use crate::bourg::fdm::frames::{orientation_from_ned, ned_to_earth, FEET_PER_METER};
+use crate::bourg::common::vector::Vector;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::{Real, Float};
+
+//Estimator and geodetic position
+use crate::estimator::{Estimator, NavigationEstimate};
+use crate::navigation::Geodetic;
+
+//Navigation state the guidance reads from the airplane
+struct Navigation
+{
+    position: Geodetic,
+    altitude: Float, //ft, the earth space height
+    v_velocity: Vector,
+    q_orientation: Quaternion,
+}
+
+fn set_navigation(fdm: &mut DataFDM, navigation: &Navigation)
+{
+    fdm.position = navigation.position;
+    fdm.v_position.z = navigation.altitude;
+    fdm.v_velocity = navigation.v_velocity;
+    fdm.q_orientation = navigation.q_orientation;
+}
+
+fn estimated_navigation(fdm: &DataFDM, estimate: &NavigationEstimate) -> Navigation
+{
+    let q = estimate.orientation;
+    let q_ned = Quaternion::new(Float::from_f64(q.w), Float::from_f64(q.i), Float::from_f64(q.j), Float::from_f64(q.k));
+    Navigation
+    {
+        position: estimate.position,
+        //The earth space altitude and the geodetic altitude can differ by a constant
+        altitude: Float::from_f64(fdm.v_position.z.to_f64() + (estimate.position.altitude - fdm.position.altitude) * FEET_PER_METER),
+        v_velocity: ned_to_earth(&estimate.velocity_ned),
+        q_orientation: orientation_from_ned(&q_ned),
+    }
+}
+
+//State the guidance flies the airplane on when its Estimator flies the guidance on the estimate and has one: a copy of
+//the airplane with the estimated position, velocity and attitude in place of the truth. The guidance measures the copy
+//and sets the controls on the airplane itself
+pub fn estimated_state(fdm: &DataFDM, estimator: Option<&Estimator>) -> Option<DataFDM>
+{
+    let estimate = estimator.filter(|estimator| estimator.guidance_on_estimate).and_then(Estimator::current)?;
+    let mut state = fdm.clone();
+    set_navigation(&mut state, &estimated_navigation(fdm, &estimate));
+    Some(state)
+}
@@ -26,6 +26,18 @@ pub fn flt_ctrl(keystate: &mut KeyboardState)
     keystate.pitch_up = false;
     keystate.pitch_down = false;
 
+    keystate.ap_altitude_hold = false;
+    keystate.ap_vertical_speed = false;
+    keystate.ap_heading_hold = false;
+    keystate.ap_airspeed_hold = false;
+    keystate.ap_wing_leveler = false;
+    keystate.ap_disengage = false;
+    keystate.ap_flight_plan = false;
+    keystate.ap_approach = false;
+    keystate.mixture_rich = false;
+    keystate.mixture_lean = false;
+    keystate.engine_start = false;
+
     //Flaps are toggled on and off, so they dont need to be set to false each time
 
     //Setup device query states
@@ -84,6 +96,54 @@ pub fn flt_ctrl(keystate: &mut KeyboardState)
         keystate.flaps_down = false;
     }
 
+    //Autopilot modes, each key engages a mode at the current value
+    if keys.contains(&Keycode::A)
+    {
+        keystate.ap_altitude_hold = true;
+    }
+    else if keys.contains(&Keycode::V)
+    {
+        keystate.ap_vertical_speed = true;
+    }
+    if keys.contains(&Keycode::H)
+    {
+        keystate.ap_heading_hold = true;
+    }
+    else if keys.contains(&Keycode::W)
+    {
+        keystate.ap_wing_leveler = true;
+    }
+    if keys.contains(&Keycode::S)
+    {
+        keystate.ap_airspeed_hold = true;
+    }
+    if keys.contains(&Keycode::X)
+    {
+        keystate.ap_disengage = true;
+    }
+    if keys.contains(&Keycode::F)
+    {
+        keystate.ap_flight_plan = true;
+    }
+    if keys.contains(&Keycode::G)
+    {
+        keystate.ap_approach = true;
+    }
+
+    //Engine mixture and starter
+    if keys.contains(&Keycode::R)
+    {
+        keystate.mixture_rich = true;
+    }
+    else if keys.contains(&Keycode::T)
+    {
+        keystate.mixture_lean = true;
+    }
+    if keys.contains(&Keycode::Y)
+    {
+        keystate.engine_start = true;
+    }
+
     //Quit program
     if keys.contains(&Keycode::Q)
     {
@@ -0,0 +1,55 @@
+//Flight plan coupling for the Bourg airplane. The guidance from the plan becomes the targets of the
+//heading hold, altitude hold and airspeed hold modes, converted to feet
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Frames and units
+use crate::bourg::fdm::frames::{earth_to_ned, FEET_PER_METER};
+use crate::bourg::common::real::Real;
+
+//Flight plan and modes
+use crate::flight_plan::FlightPlan;
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode};
+
+//The flight plan key engages the plan. The plan lets go when the heading hold it drives has been
+//disengaged, by flying the roll axis by hand or with the disengage key
+pub fn handle_flight_plan_input(plan: &mut FlightPlan, ap: &AutopilotState, keystate: &KeyboardState)
+{
+    if keystate.ap_flight_plan
+    {
+        plan.engaged = true;
+    }
+    else if plan.engaged && !matches!(ap.modes.lateral, LateralMode::HeadingHold(_))
+    {
+        plan.engaged = false;
+    }
+}
+
+//Set the autopilot targets from the plan. When the plan is complete it disengages and the autopilot
+//keeps holding the last heading and altitude
+pub fn follow_flight_plan(fdm: &DataFDM, plan: &mut FlightPlan, ap: &mut AutopilotState)
+{
+    if !plan.engaged
+    {
+        return;
+    }
+
+    let v_ned = earth_to_ned(&fdm.v_velocity);
+    match plan.guide(&fdm.position, &v_ned)
+    {
+        Some(guidance) =>
+        {
+            //The earth space altitude and the geodetic altitude can differ by a constant
+            let altitude = fdm.v_position.z.to_f64() + (guidance.altitude - fdm.position.altitude) * FEET_PER_METER;
+
+            ap.modes.lateral = LateralMode::HeadingHold(guidance.heading);
+            ap.modes.vertical = VerticalMode::AltitudeHold(altitude);
+            if let Some(airspeed) = guidance.airspeed
+            {
+                ap.modes.speed = SpeedMode::AirspeedHold(airspeed * FEET_PER_METER);
+            }
+        }
+        None => plan.engaged = false,
+    }
+}
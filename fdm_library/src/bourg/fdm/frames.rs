@@ -0,0 +1,84 @@
+//Frame conversions between the Bourg model axes and the navigation frames.
+//Body space is x forward, y left, z up. Earth space is a local tangent plane with x east, y north, z up,
+//so NED is found by swapping x and y and negating z. Distances are in feet in the model and meters in navigation
+
+//nalgebra vector type
+use nalgebra::Vector3;
+
+//Vector and Quaternion
+use crate::bourg::common::vector::{Vector, Vector64};
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::matrix::Matrix;
+use crate::bourg::common::real::{Real, Float};
+
+//Navigation frames
+use crate::navigation::{Geodetic, enu_to_ned, ned_to_enu, ned_to_ecef};
+
+//Feet per meter
+pub use crate::navigation::FEET_PER_METER;
+
+//Earth space (feet) ---> NED (meters)
+pub fn earth_to_ned(v: &Vector) -> Vector3<f64>
+{
+    enu_to_ned(&Vector3::from(v.cast::<f64>())) / FEET_PER_METER
+}
+
+//NED (meters) ---> earth space (feet)
+pub fn ned_to_earth(v_ned: &Vector3<f64>) -> Vector
+{
+    Vector64::from(ned_to_enu(v_ned) * FEET_PER_METER).cast()
+}
+
+//Body space (feet) ---> NED (meters) using the orientation Quaternion
+pub fn body_to_ned(q_orientation: &Quaternion, v_body: &Vector) -> Vector3<f64>
+{
+    earth_to_ned(&Quaternion::qvrotate(q_orientation, v_body))
+}
+
+//NED (meters) ---> body space (feet)
+pub fn ned_to_body(q_orientation: &Quaternion, v_ned: &Vector3<f64>) -> Vector
+{
+    Quaternion::qvrotate(&Quaternion::conjugate(q_orientation), &ned_to_earth(v_ned))
+}
+
+//Body space (feet) ---> ECEF (meters) at a geodetic position
+pub fn body_to_ecef(position: &Geodetic, q_orientation: &Quaternion, v_body: &Vector) -> Vector3<f64>
+{
+    ned_to_ecef(position, &body_to_ned(q_orientation, v_body))
+}
+
+//Orientation of the body in NED with aerospace body axes (x forward, y right, z down).
+//The model's orientation takes body (x forward, y left, z up) into earth space (x east, y north, z up),
+//so it is wrapped by the fixed axis swaps on both sides
+pub fn orientation_ned(q_orientation: &Quaternion) -> Quaternion
+{
+    let (earth_to_ned, frd_to_flu) = axis_swaps();
+    Quaternion::from_rotation_matrix(&(earth_to_ned * q_orientation.to_rotation_matrix() * frd_to_flu))
+}
+
+//Model orientation of an orientation in NED with aerospace body axes, the inverse of orientation_ned.
+//Both axis swaps are their own inverse
+pub fn orientation_from_ned(q_ned: &Quaternion) -> Quaternion
+{
+    let (earth_to_ned, frd_to_flu) = axis_swaps();
+    Quaternion::from_rotation_matrix(&(earth_to_ned * q_ned.to_rotation_matrix() * frd_to_flu))
+}
+
+//Earth space ---> NED and aerospace body axes ---> model body axes
+fn axis_swaps() -> (Matrix, Matrix)
+{
+    let one = Float::from_f64(1.0);
+    let zero = Float::from_f64(0.0);
+    let earth_to_ned = Matrix::new(zero, one, zero,
+                                   one, zero, zero,
+                                   zero, zero, -one);
+    let frd_to_flu = Matrix::diagonal(&Vector::new(one, -one, -one));
+    (earth_to_ned, frd_to_flu)
+}
+
+//Aerospace attitude in radians: x = roll (right wing down positive), y = pitch (nose up positive),
+//z = heading (clockwise from north), using the yaw-pitch-roll sequence in NED
+pub fn attitude_ned(q_orientation: &Quaternion) -> Vector
+{
+    orientation_ned(q_orientation).to_euler_zyx()
+}
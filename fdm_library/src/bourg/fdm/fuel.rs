@@ -0,0 +1,63 @@
+//Functions to burn the fuel of the engines on the airplane
+
+//DataFDM struct and the mounted engines
+use crate::bourg::fdm::structures::{DataFDM, Powerplant};
+
+//Mass properties, recalculated as the fuel burns
+use crate::bourg::fdm::mass_properties::calc_airplane_mass_properties;
+
+//Engine models
+use crate::engine::PistonEngine;
+use crate::jet::JetEngine;
+
+//Kilograms per slug
+pub const KILOGRAMS_PER_SLUG: f64 = 14.594;
+
+//Burn the fuel flow of the engines, the airplane's engine or jet and the mounted engines, for dt seconds from the tanks
+//in feed order, and recalculate the mass, CG and inertia as the fuel burns. Once the tanks are empty the engines stop.
+//Mounted engines without an engine model burn no fuel, and an airplane without tanks is left alone
+pub fn burn_fuel(fdm: &mut DataFDM, engine: Option<&mut PistonEngine>, jet: Option<&mut JetEngine>, dt: f64)
+{
+    if fdm.fuel.tanks.is_empty()
+    {
+        return;
+    }
+
+    let mut fuel_flow = engine.as_ref().map_or(0.0, |engine| engine.fuel_flow) + jet.as_ref().map_or(0.0, |jet| jet.fuel_flow);
+    for mount in fdm.engines.iter()
+    {
+        fuel_flow += match &mount.powerplant
+        {
+            Powerplant::Piston(engine, _) => engine.fuel_flow,
+            Powerplant::Jet(engine) => engine.fuel_flow,
+            Powerplant::Thrust(_) => 0.0,
+        };
+    }
+
+    if fdm.fuel.drain(fuel_flow, dt) > 0.0
+    {
+        calc_airplane_mass_properties(fdm);
+    }
+
+    //Fuel exhaustion
+    if fdm.fuel.exhausted()
+    {
+        if let Some(engine) = engine
+        {
+            engine.running = false;
+        }
+        if let Some(jet) = jet
+        {
+            jet.shutdown();
+        }
+        for mount in fdm.engines.iter_mut()
+        {
+            match &mut mount.powerplant
+            {
+                Powerplant::Piston(engine, _) => engine.running = false,
+                Powerplant::Jet(engine) => engine.shutdown(),
+                Powerplant::Thrust(_) => (),
+            }
+        }
+    }
+}
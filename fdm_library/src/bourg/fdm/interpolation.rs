@@ -0,0 +1,33 @@
+//Functions to record and interpolate the airplane pose between physics steps for the output Systems
+
+//Get data needed
+use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::structures::Pose;
+use crate::navigation::Geodetic;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::{Real, Float};
+use crate::scheduler::{lerp, lerp_angle};
+
+//Take a copy of the current pose
+pub fn snapshot(fdm: &DataFDM) -> Pose
+{
+    Pose
+    {
+        position: fdm.position,
+        q_orientation: fdm.q_orientation,
+    }
+}
+
+//Blend two poses, alpha = 0.0 gives the previous pose and alpha = 1.0 the current pose.
+//Position is linearly interpolated and the orientation is slerped along the shorter arc
+pub fn interpolate(previous: &Pose, current: &Pose, alpha: Float) -> Pose
+{
+    let t = alpha.to_f64();
+    let position = Geodetic::new(lerp(previous.position.latitude, current.position.latitude, t),
+                                 lerp_angle(previous.position.longitude, current.position.longitude, t),
+                                 lerp(previous.position.altitude, current.position.altitude, t));
+
+    let q_orientation = Quaternion::slerp(&previous.q_orientation, &current.q_orientation, alpha);
+
+    Pose { position, q_orientation }
+}
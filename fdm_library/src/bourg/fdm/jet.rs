@@ -0,0 +1,36 @@
+//Functions to run a jet engine on the airplane
+
+//DataFDM and KeyboardState structs
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState};
+
+//Precision of the Bourg model
+use crate::bourg::common::real::{Real, Float};
+
+//Feet per meter and newtons per pound
+use crate::bourg::fdm::frames::FEET_PER_METER;
+use crate::bourg::fdm::propeller::NEWTONS_PER_POUND;
+
+//Throttle of the thrust lever
+use crate::bourg::fdm::engine::throttle;
+
+//Engine and the air it runs in
+use crate::jet::JetEngine;
+use crate::atmosphere::Atmosphere;
+
+//Apply the starter key. This runs at the input rate
+pub fn handle_jet_input(engine: &mut JetEngine, keystate: &KeyboardState)
+{
+    if keystate.engine_start && !engine.running
+    {
+        engine.start();
+    }
+}
+
+//Run the engine for dt seconds. The thrust lever, thrustforce as a fraction of max_thrust, is the throttle,
+//and the thrust of the engine takes the place of thrustforce along the body x axis
+pub fn run_jet(fdm: &mut DataFDM, engine: &mut JetEngine, max_thrust: Float, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.altitude);
+    engine.update(throttle(fdm, max_thrust), &air, fdm.f_speed.to_f64() / FEET_PER_METER, dt);
+    fdm.engine_thrust = Some(Float::from_f64(engine.thrust / NEWTONS_PER_POUND));
+}
@@ -1,14 +1,18 @@
-//Functions to activate airplane components when respective key is pressed
+//Functions to activate airplane components when respective key is pressed.
+//They set the commands, an airplane with Actuators flies on the actual positions of its controls
 
 //DataFDM struct
 use crate::bourg::fdm::structures::DataFDM;
 
-pub fn thrust_up(fdm: &mut DataFDM, d_thrust: f32)
+//Precision of the Bourg model
+use crate::bourg::common::real::Float;
+
+pub fn thrust_up(fdm: &mut DataFDM, d_thrust: Float)
 {
     fdm.thrustforce = fdm.thrustforce + d_thrust;
 }
 
-pub fn thrust_down(fdm: &mut DataFDM, d_thrust: f32)
+pub fn thrust_down(fdm: &mut DataFDM, d_thrust: Float)
 {
     fdm.thrustforce = fdm.thrustforce - d_thrust;
 }
@@ -0,0 +1,82 @@
+//This file calculates the ground reaction on the landing gear (called inside calc_airplane_loads)
+//and the gear contact of the state a physics step ends on
+
+//Get DataFDM struct
+use crate::bourg::fdm::structures::{DataFDM, LandingGear};
+
+//Vector, quaternion modules
+use crate::bourg::common::vector::Vector;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Float;
+
+//Slip speed (ft/s) below which the tire friction is proportional to the slip, so a stopped wheel can stay at rest
+const SLIP_SPEED: Float = 1.0;
+
+//Compression (ft) of a gear leg, zero when its contact point is above the ground.
+//The ground is flat at f_ground_elevation
+fn compression(fdm: &DataFDM, gear: &LandingGear) -> Float
+{
+    let r_earth = Quaternion::qvrotate(&fdm.q_orientation, &gear.v_cg_coords);
+    let height = fdm.v_position.z + r_earth.z - fdm.f_ground_elevation;
+    if height >= 0.0 { 0.0 } else { -height }
+}
+
+//Force and moment about the CG in body space from every gear leg touching the ground.
+//Each leg is a spring and damper pushing straight up, with friction along the direction the wheel rolls and across it.
+//The loads are evaluated at every stage of the integrator, so they leave the gear state alone
+pub fn calc_gear_loads(fdm: &DataFDM) -> (Vector, Vector)
+{
+    let mut force = Vector::new(0.0, 0.0, 0.0);
+    let mut moment = Vector::new(0.0, 0.0, 0.0);
+
+    //Direction the wheels roll, the body x axis flattened onto the ground, and the direction across them (left)
+    let mut v_forward = Quaternion::qvrotate(&fdm.q_orientation, &Vector::new(1.0, 0.0, 0.0));
+    v_forward.z = 0.0;
+    if v_forward.magnitude() > 1.0e-6
+    {
+        v_forward.normalize();
+    }
+    let v_side = Vector::new(-v_forward.y, v_forward.x, 0.0);
+
+    for gear in fdm.gear.iter()
+    {
+        let f_compression = compression(fdm, gear);
+        if f_compression <= 0.0
+        {
+            continue;
+        }
+
+        //Earth space velocity of the contact point, from the linear motion plus the rotation
+        let v_point = fdm.v_velocity + Quaternion::qvrotate(&fdm.q_orientation, &fdm.v_angular_velocity.cross(&gear.v_cg_coords));
+
+        //The ground can push but not pull
+        let mut normal = gear.f_stiffness * f_compression - gear.f_damping * v_point.z;
+        if normal < 0.0
+        {
+            normal = 0.0;
+        }
+
+        //Friction opposes the slip of the tire, rolling (plus brakes) along it and sideways across it
+        let rolling = -(gear.f_rolling_friction + fdm.f_brake * gear.f_brake_friction) * normal * (v_point.dot(&v_forward) / SLIP_SPEED).clamp(-1.0, 1.0);
+        let side = -gear.f_side_friction * normal * (v_point.dot(&v_side) / SLIP_SPEED).clamp(-1.0, 1.0);
+
+        let f_earth = Vector::new(0.0, 0.0, normal) + v_forward * rolling + v_side * side;
+        let f_body = Quaternion::qvrotate(&Quaternion::conjugate(&fdm.q_orientation), &f_earth);
+
+        force += f_body;
+        moment += gear.v_cg_coords.cross(&f_body);
+    }
+
+    (force, moment)
+}
+
+//Set the compression of every gear leg from the state a physics step ended on, and return whether any touches the ground
+pub fn update_gear_contact(fdm: &mut DataFDM) -> bool
+{
+    let compressions: Vec<Float> = fdm.gear.iter().map(|gear| compression(fdm, gear)).collect();
+    for (gear, f_compression) in fdm.gear.iter_mut().zip(compressions)
+    {
+        gear.f_compression = f_compression;
+    }
+    fdm.gear.iter().any(|gear| gear.f_compression > 0.0)
+}
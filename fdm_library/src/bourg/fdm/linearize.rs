@@ -0,0 +1,371 @@
+//Linear state-space model of the Bourg airplane around a trim point, x_dot = A x + B u,
+//found by perturbing the states and controls and differencing the equations of motion.
+//The states use the aerospace conventions of control design tools rather than the model's own axes:
+//body velocities and rates in x forward, y right, z down, Euler angles in NED and position north, east, down.
+//Units are ft, ft/s, radians and rad/s. The controls are the continuous SurfaceControls used by the trim
+
+//Serialization for export
+use serde::Serialize;
+
+//File output
+use std::fs;
+use std::io;
+
+//nalgebra matrices for the eigenvalues
+use nalgebra::DMatrix;
+
+//DataFDM structure, loads and continuous controls
+use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::calc_loads::calc_airplane_loads;
+use crate::bourg::fdm::trim::SurfaceControls;
+
+//Vector, Matrix, Quaternion
+use crate::bourg::common::vector::Vector64;
+use crate::bourg::common::matrix::Matrix64;
+use crate::bourg::common::quaternion::{Quaternion, Quaternion64};
+
+pub const STATE_NAMES: [&str; 12] = ["u", "v", "w", "p", "q", "r", "phi", "theta", "psi", "north", "east", "down"];
+pub const CONTROL_NAMES: [&str; 4] = ["thrust", "elevator", "aileron", "rudder"];
+
+//Indices of the longitudinal (u, w, q, theta) and lateral-directional (v, p, r, phi) states
+pub const LONGITUDINAL_STATES: [usize; 4] = [0, 2, 4, 7];
+pub const LATERAL_STATES: [usize; 4] = [1, 3, 5, 6];
+
+//Perturbations for the numerical Jacobians
+const STATE_PERTURBATION: [f64; 12] = [0.1, 0.1, 0.1, 0.001, 0.001, 0.001, 0.001, 0.001, 0.001, 1.0, 1.0, 1.0];
+const CONTROL_PERTURBATION: [f64; 4] = [1.0, 0.01, 0.01, 0.01];
+
+//Eigenvalues with an imaginary part this small relative to their magnitude are real roots
+const REAL_ROOT_TOLERANCE: f64 = 1.0e-6;
+
+//One dynamic mode from an eigenvalue (for a complex pair, the one with positive imaginary part)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mode
+{
+    pub name: String,
+    pub real: f64,
+    pub imag: f64,
+    pub frequency: f64, //undamped natural frequency, rad/s
+    pub damping: f64, //damping ratio, 1 for a stable real root and -1 for an unstable one
+}
+
+impl Mode
+{
+    fn new(name: &str, real: f64, imag: f64) -> Mode
+    {
+        let frequency = (real * real + imag * imag).sqrt();
+        let damping = if frequency > 0.0 { -real / frequency } else { 0.0 };
+        Mode { name: name.to_string(), real, imag, frequency, damping }
+    }
+
+    //Period of the oscillation in seconds, None for a real root
+    pub fn period(&self) -> Option<f64>
+    {
+        if self.imag > 0.0 { Some(2.0 * std::f64::consts::PI / self.imag) } else { None }
+    }
+
+    //Time to halve (stable) or double (unstable) the amplitude in seconds
+    pub fn time_to_half(&self) -> f64
+    {
+        std::f64::consts::LN_2 / self.real.abs()
+    }
+}
+
+//State-space model around a trim point
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinearModel
+{
+    pub states: Vec<String>,
+    pub controls: Vec<String>,
+    pub x0: Vec<f64>, //trim state
+    pub u0: Vec<f64>, //trim controls
+    pub a: Vec<Vec<f64>>,
+    pub b: Vec<Vec<f64>>,
+    pub modes: Vec<Mode>,
+}
+
+impl LinearModel
+{
+    //Eigenvalues (real, imaginary) of A restricted to some of the states
+    pub fn eigenvalues(&self, states: &[usize]) -> Vec<(f64, f64)>
+    {
+        let n = states.len();
+        let a = DMatrix::from_fn(n, n, |i, j| self.a[states[i]][states[j]]);
+        a.complex_eigenvalues().iter().map(|c| (c.re, c.im)).collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String>
+    {
+        serde_json::to_string_pretty(self)
+    }
+
+    //A, B and the modes as three comma separated tables, each headed by its name
+    pub fn to_csv(&self) -> String
+    {
+        let mut csv = String::new();
+
+        csv.push_str(&format!("A,{}\n", self.states.join(",")));
+        for (name, row) in self.states.iter().zip(self.a.iter())
+        {
+            csv.push_str(&csv_row(name, row));
+        }
+
+        csv.push_str(&format!("\nB,{}\n", self.controls.join(",")));
+        for (name, row) in self.states.iter().zip(self.b.iter())
+        {
+            csv.push_str(&csv_row(name, row));
+        }
+
+        csv.push_str("\nmode,real,imag,frequency,damping\n");
+        for mode in self.modes.iter()
+        {
+            csv.push_str(&csv_row(&mode.name, &[mode.real, mode.imag, mode.frequency, mode.damping]));
+        }
+        csv
+    }
+
+    pub fn write_json(&self, path: &str) -> io::Result<()>
+    {
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn write_csv(&self, path: &str) -> io::Result<()>
+    {
+        fs::write(path, self.to_csv())
+    }
+}
+
+fn csv_row(name: &str, values: &[f64]) -> String
+{
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("{},{}\n", name, values.join(","))
+}
+
+//Linearize the airplane about its current state and controls, normally straight after trim().
+//The airplane is put back the way it was found
+pub fn linearize(fdm: &mut DataFDM) -> LinearModel
+{
+    let x0 = save_state(fdm);
+    let u0 = SurfaceControls::from_fdm(fdm);
+    let controls = |u: &[f64]| SurfaceControls { thrust: u[0], elevator: u[1], aileron: u[2], rudder: u[3], ..u0 };
+    let u0_vec = vec![u0.thrust, u0.elevator, u0.aileron, u0.rudder];
+
+    //Columns of A
+    let mut a = vec![vec![0.0; 12]; 12];
+    for j in 0..12
+    {
+        let column = difference(STATE_PERTURBATION[j], |h|
+        {
+            let mut x = x0.clone();
+            x[j] += h;
+            state_derivatives(fdm, &x, &u0)
+        });
+        for i in 0..12
+        {
+            a[i][j] = column[i];
+        }
+    }
+
+    //Columns of B
+    let mut b = vec![vec![0.0; 4]; 12];
+    for j in 0..4
+    {
+        let column = difference(CONTROL_PERTURBATION[j], |h|
+        {
+            let mut u = u0_vec.clone();
+            u[j] += h;
+            state_derivatives(fdm, &x0, &controls(&u))
+        });
+        for i in 0..12
+        {
+            b[i][j] = column[i];
+        }
+    }
+
+    //Put the airplane back at the trim point
+    state_derivatives(fdm, &x0, &u0);
+
+    let mut model = LinearModel
+    {
+        states: STATE_NAMES.iter().map(|s| s.to_string()).collect(),
+        controls: CONTROL_NAMES.iter().map(|s| s.to_string()).collect(),
+        x0,
+        u0: u0_vec,
+        a,
+        b,
+        modes: Vec::new(),
+    };
+    model.modes = dynamic_modes(&model);
+    model
+}
+
+//Derivative of f at zero by differences of size h. The airfoil tables are piecewise linear and the rudder
+//lift jumps at zero angle of attack, so where the two one-sided differences disagree the central difference
+//would measure the jump instead of the slope. The smaller one-sided difference is used there instead
+fn difference<F>(h: f64, mut f: F) -> Vec<f64>
+    where F: FnMut(f64) -> Vec<f64>
+{
+    let f0 = f(0.0);
+    let f_plus = f(h);
+    let f_minus = f(-h);
+
+    (0..f0.len()).map(|i|
+    {
+        let forward = (f_plus[i] - f0[i]) / h;
+        let backward = (f0[i] - f_minus[i]) / h;
+        if (forward - backward).abs() <= 0.1 * forward.abs().max(backward.abs()) + 1.0e-6
+        {
+            0.5 * (forward + backward)
+        }
+        else if forward.abs() < backward.abs()
+        {
+            forward
+        }
+        else
+        {
+            backward
+        }
+    }).collect()
+}
+
+//Fixed axis swaps: earth space (x east, y north, z up) <---> NED and body (x forward, y left, z up) <---> (x forward, y right, z down)
+fn earth_to_ned() -> Matrix64
+{
+    Matrix64::new(0.0, 1.0, 0.0,
+                  1.0, 0.0, 0.0,
+                  0.0, 0.0, -1.0)
+}
+
+fn flu_to_frd() -> Matrix64
+{
+    Matrix64::diagonal(&Vector64::new(1.0, -1.0, -1.0))
+}
+
+//Linear model state of the airplane
+pub fn save_state(fdm: &DataFDM) -> Vec<f64>
+{
+    let q: Quaternion64 = fdm.q_orientation.cast();
+    let m_body_to_earth = q.to_rotation_matrix();
+    let m_frd_to_ned = earth_to_ned() * m_body_to_earth * flu_to_frd();
+    let euler = Quaternion64::from_rotation_matrix(&m_frd_to_ned).to_euler_zyx();
+
+    let v_body = flu_to_frd() * (m_body_to_earth.transpose() * fdm.v_velocity.cast::<f64>());
+    let omega = flu_to_frd() * fdm.v_angular_velocity.cast::<f64>();
+    let position = earth_to_ned() * fdm.v_position.cast::<f64>();
+
+    vec![v_body.x, v_body.y, v_body.z, omega.x, omega.y, omega.z, euler.x, euler.y, euler.z, position.x, position.y, position.z]
+}
+
+//Set the airplane to a linear model state and controls, then return the state derivatives
+pub fn state_derivatives(fdm: &mut DataFDM, x: &[f64], controls: &SurfaceControls) -> Vec<f64>
+{
+    let (phi, theta) = (x[6], x[7]);
+    let m_frd_to_ned = Quaternion64::from_euler_zyx(phi, theta, x[8]).to_rotation_matrix();
+    let m_body_to_earth = earth_to_ned() * m_frd_to_ned * flu_to_frd();
+
+    let v_body = Vector64::new(x[0], x[1], x[2]);
+    let omega = Vector64::new(x[3], x[4], x[5]);
+    let v_earth = m_body_to_earth * (flu_to_frd() * v_body);
+
+    controls.apply(fdm);
+    fdm.q_orientation = Quaternion64::from_rotation_matrix(&m_body_to_earth).cast();
+    fdm.v_velocity = v_earth.cast();
+    fdm.v_velocity_body = (flu_to_frd() * v_body).cast();
+    fdm.v_angular_velocity = (flu_to_frd() * omega).cast();
+    fdm.v_position = (earth_to_ned() * Vector64::new(x[9], x[10], x[11])).cast();
+    fdm.f_speed = fdm.v_velocity.magnitude();
+    fdm.v_euler_angles = Quaternion::make_euler_from_q(&fdm.q_orientation);
+
+    //Forces (earth space) and moments (body space)
+    calc_airplane_loads(fdm);
+
+    let ae: Vector64 = (fdm.v_forces / fdm.mass).cast();
+    let alpha = fdm.m_inertia_inverse *
+        (fdm.v_moments - fdm.v_angular_velocity.cross(&(fdm.m_inertia * fdm.v_angular_velocity)));
+
+    //Body velocity and rates in x forward, y right, z down
+    let v_dot = flu_to_frd() * (m_body_to_earth.transpose() * ae) - omega.cross(&v_body);
+    let omega_dot = flu_to_frd() * alpha.cast::<f64>();
+
+    //Euler angle rates
+    let (p, q, r) = (omega.x, omega.y, omega.z);
+    let phi_dot = p + (q * phi.sin() + r * phi.cos()) * theta.tan();
+    let theta_dot = q * phi.cos() - r * phi.sin();
+    let psi_dot = (q * phi.sin() + r * phi.cos()) / theta.cos();
+
+    let v_ned = m_frd_to_ned * v_body;
+
+    vec![v_dot.x, v_dot.y, v_dot.z, omega_dot.x, omega_dot.y, omega_dot.z, phi_dot, theta_dot, psi_dot, v_ned.x, v_ned.y, v_ned.z]
+}
+
+//Classic modes from the longitudinal and lateral-directional blocks of A.
+//Longitudinal: the faster pair of roots is the short period and the slower pair the phugoid.
+//Lateral-directional: the oscillatory pair is the Dutch roll, the fastest real root the roll subsidence
+//and the slowest the spiral. Pairs that are real instead of oscillatory are reported as two roots with the same name
+pub fn dynamic_modes(model: &LinearModel) -> Vec<Mode>
+{
+    let mut modes = Vec::new();
+
+    //Longitudinal
+    let mut roots = model.eigenvalues(&LONGITUDINAL_STATES);
+    roots.sort_by(|a, b| magnitude(b).total_cmp(&magnitude(a)));
+    for (i, root) in roots.iter().enumerate()
+    {
+        let name = if i < 2 { "short period" } else { "phugoid" };
+        if is_real(root)
+        {
+            modes.push(Mode::new(name, root.0, 0.0));
+        }
+        else if root.1 > 0.0
+        {
+            modes.push(Mode::new(name, root.0, root.1));
+        }
+    }
+
+    //Lateral-directional
+    let roots = model.eigenvalues(&LATERAL_STATES);
+    let mut real: Vec<(f64, f64)> = roots.iter().filter(|r| is_real(r)).cloned().collect();
+    real.sort_by(|a, b| magnitude(b).total_cmp(&magnitude(a)));
+    if real.len() == 2
+    {
+        if let Some(pair) = roots.iter().find(|r| !is_real(r) && r.1 > 0.0)
+        {
+            modes.push(Mode::new("dutch roll", pair.0, pair.1));
+        }
+        modes.push(Mode::new("roll subsidence", real[0].0, 0.0));
+        modes.push(Mode::new("spiral", real[1].0, 0.0));
+    }
+    else if real.len() == 4
+    {
+        //No oscillation, the middle roots are the overdamped Dutch roll
+        let names = ["roll subsidence", "dutch roll", "dutch roll", "spiral"];
+        for (name, root) in names.iter().zip(real.iter())
+        {
+            modes.push(Mode::new(name, root.0, 0.0));
+        }
+    }
+    else
+    {
+        //Two oscillatory pairs: the slower one is the coupled roll-spiral oscillation
+        let mut pairs: Vec<(f64, f64)> = roots.iter().filter(|r| !is_real(r) && r.1 > 0.0).cloned().collect();
+        pairs.sort_by(|a, b| magnitude(b).total_cmp(&magnitude(a)));
+        let names = ["dutch roll", "roll-spiral"];
+        for (name, root) in names.iter().zip(pairs.iter())
+        {
+            modes.push(Mode::new(name, root.0, root.1));
+        }
+    }
+
+    modes
+}
+
+fn magnitude(root: &(f64, f64)) -> f64
+{
+    (root.0 * root.0 + root.1 * root.1).sqrt()
+}
+
+//Whether a root is real, the eigenvalue solver leaves a round-off imaginary part on near-real roots
+fn is_real(root: &(f64, f64)) -> bool
+{
+    root.1.abs() <= REAL_ROOT_TOLERANCE * magnitude(root)
+}
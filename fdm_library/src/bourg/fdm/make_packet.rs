@@ -2,22 +2,44 @@
 
 //Get data needed for the System to work
 use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::structures::Pose;
 use crate::flightgear::FGNetFDM;
 
+//Pose helpers
+use crate::bourg::fdm::interpolation::snapshot;
+use crate::bourg::fdm::frames::attitude_ned;
+use crate::bourg::common::real::Real;
+
+//Stall warning
+use crate::stall::load_stall_warning;
+
 pub fn load_fgnetfdm(fdm: &DataFDM, fgnet: &mut FGNetFDM)
 {
     //All data passed into the FGNetFDM struct is converted to network byte order
-    
-    //Set Roll, Pitch, Yaw in radians
-    //Negate to compensate for coordinate handedness differences
-    let roll: f32 = fdm.v_euler_angles.x.to_radians() as f32;
-    let pitch: f32 = -fdm.v_euler_angles.y.to_radians() as f32; 
-    let yaw: f32 = (90.0 + -fdm.v_euler_angles.z).to_radians() as f32;
-
-    //Lat and lon degrees need to be converted to radians for FlightGear
-    let lat: f64 = fdm.v_position.x.to_radians() as f64;
-    let lon: f64 = fdm.v_position.y.to_radians() as f64;
-    let alt: f64 = fdm.v_position.z as f64; 
+
+    //Position and attitude
+    load_pose(&snapshot(fdm), fgnet);
+
+    //Other airplane data
+    let fg_net_fdm_version = 24_u32;
+    fgnet.version = u32::from_be_bytes(fg_net_fdm_version.to_ne_bytes());
+    load_stall_warning(fdm.f_stall_warning.to_f64(), fgnet);
+
+}
+
+//Load the position and attitude of a pose, this is also used with an interpolated pose
+pub fn load_pose(pose: &Pose, fgnet: &mut FGNetFDM)
+{
+    //Roll, Pitch, Yaw in radians in FlightGear's NED convention
+    let attitude = attitude_ned(&pose.q_orientation);
+    let roll: f32 = attitude.x.to_f64() as f32;
+    let pitch: f32 = attitude.y.to_f64() as f32; 
+    let yaw: f32 = attitude.z.to_f64() as f32;
+
+    //Geodetic position is already in radians and meters
+    let lat: f64 = pose.position.latitude;
+    let lon: f64 = pose.position.longitude;
+    let alt: f64 = pose.position.altitude;
 
     //Set lat, long, alt
     fgnet.latitude = f64::from_be_bytes(lat.to_ne_bytes());
@@ -28,9 +50,4 @@ pub fn load_fgnetfdm(fdm: &DataFDM, fgnet: &mut FGNetFDM)
     fgnet.phi = f32::from_be_bytes(roll.to_ne_bytes());
     fgnet.theta = f32::from_be_bytes(pitch.to_ne_bytes()); 
     fgnet.psi = f32::from_be_bytes(yaw.to_ne_bytes());
-
-    //Other airplane data
-    let fg_net_fdm_version = 24_u32;
-    fgnet.version = u32::from_be_bytes(fg_net_fdm_version.to_ne_bytes());
-
 }
\ No newline at end of file
@@ -1,9 +1,10 @@
-//Calculate mass properties based on the airplane's different body pieces
-//This is called from inside main before the airplane Entity is created
+//Calculate mass properties based on the airplane's different body pieces and the fuel in its tanks
+//This is called from inside main before the airplane Entity is created, and again as the fuel burns
 
 //Vector, Matrix
-use crate::bourg::common::vector::Vector;
+use crate::bourg::common::vector::{Vector, Vector64};
 use crate::bourg::common::matrix::Matrix;
+use crate::bourg::common::real::{Real, Float};
 
 //Math utils
 use crate::bourg::common::math_utils::deg_to_rad;
@@ -11,82 +12,86 @@ use crate::bourg::common::math_utils::deg_to_rad;
 //DataFDM structure
 use crate::bourg::fdm::structures::DataFDM;
 
+//Kilograms per slug for the fuel
+use crate::bourg::fdm::fuel::KILOGRAMS_PER_SLUG;
+
 pub fn calc_airplane_mass_properties(fdm: &mut DataFDM)
 {
-    let mut inc: f32;
-    let mut di: f32;
+    calc_element_normals(fdm);
 
-    //Calculate the normal (perpendicular) vector to each lifting surface. This is needed for relative air velocity to find lift and drag.
-    for  i in fdm.element.iter_mut()
-    {
-        inc = deg_to_rad(i.f_incidence);
-        di = deg_to_rad(i.f_dihedral);
-        i.v_normal = Vector::new(inc.sin(), inc.cos() * di.sin(), inc.cos() * di.cos());
-        i.v_normal.normalize(); 
-    }
+    //Fuel in each tank as a point mass (slugs) at the tank position
+    let tanks: Vec<(Float, Vector)> = fdm.fuel.tanks.iter().map(|tank| (Float::from_f64(tank.quantity / KILOGRAMS_PER_SLUG), Vector64::from(tank.position).cast::<Float>())).collect();
 
     //Calculate total mass
-    let mut total_mass: f32 = 0.0;
+    let mut total_mass: Float = 0.0;
     for i in fdm.element.iter()
     {
-        total_mass = total_mass + i.f_mass;
+        total_mass += i.f_mass;
+    }
+    for (f_mass, _) in tanks.iter()
+    {
+        total_mass += *f_mass;
     }
 
     //Calculate combined center of gravity location
     let mut v_moment = Vector::new(0.0,0.0,0.0);
     for i in fdm.element.iter()
     {
-        let _tmp = i.v_d_coords * i.f_mass;
-        v_moment = v_moment + _tmp;
+        v_moment += i.v_d_coords * i.f_mass;
+    }
+    for (f_mass, v_d_coords) in tanks.iter()
+    {
+        v_moment += *v_d_coords * *f_mass;
     }
     let cg = v_moment / total_mass; 
+    fdm.v_cg = cg;
 
-    //Calculate coordinates of each element with respect to the combined CG, relative position
+    //Calculate coordinates of each element, landing gear contact point and engine with respect to the combined CG, relative position
     for i in fdm.element.iter_mut()
     {
         i.v_cg_coords = i.v_d_coords - cg;
     }
+    for i in fdm.gear.iter_mut()
+    {
+        i.v_cg_coords = i.v_d_coords - cg;
+    }
+    for i in fdm.engines.iter_mut()
+    {
+        i.v_cg_coords = i.v_d_coords - cg;
+    }
 
-    //Calculate the moments and products of intertia for the combined elements
-    let mut ixx: f32 = 0.0;
-    let mut iyy: f32 = 0.0;
-    let mut izz: f32 = 0.0;
-    let mut ixy: f32 = 0.0;
-    let mut ixz: f32 = 0.0;
-    let mut iyz: f32 = 0.0;
-
+    //Calculate the inertia tensor for the combined elements: the local inertia of each element
+    //plus its point mass inertia about the combined CG (parallel axis theorem)
+    let mut m_inertia = Matrix::default();
     for i in fdm.element.iter()
     {
-        ixx = ixx + i.v_local_inertia.x + i.f_mass *
-            (i.v_cg_coords.y * i.v_cg_coords.y +
-            i.v_cg_coords.z * i.v_cg_coords.z);
-
-        iyy = iyy + i.v_local_inertia.y + i.f_mass *
-            (i.v_cg_coords.z * i.v_cg_coords.z +
-            i.v_cg_coords.x * i.v_cg_coords.x);
-
-        izz = izz + i.v_local_inertia.z + i.f_mass *
-            (i.v_cg_coords.x * i.v_cg_coords.x +
-            i.v_cg_coords.y * i.v_cg_coords.y);
-
-        ixy = ixy + i.f_mass * (i.v_cg_coords.x * 
-            i.v_cg_coords.y);
-
-        ixz = ixz + i.f_mass * (i.v_cg_coords.x * 
-            i.v_cg_coords.z);
-        
-        iyz = iyz + i.f_mass * (i.v_cg_coords.y *
-            i.v_cg_coords.z);
+        m_inertia = m_inertia + Matrix::diagonal(&i.v_local_inertia) + Matrix::point_mass_inertia(i.f_mass, &i.v_cg_coords);
+    }
+    for (f_mass, v_d_coords) in tanks.iter()
+    {
+        m_inertia += Matrix::point_mass_inertia(*f_mass, &(*v_d_coords - cg));
     }
 
     //Finally, set up airplanes mass and inertia Matrix
     fdm.mass = total_mass;
-    fdm.m_inertia = Matrix::new(ixx, -ixy, -ixz,
-                                  -ixy, iyy, -iyz,
-                                  -ixz, -iyz, izz);
+    fdm.m_inertia = m_inertia;
 
     //Get inverse of Matrix
     fdm.m_inertia_inverse = Matrix::inverse(&fdm.m_inertia);
 }
 
+//Calculate the normal (perpendicular) vector to each lifting surface. This is needed for relative air velocity to find lift and drag.
+//It is also called by the trim solver after it changes the incidence of the control surfaces
+pub fn calc_element_normals(fdm: &mut DataFDM)
+{
+    let mut inc: Float;
+    let mut di: Float;
 
+    for  i in fdm.element.iter_mut()
+    {
+        inc = deg_to_rad(i.f_incidence);
+        di = deg_to_rad(i.f_dihedral);
+        i.v_normal = Vector::new(inc.sin(), inc.cos() * di.sin(), inc.cos() * di.cos());
+        i.v_normal.normalize(); 
+    }
+}
@@ -0,0 +1,109 @@
+//Function to advance an airplane and the Components it carries by one physics step
+
+//Get Component data needed for the physics step
+use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::structures::StateHistory;
+use crate::bourg::fdm::structures::Actuators;
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+use crate::sensors::Sensors;
+use crate::estimator::Estimator;
+use crate::air_data::AirDataComputer;
+use crate::envelope::EnvelopeMonitor;
+use crate::integrator::Integrator;
+use crate::bourg::common::real::{Real, Float};
+
+//Functions to call
+use crate::bourg::fdm::equations_of_motion::eom;
+use crate::bourg::fdm::interpolation::snapshot;
+use crate::bourg::fdm::actuators::{actuate, restore_commands};
+use crate::bourg::fdm::engine::run_engine;
+use crate::bourg::fdm::propeller::run_propeller;
+use crate::bourg::fdm::jet::run_jet;
+use crate::bourg::fdm::powerplant::run_engines;
+use crate::bourg::fdm::fuel::burn_fuel;
+use crate::bourg::fdm::sensors::sensor_truth;
+use crate::bourg::fdm::air_data::run_air_data;
+use crate::bourg::fdm::envelope::{protect, restore_pitch_command};
+
+//Optional Components of an airplane, each takes part in the physics step when the airplane has it
+pub struct Equipment<'a>
+{
+    pub history: Option<&'a mut StateHistory>,
+    pub actuators: Option<&'a mut Actuators>,
+    pub engine: Option<&'a mut PistonEngine>,
+    pub propeller: Option<&'a mut Propeller>,
+    pub jet: Option<&'a mut JetEngine>,
+    pub sensors: Option<&'a mut Sensors>,
+    pub estimator: Option<&'a mut Estimator>,
+    pub air_data: Option<&'a mut AirDataComputer>,
+    pub monitor: Option<&'a EnvelopeMonitor>,
+}
+
+//Advance the airplane by one physics step of dt seconds, eom calls calc_loads
+pub fn physics_step(fdm: &mut DataFDM, equipment: &mut Equipment, max_thrust: Float, dt: Float, integrator: &Integrator)
+{
+    if let Some(history) = equipment.history.as_mut()
+    {
+        history.previous = snapshot(fdm);
+    }
+
+    //An EnvelopeMonitor that protects the airplane takes over its pitch command for the step
+    let protected = equipment.monitor.and_then(|monitor| protect(fdm, monitor, dt.to_f64()));
+
+    //With Actuators the airplane flies on the actual positions of its controls, which follow the commands
+    if let Some(actuators) = equipment.actuators.as_mut()
+    {
+        actuate(fdm, actuators, dt.to_f64());
+    }
+
+    //A PistonEngine gives the thrust, through its Propeller when it has one, or a JetEngine does.
+    //The engines mounted on the airplane run as well, and the engines burn the fuel in the tanks
+    match (equipment.engine.as_mut(), equipment.propeller.as_mut())
+    {
+        (Some(engine), Some(propeller)) => run_propeller(fdm, engine, propeller, max_thrust, dt.to_f64()),
+        (Some(engine), None) => run_engine(fdm, engine, max_thrust, dt.to_f64()),
+        _ => (),
+    }
+    if let Some(jet) = equipment.jet.as_mut()
+    {
+        run_jet(fdm, jet, max_thrust, dt.to_f64());
+    }
+    run_engines(fdm, max_thrust, dt.to_f64());
+    burn_fuel(fdm, equipment.engine.as_deref_mut(), equipment.jet.as_deref_mut(), dt.to_f64());
+
+    eom(fdm, dt, integrator);
+
+    //The sensors and air data sample the airplane as it flew, before the commands are put back.
+    //The Estimator fuses the sensor outputs and compares the estimate with the state
+    if let Some(sensors) = equipment.sensors.as_mut()
+    {
+        let truth = sensor_truth(fdm);
+        sensors.update(&truth, dt.to_f64());
+        if let Some(estimator) = equipment.estimator.as_mut()
+        {
+            estimator.process(sensors);
+            estimator.compare(&truth);
+        }
+    }
+    if let Some(air_data) = equipment.air_data.as_mut()
+    {
+        run_air_data(fdm, air_data, dt.to_f64());
+    }
+
+    //The inputs and the autopilot keep working on the commands
+    if let Some(actuators) = equipment.actuators.as_ref()
+    {
+        restore_commands(fdm, actuators);
+    }
+    if let Some(command) = protected
+    {
+        restore_pitch_command(fdm, &command);
+    }
+
+    if let Some(history) = equipment.history.as_mut()
+    {
+        history.current = snapshot(fdm);
+    }
+}
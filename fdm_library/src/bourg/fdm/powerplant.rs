@@ -0,0 +1,101 @@
+//Functions to run the engines mounted on the airplane, each at its own position and thrust line
+
+//DataFDM and KeyboardState structs, and the mounted engines
+use crate::bourg::fdm::structures::{DataFDM, KeyboardState, Powerplant};
+
+//Precision of the Bourg model
+use crate::bourg::common::real::{Real, Float};
+
+//Feet per meter and newtons per pound
+use crate::bourg::fdm::frames::FEET_PER_METER;
+use crate::bourg::fdm::propeller::{turn_propeller, NEWTONS_PER_POUND};
+
+//Inputs and throttle of the single engine models, which apply to each mounted engine
+use crate::bourg::fdm::engine::{handle_engine_input, throttle};
+use crate::bourg::fdm::jet::handle_jet_input;
+
+//Air the engines run in
+use crate::atmosphere::Atmosphere;
+
+//FGNetFDM packet structure and the per-engine slots in it
+use crate::flightgear::FGNetFDM;
+use crate::engine::{load_engine_slot, ENGINE_RUNNING};
+use crate::jet::load_jet_slot;
+
+//Apply the mixture and starter keys to every mounted engine. This runs at the input rate, dt seconds after the previous input
+pub fn handle_engines_input(fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
+{
+    for mount in fdm.engines.iter_mut()
+    {
+        match &mut mount.powerplant
+        {
+            Powerplant::Piston(engine, _) => handle_engine_input(engine, keystate, dt),
+            Powerplant::Jet(engine) => handle_jet_input(engine, keystate),
+            Powerplant::Thrust(_) => (),
+        }
+    }
+}
+
+//Run every mounted engine for dt seconds at its own throttle, or at the thrust lever (thrustforce as a fraction of max_thrust)
+//when it follows the lever. Each engine's thrust acts along its thrust line at its position, and the moments of its propeller
+//are kept with it. An airplane without mounted engines is left alone
+pub fn run_engines(fdm: &mut DataFDM, max_thrust: Float, dt: f64)
+{
+    if fdm.engines.is_empty()
+    {
+        return;
+    }
+
+    let lever = throttle(fdm, max_thrust);
+    let air = Atmosphere::standard(fdm.position.altitude);
+    let airspeed = fdm.f_speed.to_f64() / FEET_PER_METER;
+
+    let mut engines = std::mem::take(&mut fdm.engines);
+    for mount in engines.iter_mut()
+    {
+        let throttle = mount.throttle.map_or(lever, |throttle| throttle.to_f64()).clamp(0.0, 1.0);
+        let thrust = match &mut mount.powerplant
+        {
+            Powerplant::Thrust(max) => throttle * max.to_f64(),
+            Powerplant::Piston(engine, propeller) =>
+            {
+                //Foot-pounds for the model
+                let v_moment = turn_propeller(fdm, engine, propeller, throttle, &mount.v_direction.cast::<f64>(), dt);
+                mount.v_propeller_moment = (v_moment * (FEET_PER_METER / NEWTONS_PER_POUND)).cast::<Float>();
+                propeller.thrust / NEWTONS_PER_POUND
+            }
+            Powerplant::Jet(engine) =>
+            {
+                engine.update(throttle, &air, airspeed, dt);
+                engine.thrust / NEWTONS_PER_POUND
+            }
+        };
+        mount.f_thrust = Float::from_f64(thrust);
+    }
+    fdm.engines = engines;
+}
+
+//Load the mounted engines into the per-engine slots of the FlightGear packet, it has room for four
+pub fn load_engines(fdm: &DataFDM, fgnet: &mut FGNetFDM)
+{
+    let count = fdm.engines.len().min(fgnet.rpm.len());
+    fgnet.num_engines = u32::from_be_bytes((count as u32).to_ne_bytes());
+    for (slot, mount) in fdm.engines.iter().take(count).enumerate()
+    {
+        match &mount.powerplant
+        {
+            Powerplant::Piston(engine, _) => load_engine_slot(engine, slot, fgnet),
+            Powerplant::Jet(engine) => load_jet_slot(engine, slot, fgnet),
+            Powerplant::Thrust(_) => fgnet.eng_state[slot] = f32::from_be_bytes(ENGINE_RUNNING.to_ne_bytes()),
+        }
+    }
+}
+
+//Print the thrust of every mounted engine, this is called once per frame
+pub fn print_engines(fdm: &DataFDM)
+{
+    for (i, mount) in fdm.engines.iter().enumerate()
+    {
+        println!("Engine {} (lb):   {}", i + 1, mount.f_thrust);
+    }
+}
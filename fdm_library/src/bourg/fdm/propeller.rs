@@ -0,0 +1,67 @@
+//Functions to turn a propeller with the engine on the airplane
+
+//DataFDM struct
+use crate::bourg::fdm::structures::DataFDM;
+
+//Vector module
+use crate::bourg::common::vector::Vector64;
+use crate::bourg::common::real::{Real, Float};
+
+//Feet per meter
+use crate::bourg::fdm::frames::FEET_PER_METER;
+
+//Throttle of the thrust lever
+use crate::bourg::fdm::engine::throttle;
+
+//Engine, propeller and the air they run in
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::atmosphere::Atmosphere;
+
+//Newtons per pound of force
+pub const NEWTONS_PER_POUND: f64 = 4.448;
+
+//Run the engine for dt seconds turning the propeller, which loads the engine with its torque. The thrust lever,
+//thrustforce as a fraction of max_thrust, is the throttle. The propeller gives the thrust along the body x axis
+//and the moment about the CG of its thrust line, reaction torque, gyroscopic moment and P-factor
+pub fn run_propeller(fdm: &mut DataFDM, engine: &mut PistonEngine, propeller: &mut Propeller, max_thrust: Float, dt: f64)
+{
+    let v_direction = Vector64::new(1.0, 0.0, 0.0);
+    let v_moment = turn_propeller(fdm, engine, propeller, throttle(fdm, max_thrust), &v_direction, dt);
+
+    //Thrust line through the hub
+    let v_hub = (Vector64::from(propeller.data.position) - fdm.v_cg.cast::<f64>()) / FEET_PER_METER;
+    let v_thrust = v_direction * propeller.thrust;
+
+    //Pounds and foot-pounds for the model
+    let v_moment = (v_hub.cross(&v_thrust) + v_moment) * (FEET_PER_METER / NEWTONS_PER_POUND);
+    fdm.v_propeller_moment = v_moment.cast::<Float>();
+    fdm.engine_thrust = Some(Float::from_f64(propeller.thrust / NEWTONS_PER_POUND));
+}
+
+//Run the engine for dt seconds at a throttle setting (0 - 1) turning the propeller about a shaft direction in body space.
+//Return the moment (N m) of the propeller besides its thrust through the hub: the reaction torque, the gyroscopic moment
+//and the P-factor shift of the thrust line
+pub fn turn_propeller(fdm: &DataFDM, engine: &mut PistonEngine, propeller: &mut Propeller, throttle: f64, v_direction: &Vector64, dt: f64) -> Vector64
+{
+    let air = Atmosphere::standard(fdm.position.altitude);
+    let v_velocity: Vector64 = fdm.v_velocity_body.cast::<f64>() / FEET_PER_METER;
+    let speed = v_velocity.magnitude();
+    let inflow = v_velocity.dot(v_direction);
+
+    //The propeller loads the engine at its current speed, then gives its thrust and torque at the new one
+    propeller.update(engine.rpm, inflow, air.density);
+    engine.update(throttle, &air, propeller.engine_load(), dt);
+    propeller.update(engine.rpm, inflow, air.density);
+
+    //Thrust line shifted by the P-factor with the inflow across the disc
+    let v_offset = if speed > 1.0 { (v_velocity - *v_direction * inflow).cross(v_direction) * (propeller.p_factor_shift() / speed) } else { Vector64::new(0.0, 0.0, 0.0) };
+    let v_thrust = *v_direction * propeller.thrust;
+
+    //The airframe turns against the propeller, and its spin resists the airframe turning (gyroscopic moment)
+    let v_reaction = *v_direction * propeller.reaction_torque();
+    let v_spin = *v_direction * propeller.angular_momentum();
+    let v_gyroscopic = -fdm.v_angular_velocity.cast::<f64>().cross(&v_spin);
+
+    v_offset.cross(&v_thrust) + v_reaction + v_gyroscopic
+}
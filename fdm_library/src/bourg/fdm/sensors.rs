@@ -0,0 +1,43 @@
+//Functions to find the truth the sensors measure on the airplane
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM struct
+use crate::bourg::fdm::structures::DataFDM;
+
+//Frame conversions to the aerospace axes of the sensors
+use crate::bourg::fdm::frames::{attitude_ned, earth_to_ned, FEET_PER_METER};
+
+//Gravity
+use crate::bourg::common::constants::G;
+use crate::bourg::common::vector::Vector;
+use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Real;
+
+//Sensor truth
+use crate::sensors::SensorTruth;
+
+//Truth of the airplane's state for the sensors, in aerospace axes and SI units. The specific force is the acceleration
+//over the last physics step without gravity, and the airspeed is the speed through the still air
+pub fn sensor_truth(fdm: &DataFDM) -> SensorTruth
+{
+    //Take gravity back out of the acceleration and rotate it into body space. The forces of the last load
+    //evaluation need not be those of the whole step
+    let specific_force_earth = fdm.v_acceleration - Vector::new(0.0, 0.0, G);
+    let specific_force_body = Quaternion::qvrotate(&Quaternion::conjugate(&fdm.q_orientation), &specific_force_earth);
+
+    //Body space is x forward, y left, z up, the sensor axes are x forward, y right, z down
+    let angular_velocity = &fdm.v_angular_velocity;
+    let attitude = attitude_ned(&fdm.q_orientation);
+
+    SensorTruth
+    {
+        specific_force: Vector3::new(specific_force_body.x.to_f64(), -specific_force_body.y.to_f64(), -specific_force_body.z.to_f64()) / FEET_PER_METER,
+        angular_rate: Vector3::new(angular_velocity.x.to_f64(), -angular_velocity.y.to_f64(), -angular_velocity.z.to_f64()),
+        attitude: Vector3::new(attitude.x.to_f64(), attitude.y.to_f64(), attitude.z.to_f64()),
+        position: fdm.position,
+        velocity_ned: earth_to_ned(&fdm.v_velocity),
+        airspeed: fdm.f_speed.to_f64() / FEET_PER_METER,
+    }
+}
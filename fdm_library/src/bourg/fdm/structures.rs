@@ -4,45 +4,164 @@
 use crate::bourg::common::vector::Vector;
 use crate::bourg::common::matrix::Matrix;
 use crate::bourg::common::quaternion::Quaternion;
+use crate::bourg::common::real::Float;
+
+//Geodetic position
+use crate::navigation::Geodetic;
+
+//Autopilot loops and modes
+use crate::autopilot::{Pid, PidGains, AutopilotModes};
+
+//Actuator dynamics
+use crate::actuator::Actuator;
+
+//Engine models
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+
+//Fuel tanks
+use crate::fuel::FuelSystem;
+
+//Engines an airplane is spawned with
+use crate::crash::SpawnedEngines;
 
 //Elements making up the bodystructure, this is part of the DataFDM structure
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PointMass
 {
-    pub f_mass: f32,
+    pub f_mass: Float,
     pub v_d_coords: Vector, //"design position"
     pub v_local_inertia: Vector,
-    pub f_incidence: f32,
-    pub f_dihedral: f32,
-    pub f_area: f32,
+    pub f_incidence: Float,
+    pub f_dihedral: Float,
+    pub f_area: Float,
     pub i_flap: i32,
     pub v_normal: Vector,
     pub v_cg_coords: Vector //"corrected position"
 }
 
+//Landing gear leg, a spring and damper at the tire contact point with rolling, braking and side friction.
+//This is part of the DataFDM structure, an airplane without gear legs never touches the ground
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LandingGear
+{
+    pub v_d_coords: Vector, //contact point in design coordinates, ft
+    pub f_stiffness: Float, //lb/ft
+    pub f_damping: Float, //lb s/ft
+    pub f_rolling_friction: Float, //friction coefficient rolling freely
+    pub f_brake_friction: Float, //friction coefficient added with the brakes fully on
+    pub f_side_friction: Float, //friction coefficient across the tire
+    pub v_cg_coords: Vector, //contact point relative to the CG, set with the mass properties
+    pub f_compression: Float, //ft, zero when the tire is off the ground
+}
+
+//What turns the throttle of a mounted engine into thrust
+#[derive(Debug, Clone)]
+pub enum Powerplant
+{
+    //Thrust proportional to the throttle, up to a maximum (lb)
+    Thrust(Float),
+    //Piston engine turning a propeller. The propeller turns about the thrust line at the mounting position.
+    //They are boxed, as they are much larger than the other kinds
+    Piston(Box<PistonEngine>, Box<Propeller>),
+    //Jet engine
+    Jet(JetEngine),
+}
+
+//Engine mounted on the airplane, with its own thrust line and throttle. This is part of the DataFDM structure,
+//an airplane without mounted engines has its thrust along the body x axis through the CG
+#[derive(Debug, Clone)]
+pub struct EngineMount
+{
+    pub powerplant: Powerplant,
+    pub v_d_coords: Vector, //point the thrust acts at (propeller hub or nozzle) in design coordinates, ft
+    pub v_direction: Vector, //thrust line in body space, a unit vector
+    pub throttle: Option<Float>, //throttle of this engine (0 - 1), None follows the thrust lever
+    pub v_cg_coords: Vector, //thrust point relative to the CG, set with the mass properties
+    pub f_thrust: Float, //lb along the thrust line, from the latest engine step
+    pub v_propeller_moment: Vector, //ft lb about the CG from the propeller of a piston engine, from the latest engine step
+}
+
+impl EngineMount
+{
+    //Engine at a point in design coordinates, thrusting along the body x axis and following the thrust lever
+    pub fn new(powerplant: Powerplant, v_d_coords: Vector) -> EngineMount
+    {
+        EngineMount
+        {
+            powerplant,
+            v_d_coords,
+            v_direction: Vector::new(1.0, 0.0, 0.0),
+            throttle: None,
+            v_cg_coords: Vector::new(0.0, 0.0, 0.0),
+            f_thrust: 0.0,
+            v_propeller_moment: Vector::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
 //State of the airplane
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct DataFDM
 {
-    pub mass: f32, //total mass
+    pub mass: Float, //total mass
     pub m_inertia: Matrix,
     pub m_inertia_inverse: Matrix,
-    pub v_position: Vector, // position in earth coordinates
-    pub lla_origin: Vector, // geodetic origin position
+    pub v_cg: Vector, // combined center of gravity in design coordinates, set with the mass properties
+    pub v_position: Vector, // position in earth coordinates, x east, y north, z up in feet
+    pub position: Geodetic, // geodetic position in double precision, propagated from the earth velocity
     pub v_velocity: Vector, // velocity in earth coordinates
+    pub v_acceleration: Vector, // earth space acceleration over the last step, from the change in velocity
     pub v_velocity_body: Vector, // velocity in body coordinates
     pub v_angular_velocity: Vector, // angular velocity in body coordinates
     pub v_euler_angles: Vector,   
-    pub f_speed: f32, // speed (magnitude of the velocity)
-    pub stalling: bool,
+    pub f_speed: Float, // speed (magnitude of the velocity)
+    pub stalling: bool, // a wing element is past its stall angle
+    pub f_stall_margins: [Float; 7], // attack angle (deg) left before each lifting element stalls, negative once stalled, the fuselage is not one
+    pub f_stall_asymmetry: Float, // degrees the right wing elements stall before the left ones, negative for the left wing first
+    pub f_stall_warning: Float, // smoothed stall warning (0 - 1) from the wing's stall margin
     pub flaps: bool,
     pub q_orientation: Quaternion, // orientation in earth coordinates 
     pub v_forces: Vector, // total force on body
-    pub thrustforce: f32, // magnitude of thrust
+    pub thrustforce: Float, // magnitude of thrust
+    pub engine_thrust: Option<Float>, // thrust from an engine, propeller or jet engine model, which then takes the place of thrustforce as the thrust lever
     pub v_moments: Vector, // total moment (torque) on body
+    pub v_propeller_moment: Vector, // moment about the CG from the propeller of the single engine: reaction torque, gyroscopic moment, P-factor and its thrust line
     pub element: Vec<PointMass>, // vector of point mass elements
+    pub gear: Vec<LandingGear>, // landing gear legs
+    pub engines: Vec<EngineMount>, // engines mounted at their own positions, each with its own throttle
+    pub fuel: FuelSystem, // fuel tanks in design coordinates, their fuel is part of the mass properties
+    pub f_ground_elevation: Float, // earth space height of the ground under the airplane, ft
+    pub f_brake: Float, // brake setting (0 - 1)
+    pub on_ground: bool, // a landing gear leg is touching the ground
+    pub f_touchdown_rate: Float, // earth space vertical speed at the last touchdown, negative down
+    pub f_alpha_protection: Float, // nose down elevator incidence (deg) the angle of attack protection holds, on top of the command
+}
+
+//Position and orientation of the airplane after a physics step
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Pose
+{
+    pub position: Geodetic,
+    pub q_orientation: Quaternion,
+}
+
+//Poses before and after the latest physics step, the output Systems interpolate between them
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StateHistory
+{
+    pub previous: Pose,
+    pub current: Pose,
 }
 
+//State the airplane is spawned with, a crashed airplane is reset to it
+#[derive(Debug, Clone)]
+pub struct InitialConditions
+{
+    pub fdm: DataFDM,
+    pub engines: SpawnedEngines,
+}
 
 
 //State machine for keyboard presses
@@ -59,4 +178,105 @@ pub struct KeyboardState
     pub pitch_down: bool,
     pub flaps_down: bool,
     pub zero_flaps: bool,
-}
\ No newline at end of file
+    pub ap_altitude_hold: bool, //engage at the current altitude
+    pub ap_vertical_speed: bool, //engage at the current climb rate
+    pub ap_heading_hold: bool, //engage at the current heading
+    pub ap_airspeed_hold: bool, //engage at the current airspeed
+    pub ap_wing_leveler: bool,
+    pub ap_disengage: bool, //disengage every mode
+    pub ap_flight_plan: bool, //let the flight plan drive the autopilot
+    pub ap_approach: bool, //fly the approach to the runway and land
+    pub mixture_rich: bool,
+    pub mixture_lean: bool,
+    pub engine_start: bool,
+}
+
+//Gains of the autopilot loops, set per airplane. The outer loops command the inner ones:
+//altitude (ft) -> climb rate (ft/s) -> pitch (deg) -> elevator incidence (deg),
+//heading (deg) -> bank (deg) -> aileron incidence (deg) and airspeed (ft/s) -> thrust (lb)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutopilotGains
+{
+    pub altitude: PidGains,
+    pub vertical_speed: PidGains,
+    pub approach_vertical_speed: PidGains, //vertical speed loop flown by the approach coupler, tighter to track the glide path
+    pub pitch: PidGains,
+    pub heading: PidGains,
+    pub bank: PidGains,
+    pub airspeed: PidGains,
+}
+
+impl Default for AutopilotGains
+{
+    //Tuned for the airplane in the Bourg example
+    fn default() -> Self
+    {
+        AutopilotGains
+        {
+            altitude: PidGains::new(0.15, 0.0, 0.0, -20.0, 20.0),
+            vertical_speed: PidGains::new(0.3, 0.05, 0.0, -15.0, 15.0),
+            approach_vertical_speed: PidGains::new(0.6, 0.1, 0.0, -15.0, 15.0),
+            pitch: PidGains::new(2.0, 0.5, 0.5, -10.0, 10.0),
+            heading: PidGains::new(1.0, 0.0, 0.0, -25.0, 25.0),
+            bank: PidGains::new(0.1, 0.02, 0.02, -5.0, 5.0),
+            airspeed: PidGains::new(50.0, 10.0, 0.0, 0.0, 3000.0),
+        }
+    }
+}
+
+//Autopilot of an airplane: the engaged modes and the state of each loop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutopilotState
+{
+    pub modes: AutopilotModes,
+    pub engaged: AutopilotModes, //modes the loops last ran with, a change resets the loops
+    pub altitude: Pid,
+    pub vertical_speed: Pid,
+    pub approach_vertical_speed: Pid,
+    pub pitch: Pid,
+    pub heading: Pid,
+    pub bank: Pid,
+    pub airspeed: Pid,
+}
+
+impl AutopilotState
+{
+    pub fn new(gains: AutopilotGains) -> AutopilotState
+    {
+        AutopilotState
+        {
+            modes: AutopilotModes::default(),
+            engaged: AutopilotModes::default(),
+            altitude: Pid::new(gains.altitude),
+            vertical_speed: Pid::new(gains.vertical_speed),
+            approach_vertical_speed: Pid::new(gains.approach_vertical_speed),
+            pitch: Pid::new(gains.pitch),
+            heading: Pid::new(gains.heading),
+            bank: Pid::new(gains.bank),
+            airspeed: Pid::new(gains.airspeed),
+        }
+    }
+}
+
+impl Default for AutopilotState
+{
+    fn default() -> Self
+    {
+        AutopilotState::new(AutopilotGains::default())
+    }
+}
+
+//Actuators of the control channels, set per airplane. The inputs and the autopilot command the controls
+//on DataFDM and the physics flies on the actual positions. Elevator, aileron and rudder are incidences (deg),
+//the thrust is in lb. The pitch and roll keys deflect the elevator and aileron flaps, which move in the units of
+//the flap switches on the airfoil tables (-1 to 1), and the physics flies on the switch nearest to their position
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Actuators
+{
+    pub elevator: Actuator,
+    pub aileron: Actuator,
+    pub rudder: Actuator,
+    pub thrust: Actuator,
+    pub elevator_flap: Actuator,
+    pub aileron_flap: Actuator, //positive as the roll right key commands it
+}
@@ -0,0 +1,176 @@
+//Trim the Bourg airplane for a steady flight condition.
+//The keyboard controls are on/off, so the trim uses the incidence of the control surfaces as
+//continuous controls: the horizontal tail (elements 4 and 5) and a differential change on the outer
+//wings (elements 0 and 3), together with the thrust and angle of attack. The airplane flies with no sideslip.
+//The residuals are the body space accelerations left over after the acceleration of the target flight path
+//(zero, or the centripetal acceleration of a turn): forward, up, pitch and roll.
+//The side force and yaw cannot be trimmed: the rudder lift table jumps from 0 to 0.16 at zero angle of attack,
+//so the rudder always pushes sideways. Those two accelerations are returned in TrimReport::untrimmed
+
+//DataFDM structure
+use crate::bourg::fdm::structures::DataFDM;
+
+//Loads, element normals and control surfaces
+use crate::bourg::fdm::calc_loads::calc_airplane_loads;
+use crate::bourg::fdm::mass_properties::calc_element_normals;
+use crate::bourg::fdm::keypresses::{zero_ailerons, zero_elevators};
+use crate::bourg::fdm::frames::FEET_PER_METER;
+
+//Vector, Matrix, Quaternion
+use crate::bourg::common::vector::Vector64;
+use crate::bourg::common::matrix::Matrix64;
+use crate::bourg::common::quaternion::{Quaternion, Quaternion64};
+use crate::bourg::common::real::{Real, Float};
+use crate::bourg::common::constants::G;
+
+//Trim solver
+use crate::trim::{TrimTarget, TrimSettings, TrimReport, solve};
+
+//Order of the unknowns in the TrimReport
+pub const TRIM_THRUST: usize = 0; //lb
+pub const TRIM_ALPHA: usize = 1; //angle of attack, degrees
+pub const TRIM_ELEVATOR: usize = 2; //incidence of the horizontal tail, degrees
+pub const TRIM_AILERON: usize = 3; //incidence taken off the left outer wing and added to the right, degrees
+
+//Flight condition and attitude for an angle of attack
+struct TrimState
+{
+    m_body_to_earth: Matrix64,
+    v_velocity: Vector64, //earth space
+    v_velocity_body: Vector64,
+    v_angular_velocity: Vector64, //body space
+    v_acceleration: Vector64, //earth space acceleration of the flight path
+}
+
+//Continuous controls: thrust (lb) and the incidence in degrees of the horizontal tail, the differential on
+//the outer wings (taken off the left wing and added to the right) and the rudder.
+//The outer wing incidence without the differential is kept so the controls can be written back
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SurfaceControls
+{
+    pub thrust: f64,
+    pub elevator: f64,
+    pub aileron: f64,
+    pub rudder: f64,
+    pub wing_incidence: f64,
+}
+
+impl SurfaceControls
+{
+    //Read the controls from the airplane
+    pub fn from_fdm(fdm: &DataFDM) -> SurfaceControls
+    {
+        SurfaceControls
+        {
+            thrust: fdm.thrustforce.to_f64(),
+            elevator: fdm.element[4].f_incidence.to_f64(),
+            aileron: 0.5 * (fdm.element[3].f_incidence - fdm.element[0].f_incidence).to_f64(),
+            rudder: fdm.element[6].f_incidence.to_f64(),
+            wing_incidence: 0.5 * (fdm.element[0].f_incidence + fdm.element[3].f_incidence).to_f64(),
+        }
+    }
+
+    //Write the controls to the airplane and update the element normals
+    pub fn apply(&self, fdm: &mut DataFDM)
+    {
+        fdm.thrustforce = Float::from_f64(self.thrust);
+        fdm.element[4].f_incidence = Float::from_f64(self.elevator);
+        fdm.element[5].f_incidence = Float::from_f64(self.elevator);
+        fdm.element[0].f_incidence = Float::from_f64(self.wing_incidence - self.aileron);
+        fdm.element[3].f_incidence = Float::from_f64(self.wing_incidence + self.aileron);
+        fdm.element[6].f_incidence = Float::from_f64(self.rudder);
+        calc_element_normals(fdm);
+    }
+}
+
+//Solve for thrust, angle of attack and control surface incidences at the target condition (ft, ft/s).
+//The heading is measured from the earth x axis (east) toward y (north). On return the airplane is left
+//flying the target condition with the trimmed controls, whether or not the solver converged
+pub fn trim(fdm: &mut DataFDM, target: &TrimTarget, settings: &TrimSettings) -> TrimReport
+{
+    //The trim replaces the discrete aileron and elevator deflections
+    zero_ailerons(fdm);
+    zero_elevators(fdm);
+
+    let controls = SurfaceControls::from_fdm(fdm);
+
+    fdm.v_position.z = Float::from_f64(target.altitude);
+    fdm.position.altitude = target.altitude / FEET_PER_METER;
+
+    let mut x = vec![controls.thrust, 2.0, controls.elevator, controls.aileron];
+    let perturbation = [1.0, 0.01, 0.01, 0.01];
+    let mut untrimmed = [0.0; 2];
+
+    let mut report = solve(&mut x, &perturbation, settings, |x, r|
+    {
+        let a_body = accelerations(fdm, target, &controls, x);
+        r[0] = a_body[0];
+        r[1] = a_body[2];
+        r[2] = a_body[4];
+        r[3] = a_body[3];
+        untrimmed = [a_body[1], a_body[5]];
+    });
+
+    report.untrimmed = untrimmed.to_vec();
+
+    fdm.f_speed = fdm.v_velocity.magnitude();
+    fdm.v_euler_angles = Quaternion::make_euler_from_q(&fdm.q_orientation);
+
+    report
+}
+
+//Turn rate (degrees per second) of a steady coordinated turn at a bank angle (degrees) and airspeed (ft/s)
+pub fn turn_rate(bank: f64, airspeed: f64) -> f64
+{
+    (-G.to_f64() * bank.to_radians().tan() / airspeed).to_degrees()
+}
+
+//Load the flight condition and controls into the airplane and return the body space linear
+//accelerations (x, y, z) after the flight path acceleration is taken out, then the angular accelerations
+fn accelerations(fdm: &mut DataFDM, target: &TrimTarget, controls: &SurfaceControls, x: &[f64]) -> [f64; 6]
+{
+    let state = flight_state(target, x[TRIM_ALPHA]);
+
+    SurfaceControls { thrust: x[TRIM_THRUST], elevator: x[TRIM_ELEVATOR], aileron: x[TRIM_AILERON], ..*controls }.apply(fdm);
+
+    fdm.q_orientation = Quaternion64::from_rotation_matrix(&state.m_body_to_earth).cast();
+    fdm.v_velocity = state.v_velocity.cast();
+    fdm.v_velocity_body = state.v_velocity_body.cast();
+    fdm.v_angular_velocity = state.v_angular_velocity.cast();
+
+    //Forces and moments at this condition
+    calc_airplane_loads(fdm);
+
+    let ae: Vector64 = (fdm.v_forces / fdm.mass).cast();
+    let alpha = fdm.m_inertia_inverse *
+        (fdm.v_moments - fdm.v_angular_velocity.cross(&(fdm.m_inertia * fdm.v_angular_velocity)));
+
+    let a_body = state.m_body_to_earth.transpose() * (ae - state.v_acceleration);
+    [a_body.x, a_body.y, a_body.z, alpha.x.to_f64(), alpha.y.to_f64(), alpha.z.to_f64()]
+}
+
+//Attitude, velocity and turn for the target condition and an angle of attack in degrees
+fn flight_state(target: &TrimTarget, alpha: f64) -> TrimState
+{
+    let speed = target.airspeed;
+    let gamma = target.climb_angle();
+    let heading = target.heading.to_radians();
+    let bank = target.bank.to_radians();
+
+    //Wind axes: x along the flight path, banked about it (positive right wing down)
+    let m_wind_to_earth = Matrix64::rotation_z(heading) * Matrix64::rotation_y(-gamma) * Matrix64::rotation_x(bank);
+
+    //Body axes are pitched up by the angle of attack from the wind axes (body x forward, y left, z up)
+    let m_wind_to_body = Matrix64::rotation_y(alpha.to_radians());
+    let m_body_to_earth = m_wind_to_earth * m_wind_to_body.transpose();
+
+    let v_velocity_body = m_wind_to_body * Vector64::new(speed, 0.0, 0.0);
+    let v_velocity = m_body_to_earth * v_velocity_body;
+
+    //A right turn is clockwise seen from above, so it is a negative rotation about earth z (up)
+    let turn = Vector64::new(0.0, 0.0, -turn_rate(target.bank, speed).to_radians());
+    let v_angular_velocity = m_body_to_earth.transpose() * turn;
+    let v_acceleration = turn.cross(&v_velocity);
+
+    TrimState { m_body_to_earth, v_velocity, v_velocity_body, v_angular_velocity, v_acceleration }
+}
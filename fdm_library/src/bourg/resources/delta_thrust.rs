@@ -1,3 +1,5 @@
-//Delta thrust increment/ decrement
+use crate::bourg::common::real::Float;
+
+//Delta thrust increment/ decrement per second while the thrust keys are held
 #[derive(Default)]
-pub struct DeltaThrust(pub f32);
\ No newline at end of file
+pub struct DeltaThrust(pub Float);
\ No newline at end of file
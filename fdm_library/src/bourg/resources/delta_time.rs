@@ -1,7 +1,9 @@
 //SPECS Resources
 
+use crate::bourg::common::real::Float;
+
 //Time step (delta time) resource
 #[derive(Default)]
-pub struct DeltaTime(pub f32);
+pub struct DeltaTime(pub Float);
 
 
@@ -1,4 +1,6 @@
+use crate::bourg::common::real::Float;
+
 //Max thrust potential resource
 #[derive(Default)]
-pub struct MaxThrust(pub f32);
+pub struct MaxThrust(pub Float);
 
@@ -0,0 +1,67 @@
+//This file contains the Autopilot System
+
+//SPECS
+use specs::prelude::*;
+
+//Get Component data needed to perform the System operations
+use crate::bourg::fdm::structures::DataFDM;
+use crate::bourg::fdm::structures::KeyboardState;
+use crate::bourg::fdm::structures::AutopilotState;
+
+//Get Resources
+use crate::bourg::resources::delta_time::DeltaTime;
+use crate::bourg::resources::max_thrust::MaxThrust;
+use crate::scheduler::Scheduler;
+use crate::estimator::Estimator;
+use crate::bourg::common::real::Real;
+
+//Functions to call
+use crate::bourg::fdm::autopilot::{autopilot, autopilot_on, handle_autopilot_input};
+use crate::bourg::fdm::estimator::estimated_state;
+
+//System to run the autopilot between the input and the equations of motion. The modes follow the keys when
+//the input rate group is due, the loops run once per frame over the time the physics is about to simulate.
+//An airplane whose Estimator flies the guidance on the estimate has the autopilot measure the estimate
+pub struct Autopilot;
+impl<'a> System<'a> for Autopilot
+{
+    type SystemData = (
+        Read<'a, DeltaTime>,
+        Read<'a, MaxThrust>,
+        Option<Read<'a, Scheduler>>,
+        WriteStorage<'a, DataFDM>,
+        ReadStorage<'a, KeyboardState>,
+        WriteStorage<'a, AutopilotState>,
+        ReadStorage<'a, Estimator>
+    );
+
+    fn run(&mut self, (dt, max_thrust, scheduler, mut datafdm, keyboardstate, mut autopilotstate, estimators): Self::SystemData) 
+    {
+        let max_thrust = max_thrust.0.to_f64();
+
+        //Time simulated this frame and whether the inputs are applied
+        let (dt, input_due) = match scheduler
+        {
+            Some(scheduler) => (scheduler.physics.steps as f64 * scheduler.physics.dt(), scheduler.input.is_due()),
+            None => (dt.0.to_f64(), true),
+        };
+
+        for (fdm, keystate, ap, estimator) in (&mut datafdm, &keyboardstate, &mut autopilotstate, estimators.maybe()).join() 
+        {
+            let estimated = estimated_state(fdm, estimator);
+            if input_due
+            {
+                handle_autopilot_input(estimated.as_ref().unwrap_or(fdm), ap, keystate);
+            }
+
+            if dt > 0.0
+            {
+                match &estimated
+                {
+                    Some(state) => autopilot_on(state, fdm, ap, dt, max_thrust),
+                    None => autopilot(fdm, ap, dt, max_thrust),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//This file contains the CrashDetection System
+
+//SPECS
+use specs::prelude::*;
+
+//Get Component data needed to perform the System operations
+use crate::bourg::fdm::structures::{DataFDM, StateHistory, Actuators, InitialConditions, AutopilotState};
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+use crate::sensors::Sensors;
+use crate::estimator::Estimator;
+use crate::air_data::AirDataComputer;
+use crate::flight_plan::FlightPlan;
+use crate::approach::Approach;
+use crate::envelope::EnvelopeMonitor;
+use crate::crash::CrashMonitor;
+
+//Get Resources
+use crate::bourg::resources::delta_time::DeltaTime;
+use crate::scheduler::Scheduler;
+use crate::bourg::common::real::Real;
+
+//Functions to call
+use crate::bourg::fdm::crash::{ground_contact, reset_airplane};
+use crate::crash::{reset_equipment, Equipment, print_crash};
+
+//System to check how each airplane with a CrashMonitor touches the ground after the equations of motion.
+//It logs and prints a crash as it happens, and once the monitor's reset delay is up puts the crashed airplane back
+//to the InitialConditions it was spawned with, and everything it carries that follows its state with it.
+//The equations of motion leave a crashed airplane frozen where it crashed until then.
+//It runs once per frame over the time the physics simulated
+pub struct CrashDetection;
+impl<'a> System<'a> for CrashDetection
+{
+    type SystemData = (
+        Read<'a, DeltaTime>,
+        Option<Read<'a, Scheduler>>,
+        WriteStorage<'a, DataFDM>,
+        WriteStorage<'a, CrashMonitor>,
+        ReadStorage<'a, InitialConditions>,
+        WriteStorage<'a, StateHistory>,
+        WriteStorage<'a, Actuators>,
+        WriteStorage<'a, AutopilotState>,
+        WriteStorage<'a, PistonEngine>,
+        WriteStorage<'a, Propeller>,
+        WriteStorage<'a, JetEngine>,
+        WriteStorage<'a, Sensors>,
+        WriteStorage<'a, Estimator>,
+        WriteStorage<'a, AirDataComputer>,
+        WriteStorage<'a, FlightPlan>,
+        WriteStorage<'a, Approach>,
+        WriteStorage<'a, EnvelopeMonitor>
+    );
+
+    fn run(&mut self, (dt, scheduler, mut datafdm, mut monitors, initial_conditions, mut history, mut actuators, mut autopilots, mut engines, mut propellers, mut jets, mut sensors, mut estimators, mut air_data_computers, mut flight_plans, mut approaches, mut envelope_monitors): Self::SystemData) 
+    {
+        //Time simulated this frame and whether the physics ran
+        let (dt, stepped) = match scheduler
+        {
+            Some(scheduler) => (scheduler.physics.steps as f64 * scheduler.physics.dt(), scheduler.physics.steps > 0),
+            None => (dt.0.to_f64(), true),
+        };
+        if !stepped
+        {
+            return;
+        }
+
+        for (fdm, monitor, initial, history, actuators, autopilot, engine, propeller, jet, sensors, estimator, air_data, flight_plan, approach, envelope) in (&mut datafdm, &mut monitors, initial_conditions.maybe(), (&mut history).maybe(), (&mut actuators).maybe(), (&mut autopilots).maybe(), (&mut engines).maybe(), (&mut propellers).maybe(), (&mut jets).maybe(), (&mut sensors).maybe(), (&mut estimators).maybe(), (&mut air_data_computers).maybe(), (&mut flight_plans).maybe(), (&mut approaches).maybe(), (&mut envelope_monitors).maybe()).join() 
+        {
+            let logged = monitor.events.len();
+            monitor.check(ground_contact(fdm).as_ref(), dt);
+            if monitor.reset_due()
+            {
+                if let Some(initial) = initial
+                {
+                    reset_airplane(fdm, initial, history, actuators, autopilot);
+                    reset_equipment(monitor, &initial.engines, Equipment { engine, propeller, jet, sensors, estimator, air_data, flight_plan, approach, envelope });
+                }
+            }
+
+            for event in &monitor.events[logged..]
+            {
+                println!("Crash event: {}", event);
+            }
+            print_crash(monitor);
+        }
+    }
+}
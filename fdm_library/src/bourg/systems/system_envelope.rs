@@ -0,0 +1,54 @@
+//This file contains the EnvelopeMonitoring System
+
+//SPECS
+use specs::prelude::*;
+
+//Get Component data needed to perform the System operations
+use crate::bourg::fdm::structures::DataFDM;
+use crate::envelope::EnvelopeMonitor;
+
+//Get Resources
+use crate::bourg::resources::delta_time::DeltaTime;
+use crate::scheduler::Scheduler;
+use crate::bourg::common::real::Real;
+
+//Functions to call
+use crate::bourg::fdm::envelope::envelope_state;
+use crate::envelope::print_envelope;
+
+//System to check each airplane with an EnvelopeMonitor against its limits after the equations of motion.
+//It runs once per frame over the time the physics simulated, and prints the events as they are raised
+pub struct EnvelopeMonitoring;
+impl<'a> System<'a> for EnvelopeMonitoring
+{
+    type SystemData = (
+        Read<'a, DeltaTime>,
+        Option<Read<'a, Scheduler>>,
+        ReadStorage<'a, DataFDM>,
+        WriteStorage<'a, EnvelopeMonitor>
+    );
+
+    fn run(&mut self, (dt, scheduler, datafdm, mut monitors): Self::SystemData) 
+    {
+        //Time simulated this frame and whether the physics ran
+        let (dt, stepped) = match scheduler
+        {
+            Some(scheduler) => (scheduler.physics.steps as f64 * scheduler.physics.dt(), scheduler.physics.steps > 0),
+            None => (dt.0.to_f64(), true),
+        };
+        if !stepped
+        {
+            return;
+        }
+
+        for (fdm, monitor) in (&datafdm, &mut monitors).join() 
+        {
+            let state = envelope_state(fdm);
+            for event in monitor.check(&state, dt)
+            {
+                println!("Envelope event: {}", event);
+            }
+            print_envelope(monitor);
+        }
+    }
+}
@@ -5,18 +5,30 @@ use specs::prelude::*;
 
 //FGNetFDM structure
 use crate::flightgear::FGNetFDM;
+use crate::scheduler::Scheduler;
 
 //Get function to call
 use crate::bourg::fdm::send_packet::send;
 
-//System to send packets after they are made
+//System to send packets after they are made, at the output rate when there is a Scheduler
 pub struct SendPacket;
 impl<'a> System<'a> for SendPacket
 {
-    type SystemData = ReadStorage<'a, FGNetFDM>;
+    type SystemData = (
+        Option<Read<'a, Scheduler>>,
+        ReadStorage<'a, FGNetFDM>
+    );
 
-    fn run(&mut self, fgnetfdm: Self::SystemData) 
+    fn run(&mut self, (scheduler, fgnetfdm): Self::SystemData) 
     {
+        if let Some(scheduler) = scheduler
+        {
+            if !scheduler.output.is_due()
+            {
+                return;
+            }
+        }
+
         for fgnet in fgnetfdm.join() 
         {
             send(fgnet);
@@ -0,0 +1,361 @@
+//To run unit tests for the crash module from the command line:
+//cargo test --lib crash
+
+//Crash detection shared by both models. Each model's crash module finds how the airplane touches the ground from its
+//DataFDM: the sink rate it touched down at, its bank and pitch, and whether the airframe rather than the gear is on
+//the ground. The CrashMonitor Component checks the contact against the limits of the gear: touching down faster than
+//the gear takes, a wing, the nose or the tail on the ground, contact upside down, or the airframe itself hitting the
+//ground. A crash is logged as an event and the airplane is frozen where it crashed, so an unattended run stops there
+//instead of flying on through the ground. With a reset delay the airplane is put back to its initial conditions
+//after it, and the run goes on. Each model's crash module puts its own DataFDM back, and reset_equipment here resets
+//everything shared by both models the airplane carries with it.
+//SI units and degrees: m/s and deg
+
+//SPECS
+use specs::prelude::*;
+
+//Display of the events
+use std::fmt;
+
+//Components an airplane carries that follow its state
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+use crate::sensors::Sensors;
+use crate::estimator::Estimator;
+use crate::air_data::AirDataComputer;
+use crate::flight_plan::FlightPlan;
+use crate::approach::Approach;
+use crate::envelope::EnvelopeMonitor;
+
+//Limits of the ground contact, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CrashLimits
+{
+    pub max_sink_rate: f64, //m/s, the gear takes touchdowns up to this sink rate
+    pub max_bank: f64, //deg, either way, a wingtip touches the ground past it
+    pub max_pitch: f64, //deg, the tail touches the ground past it
+    pub min_pitch: f64, //deg, negative, the nose touches the ground past it
+}
+
+impl Default for CrashLimits
+{
+    //Limits of a light airplane with tricycle gear
+    fn default() -> Self
+    {
+        CrashLimits { max_sink_rate: 3.0, max_bank: 10.0, max_pitch: 15.0, min_pitch: -10.0 }
+    }
+}
+
+//How the airplane crashed, with the value that went past the limit
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CrashCause
+{
+    HardLanding { sink_rate: f64 }, //touched down faster than the gear takes, m/s
+    WingStrike { bank: f64 }, //deg
+    TailStrike { pitch: f64 }, //deg
+    NoseStrike { pitch: f64 }, //deg
+    Inverted { bank: f64 }, //touched the ground upside down, deg
+    TerrainImpact { sink_rate: f64 }, //the airframe hit the ground clear of the gear envelope, m/s
+}
+
+//Contact of the airplane with the ground
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GroundContact
+{
+    pub sink_rate: f64, //m/s, down, at the touchdown
+    pub bank: f64, //deg
+    pub pitch: f64, //deg
+    pub airframe: bool, //the airframe rather than the gear is touching the ground
+}
+
+impl GroundContact
+{
+    //Cause of a crash with this contact, none when the gear takes it. The sink rate is only checked at the touchdown
+    pub fn crash_cause(&self, limits: &CrashLimits, touchdown: bool) -> Option<CrashCause>
+    {
+        if self.bank.abs() > 90.0
+        {
+            Some(CrashCause::Inverted { bank: self.bank })
+        }
+        else if touchdown && self.sink_rate > limits.max_sink_rate
+        {
+            Some(CrashCause::HardLanding { sink_rate: self.sink_rate })
+        }
+        else if self.bank.abs() > limits.max_bank
+        {
+            Some(CrashCause::WingStrike { bank: self.bank })
+        }
+        else if self.pitch > limits.max_pitch
+        {
+            Some(CrashCause::TailStrike { pitch: self.pitch })
+        }
+        else if self.pitch < limits.min_pitch
+        {
+            Some(CrashCause::NoseStrike { pitch: self.pitch })
+        }
+        else if self.airframe
+        {
+            Some(CrashCause::TerrainImpact { sink_rate: self.sink_rate })
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+//Event raised by the monitor, at the time (s) since it started
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CrashEvent
+{
+    Crashed { time: f64, cause: CrashCause },
+    //The airplane was put back to its initial conditions
+    Reset { time: f64 },
+}
+
+impl fmt::Display for CrashEvent
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            CrashEvent::Crashed { time, cause } => write!(f, "{:9.2} s  Crashed: {:?}", time, cause),
+            CrashEvent::Reset { time } => write!(f, "{:9.2} s  Reset to the initial conditions", time),
+        }
+    }
+}
+
+//Watch over an airplane's ground contact: the crash it is frozen at, until when, and the log of what happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrashMonitor
+{
+    pub limits: CrashLimits,
+    pub reset_delay: Option<f64>, //s a crashed airplane stays frozen before it is reset, none keeps it crashed
+    pub time: f64, //s since the monitor started
+    pub crashed: Option<(f64, CrashCause)>, //time and cause of the crash the airplane is frozen at
+    pub events: Vec<CrashEvent>, //log of every event raised, in time order
+    in_contact: bool, //the airplane was touching the ground at the last check
+}
+
+impl Component for CrashMonitor
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl CrashMonitor
+{
+    //Monitor of an airplane's ground contact, a crash keeps it crashed
+    pub fn new(limits: CrashLimits) -> CrashMonitor
+    {
+        CrashMonitor { limits, reset_delay: None, time: 0.0, crashed: None, events: Vec::new(), in_contact: false }
+    }
+
+    //Check the ground contact dt seconds on, none when the airplane is clear of the ground, and return the cause
+    //when it has just crashed, which is also logged. A crashed airplane is not checked again until it is reset
+    pub fn check(&mut self, contact: Option<&GroundContact>, dt: f64) -> Option<CrashCause>
+    {
+        self.time += dt;
+        if self.crashed.is_some()
+        {
+            return None;
+        }
+
+        let touchdown = contact.is_some() && !self.in_contact;
+        self.in_contact = contact.is_some();
+        let cause = contact.and_then(|contact| contact.crash_cause(&self.limits, touchdown))?;
+        self.crashed = Some((self.time, cause));
+        self.events.push(CrashEvent::Crashed { time: self.time, cause });
+        Some(cause)
+    }
+
+    //Whether the airplane is crashed and frozen
+    pub fn is_crashed(&self) -> bool
+    {
+        self.crashed.is_some()
+    }
+
+    //Whether the airplane has been crashed for the reset delay
+    pub fn reset_due(&self) -> bool
+    {
+        match (self.crashed, self.reset_delay)
+        {
+            (Some((time, _)), Some(delay)) => self.time - time >= delay,
+            _ => false,
+        }
+    }
+
+    //Clear the crash once the airplane is back at its initial conditions, which start clear of the ground
+    pub fn reset(&mut self)
+    {
+        self.crashed = None;
+        self.in_contact = false;
+        self.events.push(CrashEvent::Reset { time: self.time });
+    }
+}
+
+//Engines an airplane is spawned with, kept in its InitialConditions so a crashed airplane gets them back as they were
+#[derive(Debug, Clone, Default)]
+pub struct SpawnedEngines
+{
+    pub engine: Option<PistonEngine>,
+    pub propeller: Option<Propeller>,
+    pub jet: Option<JetEngine>,
+}
+
+//Optional Components shared by both models that an airplane carries, each is reset with it after a crash
+pub struct Equipment<'a>
+{
+    pub engine: Option<&'a mut PistonEngine>,
+    pub propeller: Option<&'a mut Propeller>,
+    pub jet: Option<&'a mut JetEngine>,
+    pub sensors: Option<&'a mut Sensors>,
+    pub estimator: Option<&'a mut Estimator>,
+    pub air_data: Option<&'a mut AirDataComputer>,
+    pub flight_plan: Option<&'a mut FlightPlan>,
+    pub approach: Option<&'a mut Approach>,
+    pub envelope: Option<&'a mut EnvelopeMonitor>,
+}
+
+//Reset what a crashed airplane carries once its model has put it back to its initial conditions: the engines as they
+//were spawned, or at rest without them, the sensors and estimator, the air data, the flight plan, the approach and the
+//envelope monitor. The crash monitor is cleared last
+pub fn reset_equipment(monitor: &mut CrashMonitor, spawned: &SpawnedEngines, equipment: Equipment)
+{
+    if let Some(engine) = equipment.engine
+    {
+        match spawned.engine
+        {
+            Some(spawned) => *engine = spawned,
+            None => engine.reset(),
+        }
+    }
+    if let Some(propeller) = equipment.propeller
+    {
+        match spawned.propeller.as_ref()
+        {
+            Some(spawned) => *propeller = spawned.clone(),
+            None => propeller.reset(),
+        }
+    }
+    if let Some(jet) = equipment.jet
+    {
+        match spawned.jet
+        {
+            Some(spawned) => *jet = spawned,
+            None => jet.reset(),
+        }
+    }
+    if let Some(sensors) = equipment.sensors
+    {
+        sensors.reset();
+    }
+    if let Some(estimator) = equipment.estimator
+    {
+        estimator.reset();
+    }
+    if let Some(air_data) = equipment.air_data
+    {
+        air_data.reset();
+    }
+    if let Some(flight_plan) = equipment.flight_plan
+    {
+        flight_plan.reset();
+    }
+    if let Some(approach) = equipment.approach
+    {
+        approach.reset();
+    }
+    if let Some(envelope) = equipment.envelope
+    {
+        envelope.reset();
+    }
+    monitor.reset();
+}
+
+//Print the crash the airplane is frozen at, this is called once per frame
+pub fn print_crash(monitor: &CrashMonitor)
+{
+    if let Some((time, cause)) = monitor.crashed
+    {
+        println!("Crashed at {} s: {:?}", time, cause);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Gentle touchdown wings level on the gear
+    fn landing() -> GroundContact
+    {
+        GroundContact { sink_rate: 1.0, bank: 2.0, pitch: 5.0, airframe: false }
+    }
+
+    #[test]
+    fn cause_test()
+    {
+        //The gear takes a gentle touchdown, each limit on its own is a crash
+        let limits = CrashLimits::default();
+        assert_eq!(landing().crash_cause(&limits, true), None);
+        let cases = [
+            (GroundContact { sink_rate: 4.0, ..landing() }, CrashCause::HardLanding { sink_rate: 4.0 }),
+            (GroundContact { bank: -15.0, ..landing() }, CrashCause::WingStrike { bank: -15.0 }),
+            (GroundContact { pitch: 18.0, ..landing() }, CrashCause::TailStrike { pitch: 18.0 }),
+            (GroundContact { pitch: -12.0, ..landing() }, CrashCause::NoseStrike { pitch: -12.0 }),
+            (GroundContact { bank: 170.0, ..landing() }, CrashCause::Inverted { bank: 170.0 }),
+            (GroundContact { airframe: true, ..landing() }, CrashCause::TerrainImpact { sink_rate: 1.0 }),
+        ];
+        for (contact, cause) in cases
+        {
+            assert_eq!(contact.crash_cause(&limits, true), Some(cause));
+        }
+
+        //After the touchdown the sink rate it touched down at is not checked again
+        assert_eq!(GroundContact { sink_rate: 4.0, ..landing() }.crash_cause(&limits, false), None);
+    }
+
+    #[test]
+    fn monitor_test()
+    {
+        //Flying and rolling out are fine, a wing strike on the ground crashes the airplane once
+        let mut monitor = CrashMonitor::new(CrashLimits::default());
+        assert_eq!(monitor.check(None, 0.5), None);
+        assert_eq!(monitor.check(Some(&landing()), 0.5), None);
+        let strike = GroundContact { bank: 12.0, ..landing() };
+        assert_eq!(monitor.check(Some(&strike), 0.5), Some(CrashCause::WingStrike { bank: 12.0 }));
+        assert_eq!(monitor.check(Some(&strike), 0.5), None);
+        assert!(monitor.is_crashed());
+        assert_eq!(monitor.events, vec![CrashEvent::Crashed { time: 1.5, cause: CrashCause::WingStrike { bank: 12.0 } }]);
+
+        //Without a reset delay it stays crashed
+        monitor.check(None, 10.0);
+        assert!(!monitor.reset_due());
+    }
+
+    #[test]
+    fn reset_test()
+    {
+        //With a reset delay the airplane is reset that long after the crash, and is checked again after it
+        let mut monitor = CrashMonitor::new(CrashLimits::default());
+        monitor.reset_delay = Some(2.0);
+        let hard = GroundContact { sink_rate: 6.0, ..landing() };
+        monitor.check(Some(&hard), 1.0);
+        monitor.check(None, 1.0);
+        assert!(!monitor.reset_due());
+        monitor.check(None, 1.0);
+        assert!(monitor.reset_due());
+        monitor.reset();
+        assert!(!monitor.is_crashed());
+        assert_eq!(monitor.events[1], CrashEvent::Reset { time: 3.0 });
+        assert_eq!(monitor.check(Some(&hard), 1.0), Some(CrashCause::HardLanding { sink_rate: 6.0 }));
+    }
+
+    #[test]
+    fn display_test()
+    {
+        let event = CrashEvent::Crashed { time: 61.25, cause: CrashCause::HardLanding { sink_rate: 4.5 } };
+        assert_eq!(event.to_string(), "    61.25 s  Crashed: HardLanding { sink_rate: 4.5 }");
+    }
+}
@@ -0,0 +1,399 @@
+//To run unit tests for the engine module from the command line:
+//cargo test --lib engine
+
+//Piston engine shared by both models. The throttle sets the manifold pressure and the mixture the fuel-air ratio,
+//which give the air drawn in and the power at the engine speed and air density, the fuel flow and the exhaust gas
+//and cylinder head temperatures with a thermal lag. The engine spins its shaft against the load torque of the
+//propeller. Each model's engine module turns the power into thrust. SI units: Pa, K, W, N m and kg/s,
+//the engine speed is in RPM. FlightGear's units are only used for its packet
+
+//SPECS
+use specs::prelude::*;
+
+//Standard atmosphere
+use crate::atmosphere::{Atmosphere, SEA_LEVEL_DENSITY, SEA_LEVEL_TEMPERATURE, GAS_CONSTANT};
+
+//FGNetFDM packet structure
+use crate::flightgear::FGNetFDM;
+
+//Fuel density and gallons for FlightGear
+use crate::fuel::{AVGAS_DENSITY, LITRES_PER_GALLON};
+
+//Fuel-air mass ratio of a stoichiometric gasoline mixture
+pub const STOICHIOMETRIC_FUEL_AIR_RATIO: f64 = 1.0 / 14.7;
+//Equivalence ratios (fuel-air ratio over the stoichiometric one) of best power and peak EGT,
+//and the lean and rich limits outside which the mixture does not burn
+pub const BEST_POWER_MIXTURE: f64 = 1.15;
+pub const PEAK_EGT_MIXTURE: f64 = 1.0;
+pub const LEAN_LIMIT: f64 = 0.6;
+pub const RICH_LIMIT: f64 = 1.8;
+
+//Unit conversions for FlightGear
+const PASCALS_PER_INHG: f64 = 3386.39;
+
+//Engine state in the FlightGear packet
+pub const ENGINE_OFF: f32 = 0.0;
+pub const ENGINE_RUNNING: f32 = 2.0;
+
+//Data of an engine, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PistonEngineData
+{
+    pub max_power: f64, //W, brake power at sea level, full throttle, best power mixture and rated RPM
+    pub rated_rpm: f64,
+    pub idle_rpm: f64, //speed the engine is started at
+    pub stall_rpm: f64, //the engine stops below this speed
+    pub displacement: f64, //m^3
+    pub volumetric_efficiency: f64,
+    pub friction: f64, //friction power at the rated RPM as a fraction of max_power, it grows with RPM squared
+    pub idle_manifold_pressure: f64, //manifold pressure with the throttle closed, as a fraction of the ambient pressure
+    pub full_rich: f64, //equivalence ratio with the mixture full rich at sea level
+    pub inertia: f64, //kg m^2, engine and propeller
+    pub egt_rise: f64, //K above ambient at peak EGT and full power
+    pub cht_rise: f64, //K above ambient at full power
+    pub egt_time_constant: f64, //s
+    pub cht_time_constant: f64, //s
+}
+
+impl PistonEngineData
+{
+    //Engine of a power (W), rated RPM and displacement (m^3), the rest is typical of a light airplane engine
+    pub fn new(max_power: f64, rated_rpm: f64, displacement: f64) -> PistonEngineData
+    {
+        PistonEngineData
+        {
+            max_power,
+            rated_rpm,
+            idle_rpm: 700.0,
+            stall_rpm: 350.0,
+            displacement,
+            volumetric_efficiency: 0.85,
+            friction: 0.1,
+            idle_manifold_pressure: 0.3,
+            full_rich: 1.3,
+            inertia: 1.5,
+            egt_rise: 750.0,
+            cht_rise: 180.0,
+            egt_time_constant: 5.0,
+            cht_time_constant: 60.0,
+        }
+    }
+
+    //Air drawn in (kg/s) at an engine speed and manifold pressure and temperature, one intake stroke every two revolutions
+    pub fn air_flow(&self, rpm: f64, manifold_pressure: f64, temperature: f64) -> f64
+    {
+        self.volumetric_efficiency * self.displacement * rpm / 120.0 * manifold_pressure / (GAS_CONSTANT * temperature)
+    }
+
+    //Air drawn in at sea level, full throttle and the rated RPM
+    pub fn rated_air_flow(&self) -> f64
+    {
+        self.volumetric_efficiency * self.displacement * self.rated_rpm / 120.0 * SEA_LEVEL_DENSITY
+    }
+}
+
+//Fraction of the best power a mixture (equivalence ratio) burns to. Lean of best power it falls off faster than rich of it
+pub fn combustion_efficiency(mixture: f64) -> f64
+{
+    if !(LEAN_LIMIT..=RICH_LIMIT).contains(&mixture)
+    {
+        0.0
+    }
+    else if mixture < BEST_POWER_MIXTURE
+    {
+        1.0 - 2.5 * (mixture - BEST_POWER_MIXTURE).powi(2)
+    }
+    else
+    {
+        1.0 - 0.8 * (mixture - BEST_POWER_MIXTURE).powi(2)
+    }
+}
+
+//Share of the peak exhaust gas temperature rise a mixture (equivalence ratio) gives
+fn egt_factor(mixture: f64) -> f64
+{
+    (1.0 - 1.5 * (mixture - PEAK_EGT_MIXTURE).powi(2)).max(0.0)
+}
+
+//State of a piston engine: its controls, speed, power, fuel flow and temperatures
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PistonEngine
+{
+    pub data: PistonEngineData,
+    pub mixture: f64, //mixture control, 0 cut-off - 1 full rich
+    pub running: bool,
+    pub rpm: f64,
+    pub manifold_pressure: f64, //Pa
+    pub equivalence_ratio: f64, //fuel-air ratio over the stoichiometric one
+    pub power: f64, //W, brake power, negative when friction turns the engine into a brake
+    pub torque: f64, //N m
+    pub fuel_flow: f64, //kg/s
+    pub egt: f64, //K
+    pub cht: f64, //K
+}
+
+impl Component for PistonEngine
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl PistonEngine
+{
+    //A stopped engine at the standard sea level temperature, with the mixture full rich
+    pub fn new(data: PistonEngineData) -> PistonEngine
+    {
+        PistonEngine
+        {
+            data,
+            mixture: 1.0,
+            running: false,
+            rpm: 0.0,
+            manifold_pressure: 0.0,
+            equivalence_ratio: 0.0,
+            power: 0.0,
+            torque: 0.0,
+            fuel_flow: 0.0,
+            egt: SEA_LEVEL_TEMPERATURE,
+            cht: SEA_LEVEL_TEMPERATURE,
+        }
+    }
+
+    //Start the engine, it runs at least at the idle RPM
+    pub fn start(&mut self)
+    {
+        self.running = true;
+        self.rpm = self.rpm.max(self.data.idle_rpm);
+    }
+
+    //Put the engine back cold and at rest with its mixture setting, started again at idle if it was running
+    pub fn reset(&mut self)
+    {
+        let running = self.running;
+        *self = PistonEngine { mixture: self.mixture, ..PistonEngine::new(self.data) };
+        if running
+        {
+            self.start();
+        }
+    }
+
+    //Load torque (N m) of a fixed-pitch propeller absorbing max_power at the rated RPM at sea level.
+    //It grows with the air density and RPM squared
+    pub fn default_load(&self, density: f64) -> f64
+    {
+        let rated_omega = self.data.rated_rpm.to_radians() * 6.0;
+        let omega = self.rpm.to_radians() * 6.0;
+        self.data.max_power / rated_omega * (omega / rated_omega).powi(2) * density / SEA_LEVEL_DENSITY
+    }
+
+    //Run the engine for dt seconds at a throttle setting (0 - 1) in the air around it, against a load torque (N m)
+    pub fn update(&mut self, throttle: f64, air: &Atmosphere, load_torque: f64, dt: f64)
+    {
+        let data = self.data;
+        let throttle = throttle.clamp(0.0, 1.0);
+
+        //The carburettor meters fuel with the square root of the air density, so the mixture gets richer with altitude
+        self.manifold_pressure = air.pressure * (data.idle_manifold_pressure + (1.0 - data.idle_manifold_pressure) * throttle);
+        self.equivalence_ratio = self.mixture.clamp(0.0, 1.0) * data.full_rich * (SEA_LEVEL_DENSITY / air.density).sqrt();
+        let combustion = combustion_efficiency(self.equivalence_ratio);
+        if combustion <= 0.0
+        {
+            self.running = false;
+        }
+
+        //Indicated power from the air burnt, less the friction
+        let air_flow = data.air_flow(self.rpm, self.manifold_pressure, air.temperature);
+        let rated_indicated_power = data.max_power * (1.0 + data.friction);
+        let indicated_power = if self.running { rated_indicated_power * air_flow / data.rated_air_flow() * combustion } else { 0.0 };
+        self.power = indicated_power - data.friction * data.max_power * (self.rpm / data.rated_rpm).powi(2);
+        self.fuel_flow = if self.running { air_flow * self.equivalence_ratio * STOICHIOMETRIC_FUEL_AIR_RATIO } else { 0.0 };
+
+        let omega = self.rpm.to_radians() * 6.0;
+        self.torque = if omega > 0.0 { self.power / omega } else { 0.0 };
+
+        //Spin the shaft. The engine stops when it turns too slowly, and a stopped engine that slow comes to rest on its compression
+        let omega = (omega + (self.torque - load_torque) / data.inertia * dt).max(0.0);
+        self.rpm = omega.to_degrees() / 6.0;
+        if self.rpm < data.stall_rpm
+        {
+            self.running = false;
+            self.rpm = 0.0;
+        }
+
+        //Temperatures lag behind the power
+        let load = indicated_power / rated_indicated_power;
+        let (egt, cht) = if self.running
+        {
+            let heat = egt_factor(self.equivalence_ratio);
+            (air.temperature + data.egt_rise * heat * (0.6 + 0.4 * load), air.temperature + data.cht_rise * heat * load)
+        }
+        else
+        {
+            (air.temperature, air.temperature)
+        };
+        self.egt += (egt - self.egt) * (1.0 - (-dt / data.egt_time_constant).exp());
+        self.cht += (cht - self.cht) * (1.0 - (-dt / data.cht_time_constant).exp());
+    }
+}
+
+//Load the engine data into the FlightGear packet as its only engine, in its units and in network byte order
+pub fn load_engine(engine: &PistonEngine, fgnet: &mut FGNetFDM)
+{
+    fgnet.num_engines = u32::from_be_bytes(1_u32.to_ne_bytes());
+    load_engine_slot(engine, 0, fgnet);
+}
+
+//Load the engine data into one of the per-engine slots of the FlightGear packet
+pub fn load_engine_slot(engine: &PistonEngine, slot: usize, fgnet: &mut FGNetFDM)
+{
+    let fahrenheit = |kelvin: f64| ((kelvin - 273.15) * 1.8 + 32.0) as f32;
+    let state = if engine.running { ENGINE_RUNNING } else { ENGINE_OFF };
+    let rpm = engine.rpm as f32;
+    let fuel_flow = (engine.fuel_flow * 3600.0 / (AVGAS_DENSITY * LITRES_PER_GALLON)) as f32; //gallons/hr
+    let manifold_pressure = (engine.manifold_pressure / PASCALS_PER_INHG) as f32; //inHg
+
+    fgnet.eng_state[slot] = f32::from_be_bytes(state.to_ne_bytes());
+    fgnet.rpm[slot] = f32::from_be_bytes(rpm.to_ne_bytes());
+    fgnet.fuel_flow[slot] = f32::from_be_bytes(fuel_flow.to_ne_bytes());
+    fgnet.egt[slot] = f32::from_be_bytes(fahrenheit(engine.egt).to_ne_bytes());
+    fgnet.cht[slot] = f32::from_be_bytes(fahrenheit(engine.cht).to_ne_bytes());
+    fgnet.mp_osi[slot] = f32::from_be_bytes(manifold_pressure.to_ne_bytes());
+}
+
+//Print the engine state, this is called once per frame
+pub fn print_engine(engine: &PistonEngine)
+{
+    println!("Engine RPM:       {}", engine.rpm);
+    println!("Manifold (inHg):  {}", engine.manifold_pressure / PASCALS_PER_INHG);
+    println!("Power (kW):       {}", engine.power / 1000.0);
+    println!("Fuel flow (kg/h): {}", engine.fuel_flow * 3600.0);
+    println!("EGT (K):          {}", engine.egt);
+    println!("CHT (K):          {}", engine.cht);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //160 hp engine of 5.24 l at 2700 RPM
+    fn test_engine() -> PistonEngine
+    {
+        PistonEngine::new(PistonEngineData::new(119310.0, 2700.0, 0.00524))
+    }
+
+    //Run an engine for a time (s) at 240 Hz against the default load
+    fn run(engine: &mut PistonEngine, throttle: f64, air: &Atmosphere, time: f64)
+    {
+        for _ in 0..(time * 240.0) as usize
+        {
+            let load = engine.default_load(air.density);
+            engine.update(throttle, air, load, 1.0 / 240.0);
+        }
+    }
+
+    #[test]
+    fn power_test()
+    {
+        //Rated power at sea level, full throttle, best power mixture and the rated RPM
+        let sea_level = Atmosphere::standard(0.0);
+        let mut engine = test_engine();
+        engine.start();
+        engine.rpm = 2700.0;
+        engine.mixture = BEST_POWER_MIXTURE / engine.data.full_rich;
+        engine.update(1.0, &sea_level, 0.0, 0.0);
+        assert!((engine.power - 119310.0).abs() < 1.0);
+        assert!((engine.manifold_pressure - sea_level.pressure).abs() < 1e-9);
+
+        //Against the default load the engine settles at the rated RPM, full rich gives a little less power
+        run(&mut engine, 1.0, &sea_level, 10.0);
+        assert!((engine.rpm - 2700.0).abs() < 1.0);
+        engine.mixture = 1.0;
+        run(&mut engine, 1.0, &sea_level, 10.0);
+        assert!(engine.rpm < 2700.0 && engine.rpm > 2650.0);
+
+        //At idle the engine turns slowly and keeps running
+        run(&mut engine, 0.0, &sea_level, 10.0);
+        assert!(engine.running && engine.rpm > 700.0 && engine.rpm < 1500.0);
+        assert!((engine.manifold_pressure / sea_level.pressure - 0.3).abs() < 1e-9);
+
+        //Fuel flow of a light airplane engine at full power, gallons per hour
+        run(&mut engine, 1.0, &sea_level, 10.0);
+        let gph = engine.fuel_flow * 3600.0 / (AVGAS_DENSITY * LITRES_PER_GALLON);
+        assert!(gph > 10.0 && gph < 15.0, "{}", gph);
+    }
+
+    #[test]
+    fn altitude_test()
+    {
+        //Full throttle full rich at 3000 m: less power and an over-rich mixture, leaning gives some of the power back
+        let air = Atmosphere::standard(3000.0);
+        let mut engine = test_engine();
+        engine.start();
+        run(&mut engine, 1.0, &air, 10.0);
+        let full_rich = engine.power;
+        assert!(full_rich < 0.8 * 119310.0);
+        assert!(engine.equivalence_ratio > 1.4);
+
+        engine.mixture = BEST_POWER_MIXTURE / engine.equivalence_ratio;
+        run(&mut engine, 1.0, &air, 10.0);
+        assert!((engine.equivalence_ratio - BEST_POWER_MIXTURE).abs() < 1e-9);
+        assert!(engine.power > full_rich);
+    }
+
+    #[test]
+    fn mixture_test()
+    {
+        //EGT peaks at the stoichiometric mixture
+        let sea_level = Atmosphere::standard(0.0);
+        let mut peak = (0.0, 0.0);
+        for step in 0..=10
+        {
+            let mut engine = test_engine();
+            engine.start();
+            engine.mixture = (0.5 + 0.05 * step as f64) / engine.data.full_rich;
+            run(&mut engine, 1.0, &sea_level, 30.0);
+            if engine.egt > peak.0
+            {
+                peak = (engine.egt, engine.equivalence_ratio);
+            }
+        }
+        assert!((peak.1 - PEAK_EGT_MIXTURE).abs() < 0.06);
+
+        //The EGT lags behind: after one time constant it has made 63% of its way back from a cold start
+        let mut engine = test_engine();
+        engine.start();
+        run(&mut engine, 1.0, &sea_level, 30.0);
+        let steady = engine.egt;
+        engine.egt = sea_level.temperature;
+        let time_constant = engine.data.egt_time_constant;
+        run(&mut engine, 1.0, &sea_level, time_constant);
+        let made = (engine.egt - sea_level.temperature) / (steady - sea_level.temperature);
+        assert!((made - (1.0 - (-1.0_f64).exp())).abs() < 0.01, "{}", made);
+
+        //Cut-off stops the engine and it spins down
+        let mut engine = test_engine();
+        engine.start();
+        run(&mut engine, 1.0, &sea_level, 5.0);
+        engine.mixture = 0.0;
+        run(&mut engine, 1.0, &sea_level, 10.0);
+        assert!(!engine.running);
+        assert_eq!((engine.rpm, engine.fuel_flow), (0.0, 0.0));
+    }
+
+    #[test]
+    fn packet_test()
+    {
+        let mut engine = test_engine();
+        engine.start();
+        run(&mut engine, 1.0, &Atmosphere::standard(0.0), 10.0);
+
+        let mut fgnet = FGNetFDM::default();
+        load_engine(&engine, &mut fgnet);
+        let value = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+        assert_eq!(u32::from_be_bytes(fgnet.num_engines.to_ne_bytes()), 1);
+        assert_eq!(value(fgnet.eng_state[0]), 2.0);
+        assert_eq!(value(fgnet.rpm[0]), engine.rpm as f32);
+        assert!((value(fgnet.mp_osi[0]) - 29.92).abs() < 0.01);
+        assert!(value(fgnet.egt[0]) > 1000.0 && value(fgnet.egt[0]) < 1600.0);
+    }
+}
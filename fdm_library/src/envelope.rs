@@ -0,0 +1,375 @@
+//To run unit tests for the envelope module from the command line:
+//cargo test --lib envelope
+
+//Flight envelope monitoring shared by both models. Each model's envelope module finds the state the limits are
+//checked against from its DataFDM: the calibrated airspeed, the load factor along the body z axis, the angle of attack,
+//the bank and the pitch. The EnvelopeMonitor Component checks them against the limits of the airplane and raises an
+//event when a limit is exceeded and another when the airplane is back within it, with the worst value reached, and keeps
+//them in its log for post-flight review. A limit counts as recovered a small fraction inside it, so a value sitting
+//on the limit does not flood the log. With protection on, each model clips its pitch control before it reaches the
+//equations of motion, so the angle of attack stays below the limit.
+//SI units and degrees: m/s and deg, the load factor is in g
+
+//SPECS
+use specs::prelude::*;
+
+//Display of the events
+use std::fmt;
+
+//Standard gravity (m/s^2), a load factor of 1
+pub const STANDARD_GRAVITY: f64 = 9.80665;
+
+//Fraction of a limit inside it the value must come back to for the limit to be recovered
+pub const RECOVERY_MARGIN: f64 = 0.02;
+
+//Angle of attack (deg) below the limit where the protection starts to clip the pitch control
+pub const PROTECTION_MARGIN: f64 = 2.0;
+
+//Limits of the flight envelope, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EnvelopeLimits
+{
+    pub never_exceed_speed: f64, //m/s, calibrated airspeed
+    pub stall_speed: f64, //m/s, calibrated airspeed
+    pub max_load_factor: f64, //g
+    pub min_load_factor: f64, //g, negative
+    pub max_alpha: f64, //deg
+    pub max_bank: f64, //deg, either way
+    pub max_pitch: f64, //deg, up or down
+}
+
+impl EnvelopeLimits
+{
+    //Limits of an airplane of a stall speed and never exceed speed (m/s), the rest those of the normal category:
+    //+3.8 and -1.52 g, 60 degrees of bank and 30 of pitch, and a 15 degree angle of attack
+    pub fn new(stall_speed: f64, never_exceed_speed: f64) -> EnvelopeLimits
+    {
+        EnvelopeLimits
+        {
+            never_exceed_speed,
+            stall_speed,
+            max_load_factor: 3.8,
+            min_load_factor: -1.52,
+            max_alpha: 15.0,
+            max_bank: 60.0,
+            max_pitch: 30.0,
+        }
+    }
+}
+
+//Limits of the flight envelope
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Limit
+{
+    Overspeed, //faster than the never exceed speed
+    Underspeed, //slower than the stall speed in the air
+    PositiveLoadFactor,
+    NegativeLoadFactor,
+    AngleOfAttack, //past the angle of attack limit in the air
+    Bank,
+    Pitch,
+}
+
+impl Limit
+{
+    pub const ALL: [Limit; 7] = [Limit::Overspeed, Limit::Underspeed, Limit::PositiveLoadFactor, Limit::NegativeLoadFactor,
+                                 Limit::AngleOfAttack, Limit::Bank, Limit::Pitch];
+
+    //Value of the state this limit is on, and how far past the limit it is, negative inside it.
+    //On the ground the speed and angle of attack are not limited
+    fn check(&self, limits: &EnvelopeLimits, state: &EnvelopeState) -> Option<(f64, f64)>
+    {
+        match self
+        {
+            Limit::Overspeed => Some((state.airspeed, state.airspeed - limits.never_exceed_speed)),
+            Limit::Underspeed if state.airborne => Some((state.airspeed, limits.stall_speed - state.airspeed)),
+            Limit::PositiveLoadFactor => Some((state.load_factor, state.load_factor - limits.max_load_factor)),
+            Limit::NegativeLoadFactor => Some((state.load_factor, limits.min_load_factor - state.load_factor)),
+            Limit::AngleOfAttack if state.airborne => Some((state.alpha, state.alpha - limits.max_alpha)),
+            Limit::Bank => Some((state.bank, state.bank.abs() - limits.max_bank)),
+            Limit::Pitch => Some((state.pitch, state.pitch.abs() - limits.max_pitch)),
+            _ => None,
+        }
+    }
+
+    //Size of the limit, the recovery margin is a fraction of it
+    fn size(&self, limits: &EnvelopeLimits) -> f64
+    {
+        match self
+        {
+            Limit::Overspeed => limits.never_exceed_speed,
+            Limit::Underspeed => limits.stall_speed,
+            Limit::PositiveLoadFactor => limits.max_load_factor,
+            Limit::NegativeLoadFactor => limits.min_load_factor.abs(),
+            Limit::AngleOfAttack => limits.max_alpha,
+            Limit::Bank => limits.max_bank,
+            Limit::Pitch => limits.max_pitch,
+        }
+    }
+}
+
+//State of the airplane the limits are checked against
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct EnvelopeState
+{
+    pub airspeed: f64, //m/s, calibrated airspeed
+    pub load_factor: f64, //g, along the body z axis, 1 in level flight
+    pub alpha: f64, //deg
+    pub bank: f64, //deg, right wing down
+    pub pitch: f64, //deg, nose up
+    pub airborne: bool,
+}
+
+//Event raised by the monitor, at the time (s) since it started
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EnvelopeEvent
+{
+    //A limit was exceeded with a value
+    Exceeded { time: f64, limit: Limit, value: f64 },
+    //The airplane is back within a limit, the peak is the worst value reached past it
+    Recovered { time: f64, limit: Limit, peak: f64 },
+}
+
+impl EnvelopeEvent
+{
+    pub fn limit(&self) -> Limit
+    {
+        match self
+        {
+            EnvelopeEvent::Exceeded { limit, .. } | EnvelopeEvent::Recovered { limit, .. } => *limit,
+        }
+    }
+}
+
+impl fmt::Display for EnvelopeEvent
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            EnvelopeEvent::Exceeded { time, limit, value } => write!(f, "{:9.2} s  {:?} exceeded at {:.2}", time, limit, value),
+            EnvelopeEvent::Recovered { time, limit, peak } => write!(f, "{:9.2} s  {:?} recovered, peak {:.2}", time, limit, peak),
+        }
+    }
+}
+
+//Exceedance in progress
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Exceedance
+{
+    limit: Limit,
+    peak: f64, //worst value past the limit
+    excess: f64, //how far past the limit the peak is
+}
+
+//Envelope monitor of an airplane: its limits, whether it protects the airplane, and the event log
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeMonitor
+{
+    pub limits: EnvelopeLimits,
+    pub protection: bool, //clip the pitch control to hold the angle of attack below its limit
+    pub time: f64, //s since the monitor started
+    pub events: Vec<EnvelopeEvent>, //log of every event raised, in time order
+    exceeded: Vec<Exceedance>,
+}
+
+impl Component for EnvelopeMonitor
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl EnvelopeMonitor
+{
+    //Monitor of an airplane's limits, without protection
+    pub fn new(limits: EnvelopeLimits) -> EnvelopeMonitor
+    {
+        EnvelopeMonitor { limits, protection: false, time: 0.0, events: Vec::new(), exceeded: Vec::new() }
+    }
+
+    //Check the state dt seconds on against the limits and return the events it raised, which are also logged
+    pub fn check(&mut self, state: &EnvelopeState, dt: f64) -> &[EnvelopeEvent]
+    {
+        self.time += dt;
+        let first = self.events.len();
+
+        for limit in Limit::ALL
+        {
+            let check = limit.check(&self.limits, state);
+            let margin = RECOVERY_MARGIN * limit.size(&self.limits);
+            match (self.exceeded.iter().position(|exceedance| exceedance.limit == limit), check)
+            {
+                //Newly exceeded
+                (None, Some((value, excess))) if excess > 0.0 =>
+                {
+                    self.exceeded.push(Exceedance { limit, peak: value, excess });
+                    self.events.push(EnvelopeEvent::Exceeded { time: self.time, limit, value });
+                }
+                //Still exceeded, keep the worst value
+                (Some(index), Some((value, excess))) if excess > -margin =>
+                {
+                    let exceedance = &mut self.exceeded[index];
+                    if excess > exceedance.excess
+                    {
+                        exceedance.peak = value;
+                        exceedance.excess = excess;
+                    }
+                }
+                //Back within the limit, or it is not checked any more
+                (Some(index), _) =>
+                {
+                    let exceedance = self.exceeded.remove(index);
+                    self.events.push(EnvelopeEvent::Recovered { time: self.time, limit, peak: exceedance.peak });
+                }
+                _ => (),
+            }
+        }
+
+        &self.events[first..]
+    }
+
+    //Forget the limits exceeded now when the airplane is put somewhere else, the log is kept
+    pub fn reset(&mut self)
+    {
+        self.exceeded.clear();
+    }
+
+    //Whether a limit is exceeded now
+    pub fn is_exceeded(&self, limit: Limit) -> bool
+    {
+        self.exceeded.iter().any(|exceedance| exceedance.limit == limit)
+    }
+
+    //Limits exceeded now
+    pub fn exceeded(&self) -> Vec<Limit>
+    {
+        self.exceeded.iter().map(|exceedance| exceedance.limit).collect()
+    }
+
+    //Angle of attack (deg) the protection holds the airplane below, none without protection
+    pub fn alpha_protection(&self) -> Option<f64>
+    {
+        if self.protection
+        {
+            Some(self.limits.max_alpha - PROTECTION_MARGIN)
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+//Print the limits exceeded now, this is called once per frame
+pub fn print_envelope(monitor: &EnvelopeMonitor)
+{
+    if !monitor.exceeded.is_empty()
+    {
+        println!("Envelope exceeded: {:?}", monitor.exceeded());
+    }
+}
+
+//Print the log of events for post-flight review
+pub fn print_envelope_events(monitor: &EnvelopeMonitor)
+{
+    for event in &monitor.events
+    {
+        println!("{}", event);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Level flight well within the limits
+    fn cruise() -> EnvelopeState
+    {
+        EnvelopeState { airspeed: 50.0, load_factor: 1.0, alpha: 4.0, bank: 0.0, pitch: 2.0, airborne: true }
+    }
+
+    fn monitor() -> EnvelopeMonitor
+    {
+        EnvelopeMonitor::new(EnvelopeLimits::new(25.0, 80.0))
+    }
+
+    #[test]
+    fn exceedance_test()
+    {
+        //Nothing to report in cruise
+        let mut monitor = monitor();
+        assert!(monitor.check(&cruise(), 0.1).is_empty());
+
+        //Pulling 4.5 g in a 70 degree bank exceeds both limits at once
+        let pull = EnvelopeState { load_factor: 4.5, bank: -70.0, ..cruise() };
+        let events = monitor.check(&pull, 0.1).to_vec();
+        assert_eq!(events.len(), 2);
+        assert!(events.contains(&EnvelopeEvent::Exceeded { time: 0.2, limit: Limit::PositiveLoadFactor, value: 4.5 }));
+        assert!(events.contains(&EnvelopeEvent::Exceeded { time: 0.2, limit: Limit::Bank, value: -70.0 }));
+        assert!(monitor.is_exceeded(Limit::Bank) && !monitor.is_exceeded(Limit::Pitch));
+
+        //It is raised once however long it lasts, and the recovery carries the peak
+        monitor.check(&EnvelopeState { load_factor: 5.0, ..pull }, 0.1);
+        assert!(monitor.check(&EnvelopeState { load_factor: 4.0, ..pull }, 0.1).is_empty());
+        let events = monitor.check(&cruise(), 0.1).to_vec();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|event| matches!(event, EnvelopeEvent::Recovered { limit: Limit::PositiveLoadFactor, peak, .. } if *peak == 5.0)));
+        assert!(monitor.exceeded().is_empty());
+        assert_eq!(monitor.events.len(), 4);
+    }
+
+    #[test]
+    fn limits_test()
+    {
+        //Each limit on its own, either way where it applies
+        let cases = [
+            (EnvelopeState { airspeed: 85.0, ..cruise() }, Limit::Overspeed),
+            (EnvelopeState { airspeed: 20.0, ..cruise() }, Limit::Underspeed),
+            (EnvelopeState { load_factor: -2.0, ..cruise() }, Limit::NegativeLoadFactor),
+            (EnvelopeState { alpha: 18.0, ..cruise() }, Limit::AngleOfAttack),
+            (EnvelopeState { bank: 65.0, ..cruise() }, Limit::Bank),
+            (EnvelopeState { pitch: -35.0, ..cruise() }, Limit::Pitch),
+        ];
+        for (state, limit) in cases
+        {
+            let mut monitor = monitor();
+            let events = monitor.check(&state, 0.1);
+            assert_eq!(events.len(), 1, "{:?}", limit);
+            assert_eq!(events[0].limit(), limit);
+        }
+
+        //On the ground the airplane may be slow and at any angle of attack
+        let mut monitor = monitor();
+        assert!(monitor.check(&EnvelopeState { airspeed: 0.0, alpha: 30.0, airborne: false, ..cruise() }, 0.1).is_empty());
+    }
+
+    #[test]
+    fn recovery_margin_test()
+    {
+        //A value hovering on the limit is one exceedance, it recovers once a margin inside it
+        let mut monitor = monitor();
+        let bank = |bank: f64| EnvelopeState { bank, ..cruise() };
+        monitor.check(&bank(60.5), 0.1);
+        assert!(monitor.check(&bank(59.5), 0.1).is_empty());
+        assert!(monitor.check(&bank(60.5), 0.1).is_empty());
+        assert_eq!(monitor.check(&bank(58.0), 0.1).len(), 1);
+        assert!(!monitor.is_exceeded(Limit::Bank));
+    }
+
+    #[test]
+    fn protection_test()
+    {
+        //The protection holds the angle of attack a margin below the limit
+        let mut monitor = monitor();
+        assert_eq!(monitor.alpha_protection(), None);
+        monitor.protection = true;
+        assert_eq!(monitor.alpha_protection(), Some(15.0 - PROTECTION_MARGIN));
+    }
+
+    #[test]
+    fn display_test()
+    {
+        let event = EnvelopeEvent::Exceeded { time: 12.5, limit: Limit::Overspeed, value: 82.3 };
+        assert_eq!(event.to_string(), "    12.50 s  Overspeed exceeded at 82.30");
+    }
+}
@@ -0,0 +1,630 @@
+//To run unit tests for the estimator module from the command line:
+//cargo test --lib estimator
+
+//Navigation state estimator shared by both models: an error-state extended Kalman filter fusing IMU, GPS, baro and
+//magnetometer measurements into an estimated attitude, velocity and position. The IMU drives the prediction, its
+//specific force and angular rate less the estimated biases are integrated in NED, and the GPS position and velocity,
+//the baro altitude and the magnetometer heading correct it. The error state is the position (m, NED), velocity,
+//attitude (rad, small rotations about the NED axes) and the accelerometer and gyro biases.
+//The measurements come from the Sensors Component or from a recorded log of readings fed to the filter in time order.
+//Each model's estimator module compares the estimate with the truth and can fly the guidance on the estimate.
+//Aerospace axes and SI units as in the sensors module
+
+//SPECS
+use specs::prelude::*;
+
+use std::collections::VecDeque;
+
+//nalgebra matrices and the attitude quaternion
+use nalgebra::{Matrix3, Rotation3, SMatrix, SVector, UnitQuaternion, Vector3};
+
+//Sensor readings and the truth they measure
+use crate::sensors::{Sensors, SensorSuiteData, SensorTruth, ImuReading, GpsFix, AirDataReading, MagnetometerReading};
+
+//Geodetic position
+use crate::navigation::{Geodetic, radii_of_curvature, propagate, wrap_longitude};
+
+//Number of error states and where each one starts
+const STATES: usize = 15;
+const POSITION: usize = 0;
+const VELOCITY: usize = 3;
+const ATTITUDE: usize = 6;
+const ACCELEROMETER_BIAS: usize = 9;
+const GYRO_BIAS: usize = 12;
+
+//Gravity (m/s^2), down
+const GRAVITY: f64 = 9.80665;
+
+type Covariance = SMatrix<f64, STATES, STATES>;
+
+//Noise the filter assumes, set per airplane to match its sensors. Standard deviations
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EstimatorData
+{
+    pub accelerometer_noise: f64, //m/s^2 of each IMU sample
+    pub gyro_noise: f64, //rad/s of each IMU sample
+    pub accelerometer_bias: f64, //m/s^2 at turn-on
+    pub gyro_bias: f64, //rad/s at turn-on
+    pub accelerometer_bias_walk: f64, //m/s^2 after one second
+    pub gyro_bias_walk: f64, //rad/s after one second
+    pub gps_position_noise: f64, //m, horizontal
+    pub gps_altitude_noise: f64, //m
+    pub gps_velocity_noise: f64, //m/s
+    pub baro_noise: f64, //m of the baro altitude
+    pub heading_noise: f64, //rad of the magnetic heading
+    pub initial_attitude: f64, //rad of the roll and pitch levelled from the accelerometers
+    pub field: Vector3<f64>, //earth magnetic field in NED the magnetometer heading is found with
+}
+
+impl EstimatorData
+{
+    //Noise matching a sensor suite. The scale factor errors are lumped into the IMU noise, the static pressure bias
+    //into the baro noise and the hard iron bias into the heading noise
+    pub fn from_sensors(data: &SensorSuiteData) -> EstimatorData
+    {
+        let imu = data.imu;
+        let horizontal_field = data.magnetometer.field.x.hypot(data.magnetometer.field.y);
+        let pressure_per_meter = 12.0; //Pa, near sea level
+
+        EstimatorData
+        {
+            accelerometer_noise: imu.accelerometer.noise.hypot(imu.accelerometer.scale_factor * GRAVITY),
+            gyro_noise: imu.gyro.noise,
+            accelerometer_bias: imu.accelerometer.bias,
+            gyro_bias: imu.gyro.bias,
+            accelerometer_bias_walk: imu.accelerometer.bias_walk,
+            gyro_bias_walk: imu.gyro.bias_walk,
+            gps_position_noise: data.gps.position_noise,
+            gps_altitude_noise: data.gps.altitude_noise,
+            gps_velocity_noise: data.gps.velocity_noise,
+            baro_noise: data.pitot_static.static_noise.hypot(data.pitot_static.static_bias) / pressure_per_meter,
+            heading_noise: data.magnetometer.noise.hypot(data.magnetometer.bias) / horizontal_field,
+            initial_attitude: 2.0_f64.to_radians(),
+            field: data.magnetometer.field,
+        }
+    }
+}
+
+//Estimated state and its standard deviations from the covariance
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NavigationEstimate
+{
+    pub time: f64, //s, of the last IMU sample
+    pub position: Geodetic,
+    pub velocity_ned: Vector3<f64>, //m/s
+    pub orientation: UnitQuaternion<f64>, //body axes to NED
+    pub attitude: Vector3<f64>, //rad, x = roll, y = pitch, z = heading clockwise from north
+    pub accelerometer_bias: Vector3<f64>, //m/s^2
+    pub gyro_bias: Vector3<f64>, //rad/s
+    pub position_std: Vector3<f64>, //m, NED
+    pub velocity_std: Vector3<f64>, //m/s
+    pub attitude_std: Vector3<f64>, //rad, about the NED axes
+}
+
+//Estimate less the truth
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct EstimationErrors
+{
+    pub position: Vector3<f64>, //m, NED
+    pub velocity: Vector3<f64>, //m/s, NED
+    pub attitude: Vector3<f64>, //rad, roll, pitch and heading
+}
+
+//Running error statistics over the comparisons with the truth
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ErrorStatistics
+{
+    pub samples: u64,
+    pub position_squares: Vector3<f64>, //sums of the squared errors
+    pub velocity_squares: Vector3<f64>,
+    pub attitude_squares: Vector3<f64>,
+    pub max_horizontal_position: f64, //m, largest horizontal position error
+}
+
+impl ErrorStatistics
+{
+    pub fn add(&mut self, errors: &EstimationErrors)
+    {
+        self.samples += 1;
+        self.position_squares += errors.position.component_mul(&errors.position);
+        self.velocity_squares += errors.velocity.component_mul(&errors.velocity);
+        self.attitude_squares += errors.attitude.component_mul(&errors.attitude);
+        self.max_horizontal_position = self.max_horizontal_position.max(errors.position.x.hypot(errors.position.y));
+    }
+
+    //Root mean square errors on each axis
+    pub fn rms_position(&self) -> Vector3<f64>
+    {
+        self.rms(&self.position_squares)
+    }
+
+    pub fn rms_velocity(&self) -> Vector3<f64>
+    {
+        self.rms(&self.velocity_squares)
+    }
+
+    pub fn rms_attitude(&self) -> Vector3<f64>
+    {
+        self.rms(&self.attitude_squares)
+    }
+
+    fn rms(&self, squares: &Vector3<f64>) -> Vector3<f64>
+    {
+        if self.samples == 0
+        {
+            return Vector3::zeros();
+        }
+        (squares / self.samples as f64).map(f64::sqrt)
+    }
+}
+
+//Navigation filter Component, published alongside the airplane's DataFDM. Until it has aligned there is no estimate
+#[derive(Debug, Clone)]
+pub struct Estimator
+{
+    pub data: EstimatorData,
+    pub guidance_on_estimate: bool, //the flight plan, approach and autopilot fly on the estimate instead of the truth
+    pub estimate: Option<NavigationEstimate>,
+    pub errors: Option<EstimationErrors>, //of the last comparison with the truth
+    pub statistics: ErrorStatistics,
+    pub time: f64, //s, of the sensors last processed, the estimate is of the last IMU sample before it
+    covariance: Covariance,
+    gps_time: f64, //s, of the last reading used from each sensor
+    air_data_time: f64,
+    magnetometer_time: f64,
+    history: VecDeque<PastState>, //recent positions and velocities, to compare the GPS fixes with when they were measured
+}
+
+//Estimated position and velocity at the time of an IMU sample
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PastState
+{
+    time: f64, //s
+    position: Geodetic,
+    velocity_ned: Vector3<f64>, //m/s
+}
+
+//How long the past states are kept for (s), longer than any GPS latency
+const HISTORY_LENGTH: f64 = 1.0;
+
+impl Component for Estimator
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl Estimator
+{
+    pub fn new(data: EstimatorData) -> Estimator
+    {
+        Estimator
+        {
+            data,
+            guidance_on_estimate: false,
+            estimate: None,
+            errors: None,
+            statistics: ErrorStatistics::default(),
+            time: 0.0,
+            covariance: Covariance::zeros(),
+            gps_time: f64::NEG_INFINITY,
+            air_data_time: f64::NEG_INFINITY,
+            magnetometer_time: f64::NEG_INFINITY,
+            history: VecDeque::new(),
+        }
+    }
+
+    //Start the filter over when the airplane is put somewhere else, it aligns again once the sensors have output.
+    //The data and whether the guidance flies on the estimate are kept
+    pub fn reset(&mut self)
+    {
+        *self = Estimator { guidance_on_estimate: self.guidance_on_estimate, ..Estimator::new(self.data) };
+    }
+
+    //Feed the filter the sensor outputs it has not used yet: it aligns once the IMU, GPS and magnetometer have
+    //all output, then each new IMU sample predicts and each new GPS fix, air data sample and magnetometer sample corrects
+    pub fn process(&mut self, sensors: &Sensors)
+    {
+        self.time = sensors.time;
+        if self.estimate.is_none()
+        {
+            if let (Some(imu), Some(fix), Some(magnetometer)) = (sensors.imu, sensors.gps, sensors.magnetometer)
+            {
+                self.align(&imu, &fix, &magnetometer);
+            }
+            return;
+        }
+
+        if let Some(imu) = sensors.imu
+        {
+            self.predict(&imu);
+        }
+        if let Some(fix) = sensors.gps
+        {
+            self.correct_gps(&fix);
+        }
+        if let Some(air_data) = sensors.air_data
+        {
+            self.correct_baro(&air_data);
+        }
+        if let Some(magnetometer) = sensors.magnetometer
+        {
+            self.correct_magnetometer(&magnetometer);
+        }
+    }
+
+    //Start the estimate at the IMU sample's time: roll and pitch levelled from the specific force, as in
+    //unaccelerated flight, the heading from the magnetometer and the position and velocity from the GPS fix
+    pub fn align(&mut self, imu: &ImuReading, fix: &GpsFix, magnetometer: &MagnetometerReading)
+    {
+        let force = imu.specific_force;
+        let roll = (-force.y).atan2(-force.z);
+        let pitch = force.x.atan2(force.y.hypot(force.z));
+        let heading = magnetic_heading(&magnetometer.field, roll, pitch, &self.data.field);
+
+        let data = self.data;
+        let mut variances = SVector::<f64, STATES>::zeros();
+        variances.fixed_rows_mut::<3>(POSITION).copy_from(&Vector3::new(data.gps_position_noise, data.gps_position_noise, data.gps_altitude_noise).map(|x| x * x));
+        variances.fixed_rows_mut::<3>(VELOCITY).fill(data.gps_velocity_noise.powi(2));
+        variances.fixed_rows_mut::<3>(ATTITUDE).copy_from(&Vector3::new(data.initial_attitude, data.initial_attitude, data.heading_noise).map(|x| x * x));
+        variances.fixed_rows_mut::<3>(ACCELEROMETER_BIAS).fill(data.accelerometer_bias.powi(2));
+        variances.fixed_rows_mut::<3>(GYRO_BIAS).fill(data.gyro_bias.powi(2));
+        self.covariance = Covariance::from_diagonal(&variances);
+
+        let orientation = UnitQuaternion::from_euler_angles(roll, pitch, heading);
+        self.estimate = Some(NavigationEstimate
+        {
+            time: imu.time,
+            position: propagate(&fix.position, &fix.velocity_ned, imu.time - fix.time),
+            velocity_ned: fix.velocity_ned,
+            orientation,
+            attitude: Vector3::zeros(),
+            accelerometer_bias: Vector3::zeros(),
+            gyro_bias: Vector3::zeros(),
+            position_std: Vector3::zeros(),
+            velocity_std: Vector3::zeros(),
+            attitude_std: Vector3::zeros(),
+        });
+        self.gps_time = fix.time;
+        self.magnetometer_time = magnetometer.time;
+        self.history.clear();
+        self.remember();
+        self.publish();
+    }
+
+    //Integrate an IMU sample newer than the estimate, over the time since the last one
+    pub fn predict(&mut self, imu: &ImuReading)
+    {
+        let data = self.data;
+        let estimate = match self.estimate.as_mut()
+        {
+            Some(estimate) if imu.time > estimate.time => estimate,
+            _ => return,
+        };
+        let dt = imu.time - estimate.time;
+        estimate.time = imu.time;
+
+        //Strapdown: the attitude turns by the angular rate, the velocity and position follow the specific force and gravity
+        let rotation = estimate.orientation.to_rotation_matrix();
+        let force = rotation * (imu.specific_force - estimate.accelerometer_bias);
+        let acceleration = force + Vector3::new(0.0, 0.0, GRAVITY);
+        estimate.position = propagate(&estimate.position, &(estimate.velocity_ned + 0.5 * acceleration * dt), dt);
+        estimate.velocity_ned += acceleration * dt;
+        estimate.orientation *= UnitQuaternion::from_scaled_axis((imu.angular_rate - estimate.gyro_bias) * dt);
+
+        //Error state transition and the noise the step adds
+        let mut transition = Covariance::identity();
+        transition.fixed_view_mut::<3, 3>(POSITION, VELOCITY).copy_from(&(Matrix3::identity() * dt));
+        transition.fixed_view_mut::<3, 3>(VELOCITY, ATTITUDE).copy_from(&(-force.cross_matrix() * dt));
+        transition.fixed_view_mut::<3, 3>(VELOCITY, ACCELEROMETER_BIAS).copy_from(&(-rotation.matrix() * dt));
+        transition.fixed_view_mut::<3, 3>(ATTITUDE, GYRO_BIAS).copy_from(&(-rotation.matrix() * dt));
+
+        let mut noise = SVector::<f64, STATES>::zeros();
+        noise.fixed_rows_mut::<3>(VELOCITY).fill((data.accelerometer_noise * dt).powi(2));
+        noise.fixed_rows_mut::<3>(ATTITUDE).fill((data.gyro_noise * dt).powi(2));
+        noise.fixed_rows_mut::<3>(ACCELEROMETER_BIAS).fill(data.accelerometer_bias_walk.powi(2) * dt);
+        noise.fixed_rows_mut::<3>(GYRO_BIAS).fill(data.gyro_bias_walk.powi(2) * dt);
+
+        self.covariance = transition * self.covariance * transition.transpose() + Covariance::from_diagonal(&noise);
+        self.remember();
+        self.publish();
+    }
+
+    //Correct with a GPS fix newer than the last one. The fix is compared with the estimate from when it was measured,
+    //the error is taken to have stayed the same over the latency. Without a past state that old the current estimate
+    //is moved back to the fix's time instead
+    pub fn correct_gps(&mut self, fix: &GpsFix)
+    {
+        let estimate = match self.estimate
+        {
+            Some(estimate) if fix.time > self.gps_time => estimate,
+            _ => return,
+        };
+        self.gps_time = fix.time;
+
+        //The last past state before the fix, moved on to the fix's time
+        let past = self.history.iter().rev().find(|past| past.time <= fix.time).copied().unwrap_or(PastState
+        {
+            time: estimate.time,
+            position: estimate.position,
+            velocity_ned: estimate.velocity_ned,
+        });
+        let position = propagate(&past.position, &past.velocity_ned, fix.time - past.time);
+        let mut residual = SVector::<f64, 6>::zeros();
+        residual.fixed_rows_mut::<3>(0).copy_from(&ned_offset(&position, &fix.position));
+        residual.fixed_rows_mut::<3>(3).copy_from(&(fix.velocity_ned - past.velocity_ned));
+
+        let mut h = SMatrix::<f64, 6, STATES>::zeros();
+        h.fixed_view_mut::<6, 6>(0, POSITION).fill_with_identity();
+
+        let data = self.data;
+        let noise = SVector::<f64, 6>::from([data.gps_position_noise, data.gps_position_noise, data.gps_altitude_noise,
+                                             data.gps_velocity_noise, data.gps_velocity_noise, data.gps_velocity_noise]);
+        self.correct(&residual, &h, &noise);
+    }
+
+    //Correct with the baro altitude of an air data sample newer than the last one
+    pub fn correct_baro(&mut self, air_data: &AirDataReading)
+    {
+        let estimate = match self.estimate
+        {
+            Some(estimate) if air_data.time > self.air_data_time => estimate,
+            _ => return,
+        };
+        self.air_data_time = air_data.time;
+
+        //The altitude is up, the position error down
+        let mut h = SMatrix::<f64, 1, STATES>::zeros();
+        h[POSITION + 2] = -1.0;
+        self.correct(&SVector::<f64, 1>::new(air_data.altitude - estimate.position.altitude), &h, &SVector::<f64, 1>::new(self.data.baro_noise));
+    }
+
+    //Correct the heading with a magnetometer sample newer than the last one
+    pub fn correct_magnetometer(&mut self, magnetometer: &MagnetometerReading)
+    {
+        let estimate = match self.estimate
+        {
+            Some(estimate) if magnetometer.time > self.magnetometer_time => estimate,
+            _ => return,
+        };
+        self.magnetometer_time = magnetometer.time;
+
+        let heading = magnetic_heading(&magnetometer.field, estimate.attitude.x, estimate.attitude.y, &self.data.field);
+        let mut h = SMatrix::<f64, 1, STATES>::zeros();
+        h[ATTITUDE + 2] = 1.0;
+        self.correct(&SVector::<f64, 1>::new(wrap_angle(heading - estimate.attitude.z)), &h, &SVector::<f64, 1>::new(self.data.heading_noise));
+    }
+
+    //The estimate moved on from its IMU sample to the time of the sensors last processed at its velocity
+    pub fn current(&self) -> Option<NavigationEstimate>
+    {
+        self.estimate.map(|estimate| NavigationEstimate
+        {
+            time: self.time,
+            position: propagate(&estimate.position, &estimate.velocity_ned, self.time - estimate.time),
+            ..estimate
+        })
+    }
+
+    //Compare the current estimate with the truth at the time of the sensors last processed,
+    //keeping the errors and adding them to the statistics
+    pub fn compare(&mut self, truth: &SensorTruth)
+    {
+        if let Some(estimate) = self.current()
+        {
+            let errors = EstimationErrors
+            {
+                position: ned_offset(&truth.position, &estimate.position),
+                velocity: estimate.velocity_ned - truth.velocity_ned,
+                attitude: (estimate.attitude - truth.attitude).map(wrap_angle),
+            };
+            self.statistics.add(&errors);
+            self.errors = Some(errors);
+        }
+    }
+
+    //Kalman update with a residual (measured less estimated), its sensitivity to the error state and its noise,
+    //using the Joseph form to keep the covariance symmetric and positive
+    fn correct<const M: usize>(&mut self, residual: &SVector<f64, M>, h: &SMatrix<f64, M, STATES>, noise: &SVector<f64, M>)
+    {
+        let r = SMatrix::<f64, M, M>::from_diagonal(&noise.component_mul(noise));
+        let innovation = h * self.covariance * h.transpose() + r;
+        let inverse = match innovation.try_inverse()
+        {
+            Some(inverse) => inverse,
+            None => return,
+        };
+        let gain = self.covariance * h.transpose() * inverse;
+        let correction = gain * residual;
+
+        let update = Covariance::identity() - gain * h;
+        self.covariance = update * self.covariance * update.transpose() + gain * r * gain.transpose();
+
+        //The past states move with the estimate, so a later fix is not compared with positions already corrected
+        let position = correction.fixed_rows::<3>(POSITION).into_owned();
+        let velocity = correction.fixed_rows::<3>(VELOCITY).into_owned();
+        for past in self.history.iter_mut()
+        {
+            past.position = offset_position(&past.position, &position);
+            past.velocity_ned += velocity;
+        }
+
+        if let Some(estimate) = self.estimate.as_mut()
+        {
+            estimate.position = offset_position(&estimate.position, &position);
+            estimate.velocity_ned += velocity;
+            estimate.orientation = UnitQuaternion::from_scaled_axis(correction.fixed_rows::<3>(ATTITUDE).into_owned()) * estimate.orientation;
+            estimate.accelerometer_bias += correction.fixed_rows::<3>(ACCELEROMETER_BIAS);
+            estimate.gyro_bias += correction.fixed_rows::<3>(GYRO_BIAS);
+        }
+        self.publish();
+    }
+
+    //Keep the estimated position and velocity, dropping the ones older than the history
+    fn remember(&mut self)
+    {
+        if let Some(estimate) = self.estimate
+        {
+            self.history.push_back(PastState { time: estimate.time, position: estimate.position, velocity_ned: estimate.velocity_ned });
+            while self.history.front().is_some_and(|past| past.time < estimate.time - HISTORY_LENGTH)
+            {
+                self.history.pop_front();
+            }
+        }
+    }
+
+    //Update the attitude angles and standard deviations of the estimate
+    fn publish(&mut self)
+    {
+        let deviations = self.covariance.diagonal().map(|variance| variance.max(0.0).sqrt());
+        if let Some(estimate) = self.estimate.as_mut()
+        {
+            let (roll, pitch, yaw) = estimate.orientation.euler_angles();
+            estimate.attitude = Vector3::new(roll, pitch, yaw.rem_euclid(2.0 * std::f64::consts::PI));
+            estimate.position_std = deviations.fixed_rows::<3>(POSITION).into_owned();
+            estimate.velocity_std = deviations.fixed_rows::<3>(VELOCITY).into_owned();
+            estimate.attitude_std = deviations.fixed_rows::<3>(ATTITUDE).into_owned();
+        }
+    }
+}
+
+//Heading (rad, clockwise from north) of a body axes magnetometer reading at a roll and pitch, with the earth field in NED
+pub fn magnetic_heading(field_body: &Vector3<f64>, roll: f64, pitch: f64, field_ned: &Vector3<f64>) -> f64
+{
+    let level = Rotation3::from_euler_angles(roll, pitch, 0.0) * field_body;
+    let declination = field_ned.y.atan2(field_ned.x);
+    ((-level.y).atan2(level.x) + declination).rem_euclid(2.0 * std::f64::consts::PI)
+}
+
+//NED offset (m) from one position to another close by
+fn ned_offset(from: &Geodetic, to: &Geodetic) -> Vector3<f64>
+{
+    let (r_meridian, r_normal) = radii_of_curvature(from.latitude);
+    Vector3::new((to.latitude - from.latitude) * (r_meridian + from.altitude),
+                 wrap_longitude(to.longitude - from.longitude) * (r_normal + from.altitude) * from.latitude.cos(),
+                 from.altitude - to.altitude)
+}
+
+//Position moved by a small NED offset (m)
+fn offset_position(position: &Geodetic, offset: &Vector3<f64>) -> Geodetic
+{
+    let (r_meridian, r_normal) = radii_of_curvature(position.latitude);
+    let latitude = position.latitude + offset.x / (r_meridian + position.altitude);
+    let longitude = position.longitude + offset.y / ((r_normal + position.altitude) * position.latitude.cos());
+    Geodetic::new(latitude, wrap_longitude(longitude), position.altitude - offset.z)
+}
+
+//Keep an angle within -pi..pi
+fn wrap_angle(angle: f64) -> f64
+{
+    let pi = std::f64::consts::PI;
+    (angle + pi).rem_euclid(2.0 * pi) - pi
+}
+
+//Print the estimate and the RMS errors so far, this is called once per frame
+pub fn print_estimate(estimator: &Estimator)
+{
+    if let Some(estimate) = estimator.estimate
+    {
+        println!("Est lat/lon (deg):  {} {}", estimate.position.latitude_deg(), estimate.position.longitude_deg());
+        println!("Est altitude (m):   {}", estimate.position.altitude);
+        println!("Est attitude (deg): {:?}", estimate.attitude.map(f64::to_degrees).as_slice());
+        println!("RMS position (m):   {:?}", estimator.statistics.rms_position().as_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::sensors::Sensors;
+
+    //Straight and level flight heading east at 50 m/s and 1000 m, moved on t seconds
+    fn truth_at(t: f64) -> SensorTruth
+    {
+        let start = Geodetic::from_degrees(39.8, -84.0, 1000.0);
+        let velocity_ned = Vector3::new(0.0, 50.0, 0.0);
+        SensorTruth
+        {
+            specific_force: Vector3::new(0.0, 0.0, -GRAVITY),
+            angular_rate: Vector3::zeros(),
+            attitude: Vector3::new(0.0, 0.0, 0.5 * std::f64::consts::PI),
+            position: propagate(&start, &velocity_ned, t),
+            velocity_ned,
+            airspeed: 50.0,
+        }
+    }
+
+    //Fly the sensors and filter for a time at 240 Hz
+    fn fly(sensors: &mut Sensors, estimator: &mut Estimator, time: f64)
+    {
+        for _ in 0..(time * 240.0).round() as usize
+        {
+            sensors.update(&truth_at(sensors.time + 1.0 / 240.0), 1.0 / 240.0);
+            estimator.process(sensors);
+            estimator.compare(&truth_at(sensors.time));
+        }
+    }
+
+    #[test]
+    fn heading_test()
+    {
+        //Heading east with the north field along the left wing, and rolled the heading is still found
+        let field = Vector3::new(20.0, 0.0, 45.0);
+        assert!((magnetic_heading(&Vector3::new(0.0, -20.0, 45.0), 0.0, 0.0, &field) - 0.5 * std::f64::consts::PI).abs() < 1e-12);
+        let attitude = Vector3::new(0.3, 0.1, 2.0);
+        let reading = crate::sensors::ned_to_body(&attitude, &field);
+        assert!((magnetic_heading(&reading, attitude.x, attitude.y, &field) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn alignment_test()
+    {
+        //No estimate until the GPS has a fix, then one close to the truth
+        let data = SensorSuiteData::default();
+        let mut sensors = Sensors::new(data);
+        let mut estimator = Estimator::new(EstimatorData::from_sensors(&data));
+        fly(&mut sensors, &mut estimator, 0.3);
+        assert!(estimator.estimate.is_none() && estimator.statistics.samples == 0);
+        fly(&mut sensors, &mut estimator, 0.2);
+        let errors = estimator.errors.unwrap();
+        assert!(errors.position.norm() < 15.0, "{:?}", errors);
+        assert!(errors.attitude.norm() < 0.1, "{:?}", errors);
+    }
+
+    #[test]
+    fn convergence_test()
+    {
+        //After a minute the filter is better than the GPS alone and its attitude is good to a fraction of a degree
+        let data = SensorSuiteData::default();
+        let mut sensors = Sensors::new(data);
+        let mut estimator = Estimator::new(EstimatorData::from_sensors(&data));
+        fly(&mut sensors, &mut estimator, 30.0);
+        estimator.statistics = ErrorStatistics::default();
+        fly(&mut sensors, &mut estimator, 30.0);
+
+        let statistics = estimator.statistics;
+        assert!(statistics.samples == 30 * 240);
+        assert!(statistics.rms_position().x < data.gps.position_noise && statistics.rms_position().y < data.gps.position_noise, "{:?}", statistics.rms_position());
+        assert!(statistics.rms_position().z < 2.0, "{:?}", statistics.rms_position());
+        assert!(statistics.rms_velocity().norm() < 0.2, "{:?}", statistics.rms_velocity());
+        assert!(statistics.rms_attitude().x < 0.5_f64.to_radians() && statistics.rms_attitude().y < 0.5_f64.to_radians(), "{:?}", statistics.rms_attitude());
+        assert!(statistics.rms_attitude().z < 3.0_f64.to_radians(), "{:?}", statistics.rms_attitude());
+
+        //The standard deviations shrink from the alignment and the gyro bias is found
+        let estimate = estimator.estimate.unwrap();
+        assert!(estimate.position_std.x < data.gps.position_noise);
+        assert!(estimate.attitude_std.x < 2.0_f64.to_radians());
+        let gyro_bias = sensors.imu.unwrap().angular_rate;
+        assert!((estimate.gyro_bias - gyro_bias).norm() < 0.005, "{:?} {:?}", estimate.gyro_bias, gyro_bias);
+    }
+
+    #[test]
+    fn statistics_test()
+    {
+        let mut statistics = ErrorStatistics::default();
+        assert_eq!(statistics.rms_position(), Vector3::zeros());
+        statistics.add(&EstimationErrors { position: Vector3::new(3.0, 4.0, 1.0), ..EstimationErrors::default() });
+        statistics.add(&EstimationErrors { position: Vector3::new(-3.0, 0.0, 1.0), ..EstimationErrors::default() });
+        assert_eq!(statistics.rms_position(), Vector3::new(3.0, 8.0_f64.sqrt(), 1.0));
+        assert_eq!(statistics.max_horizontal_position, 5.0);
+    }
+}
@@ -0,0 +1,290 @@
+//To run unit tests for the flight_plan module from the command line:
+//cargo test --lib flight_plan
+
+//Flight plan following shared by both models. The plan is a list of waypoints flown one leg at a time
+//along great circles, and the lateral guidance points the airplane at a spot a fixed distance ahead on the
+//leg (L1 guidance), which gives a heading target for the autopilot. Each model's flight_plan module turns the
+//guidance into autopilot modes in its own units. Altitudes are meters, speeds m/s and headings degrees
+
+//SPECS
+use specs::prelude::*;
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//Great circle routines
+use crate::navigation::{Geodetic, distance, bearing, cross_track, along_track};
+
+//A point of the flight plan, with an optional airspeed to fly the leg toward it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Waypoint
+{
+    pub position: Geodetic,
+    pub airspeed: Option<f64>, //m/s
+}
+
+impl Waypoint
+{
+    pub fn from_degrees(latitude: f64, longitude: f64, altitude: f64) -> Waypoint
+    {
+        Waypoint { position: Geodetic::from_degrees(latitude, longitude, altitude), airspeed: None }
+    }
+
+    pub fn with_airspeed(mut self, airspeed: f64) -> Waypoint
+    {
+        self.airspeed = Some(airspeed);
+        self
+    }
+}
+
+//Output of the guidance for the active leg
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Guidance
+{
+    pub heading: f64, //deg, clockwise from north
+    pub altitude: f64, //m, altitude of the active waypoint
+    pub airspeed: Option<f64>, //m/s, speed constraint of the active waypoint
+    pub cross_track: f64, //m, positive right of the leg
+    pub distance_to_waypoint: f64, //m
+    pub distance_to_go: f64, //m, to the active waypoint and along the rest of the plan
+    pub time_to_waypoint: f64, //s, at the current ground speed
+    pub time_to_go: f64, //s
+}
+
+//Waypoints of the plan, the leg being flown and the settings of the guidance along it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightPlan
+{
+    pub waypoints: Vec<Waypoint>,
+    pub active: usize, //index of the waypoint being flown to
+    pub leg_start: Option<Geodetic>, //start of the active leg, the position where the plan was engaged for the first leg
+    pub capture_radius: f64, //m, a waypoint is captured inside this distance or when the turn onto the next leg is due
+    pub l1_distance: f64, //m, how far ahead on the leg the guidance aims
+    pub turn_bank: f64, //deg, bank angle the turns are planned with, the turn onto the next leg starts one turn radius early
+    pub repeat: bool, //fly back to the first waypoint after the last one, for racetracks and survey loops
+    pub engaged: bool, //the plan is driving the autopilot
+    pub guidance: Guidance, //latest guidance output
+}
+
+impl Component for FlightPlan
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl Default for FlightPlan
+{
+    fn default() -> Self
+    {
+        FlightPlan::new(Vec::new())
+    }
+}
+
+impl FlightPlan
+{
+    pub fn new(waypoints: Vec<Waypoint>) -> FlightPlan
+    {
+        FlightPlan
+        {
+            waypoints,
+            active: 0,
+            leg_start: None,
+            capture_radius: 200.0,
+            l1_distance: 700.0,
+            turn_bank: 20.0,
+            repeat: false,
+            engaged: false,
+            guidance: Guidance::default(),
+        }
+    }
+
+    //Fly the plan again from its first waypoint, with the first leg starting where the airplane is next guided from.
+    //Whether it is engaged is kept
+    pub fn reset(&mut self)
+    {
+        self.active = 0;
+        self.leg_start = None;
+        self.guidance = Guidance::default();
+    }
+
+    //Every waypoint has been captured
+    pub fn is_complete(&self) -> bool
+    {
+        self.active >= self.waypoints.len()
+    }
+
+    //Waypoint being flown to
+    pub fn active_waypoint(&self) -> Option<&Waypoint>
+    {
+        self.waypoints.get(self.active)
+    }
+
+    //Waypoint after the active one
+    fn next_waypoint(&self) -> Option<&Waypoint>
+    {
+        match self.waypoints.get(self.active + 1)
+        {
+            Some(waypoint) => Some(waypoint),
+            None if self.repeat => self.waypoints.first(),
+            None => None,
+        }
+    }
+
+    //Distance before the active waypoint to start turning onto the next leg, so the turn ends on it
+    fn turn_lead(&self, start: &Geodetic, ground_speed: f64) -> f64
+    {
+        let (waypoint, next) = match (self.active_waypoint(), self.next_waypoint())
+        {
+            (Some(waypoint), Some(next)) => (waypoint.position, next.position),
+            _ => return 0.0,
+        };
+
+        let turn = (bearing(&waypoint, &next) - bearing(start, &waypoint)).to_degrees();
+        let turn = (turn + 180.0).rem_euclid(360.0) - 180.0;
+        let radius = ground_speed * ground_speed / (9.81 * self.turn_bank.to_radians().tan());
+        (radius * (0.5 * turn.abs()).min(60.0).to_radians().tan()).min(0.5 * distance(&waypoint, &next))
+    }
+
+    //Move on to the next leg, starting from the waypoint just captured
+    fn advance(&mut self)
+    {
+        self.leg_start = self.active_waypoint().map(|w| w.position);
+        self.active += 1;
+        if self.repeat && self.is_complete()
+        {
+            self.active = 0;
+        }
+    }
+
+    //Guidance from a position and NED ground velocity (m/s). Captured waypoints are passed over,
+    //and None is returned once the plan is complete
+    pub fn guide(&mut self, position: &Geodetic, v_ned: &Vector3<f64>) -> Option<Guidance>
+    {
+        if self.leg_start.is_none()
+        {
+            self.leg_start = Some(*position);
+        }
+
+        let ground_speed = (v_ned.x * v_ned.x + v_ned.y * v_ned.y).sqrt();
+
+        //Capture: close enough, or the turn onto the next leg is due. At most one lap of the plan per call
+        for _ in 0..self.waypoints.len()
+        {
+            let waypoint = self.active_waypoint()?.position;
+            let start = self.leg_start.unwrap_or(*position);
+            let leg = distance(&start, &waypoint);
+            let lead = self.turn_lead(&start, ground_speed);
+            if distance(position, &waypoint) < self.capture_radius || (leg > 0.0 && along_track(&start, &waypoint, position) >= leg - lead)
+            {
+                self.advance();
+            }
+            else
+            {
+                break;
+            }
+        }
+        let waypoint = *self.active_waypoint()?;
+        let start = self.leg_start.unwrap_or(*position);
+
+        //Aim at the point l1_distance ahead on the leg, straight at the waypoint once it is closer than that
+        let distance_to_waypoint = distance(position, &waypoint.position);
+        let cross = if distance(&start, &waypoint.position) > 0.0 { cross_track(&start, &waypoint.position, position) } else { 0.0 };
+        let heading = if distance_to_waypoint > self.l1_distance && cross.abs() < self.l1_distance
+        {
+            let track = bearing(&start, &waypoint.position).to_degrees();
+            track - cross.atan2((self.l1_distance * self.l1_distance - cross * cross).sqrt()).to_degrees()
+        }
+        else
+        {
+            bearing(position, &waypoint.position).to_degrees()
+        };
+
+        //Rest of the plan, repeating plans count up to the end of the list
+        let mut distance_to_go = distance_to_waypoint;
+        for pair in self.waypoints[self.active..].windows(2)
+        {
+            distance_to_go += distance(&pair[0].position, &pair[1].position);
+        }
+
+        let time = |d: f64| if ground_speed > 0.0 { d / ground_speed } else { f64::INFINITY };
+
+        self.guidance = Guidance
+        {
+            heading: heading.rem_euclid(360.0),
+            altitude: waypoint.position.altitude,
+            airspeed: waypoint.airspeed,
+            cross_track: cross,
+            distance_to_waypoint,
+            distance_to_go,
+            time_to_waypoint: time(distance_to_waypoint),
+            time_to_go: time(distance_to_go),
+        };
+        Some(self.guidance)
+    }
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Square of 0.05 degree legs around Wright-Patterson
+    fn square() -> Vec<Waypoint>
+    {
+        vec![Waypoint::from_degrees(39.85, -84.05, 600.0),
+             Waypoint::from_degrees(39.85, -84.00, 700.0).with_airspeed(60.0),
+             Waypoint::from_degrees(39.80, -84.00, 700.0),
+             Waypoint::from_degrees(39.80, -84.05, 600.0)]
+    }
+
+    #[test]
+    fn guidance_test()
+    {
+        //Leg due north, the airplane is on it flying north at 50 m/s
+        let mut plan = FlightPlan::new(square());
+        plan.leg_start = Some(Geodetic::from_degrees(39.80, -84.05, 600.0));
+        let velocity = Vector3::new(50.0, 0.0, 0.0);
+
+        let guidance = plan.guide(&Geodetic::from_degrees(39.81, -84.05, 600.0), &velocity).unwrap();
+        assert!(guidance.heading < 1.0e-6 || guidance.heading > 360.0 - 1.0e-6);
+        assert!(guidance.cross_track.abs() < 1.0e-3);
+        assert_eq!(guidance.altitude, 600.0);
+        assert_eq!(guidance.airspeed, None);
+        assert!((guidance.time_to_waypoint - guidance.distance_to_waypoint / 50.0).abs() < 1.0e-9);
+        assert!(guidance.distance_to_go > guidance.distance_to_waypoint);
+
+        //East of the leg the guidance turns back to the left, west of it to the right
+        let right = plan.guide(&Geodetic::from_degrees(39.81, -84.049, 600.0), &velocity).unwrap();
+        assert!(right.cross_track > 0.0 && right.heading > 270.0);
+        let left = plan.guide(&Geodetic::from_degrees(39.81, -84.051, 600.0), &velocity).unwrap();
+        assert!(left.cross_track < 0.0 && left.heading > 0.0 && left.heading < 90.0);
+    }
+
+    #[test]
+    fn capture_test()
+    {
+        let mut plan = FlightPlan::new(square());
+        let velocity = Vector3::new(0.0, 50.0, 0.0);
+
+        //Inside the capture radius of the first waypoint, the next leg heads east with a speed constraint
+        let guidance = plan.guide(&Geodetic::from_degrees(39.8501, -84.05, 600.0), &velocity).unwrap();
+        assert_eq!(plan.active, 1);
+        assert_eq!(guidance.airspeed, Some(60.0));
+        assert!((guidance.heading - 90.0).abs() < 1.0);
+
+        //Overshooting abeam the second waypoint also captures it
+        plan.guide(&Geodetic::from_degrees(39.86, -83.99, 700.0), &velocity).unwrap();
+        assert_eq!(plan.active, 2);
+
+        //After the last waypoint the plan is complete, unless it repeats
+        plan.active = 3;
+        plan.leg_start = Some(square()[2].position);
+        assert_eq!(plan.guide(&square()[3].position, &velocity), None);
+        assert!(plan.is_complete());
+
+        plan.active = 3;
+        plan.repeat = true;
+        assert!(plan.guide(&square()[3].position, &velocity).is_some());
+        assert_eq!(plan.active, 0);
+    }
+}
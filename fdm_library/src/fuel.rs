@@ -0,0 +1,183 @@
+//To run unit tests for the fuel module from the command line:
+//cargo test --lib fuel
+
+//Fuel system shared by both models. The tanks are point masses at their positions on the airplane, each holding up to
+//its capacity. The engines draw their fuel flow from the tanks in feed order: the tanks of the lowest order feed first,
+//evenly while they all hold fuel, and the next order takes over once they run dry. Each model's fuel module burns the
+//fuel, updates the mass properties and stops the engines when the tanks are empty.
+//SI units: kg and kg/s, the positions are in the model's design coordinates
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//FGNetFDM packet structure
+use crate::flightgear::FGNetFDM;
+
+//Fuel densities (kg/l) and unit conversions for FlightGear
+pub const AVGAS_DENSITY: f64 = 0.72;
+pub const JET_FUEL_DENSITY: f64 = 0.8;
+pub const LITRES_PER_GALLON: f64 = 3.785;
+
+//Fuel tank, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FuelTank
+{
+    pub position: Vector3<f64>, //in the model's design coordinates, the point mass model ignores it
+    pub capacity: f64, //kg
+    pub quantity: f64, //kg
+    pub feed_order: u32, //the tanks feed from the lowest order up, tanks of the same order feed together
+    pub density: f64, //kg/l of the fuel in the tank
+}
+
+impl FuelTank
+{
+    //A full tank of a capacity (kg) of avgas
+    pub fn new(position: Vector3<f64>, capacity: f64, feed_order: u32) -> FuelTank
+    {
+        FuelTank { position, capacity, quantity: capacity, feed_order, density: AVGAS_DENSITY }
+    }
+
+    //Quantity in US gallons, as FlightGear shows it
+    pub fn gallons(&self) -> f64
+    {
+        self.quantity / (self.density * LITRES_PER_GALLON)
+    }
+}
+
+//Fuel tanks of an airplane. Without tanks the fuel is not modelled, the engines never run dry and the mass stays constant
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FuelSystem
+{
+    pub tanks: Vec<FuelTank>,
+}
+
+impl FuelSystem
+{
+    pub fn new(tanks: Vec<FuelTank>) -> FuelSystem
+    {
+        FuelSystem { tanks }
+    }
+
+    //Fuel left in all the tanks (kg)
+    pub fn quantity(&self) -> f64
+    {
+        self.tanks.iter().map(|tank| tank.quantity).sum()
+    }
+
+    //The airplane has tanks and they are all empty
+    pub fn exhausted(&self) -> bool
+    {
+        !self.tanks.is_empty() && self.quantity() <= 0.0
+    }
+
+    //Draw a fuel flow (kg/s) for dt seconds from the tanks in feed order. Return the fuel drawn (kg),
+    //which falls short of the demand when the tanks run dry
+    pub fn drain(&mut self, fuel_flow: f64, dt: f64) -> f64
+    {
+        let demand = fuel_flow.max(0.0) * dt;
+        let mut remaining = demand;
+
+        let mut orders: Vec<u32> = self.tanks.iter().map(|tank| tank.feed_order).collect();
+        orders.sort_unstable();
+        orders.dedup();
+
+        for order in orders
+        {
+            //The tanks of this order share the demand evenly. Each pass either meets it or runs a tank dry
+            while remaining > 0.0
+            {
+                let feeding: Vec<usize> = (0..self.tanks.len()).filter(|&i| self.tanks[i].feed_order == order && self.tanks[i].quantity > 0.0).collect();
+                if feeding.is_empty()
+                {
+                    break;
+                }
+
+                let share = remaining / feeding.len() as f64;
+                let runs_dry = feeding.iter().any(|&i| self.tanks[i].quantity <= share);
+                for i in feeding
+                {
+                    let drawn = share.min(self.tanks[i].quantity);
+                    self.tanks[i].quantity -= drawn;
+                    remaining -= drawn;
+                }
+                if !runs_dry
+                {
+                    remaining = 0.0;
+                }
+            }
+        }
+
+        demand - remaining
+    }
+}
+
+//Load the tank quantities into the FlightGear packet, in US gallons and in network byte order. It has room for four tanks
+pub fn load_fuel(fuel: &FuelSystem, fgnet: &mut FGNetFDM)
+{
+    let count = fuel.tanks.len().min(fgnet.fuel_quantity.len());
+    fgnet.num_tanks = u32::from_be_bytes((count as u32).to_ne_bytes());
+    for (slot, tank) in fuel.tanks.iter().take(count).enumerate()
+    {
+        fgnet.fuel_quantity[slot] = f32::from_be_bytes((tank.gallons() as f32).to_ne_bytes());
+    }
+}
+
+//Print the quantity in each tank, this is called once per frame
+pub fn print_fuel(fuel: &FuelSystem)
+{
+    for (i, tank) in fuel.tanks.iter().enumerate()
+    {
+        println!("Tank {} (kg):     {}", i + 1, tank.quantity);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Two wing tanks of 80 kg feeding together, then a 40 kg fuselage tank
+    fn test_fuel() -> FuelSystem
+    {
+        FuelSystem::new(vec![
+            FuelTank::new(Vector3::new(0.0, 2.0, 0.0), 80.0, 0),
+            FuelTank::new(Vector3::new(0.0, -2.0, 0.0), 80.0, 0),
+            FuelTank::new(Vector3::new(1.0, 0.0, 0.0), 40.0, 1),
+        ])
+    }
+
+    #[test]
+    fn feed_test()
+    {
+        //The wing tanks feed first and evenly, the fuselage tank is left alone
+        let mut fuel = test_fuel();
+        assert_eq!(fuel.drain(0.01, 1000.0), 10.0);
+        assert_eq!((fuel.tanks[0].quantity, fuel.tanks[1].quantity, fuel.tanks[2].quantity), (75.0, 75.0, 40.0));
+
+        //With one wing tank lower, the other one carries on alone once it runs dry, then the fuselage tank takes over
+        fuel.tanks[0].quantity = 5.0;
+        assert!((fuel.drain(1.0, 100.0) - 100.0).abs() < 1e-9);
+        assert_eq!((fuel.tanks[0].quantity, fuel.tanks[1].quantity), (0.0, 0.0));
+        assert!((fuel.tanks[2].quantity - 20.0).abs() < 1e-9);
+        assert!(!fuel.exhausted());
+
+        //The demand falls short when the tanks run dry
+        assert!((fuel.drain(1.0, 30.0) - 20.0).abs() < 1e-9);
+        assert!(fuel.exhausted());
+        assert_eq!(fuel.drain(1.0, 1.0), 0.0);
+
+        //Without tanks the fuel is not modelled
+        assert!(!FuelSystem::default().exhausted());
+    }
+
+    #[test]
+    fn packet_test()
+    {
+        let mut fgnet = FGNetFDM::default();
+        load_fuel(&test_fuel(), &mut fgnet);
+        let value = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+        assert_eq!(u32::from_be_bytes(fgnet.num_tanks.to_ne_bytes()), 3);
+        assert!((value(fgnet.fuel_quantity[0]) - 29.36).abs() < 0.01);
+        assert!((value(fgnet.fuel_quantity[2]) - 14.68).abs() < 0.01);
+    }
+}
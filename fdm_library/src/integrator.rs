@@ -0,0 +1,358 @@
+//To run unit tests for the integrator module from the command line:
+//cargo test --lib integrator
+
+//Generic ODE integrators for systems of the form dy/dt = f(t, y)
+//The derivative function is passed in as a closure that fills in dydt for a given time and state,
+//so both the Bourg and Palmer models can share the same solvers
+
+//Integration method used to advance the state each time step
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum IntegrationMethod
+{
+    Euler, //explicit (forward) Euler
+    SemiImplicitEuler, //rate states first, then the remaining states using the updated rates
+    #[default]
+    RungeKutta4, //classic fixed-step 4th-order Runge-Kutta
+    DormandPrince45, //adaptive 5th-order Runge-Kutta with embedded 4th-order error estimate
+}
+
+//Integrator configuration. This is also used as a SPECS resource so the method can be chosen per simulation
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Integrator
+{
+    pub method: IntegrationMethod,
+    pub tolerance: f64, //Dormand-Prince: allowed local error, scaled by (1 + |y|)
+    pub min_step: f64, //Dormand-Prince: smallest sub-step before the error check is ignored
+    pub max_step: f64, //Dormand-Prince: largest sub-step, 0.0 means the whole time step
+}
+
+impl Default for Integrator
+{
+    fn default() -> Self
+    {
+        Integrator
+        {
+            method: IntegrationMethod::default(),
+            tolerance: 1.0e-6,
+            min_step: 1.0e-6,
+            max_step: 0.0,
+        }
+    }
+}
+
+//Statistics of a single call to Integrator::step
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct StepReport
+{
+    pub steps: usize, //accepted sub-steps
+    pub rejected: usize, //rejected sub-steps (adaptive only)
+    pub error: f64, //largest scaled error estimate of the accepted sub-steps (adaptive only)
+}
+
+impl Integrator
+{
+    pub fn new(method: IntegrationMethod) -> Integrator
+    {
+        Integrator { method, ..Default::default() }
+    }
+
+    //Advance the state y from t to t + dt.
+    //rate_states lists the indices updated first by the semi-implicit Euler method (velocities, angular rates),
+    //it is ignored by the other methods
+    pub fn step<F>(&self, t: f64, y: &mut [f64], dt: f64, rate_states: &[usize], mut f: F) -> StepReport
+    where
+        F: FnMut(f64, &[f64], &mut [f64]),
+    {
+        match self.method
+        {
+            IntegrationMethod::Euler =>
+            {
+                euler(t, y, dt, &mut f);
+                StepReport { steps: 1, ..Default::default() }
+            }
+            IntegrationMethod::SemiImplicitEuler =>
+            {
+                semi_implicit_euler(t, y, dt, rate_states, &mut f);
+                StepReport { steps: 1, ..Default::default() }
+            }
+            IntegrationMethod::RungeKutta4 =>
+            {
+                rk4(t, y, dt, &mut f);
+                StepReport { steps: 1, ..Default::default() }
+            }
+            IntegrationMethod::DormandPrince45 => self.adaptive(t, y, dt, &mut f),
+        }
+    }
+
+    //Integrate over dt with as many Dormand-Prince sub-steps as the tolerance requires
+    fn adaptive<F>(&self, t: f64, y: &mut [f64], dt: f64, f: &mut F) -> StepReport
+    where
+        F: FnMut(f64, &[f64], &mut [f64]),
+    {
+        let mut report = StepReport::default();
+        let t_end = t + dt;
+        let mut time = t;
+        let mut h = if self.max_step > 0.0 { dt.min(self.max_step) } else { dt };
+        let mut trial = y.to_vec();
+
+        while time < t_end
+        {
+            //Do not step past the end of the interval
+            if time + h > t_end
+            {
+                h = t_end - time;
+            }
+
+            trial.copy_from_slice(y);
+            let err = dormand_prince(time, &mut trial, h, f) / self.tolerance;
+
+            if err <= 1.0 || h <= self.min_step
+            {
+                time += h;
+                y.copy_from_slice(&trial);
+                report.steps += 1;
+                report.error = report.error.max(err * self.tolerance);
+            }
+            else
+            {
+                report.rejected += 1;
+            }
+
+            //Grow or shrink the next sub-step based on the error estimate (5th-order scaling)
+            let scale = if err == 0.0 { 5.0 } else { (0.9 * err.powf(-0.2)).clamp(0.2, 5.0) };
+            h = (h * scale).max(self.min_step);
+            if self.max_step > 0.0
+            {
+                h = h.min(self.max_step);
+            }
+        }
+        report
+    }
+}
+
+//Explicit Euler: y(t + dt) = y + dt * f(t, y)
+pub fn euler<F>(t: f64, y: &mut [f64], dt: f64, f: &mut F)
+where
+    F: FnMut(f64, &[f64], &mut [f64]),
+{
+    let mut k = vec![0.0; y.len()];
+    f(t, y, &mut k);
+    for i in 0..y.len()
+    {
+        y[i] += dt * k[i];
+    }
+}
+
+//Semi-implicit (symplectic) Euler: the rate states are advanced first, then the derivatives are
+//re-evaluated so the remaining states are advanced with the updated rates
+pub fn semi_implicit_euler<F>(t: f64, y: &mut [f64], dt: f64, rate_states: &[usize], f: &mut F)
+where
+    F: FnMut(f64, &[f64], &mut [f64]),
+{
+    let mut k = vec![0.0; y.len()];
+    f(t, y, &mut k);
+    for &i in rate_states
+    {
+        y[i] += dt * k[i];
+    }
+
+    f(t, y, &mut k);
+    for i in 0..y.len()
+    {
+        if !rate_states.contains(&i)
+        {
+            y[i] += dt * k[i];
+        }
+    }
+}
+
+//Classic 4th-order Runge-Kutta. Each stage is stored as a delta (dt * k) like Palmer's original solver
+pub fn rk4<F>(t: f64, y: &mut [f64], dt: f64, f: &mut F)
+where
+    F: FnMut(f64, &[f64], &mut [f64]),
+{
+    let n = y.len();
+    let mut tmp = vec![0.0; n];
+    let mut dq1 = vec![0.0; n];
+    let mut dq2 = vec![0.0; n];
+    let mut dq3 = vec![0.0; n];
+    let mut dq4 = vec![0.0; n];
+
+    f(t, y, &mut dq1);
+    for i in 0..n
+    {
+        dq1[i] *= dt;
+        tmp[i] = y[i] + 0.5 * dq1[i];
+    }
+
+    f(t + 0.5 * dt, &tmp, &mut dq2);
+    for i in 0..n
+    {
+        dq2[i] *= dt;
+        tmp[i] = y[i] + 0.5 * dq2[i];
+    }
+
+    f(t + 0.5 * dt, &tmp, &mut dq3);
+    for i in 0..n
+    {
+        dq3[i] *= dt;
+        tmp[i] = y[i] + dq3[i];
+    }
+
+    f(t + dt, &tmp, &mut dq4);
+    for i in 0..n
+    {
+        dq4[i] *= dt;
+        y[i] += (dq1[i] + 2.0 * dq2[i] + 2.0 * dq3[i] + dq4[i]) / 6.0;
+    }
+}
+
+//Dormand-Prince tableau
+const DP_C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const DP_A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+    [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+    [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+];
+const DP_B5: [f64; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+const DP_B4: [f64; 7] = [5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0];
+
+//Single Dormand-Prince step. y is advanced with the 5th-order solution and the
+//largest difference to the 4th-order solution, scaled by (1 + |y|), is returned
+pub fn dormand_prince<F>(t: f64, y: &mut [f64], dt: f64, f: &mut F) -> f64
+where
+    F: FnMut(f64, &[f64], &mut [f64]),
+{
+    let n = y.len();
+    let mut k = vec![vec![0.0; n]; 7];
+    let mut tmp = vec![0.0; n];
+
+    for stage in 0..7
+    {
+        for i in 0..n
+        {
+            let mut sum = 0.0;
+            for (j, kj) in k.iter().enumerate().take(stage)
+            {
+                sum += DP_A[stage][j] * kj[i];
+            }
+            tmp[i] = y[i] + dt * sum;
+        }
+        f(t + DP_C[stage] * dt, &tmp, &mut k[stage]);
+    }
+
+    let mut err: f64 = 0.0;
+    for i in 0..n
+    {
+        let mut high = 0.0;
+        let mut low = 0.0;
+        for (stage, ks) in k.iter().enumerate()
+        {
+            high += DP_B5[stage] * ks[i];
+            low += DP_B4[stage] * ks[i];
+        }
+        let scale = 1.0 + y[i].abs();
+        y[i] += dt * high;
+        err = err.max((dt * (high - low)).abs() / scale);
+    }
+    err
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Exponential decay dy/dt = -y, exact solution y = e^-t
+    fn decay(_t: f64, y: &[f64], dydt: &mut [f64])
+    {
+        dydt[0] = -y[0];
+    }
+
+    //Undamped oscillator: y[0] = velocity, y[1] = position
+    fn oscillator(_t: f64, y: &[f64], dydt: &mut [f64])
+    {
+        dydt[0] = -y[1];
+        dydt[1] = y[0];
+    }
+
+    fn run(integrator: &Integrator, f: fn(f64, &[f64], &mut [f64]), y: &mut [f64], dt: f64, steps: usize, rate_states: &[usize])
+    {
+        for i in 0..steps
+        {
+            integrator.step(i as f64 * dt, y, dt, rate_states, f);
+        }
+    }
+
+    #[test]
+    fn euler_decay_test()
+    {
+        let mut y = [1.0];
+        run(&Integrator::new(IntegrationMethod::Euler), decay, &mut y, 0.1, 1, &[]);
+        assert_eq!(y[0], 0.9);
+    }
+
+    #[test]
+    fn rk4_decay_test()
+    {
+        let mut y = [1.0];
+        run(&Integrator::new(IntegrationMethod::RungeKutta4), decay, &mut y, 0.1, 10, &[]);
+        assert!((y[0] - (-1.0_f64).exp()).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn rk4_convergence_order_test()
+    {
+        //Halving the step should reduce the error by about 2^4
+        let exact = (-1.0_f64).exp();
+        let rk4 = Integrator::new(IntegrationMethod::RungeKutta4);
+
+        let mut coarse = [1.0];
+        run(&rk4, decay, &mut coarse, 0.2, 5, &[]);
+        let mut fine = [1.0];
+        run(&rk4, decay, &mut fine, 0.1, 10, &[]);
+
+        let ratio = (coarse[0] - exact).abs() / (fine[0] - exact).abs();
+        assert!(ratio > 14.0 && ratio < 18.0);
+    }
+
+    #[test]
+    fn dormand_prince_tolerance_test()
+    {
+        let integrator = Integrator { method: IntegrationMethod::DormandPrince45, tolerance: 1.0e-10, ..Default::default() };
+        let mut y = [1.0];
+        let report = integrator.step(0.0, &mut y, 1.0, &[], decay);
+
+        assert!((y[0] - (-1.0_f64).exp()).abs() < 1.0e-9);
+        assert!(report.steps > 1);
+    }
+
+    #[test]
+    fn dormand_prince_max_step_test()
+    {
+        let integrator = Integrator { method: IntegrationMethod::DormandPrince45, tolerance: 1.0, max_step: 0.25, ..Default::default() };
+        let mut y = [1.0];
+        let report = integrator.step(0.0, &mut y, 1.0, &[], decay);
+        assert_eq!(report.steps, 4);
+    }
+
+    #[test]
+    fn semi_implicit_euler_energy_test()
+    {
+        //Symplectic Euler keeps the oscillator energy bounded where explicit Euler grows it
+        let mut semi = [0.0, 1.0];
+        run(&Integrator::new(IntegrationMethod::SemiImplicitEuler), oscillator, &mut semi, 0.1, 1000, &[0]);
+        let mut explicit = [0.0, 1.0];
+        run(&Integrator::new(IntegrationMethod::Euler), oscillator, &mut explicit, 0.1, 1000, &[0]);
+
+        let semi_energy = semi[0] * semi[0] + semi[1] * semi[1];
+        let explicit_energy = explicit[0] * explicit[0] + explicit[1] * explicit[1];
+        assert!((semi_energy - 1.0).abs() < 0.1);
+        assert!(explicit_energy > 2.0);
+    }
+}
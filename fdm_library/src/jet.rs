@@ -0,0 +1,280 @@
+//To run unit tests for the jet module from the command line:
+//cargo test --lib jet
+
+//Jet engine shared by both models. The throttle commands the fan speed N1 between idle and full, and the spool follows it
+//with a lag, slower accelerating than decelerating. The thrust at a fan speed lapses with the air density and the Mach number,
+//and the fuel burn is the thrust times the thrust specific fuel consumption (TSFC), which grows with the Mach number and
+//the temperature. Each model's jet module puts the thrust on the airplane in place of its thrust lever or propeller.
+//SI units: N, kg/s and K, the fan speed N1 is a fraction of its maximum
+
+//SPECS
+use specs::prelude::*;
+
+//Standard atmosphere
+use crate::atmosphere::{Atmosphere, SEA_LEVEL_DENSITY, SEA_LEVEL_TEMPERATURE};
+
+//FGNetFDM packet structure and the engine states in it
+use crate::flightgear::FGNetFDM;
+use crate::engine::{ENGINE_OFF, ENGINE_RUNNING};
+
+//Fuel density and gallons for FlightGear
+use crate::fuel::{JET_FUEL_DENSITY, LITRES_PER_GALLON};
+
+//Data of a jet engine, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JetEngineData
+{
+    pub max_thrust: f64, //N, static at sea level and full N1
+    pub max_rpm: f64, //fan speed at full N1, for the packet
+    pub idle_n1: f64, //N1 with the throttle closed
+    pub idle_thrust: f64, //thrust at idle as a fraction of the thrust at full N1
+    pub spool_up: f64, //s, time constant of the spool accelerating
+    pub spool_down: f64, //s, time constant of the spool decelerating
+    pub density_exponent: f64, //the thrust lapses with the density ratio to this power
+    pub mach_lapse: f64, //fraction of the thrust lost per unit of Mach number
+    pub tsfc: f64, //kg/(N s), static at sea level
+    pub tsfc_mach: f64, //growth of the TSFC per unit of Mach number
+}
+
+impl JetEngineData
+{
+    //Engine of a static thrust (N) and fan speed (RPM), the rest is typical of the small turbofan of a jet trainer
+    pub fn new(max_thrust: f64, max_rpm: f64) -> JetEngineData
+    {
+        JetEngineData
+        {
+            max_thrust,
+            max_rpm,
+            idle_n1: 0.6,
+            idle_thrust: 0.06,
+            spool_up: 2.5,
+            spool_down: 1.2,
+            density_exponent: 0.7,
+            mach_lapse: 0.3,
+            tsfc: 2.0e-5,
+            tsfc_mach: 0.4,
+        }
+    }
+
+    //Thrust at full N1 in the air around the engine at a Mach number
+    pub fn available_thrust(&self, air: &Atmosphere, mach: f64) -> f64
+    {
+        self.max_thrust * (air.density / SEA_LEVEL_DENSITY).powf(self.density_exponent) * (1.0 - self.mach_lapse * mach).max(0.0)
+    }
+
+    //Fraction of the available thrust at a fan speed. It rises from the idle thrust to full at full N1,
+    //and below idle, while the engine starts or winds down, with the square of the fan speed
+    pub fn thrust_fraction(&self, n1: f64) -> f64
+    {
+        if n1 < self.idle_n1
+        {
+            self.idle_thrust * (n1 / self.idle_n1).powi(2)
+        }
+        else
+        {
+            self.idle_thrust + (1.0 - self.idle_thrust) * (n1 - self.idle_n1) / (1.0 - self.idle_n1)
+        }
+    }
+}
+
+//State of a jet engine: its fan speed, the Mach number it runs at, thrust and fuel flow
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JetEngine
+{
+    pub data: JetEngineData,
+    pub running: bool,
+    pub n1: f64, //fan speed as a fraction of its maximum
+    pub mach: f64,
+    pub thrust: f64, //N
+    pub fuel_flow: f64, //kg/s
+}
+
+impl Component for JetEngine
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl JetEngine
+{
+    //A stopped engine
+    pub fn new(data: JetEngineData) -> JetEngine
+    {
+        JetEngine { data, running: false, n1: 0.0, mach: 0.0, thrust: 0.0, fuel_flow: 0.0 }
+    }
+
+    //Start the engine, it spools up to idle
+    pub fn start(&mut self)
+    {
+        self.running = true;
+    }
+
+    //Stop the engine, it winds down
+    pub fn shutdown(&mut self)
+    {
+        self.running = false;
+    }
+
+    //Put the engine back at rest, at idle if it was running
+    pub fn reset(&mut self)
+    {
+        let running = self.running;
+        *self = JetEngine::new(self.data);
+        if running
+        {
+            self.start();
+            self.n1 = self.data.idle_n1;
+        }
+    }
+
+    //Run the engine for dt seconds at a throttle setting (0 - 1) in the air around it, flying at an airspeed (m/s)
+    pub fn update(&mut self, throttle: f64, air: &Atmosphere, airspeed: f64, dt: f64)
+    {
+        let data = self.data;
+
+        //The spool follows the fan speed the throttle commands, with the exact step of the lag
+        let target = if self.running { data.idle_n1 + (1.0 - data.idle_n1) * throttle.clamp(0.0, 1.0) } else { 0.0 };
+        let time_constant = if target > self.n1 { data.spool_up } else { data.spool_down };
+        if dt > 0.0
+        {
+            self.n1 += (target - self.n1) * (1.0 - (-dt / time_constant).exp());
+        }
+
+        //Thrust lapse and fuel burn. A stopped engine gives no thrust and burns nothing
+        self.mach = airspeed.max(0.0) / air.speed_of_sound();
+        self.thrust = if self.running { data.available_thrust(air, self.mach) * data.thrust_fraction(self.n1) } else { 0.0 };
+        let tsfc = data.tsfc * (1.0 + data.tsfc_mach * self.mach) * (air.temperature / SEA_LEVEL_TEMPERATURE).sqrt();
+        self.fuel_flow = tsfc * self.thrust;
+    }
+}
+
+//Load the engine data into the FlightGear packet as its only engine, in its units and in network byte order
+pub fn load_jet(engine: &JetEngine, fgnet: &mut FGNetFDM)
+{
+    fgnet.num_engines = u32::from_be_bytes(1_u32.to_ne_bytes());
+    load_jet_slot(engine, 0, fgnet);
+}
+
+//Load the engine data into one of the per-engine slots of the FlightGear packet
+pub fn load_jet_slot(engine: &JetEngine, slot: usize, fgnet: &mut FGNetFDM)
+{
+    let state = if engine.running { ENGINE_RUNNING } else { ENGINE_OFF };
+    let rpm = (engine.n1 * engine.data.max_rpm) as f32;
+    let fuel_flow = (engine.fuel_flow * 3600.0 / (JET_FUEL_DENSITY * LITRES_PER_GALLON)) as f32; //gallons/hr
+
+    fgnet.eng_state[slot] = f32::from_be_bytes(state.to_ne_bytes());
+    fgnet.rpm[slot] = f32::from_be_bytes(rpm.to_ne_bytes());
+    fgnet.fuel_flow[slot] = f32::from_be_bytes(fuel_flow.to_ne_bytes());
+}
+
+//Print the engine state, this is called once per frame
+pub fn print_jet(engine: &JetEngine)
+{
+    println!("N1 (%):           {}", engine.n1 * 100.0);
+    println!("Thrust (N):       {}", engine.thrust);
+    println!("Fuel flow (kg/h): {}", engine.fuel_flow * 3600.0);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Small turbofan of 14 kN at 16000 RPM, idling
+    fn test_jet() -> JetEngine
+    {
+        let mut engine = JetEngine::new(JetEngineData::new(14000.0, 16000.0));
+        engine.start();
+        engine.n1 = engine.data.idle_n1;
+        engine
+    }
+
+    //Run an engine for a time (s) at 240 Hz
+    fn run(engine: &mut JetEngine, throttle: f64, air: &Atmosphere, airspeed: f64, time: f64)
+    {
+        for _ in 0..(time * 240.0) as usize
+        {
+            engine.update(throttle, air, airspeed, 1.0 / 240.0);
+        }
+    }
+
+    #[test]
+    fn spool_test()
+    {
+        //Idle thrust standing still at sea level
+        let sea_level = Atmosphere::standard(0.0);
+        let mut engine = test_jet();
+        run(&mut engine, 0.0, &sea_level, 0.0, 1.0);
+        assert!((engine.thrust - 0.06 * 14000.0).abs() < 0.1);
+
+        //Slamming the throttle open, the spool has made 63% of its way to full N1 after one spool-up time constant
+        let time_constant = engine.data.spool_up;
+        run(&mut engine, 1.0, &sea_level, 0.0, time_constant);
+        let made = (engine.n1 - 0.6) / 0.4;
+        assert!((made - (1.0 - (-1.0_f64).exp())).abs() < 1e-3, "{}", made);
+        assert!(engine.thrust < 0.7 * 14000.0);
+
+        //Full static thrust once spooled up, and it spools down faster than up
+        run(&mut engine, 1.0, &sea_level, 0.0, 30.0);
+        assert!((engine.thrust - 14000.0).abs() < 1.0);
+        run(&mut engine, 0.0, &sea_level, 0.0, time_constant);
+        assert!((engine.n1 - 0.6) / 0.4 < 1.0 - made);
+
+        //Shut down it winds down to nothing, started again it spools back up to idle
+        engine.shutdown();
+        run(&mut engine, 1.0, &sea_level, 0.0, 30.0);
+        assert!(engine.n1 < 1e-6);
+        assert_eq!((engine.thrust, engine.fuel_flow), (0.0, 0.0));
+        engine.start();
+        run(&mut engine, 0.0, &sea_level, 0.0, 30.0);
+        assert!((engine.n1 - 0.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn lapse_test()
+    {
+        //The thrust lapses with the density ratio to the 0.7 and with the Mach number
+        let mut engine = test_jet();
+        engine.n1 = 1.0;
+        let high = Atmosphere::standard(8000.0);
+        engine.update(1.0, &high, 0.0, 0.0);
+        assert!((engine.thrust - 14000.0 * (high.density / SEA_LEVEL_DENSITY).powf(0.7)).abs() < 1e-6);
+
+        let static_thrust = engine.thrust;
+        engine.update(1.0, &high, 0.5 * high.speed_of_sound(), 0.0);
+        assert!((engine.mach - 0.5).abs() < 1e-12);
+        assert!((engine.thrust - 0.85 * static_thrust).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fuel_test()
+    {
+        //The fuel burn is the thrust times the TSFC, which grows with the Mach number and falls with the temperature
+        let sea_level = Atmosphere::standard(0.0);
+        let mut engine = test_jet();
+        engine.n1 = 1.0;
+        engine.update(1.0, &sea_level, 0.0, 0.0);
+        assert!((engine.fuel_flow - 2.0e-5 * 14000.0).abs() < 1e-5);
+
+        engine.update(1.0, &sea_level, 0.5 * sea_level.speed_of_sound(), 0.0);
+        assert!((engine.fuel_flow / engine.thrust - 1.2 * 2.0e-5).abs() < 1e-12);
+
+        let high = Atmosphere::standard(11000.0);
+        engine.update(1.0, &high, 0.0, 0.0);
+        assert!(engine.fuel_flow / engine.thrust < 2.0e-5);
+    }
+
+    #[test]
+    fn packet_test()
+    {
+        let mut engine = test_jet();
+        run(&mut engine, 1.0, &Atmosphere::standard(0.0), 0.0, 30.0);
+
+        let mut fgnet = FGNetFDM::default();
+        load_jet(&engine, &mut fgnet);
+        let value = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+        assert_eq!(u32::from_be_bytes(fgnet.num_engines.to_ne_bytes()), 1);
+        assert_eq!(value(fgnet.eng_state[0]), 2.0);
+        assert!((value(fgnet.rpm[0]) - 16000.0).abs() < 1.0);
+        assert!(value(fgnet.fuel_flow[0]) > 300.0 && value(fgnet.fuel_flow[0]) < 350.0);
+    }
+}
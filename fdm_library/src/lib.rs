@@ -5,5 +5,62 @@ pub mod bourg;
 //FlightGear packet structure
 pub mod flightgear;
 
+//ODE integrators shared by both models
+pub mod integrator;
+
+//Trim solver for steady flight conditions
+pub mod trim;
+
+//PID loops and modes shared by the autopilots
+pub mod autopilot;
+
+//Waypoint flight plans and lateral guidance
+pub mod flight_plan;
+
+//ILS style approach and autoland guidance
+pub mod approach;
+
+//Actuator dynamics of the control channels
+pub mod actuator;
+
+//International Standard Atmosphere
+pub mod atmosphere;
+
+//Piston engine
+pub mod engine;
+
+//Propeller with thrust and power coefficient tables
+pub mod propeller;
+
+//Jet engine with spool lag and thrust lapse
+pub mod jet;
+
+//Fuel tanks feeding the engines
+pub mod fuel;
+
+//Stall warning
+pub mod stall;
+
+//Simulated sensors with noise models
+pub mod sensors;
+
+//Navigation state estimator fusing the sensors
+pub mod estimator;
+
+//Air data computer
+pub mod air_data;
+
+//Flight envelope monitoring and protection
+pub mod envelope;
+
+//Crash and ground impact detection
+pub mod crash;
+
+//Multi-rate scheduling of the Systems
+pub mod scheduler;
+
+//WGS84 geodetic navigation and frame conversions
+pub mod navigation;
+
 
 
@@ -0,0 +1,398 @@
+//To run unit tests for the navigation module from the command line:
+//cargo test --lib navigation
+
+//Geodetic navigation on the WGS84 ellipsoid in double precision.
+//Position is kept as latitude/longitude/altitude and is propagated from a North-East-Down velocity
+//using the meridian and prime vertical radii of curvature, with ECEF conversions for checking and interop
+
+//nalgebra vector and matrix types
+use nalgebra::{Matrix3, Vector3};
+
+//WGS84 ellipsoid
+pub const WGS84_A: f64 = 6378137.0; //semi-major axis, meters
+pub const WGS84_F: f64 = 1.0 / 298.257223563; //flattening
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F); //first eccentricity squared
+
+//Mean earth radius used by the great circle routines, meters
+pub const EARTH_MEAN_RADIUS: f64 = 6371008.8;
+
+//Feet in a meter, for the Bourg model, which works in feet, and the altitudes and climb rates FlightGear shows
+pub const FEET_PER_METER: f64 = 3.28084;
+
+//Geodetic position, angles in radians and altitude in meters above the ellipsoid
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Geodetic
+{
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+}
+
+impl Geodetic
+{
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Geodetic
+    {
+        Geodetic { latitude, longitude, altitude }
+    }
+
+    pub fn from_degrees(latitude: f64, longitude: f64, altitude: f64) -> Geodetic
+    {
+        Geodetic::new(latitude.to_radians(), longitude.to_radians(), altitude)
+    }
+
+    pub fn latitude_deg(&self) -> f64
+    {
+        self.latitude.to_degrees()
+    }
+
+    pub fn longitude_deg(&self) -> f64
+    {
+        self.longitude.to_degrees()
+    }
+}
+
+//Meridian (north-south) and prime vertical (east-west) radii of curvature at a latitude
+pub fn radii_of_curvature(latitude: f64) -> (f64, f64)
+{
+    let sin_lat = latitude.sin();
+    let w2 = 1.0 - WGS84_E2 * sin_lat * sin_lat;
+    let w = w2.sqrt();
+
+    let r_meridian = WGS84_A * (1.0 - WGS84_E2) / (w2 * w);
+    let r_normal = WGS84_A / w;
+    (r_meridian, r_normal)
+}
+
+//Rate of change of latitude, longitude (rad/s) and altitude (m/s) for a NED velocity in m/s
+pub fn geodetic_rates(position: &Geodetic, v_ned: &Vector3<f64>) -> Vector3<f64>
+{
+    let (r_meridian, r_normal) = radii_of_curvature(position.latitude);
+
+    Vector3::new(v_ned.x / (r_meridian + position.altitude),
+                 v_ned.y / ((r_normal + position.altitude) * position.latitude.cos()),
+                 -v_ned.z)
+}
+
+//Move a position by a constant NED velocity for dt seconds (midpoint rule)
+pub fn propagate(position: &Geodetic, v_ned: &Vector3<f64>, dt: f64) -> Geodetic
+{
+    let k1 = geodetic_rates(position, v_ned);
+    let mid = Geodetic::new(position.latitude + 0.5 * dt * k1.x,
+                            position.longitude + 0.5 * dt * k1.y,
+                            position.altitude + 0.5 * dt * k1.z);
+    let k2 = geodetic_rates(&mid, v_ned);
+
+    Geodetic::new(position.latitude + dt * k2.x,
+                  wrap_longitude(position.longitude + dt * k2.y),
+                  position.altitude + dt * k2.z)
+}
+
+//Keep longitude within -pi..pi
+pub fn wrap_longitude(longitude: f64) -> f64
+{
+    let pi = std::f64::consts::PI;
+    let mut lon = longitude;
+    while lon > pi
+    {
+        lon -= 2.0 * pi;
+    }
+    while lon < -pi
+    {
+        lon += 2.0 * pi;
+    }
+    lon
+}
+
+//Earth-centered earth-fixed coordinates in meters
+pub fn geodetic_to_ecef(position: &Geodetic) -> Vector3<f64>
+{
+    let (_, r_normal) = radii_of_curvature(position.latitude);
+    let (sin_lat, cos_lat) = position.latitude.sin_cos();
+    let (sin_lon, cos_lon) = position.longitude.sin_cos();
+
+    Vector3::new((r_normal + position.altitude) * cos_lat * cos_lon,
+                 (r_normal + position.altitude) * cos_lat * sin_lon,
+                 (r_normal * (1.0 - WGS84_E2) + position.altitude) * sin_lat)
+}
+
+//Iterative conversion from ECEF back to geodetic, converges to well under a millimeter
+pub fn ecef_to_geodetic(ecef: &Vector3<f64>) -> Geodetic
+{
+    let p = (ecef.x * ecef.x + ecef.y * ecef.y).sqrt();
+    let longitude = ecef.y.atan2(ecef.x);
+    let mut latitude = ecef.z.atan2(p * (1.0 - WGS84_E2));
+    let mut altitude = 0.0;
+
+    for _ in 0..6
+    {
+        let (_, r_normal) = radii_of_curvature(latitude);
+        altitude = if latitude.cos().abs() > 1.0e-9
+        {
+            p / latitude.cos() - r_normal
+        }
+        else
+        {
+            ecef.z.abs() / latitude.sin().abs() - r_normal * (1.0 - WGS84_E2)
+        };
+        latitude = ecef.z.atan2(p * (1.0 - WGS84_E2 * r_normal / (r_normal + altitude)));
+    }
+
+    Geodetic::new(latitude, longitude, altitude)
+}
+
+//Rotation matrix taking NED vectors at a position into ECEF
+pub fn ned_to_ecef_matrix(position: &Geodetic) -> Matrix3<f64>
+{
+    let (sin_lat, cos_lat) = position.latitude.sin_cos();
+    let (sin_lon, cos_lon) = position.longitude.sin_cos();
+
+    Matrix3::new(-sin_lat * cos_lon, -sin_lon, -cos_lat * cos_lon,
+                 -sin_lat * sin_lon,  cos_lon, -cos_lat * sin_lon,
+                  cos_lat,            0.0,     -sin_lat)
+}
+
+pub fn ned_to_ecef(position: &Geodetic, v_ned: &Vector3<f64>) -> Vector3<f64>
+{
+    ned_to_ecef_matrix(position) * v_ned
+}
+
+pub fn ecef_to_ned(position: &Geodetic, v_ecef: &Vector3<f64>) -> Vector3<f64>
+{
+    ned_to_ecef_matrix(position).transpose() * v_ecef
+}
+
+//East-North-Up <---> North-East-Down
+pub fn enu_to_ned(v_enu: &Vector3<f64>) -> Vector3<f64>
+{
+    Vector3::new(v_enu.y, v_enu.x, -v_enu.z)
+}
+
+pub fn ned_to_enu(v_ned: &Vector3<f64>) -> Vector3<f64>
+{
+    Vector3::new(v_ned.y, v_ned.x, -v_ned.z)
+}
+
+//Great circle routines on a sphere of the mean earth radius, good to a few tenths of a percent,
+//which is plenty for guidance. Bearings are radians clockwise from north, distances are meters
+
+//Angular distance between two positions (haversine formula), radians
+fn central_angle(from: &Geodetic, to: &Geodetic) -> f64
+{
+    let d_lat = to.latitude - from.latitude;
+    let d_lon = to.longitude - from.longitude;
+    let h = (0.5 * d_lat).sin().powi(2) + from.latitude.cos() * to.latitude.cos() * (0.5 * d_lon).sin().powi(2);
+    2.0 * h.sqrt().min(1.0).asin()
+}
+
+//Great circle distance over the ground, altitude is ignored
+pub fn distance(from: &Geodetic, to: &Geodetic) -> f64
+{
+    EARTH_MEAN_RADIUS * central_angle(from, to)
+}
+
+//Initial bearing of the great circle from one position to another, 0 - 2 pi
+pub fn bearing(from: &Geodetic, to: &Geodetic) -> f64
+{
+    let d_lon = to.longitude - from.longitude;
+    let y = d_lon.sin() * to.latitude.cos();
+    let x = from.latitude.cos() * to.latitude.sin() - from.latitude.sin() * to.latitude.cos() * d_lon.cos();
+    y.atan2(x).rem_euclid(2.0 * std::f64::consts::PI)
+}
+
+//Distance of a position from the great circle through start and end, positive to the right of the track
+pub fn cross_track(start: &Geodetic, end: &Geodetic, position: &Geodetic) -> f64
+{
+    let angle = central_angle(start, position);
+    let relative_bearing = bearing(start, position) - bearing(start, end);
+    EARTH_MEAN_RADIUS * (angle.sin() * relative_bearing.sin()).clamp(-1.0, 1.0).asin()
+}
+
+//Distance along the great circle from start toward end to the point abeam a position, negative behind start
+pub fn along_track(start: &Geodetic, end: &Geodetic, position: &Geodetic) -> f64
+{
+    let angle = central_angle(start, position);
+    let relative_bearing = bearing(start, position) - bearing(start, end);
+    let cross = (angle.sin() * relative_bearing.sin()).clamp(-1.0, 1.0).asin();
+    let along = (angle.cos() / cross.cos()).clamp(-1.0, 1.0).acos();
+    EARTH_MEAN_RADIUS * along * relative_bearing.cos().signum()
+}
+
+
+//Position reached by following the great circle from a position at an initial bearing for a distance
+pub fn destination(from: &Geodetic, bearing: f64, distance: f64) -> Geodetic
+{
+    let angle = distance / EARTH_MEAN_RADIUS;
+    let (sin_lat, cos_lat) = from.latitude.sin_cos();
+    let latitude = (sin_lat * angle.cos() + cos_lat * angle.sin() * bearing.cos()).clamp(-1.0, 1.0).asin();
+    let longitude = from.longitude + (bearing.sin() * angle.sin() * cos_lat).atan2(angle.cos() - sin_lat * latitude.sin());
+    Geodetic::new(latitude, wrap_longitude(longitude), from.altitude)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Meridian arc length from the equator (Helmert's series), used as an independent reference
+    fn meridian_arc(latitude: f64) -> f64
+    {
+        let n = WGS84_F / (2.0 - WGS84_F);
+        let n2 = n * n;
+        let n3 = n2 * n;
+        let n4 = n3 * n;
+        WGS84_A / (1.0 + n) * ((1.0 + n2 / 4.0 + n4 / 64.0) * latitude
+            - 1.5 * (n - n3 / 8.0) * (2.0 * latitude).sin()
+            + 15.0 / 16.0 * (n2 - n4 / 4.0) * (4.0 * latitude).sin()
+            - 35.0 / 48.0 * n3 * (6.0 * latitude).sin()
+            + 315.0 / 512.0 * n4 * (8.0 * latitude).sin())
+    }
+
+    //Fly at a constant NED velocity for the given time using a fixed step
+    fn fly(start: Geodetic, v_ned: Vector3<f64>, dt: f64, seconds: f64) -> Geodetic
+    {
+        let mut position = start;
+        let steps = (seconds / dt).round() as usize;
+        for _ in 0..steps
+        {
+            position = propagate(&position, &v_ned, dt);
+        }
+        position
+    }
+
+    #[test]
+    fn radii_of_curvature_test()
+    {
+        let (r_meridian, r_normal) = radii_of_curvature(0.0);
+        assert!((r_normal - WGS84_A).abs() < 1.0e-6);
+        assert!((r_meridian - WGS84_A * (1.0 - WGS84_E2)).abs() < 1.0e-6);
+
+        //Both radii are equal at the poles
+        let (r_meridian, r_normal) = radii_of_curvature(std::f64::consts::FRAC_PI_2);
+        assert!((r_meridian - r_normal).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn ecef_round_trip_test()
+    {
+        let wpafb = Geodetic::from_degrees(39.826, -84.045, 248.0);
+        let back = ecef_to_geodetic(&geodetic_to_ecef(&wpafb));
+
+        assert!((back.latitude - wpafb.latitude).abs() < 1.0e-12);
+        assert!((back.longitude - wpafb.longitude).abs() < 1.0e-12);
+        assert!((back.altitude - wpafb.altitude).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn ned_ecef_rotation_test()
+    {
+        //At lat 0, lon 0: north is ECEF +z, east is +y and down is -x
+        let origin = Geodetic::new(0.0, 0.0, 0.0);
+        let north = ned_to_ecef(&origin, &Vector3::new(1.0, 0.0, 0.0));
+        let east = ned_to_ecef(&origin, &Vector3::new(0.0, 1.0, 0.0));
+        let down = ned_to_ecef(&origin, &Vector3::new(0.0, 0.0, 1.0));
+
+        assert!((north - Vector3::new(0.0, 0.0, 1.0)).norm() < 1.0e-12);
+        assert!((east - Vector3::new(0.0, 1.0, 0.0)).norm() < 1.0e-12);
+        assert!((down - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1.0e-12);
+
+        let v = Vector3::new(3.0, -4.0, 5.0);
+        let wpafb = Geodetic::from_degrees(39.826, -84.045, 248.0);
+        assert!((ecef_to_ned(&wpafb, &ned_to_ecef(&wpafb, &v)) - v).norm() < 1.0e-12);
+        assert_eq!(ned_to_enu(&enu_to_ned(&v)), v);
+    }
+
+    #[test]
+    fn long_flight_north_test()
+    {
+        //One hour due north from the equator at 100 m/s, 240 Hz steps
+        let end = fly(Geodetic::new(0.0, 0.0, 0.0), Vector3::new(100.0, 0.0, 0.0), 1.0 / 240.0, 3600.0);
+
+        assert!((meridian_arc(end.latitude) - 360000.0).abs() < 0.01);
+        assert_eq!(end.longitude, 0.0);
+    }
+
+    #[test]
+    fn long_flight_east_test()
+    {
+        //One hour due east along the equator at 10 km altitude
+        let end = fly(Geodetic::new(0.0, 0.0, 10000.0), Vector3::new(0.0, 100.0, 0.0), 1.0 / 240.0, 3600.0);
+
+        assert!((end.longitude * (WGS84_A + 10000.0) - 360000.0).abs() < 0.01);
+        assert!(end.latitude.abs() < 1.0e-15);
+        assert!((end.altitude - 10000.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn long_flight_ecef_test()
+    {
+        //One hour north-east with a climb from Wright-Patterson, compared against integrating the same
+        //NED velocity in ECEF, which does not use the radii of curvature
+        let start = Geodetic::from_degrees(39.826, -84.045, 248.0);
+        let v_ned = Vector3::new(70.0, 70.0, -2.0);
+        let dt = 0.1;
+
+        let geodetic = fly(start, v_ned, dt, 3600.0);
+
+        let mut ecef = geodetic_to_ecef(&start);
+        for _ in 0..36000
+        {
+            //Midpoint rule in ECEF so both methods have the same order
+            let here = ecef_to_geodetic(&ecef);
+            let mid = ecef_to_geodetic(&(ecef + ned_to_ecef(&here, &v_ned) * (0.5 * dt)));
+            ecef += ned_to_ecef(&mid, &v_ned) * dt;
+        }
+
+        let difference = (geodetic_to_ecef(&geodetic) - ecef).norm();
+        assert!(difference < 1.0);
+        assert!((geodetic.altitude - (248.0 + 7200.0)).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn great_circle_test()
+    {
+        //One degree of latitude, and a bearing due north
+        let start = Geodetic::from_degrees(39.0, -84.0, 0.0);
+        let north = Geodetic::from_degrees(40.0, -84.0, 0.0);
+        assert!((distance(&start, &north) - EARTH_MEAN_RADIUS * 1.0_f64.to_radians()).abs() < 1.0e-6);
+        assert!(bearing(&start, &north).abs() < 1.0e-12);
+
+        //Due east along the equator, then the bearing back is west
+        let a = Geodetic::from_degrees(0.0, 10.0, 0.0);
+        let b = Geodetic::from_degrees(0.0, 11.0, 0.0);
+        assert!((bearing(&a, &b).to_degrees() - 90.0).abs() < 1.0e-9);
+        assert!((bearing(&b, &a).to_degrees() - 270.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn cross_track_test()
+    {
+        //Track north along a meridian, a point to the east is right of the track
+        let start = Geodetic::from_degrees(39.0, -84.0, 0.0);
+        let end = Geodetic::from_degrees(40.0, -84.0, 0.0);
+        let east = Geodetic::from_degrees(39.5, -83.99, 0.0);
+        let west = Geodetic::from_degrees(39.5, -84.01, 0.0);
+
+        let expected = distance(&Geodetic::from_degrees(39.5, -84.0, 0.0), &east);
+        assert!((cross_track(&start, &end, &east) - expected).abs() < 0.5);
+        assert!((cross_track(&start, &end, &west) + expected).abs() < 0.5);
+
+        //Abeam half way along the leg, and behind the start
+        let half = distance(&start, &end) * 0.5;
+        assert!((along_track(&start, &end, &east) - half).abs() < 1.0);
+        assert!(along_track(&start, &end, &Geodetic::from_degrees(38.9, -84.0, 0.0)) < 0.0);
+    }
+
+    #[test]
+    fn destination_test()
+    {
+        //Going out and coming back along the reverse bearing returns to the start
+        let start = Geodetic::from_degrees(39.826, -84.045, 248.0);
+        let end = destination(&start, 60.0_f64.to_radians(), 5000.0);
+        assert!((distance(&start, &end) - 5000.0).abs() < 1.0e-6);
+        assert!((bearing(&start, &end).to_degrees() - 60.0).abs() < 1.0e-9);
+        assert_eq!(end.altitude, 248.0);
+
+        let back = destination(&end, bearing(&end, &start), 5000.0);
+        assert!(distance(&start, &back) < 1.0e-6);
+    }
+}
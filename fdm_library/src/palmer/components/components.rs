@@ -6,6 +6,10 @@ use specs::prelude::*;
 //Import structures to make into Components
 use crate::palmer::fdm::structures::KeyboardState;
 use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::StateHistory;
+use crate::palmer::fdm::structures::AutopilotState;
+use crate::palmer::fdm::structures::Actuators;
+use crate::palmer::fdm::structures::InitialConditions;
 use crate::flightgear::FGNetFDM;
 
 //Component holding the state of the airplane
@@ -20,6 +24,30 @@ impl Component for KeyboardState
     type Storage = VecStorage<Self>;
 }
 
+//Component holding the last two physics states for interpolated output
+impl Component for StateHistory
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the autopilot modes and loops
+impl Component for AutopilotState
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the actuators of the control channels
+impl Component for Actuators
+{
+    type Storage = VecStorage<Self>;
+}
+
+//Component holding the state a crashed airplane is reset to
+impl Component for InitialConditions
+{
+    type Storage = VecStorage<Self>;
+}
+
 //Component containing the FGNetFDM structure to be sent to FlightGear
 impl Component for FGNetFDM
 {
@@ -3,5 +3,21 @@ pub mod plane_right_hand_side;
 pub mod flight_control;
 pub mod make_packet;
 pub mod send_packet;
+pub mod interpolation;
+pub mod trim;
+pub mod autopilot;
+pub mod flight_plan;
+pub mod approach;
+pub mod actuators;
+pub mod engine;
+pub mod propeller;
+pub mod jet;
+pub mod fuel;
+pub mod sensors;
+pub mod estimator;
+pub mod air_data;
+pub mod envelope;
+pub mod crash;
+pub mod physics_step;
 
 pub mod structures;
\ No newline at end of file
@@ -0,0 +1,44 @@
+//Functions to move the angle of attack, bank, throttle and flaps through their actuators
+
+//DataFDM and Actuators structs
+use crate::palmer::fdm::structures::{DataFDM, Actuators};
+
+impl Actuators
+{
+    //Put every actuator at rest at the current controls of the airplane, so nothing moves when they are engaged
+    pub fn reset(&mut self, fdm: &DataFDM)
+    {
+        self.alpha.reset(fdm.alpha);
+        self.bank.reset(fdm.bank);
+        self.throttle.reset(fdm.throttle);
+        self.flap.reset(fdm.flap);
+    }
+}
+
+//Take the controls on the airplane as the commands, move the actuators for dt seconds and
+//put their actual positions on the airplane for the physics step
+pub fn actuate(fdm: &mut DataFDM, actuators: &mut Actuators, dt: f64)
+{
+    fdm.alpha = actuators.alpha.update(fdm.alpha, dt);
+    fdm.bank = actuators.bank.update(fdm.bank, dt);
+    fdm.throttle = actuators.throttle.update(fdm.throttle, dt);
+    fdm.flap = actuators.flap.update(fdm.flap, dt);
+}
+
+//Put the commands back on the airplane after the physics step, so the inputs and the autopilot keep working on them
+pub fn restore_commands(fdm: &mut DataFDM, actuators: &Actuators)
+{
+    fdm.alpha = actuators.alpha.command;
+    fdm.bank = actuators.bank.command;
+    fdm.throttle = actuators.throttle.command;
+    fdm.flap = actuators.flap.command;
+}
+
+//Print the commanded and actual positions, this is called once per frame
+pub fn print_actuators(actuators: &Actuators)
+{
+    println!("Angle of attack (deg) =   {} commanded, {} actual", actuators.alpha.command, actuators.alpha.position);
+    println!("Bank angle (deg) =        {} commanded, {} actual", actuators.bank.command, actuators.bank.position);
+    println!("Throttle % =              {} commanded, {} actual", actuators.throttle.command * 100.0, actuators.throttle.position * 100.0);
+    println!("Flap deflection (deg) =   {} commanded, {} actual", actuators.flap.command, actuators.flap.position);
+}
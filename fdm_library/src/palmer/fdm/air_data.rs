@@ -0,0 +1,26 @@
+//Functions to run the air data computer on the airplane
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Air data computer and standard atmosphere
+use crate::air_data::AirDataComputer;
+use crate::atmosphere::Atmosphere;
+
+//Relative wind (m/s) in the aerospace body axes. The airplane is a point mass flying at its angle of attack without sideslip,
+//so the wind comes from below the nose at that angle
+pub fn relative_wind(fdm: &DataFDM) -> Vector3<f64>
+{
+    let alpha = fdm.alpha.to_radians();
+    Vector3::new(fdm.airspeed * alpha.cos(), 0.0, fdm.airspeed * alpha.sin())
+}
+
+//Update the air data computer dt seconds on in the air around the airplane
+pub fn run_air_data(fdm: &DataFDM, computer: &mut AirDataComputer, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.z);
+    computer.update(&air, &relative_wind(fdm), dt);
+}
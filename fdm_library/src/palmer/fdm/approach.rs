@@ -0,0 +1,93 @@
+//Approach and autoland coupling for the Palmer airplane. The approach guidance becomes the targets of the
+//heading hold, vertical speed and airspeed hold modes. On the runway the autopilot lets go with the wings
+//level, the angle of attack at zero and the throttle closed
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::palmer::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Approach, position and modes
+use crate::approach::{Approach, ApproachPhase};
+use crate::navigation::Geodetic;
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode, AutopilotModes};
+
+//The approach key engages the approach. Until the flare, flying the roll axis by hand or the disengage key
+//lets go of the approach, after that the airplane is committed to land
+pub fn handle_approach_input(approach: &mut Approach, ap: &AutopilotState, keystate: &KeyboardState)
+{
+    if keystate.ap_approach
+    {
+        approach.engage();
+    }
+    else if approach.phase == ApproachPhase::Approach && !matches!(ap.modes.lateral, LateralMode::HeadingHold(_))
+    {
+        approach.phase = ApproachPhase::Off;
+    }
+}
+
+//Measurements the approach is guided on
+struct Measurements
+{
+    position: Geodetic,
+    v_ned: Vector3<f64>, //m/s
+    touchdown: Option<f64>, //m/s, vertical speed of the touchdown once on the ground
+}
+
+fn measure(fdm: &DataFDM, approach: &Approach) -> Measurements
+{
+    //The ground is at z = 0 and is taken to be the runway elevation. The velocities are east, north and up
+    Measurements
+    {
+        position: Geodetic::from_degrees(fdm.position.x, fdm.position.y, approach.runway.threshold.altitude + fdm.q[5]),
+        v_ned: Vector3::new(fdm.q[2], fdm.q[0], -fdm.q[4]),
+        touchdown: if fdm.on_ground { Some(fdm.touchdown_rate) } else { None },
+    }
+}
+
+//Set the autopilot targets and controls for the phase of the approach
+pub fn fly_approach(fdm: &mut DataFDM, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    let m = measure(fdm, approach);
+    couple(fdm, &m, approach, ap);
+}
+
+//Fly the approach on the measurements of another state of the airplane, such as its navigation estimate,
+//and set the controls on the airplane
+pub fn fly_approach_on(state: &DataFDM, fdm: &mut DataFDM, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    couple(fdm, &measure(state, approach), approach, ap);
+}
+
+fn couple(fdm: &mut DataFDM, m: &Measurements, approach: &mut Approach, ap: &mut AutopilotState)
+{
+    if !approach.is_engaged()
+    {
+        ap.modes.coupled = false;
+        return;
+    }
+
+    match approach.guide(&m.position, &m.v_ned, m.touchdown)
+    {
+        Some(guidance) => match guidance.phase
+        {
+            ApproachPhase::Approach | ApproachPhase::Flare =>
+            {
+                ap.modes.lateral = LateralMode::HeadingHold(guidance.heading);
+                ap.modes.coupled = true;
+                ap.modes.vertical = VerticalMode::VerticalSpeed(guidance.vertical_speed);
+                //The thrust is held at its approach setting through the flare and closed on touchdown
+                ap.modes.speed = if guidance.phase == ApproachPhase::Flare { SpeedMode::Off } else { SpeedMode::AirspeedHold(guidance.airspeed) };
+            }
+            _ =>
+            {
+                ap.modes = AutopilotModes::default();
+                fdm.throttle = 0.0;
+                fdm.alpha = 0.0;
+                fdm.bank = 0.0;
+            }
+        },
+        None => ap.modes = AutopilotModes::default(),
+    }
+}
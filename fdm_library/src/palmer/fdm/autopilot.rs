@@ -0,0 +1,163 @@
+//Autopilot for the Palmer airplane, called by the Autopilot System between the input and the equations of motion.
+//The Palmer model is flown by angle of attack, bank angle and throttle, so the cascaded loops command those directly
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::palmer::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Shared modes
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode, AutopilotModes, heading_error};
+
+use std::mem::discriminant;
+
+//Measurements used by the loops
+struct Measurements
+{
+    altitude: f64, //m
+    vertical_speed: f64, //m/s
+    airspeed: f64, //m/s
+    heading: f64, //deg, clockwise from north
+}
+
+fn measure(fdm: &DataFDM) -> Measurements
+{
+    //The x axis points east and the heading angle is measured from it toward north
+    let heading_angle = fdm.q[2].atan2(fdm.q[0]).to_degrees();
+    Measurements
+    {
+        altitude: fdm.q[5],
+        vertical_speed: fdm.q[4],
+        airspeed: (fdm.q[0] * fdm.q[0] + fdm.q[2] * fdm.q[2] + fdm.q[4] * fdm.q[4]).sqrt(),
+        heading: (90.0 - heading_angle).rem_euclid(360.0),
+    }
+}
+
+//Engage and disengage modes from the autopilot keys. A mode engages at the current value of what it holds,
+//and flying an axis by hand (angle of attack, bank or throttle keys) disengages the modes on that axis
+pub fn handle_autopilot_input(fdm: &DataFDM, ap: &mut AutopilotState, keystate: &KeyboardState)
+{
+    let m = measure(fdm);
+
+    if keystate.aoa_up || keystate.aoa_down
+    {
+        ap.modes.vertical = VerticalMode::Off;
+    }
+    if keystate.bank_left || keystate.bank_right
+    {
+        ap.modes.lateral = LateralMode::Off;
+    }
+    if keystate.throttle_up || keystate.throttle_down
+    {
+        ap.modes.speed = SpeedMode::Off;
+    }
+
+    if keystate.ap_disengage
+    {
+        ap.modes = AutopilotModes::default();
+    }
+    if keystate.ap_altitude_hold
+    {
+        ap.modes.vertical = VerticalMode::AltitudeHold(m.altitude);
+    }
+    else if keystate.ap_vertical_speed
+    {
+        ap.modes.vertical = VerticalMode::VerticalSpeed(m.vertical_speed);
+    }
+    if keystate.ap_heading_hold
+    {
+        ap.modes.lateral = LateralMode::HeadingHold(m.heading);
+    }
+    else if keystate.ap_wing_leveler
+    {
+        ap.modes.lateral = LateralMode::WingLeveler;
+    }
+    if keystate.ap_airspeed_hold
+    {
+        ap.modes.speed = SpeedMode::AirspeedHold(m.airspeed);
+    }
+}
+
+//Run the engaged loops for dt seconds and set the angle of attack, bank and throttle.
+//When a mode is disengaged its control stays where the autopilot left it
+pub fn autopilot(fdm: &mut DataFDM, ap: &mut AutopilotState, dt: f64)
+{
+    let m = measure(fdm);
+    run_loops(fdm, &m, ap, dt);
+}
+
+//Run the engaged loops on the measurements of another state of the airplane, such as its navigation estimate,
+//and set the controls on the airplane
+pub fn autopilot_on(state: &DataFDM, fdm: &mut DataFDM, ap: &mut AutopilotState, dt: f64)
+{
+    run_loops(fdm, &measure(state), ap, dt);
+}
+
+impl AutopilotState
+{
+    //Start every loop again from the controls of a state the airplane is put back to, as if the modes had just been engaged
+    pub fn reset(&mut self, fdm: &DataFDM)
+    {
+        start_loops(fdm, &measure(fdm), self, [true; 3]);
+    }
+}
+
+//Start the loops of the vertical, lateral and speed axes that are due from the measurements and controls,
+//and keep the modes they run with
+fn start_loops(fdm: &DataFDM, m: &Measurements, ap: &mut AutopilotState, [vertical, lateral, speed]: [bool; 3])
+{
+    if vertical
+    {
+        ap.altitude.reset(m.vertical_speed);
+        ap.vertical_speed.reset(fdm.alpha);
+        ap.approach_vertical_speed.reset(fdm.alpha);
+    }
+    if lateral
+    {
+        ap.heading.reset(fdm.bank);
+    }
+    if speed
+    {
+        ap.airspeed.reset(fdm.throttle);
+    }
+    ap.engaged = ap.modes;
+}
+
+fn run_loops(fdm: &mut DataFDM, m: &Measurements, ap: &mut AutopilotState, dt: f64)
+{
+    if !ap.modes.is_engaged() && !ap.engaged.is_engaged()
+    {
+        return;
+    }
+
+    //A newly engaged mode starts its loops from the current controls so nothing jumps
+    let vertical = discriminant(&ap.modes.vertical) != discriminant(&ap.engaged.vertical) || ap.modes.coupled != ap.engaged.coupled;
+    let lateral = discriminant(&ap.modes.lateral) != discriminant(&ap.engaged.lateral);
+    let speed = discriminant(&ap.modes.speed) != discriminant(&ap.engaged.speed);
+    start_loops(fdm, m, ap, [vertical, lateral, speed]);
+
+    //Pitch axis: altitude -> climb rate -> angle of attack. The approach coupler's climb rate runs on its own loop
+    let vertical_speed = if ap.modes.coupled { &mut ap.approach_vertical_speed } else { &mut ap.vertical_speed };
+    match ap.modes.vertical
+    {
+        VerticalMode::AltitudeHold(altitude) =>
+        {
+            let climb_rate = ap.altitude.update(altitude, m.altitude, dt);
+            fdm.alpha = vertical_speed.update(climb_rate, m.vertical_speed, dt);
+        }
+        VerticalMode::VerticalSpeed(climb_rate) => fdm.alpha = vertical_speed.update(climb_rate, m.vertical_speed, dt),
+        VerticalMode::Off => (),
+    }
+
+    //Roll axis: heading -> bank
+    match ap.modes.lateral
+    {
+        LateralMode::HeadingHold(heading) => fdm.bank = ap.heading.update(heading_error(heading, m.heading), 0.0, dt),
+        LateralMode::WingLeveler => fdm.bank = 0.0,
+        LateralMode::Off => (),
+    }
+
+    //Throttle: airspeed -> throttle
+    if let SpeedMode::AirspeedHold(airspeed) = ap.modes.speed
+    {
+        fdm.throttle = ap.airspeed.update(airspeed, m.airspeed, dt);
+    }
+}
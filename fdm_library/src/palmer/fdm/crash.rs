@@ -0,0 +1,52 @@
+//Functions to find how the airplane touches the ground for the crash monitor and to reset it after a crash
+
+//DataFDM, StateHistory, Actuators, AutopilotState and InitialConditions structs
+use crate::palmer::fdm::structures::{DataFDM, StateHistory, Actuators, AutopilotState, InitialConditions};
+
+//Poses
+use crate::palmer::fdm::interpolation::snapshot;
+
+//Ground contact
+use crate::crash::GroundContact;
+
+//Contact of the airplane with the ground, none when it is clear of it. The airplane is a point mass held up by the
+//ground, so only its gear ever touches it, and its pitch is the climb angle plus the angle of attack. The right hand
+//side holds an airplane resting at zero height there without it sinking into the ground to touch down, which is
+//contact at its vertical speed
+pub fn ground_contact(fdm: &DataFDM) -> Option<GroundContact>
+{
+    if !fdm.on_ground && fdm.q[5] > 0.0
+    {
+        return None;
+    }
+
+    let vertical_speed = if fdm.on_ground { fdm.touchdown_rate } else { fdm.q[4] };
+
+    Some(GroundContact
+    {
+        sink_rate: -vertical_speed,
+        bank: fdm.bank,
+        pitch: fdm.climb_angle.to_degrees() + fdm.alpha,
+        airframe: false,
+    })
+}
+
+//Put a crashed airplane back to its initial conditions, with its output poses, actuators and autopilot loops at rest
+//there. What else it carries is reset after it with reset_equipment
+pub fn reset_airplane(fdm: &mut DataFDM, initial: &InitialConditions, history: Option<&mut StateHistory>, actuators: Option<&mut Actuators>, autopilot: Option<&mut AutopilotState>)
+{
+    *fdm = initial.fdm.clone();
+    if let Some(history) = history
+    {
+        history.previous = snapshot(fdm);
+        history.current = history.previous;
+    }
+    if let Some(actuators) = actuators
+    {
+        actuators.reset(fdm);
+    }
+    if let Some(autopilot) = autopilot
+    {
+        autopilot.reset(fdm);
+    }
+}
@@ -0,0 +1,37 @@
+//Functions to run a piston engine on the airplane
+
+//DataFDM and KeyboardState structs
+use crate::palmer::fdm::structures::{DataFDM, KeyboardState};
+
+//Engine and the air it runs in
+use crate::engine::PistonEngine;
+use crate::atmosphere::Atmosphere;
+
+//Mixture travel per second while a mixture key is held
+pub const MIXTURE_RATE: f64 = 0.5;
+
+//Apply the mixture and starter keys. This runs at the input rate, dt seconds after the previous input
+pub fn handle_engine_input(engine: &mut PistonEngine, keystate: &KeyboardState, dt: f64)
+{
+    if keystate.mixture_rich
+    {
+        engine.mixture = (engine.mixture + MIXTURE_RATE * dt).min(1.0);
+    }
+    else if keystate.mixture_lean
+    {
+        engine.mixture = (engine.mixture - MIXTURE_RATE * dt).max(0.0);
+    }
+    if keystate.engine_start && !engine.running
+    {
+        engine.start();
+    }
+}
+
+//Run the engine for dt seconds at the throttle setting, its power drives the propeller
+pub fn run_engine(fdm: &mut DataFDM, engine: &mut PistonEngine, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.z);
+    let load = engine.default_load(air.density);
+    engine.update(fdm.throttle, &air, load, dt);
+    fdm.shaft_power = Some(engine.power);
+}
@@ -0,0 +1,61 @@
+//Functions to find the state the envelope limits are checked against and to protect the airplane from them
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Relative wind and the truth of the sensors, which has the acceleration
+use crate::palmer::fdm::air_data::relative_wind;
+use crate::palmer::fdm::sensors::sensor_truth;
+
+//Envelope monitor and air data
+use crate::envelope::{EnvelopeState, EnvelopeMonitor, STANDARD_GRAVITY};
+use crate::sensors::{impact_pressure, calibrated_airspeed};
+use crate::atmosphere::Atmosphere;
+
+//State of the airplane for the envelope monitor. The airplane is a point mass flying at its angle of attack,
+//its load factor is the acceleration without gravity along the body z axis, so it is 1 in level flight.
+//It is taken at the angle of attack and bank the last physics step flew with, not the commands put back after it
+pub fn envelope_state(fdm: &DataFDM) -> EnvelopeState
+{
+    let mut flown = fdm.clone();
+    if let Some(pose) = fdm.flown
+    {
+        flown.alpha = pose.alpha;
+        flown.bank = pose.bank;
+    }
+    let fdm = &flown;
+    let air = Atmosphere::standard(fdm.position.z);
+    let truth = sensor_truth(fdm);
+
+    EnvelopeState
+    {
+        airspeed: calibrated_airspeed(impact_pressure(&air, relative_wind(fdm).norm())),
+        load_factor: -truth.specific_force.z / STANDARD_GRAVITY,
+        alpha: fdm.alpha,
+        bank: fdm.bank,
+        pitch: truth.attitude.y.to_degrees(),
+        airborne: !fdm.on_ground,
+    }
+}
+
+//Clip the angle of attack command for a physics step when the monitor protects the airplane, the airplane flies its
+//angle of attack. Return the command it took over, which is put back after the step with restore_alpha_command
+pub fn protect(fdm: &mut DataFDM, monitor: &EnvelopeMonitor) -> Option<f64>
+{
+    match monitor.alpha_protection()
+    {
+        Some(limit) if fdm.alpha > limit =>
+        {
+            let command = fdm.alpha;
+            fdm.alpha = limit;
+            Some(command)
+        }
+        _ => None,
+    }
+}
+
+//Put the angle of attack command the protection took over back on the airplane after the physics step
+pub fn restore_alpha_command(fdm: &mut DataFDM, command: f64)
+{
+    fdm.alpha = command;
+}
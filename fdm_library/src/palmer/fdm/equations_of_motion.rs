@@ -7,70 +7,89 @@ use coord_transforms::prelude::*;
 use crate::palmer::fdm::structures::KeyboardState;
 use crate::palmer::fdm::structures::DataFDM;
 
-//get plane_right_hand_side function
-use crate::palmer::fdm::plane_right_hand_side::plane_rhs;
+//get plane_right_hand_side derivative function
+use crate::palmer::fdm::plane_right_hand_side::plane_derivatives;
 
-pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
-{
+//Pose of the airplane
+use crate::palmer::fdm::interpolation::snapshot;
+
+//ODE integrator
+use crate::integrator::Integrator;
 
-    let mut qcopy = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-    let mut dq1 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-    let mut dq2 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-    let mut dq3 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
-    let mut dq4 = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+//Stall warning
+use crate::stall::smooth_warning;
 
+//Indices of the velocity states in q = [vx, x, vy, y, vz, z], these are advanced first by semi-implicit Euler
+pub const VELOCITY_STATES: [usize; 3] = [0, 2, 4];
+
+//Height (m) the airplane has to climb from the ground before it is airborne again
+pub const LIFTOFF_HEIGHT: f64 = 1.0;
+
+//Apply the keyboard states to the throttle, angle of attack, bank and flaps. This runs at the input rate, dt seconds
+//after the previous input, and the controls move at the rates of the control limits of the airplane
+pub fn handle_input(fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
+{
+    let limits = &fdm.control_limits;
 
     //Handle the input states
     //Thrust states
-    if fdm.throttle < 1.0 && keystate.throttle_up == true
+    if keystate.throttle_up == true
     {
-        fdm.throttle = fdm.throttle + 0.05;
+        fdm.throttle = (fdm.throttle + limits.throttle_rate * dt).min(1.0);
     }   
-    else if fdm.throttle > 0.0 && keystate.throttle_down == true
+    else if keystate.throttle_down == true
     {
-        fdm.throttle = fdm.throttle - 0.05;
-        if fdm.throttle < 0.001 
-        {
-            fdm.throttle = 0.0;
-        }
+        fdm.throttle = (fdm.throttle - limits.throttle_rate * dt).max(0.0);
     }  
 
     //Angle of attack states
-    if fdm.alpha < 20.0 && keystate.aoa_up == true
+    if keystate.aoa_up == true
     {
-        fdm.alpha = fdm.alpha + 1.0;
-    
+        fdm.alpha = (fdm.alpha + limits.alpha_rate * dt).min(limits.alpha_max.max(fdm.alpha));
     }  
-    else if fdm.alpha > -16.0 && keystate.aoa_down == true
+    else if keystate.aoa_down == true
     {
-        fdm.alpha = fdm.alpha - 1.0
+        fdm.alpha = (fdm.alpha - limits.alpha_rate * dt).max(limits.alpha_min.min(fdm.alpha));
     }  
 
     //Bank states
-    if fdm.bank < 20.0 && keystate.bank_right == true
+    if keystate.bank_right == true
     {
-        fdm.bank = fdm.bank + 1.0;
-    
+        fdm.bank = (fdm.bank + limits.bank_rate * dt).min(limits.bank_max.max(fdm.bank));
     }  
-    else if fdm.bank > -20.0 && keystate.bank_left == true
+    else if keystate.bank_left == true
     {
-        fdm.bank = fdm.bank - 1.0;
+        fdm.bank = (fdm.bank - limits.bank_rate * dt).max((-limits.bank_max).min(fdm.bank));
     }  
 
-    //Flap states
-    if fdm.flap == 0.0 && keystate.flaps_down == true
+    //Flap states, down to the next detent or back up to the first one
+    if keystate.flaps_down == true
     {
-        fdm.flap = 20.0;
-    }  
-    else if fdm.flap == 20.0 && keystate.flaps_down == true
-    {
-        fdm.flap = 40.0;
+        if let Some(detent) = limits.flap_detents.iter().find(|detent| **detent > fdm.flap)
+        {
+            fdm.flap = *detent;
+        }
     }  
-    else if (fdm.flap == 20.0 || fdm.flap == 40.0) && keystate.zero_flaps == true
+    else if keystate.zero_flaps == true
     {
-        fdm.flap = 0.0;
+        if let Some(detent) = limits.flap_detents.first()
+        {
+            fdm.flap = *detent;
+        }
     }  
+}
 
+//Advance the airplane state by one physics time step
+pub fn eom(fdm: &mut DataFDM, dt: f64, integrator: &Integrator)
+{
+    //The bank and angle of attack are set between the steps, they changed at these rates since the last one
+    let (bank_rate, alpha_rate) = match fdm.flown
+    {
+        Some(flown) if dt > 0.0 => ((fdm.bank - flown.bank) / dt, (fdm.alpha - flown.alpha) / dt),
+        _ => (0.0, 0.0),
+    };
+    fdm.bank_rate = bank_rate;
+    fdm.alpha_rate = alpha_rate;
 
     //Retrieve value of dependent variable
     let mut q = fdm.q.clone();
@@ -78,27 +97,38 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
     //Get the static time variable DT
     let ds = dt;
 
-    // Compute the four Runge-Kutta steps, The return 
-    // value of plane_right_hand_side method is an array
-    // of delta-q values for each of the four steps
-    plane_rhs(&mut fdm, &mut q, &mut qcopy,  &ds, 0.0, &mut dq1);
-    plane_rhs(&mut fdm, &mut q, &mut dq1,    &ds, 0.5, &mut dq2);
-    plane_rhs(&mut fdm, &mut q, &mut dq2,    &ds, 0.5, &mut dq3);
-    plane_rhs(&mut fdm, &mut q, &mut dq3,    &ds, 1.0, &mut dq4);
+    // Advance the ODEs with the configured integration method (4th-order
+    // Runge-Kutta by default), which calls plane_derivatives for each stage
+    integrator.step(0.0, &mut q, ds, &VELOCITY_STATES, |_, y, dydt| plane_derivatives(fdm, y, dydt));
 
-    //  Update the dependent and independent variable values
-    //  at the new dependent variable location and store the
-    //  values in the ODE object arrays.
+    //  Store the values in the ODE object arrays.
+    fdm.q = q;
 
-    for i in 0..6
+    //Ground handling, the airplane cannot sink into the ground. The vertical speed on first contact is kept as the touchdown rate.
+    //Nothing holds the airplane down once lift matches the weight, so it only counts as airborne again after
+    //climbing LIFTOFF_HEIGHT, and a skip along the runway is a single touchdown
+    if fdm.q[5] < 0.0
+    {
+        if !fdm.on_ground
+        {
+            fdm.touchdown_rate = fdm.q[4];
+            fdm.on_ground = true;
+        }
+        fdm.q[5] = 0.0;
+        fdm.q[4] = fdm.q[4].max(0.0);
+    }
+    else if fdm.q[5] > LIFTOFF_HEIGHT
     {
-        q[i] = q[i] + (dq1[i] + 2.0 * dq2[i] + 2.0 * dq3[i] + dq4[i]) / 6.0;
-        fdm.q[i] = q[i];
+        fdm.on_ground = false;
     }
 
     //Calculate airspeed
     fdm.airspeed = (fdm.q[0] * fdm.q[0] + fdm.q[2] * fdm.q[2] + fdm.q[4] * fdm.q[4]).sqrt();
 
+    //Stall margin of the wing, where the Cl curve turns down, and the stall warning moving towards it
+    fdm.stall_margin = fdm.mass_properties.alpha_cl_max - fdm.alpha;
+    fdm.stall_warning = smooth_warning(fdm.stall_warning, fdm.stall_margin, dt);
+
     //Calculate displacement based on velocities to add to the latitude and longitude
 
     //Create WGS84 ellipsoid
@@ -123,8 +153,12 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
     //Update position by adding old position and displacement with respect to time
     fdm.position = fdm.position + displacement * ds;
 
-    
-    //Print some relevant data
+    fdm.flown = Some(snapshot(fdm));
+}
+
+//Print some relevant data, this is called once per frame rather than every physics sub-step
+pub fn print_state(fdm: &DataFDM)
+{
     println!("Latitude (deg) x-axis =   {}", fdm.position.x);
     println!("Longitude (deg) y-axis =  {}", fdm.position.y);
     println!("Altitude (m) =            {}", fdm.q[5]);
@@ -136,5 +170,5 @@ pub fn eom(mut fdm: &mut DataFDM, keystate: &KeyboardState, dt: f64)
     println!("Angle of attack (deg) =   {}", fdm.alpha);
     println!("Bank angle (deg) =        {}", fdm.bank);
     println!("Flap deflection (deg) =   {}", fdm.flap);
-
+    println!("Stall warning =           {}", fdm.stall_warning);
 }
@@ -0,0 +1,51 @@
+//Functions to fly the guidance on the navigation estimate of the airplane
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Estimator and frames
+use crate::estimator::{Estimator, NavigationEstimate};
+use crate::navigation::ned_to_enu;
+
+//Navigation state the guidance reads from the airplane: the position and the velocity and height states
+struct Navigation
+{
+    position: Vector3<f64>, //lat, lon (deg) and altitude (m)
+    velocity: Vector3<f64>, //m/s, x east, y north, z up
+    height: f64, //m, the height state above the origin
+}
+
+fn set_navigation(fdm: &mut DataFDM, navigation: &Navigation)
+{
+    fdm.position = navigation.position;
+    fdm.q[0] = navigation.velocity.x;
+    fdm.q[2] = navigation.velocity.y;
+    fdm.q[4] = navigation.velocity.z;
+    fdm.q[5] = navigation.height;
+}
+
+//The height state keeps its offset from the altitude
+fn estimated_navigation(fdm: &DataFDM, estimate: &NavigationEstimate) -> Navigation
+{
+    let altitude = estimate.position.altitude;
+    Navigation
+    {
+        position: Vector3::new(estimate.position.latitude_deg(), estimate.position.longitude_deg(), altitude),
+        velocity: ned_to_enu(&estimate.velocity_ned),
+        height: fdm.q[5] + (altitude - fdm.position.z),
+    }
+}
+
+//State the guidance flies the airplane on when its Estimator flies the guidance on the estimate and has one: a copy of
+//the airplane with the estimated position and velocity in place of the truth. The guidance measures the copy and sets
+//the controls on the airplane itself. The bank and angle of attack are controls of the point mass, so they are kept
+pub fn estimated_state(fdm: &DataFDM, estimator: Option<&Estimator>) -> Option<DataFDM>
+{
+    let estimate = estimator.filter(|estimator| estimator.guidance_on_estimate).and_then(Estimator::current)?;
+    let mut state = fdm.clone();
+    set_navigation(&mut state, &estimated_navigation(fdm, &estimate));
+    Some(state)
+}
@@ -0,0 +1,57 @@
+//Flight plan coupling for the Palmer airplane. The guidance from the plan becomes the targets of the
+//heading hold, altitude hold and airspeed hold modes
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM, KeyboardState and AutopilotState structures
+use crate::palmer::fdm::structures::{DataFDM, KeyboardState, AutopilotState};
+
+//Flight plan, position and modes
+use crate::flight_plan::FlightPlan;
+use crate::navigation::Geodetic;
+use crate::autopilot::{VerticalMode, LateralMode, SpeedMode};
+
+//The flight plan key engages the plan. The plan lets go when the heading hold it drives has been
+//disengaged, by banking by hand or with the disengage key
+pub fn handle_flight_plan_input(plan: &mut FlightPlan, ap: &AutopilotState, keystate: &KeyboardState)
+{
+    if keystate.ap_flight_plan
+    {
+        plan.engaged = true;
+    }
+    else if plan.engaged && !matches!(ap.modes.lateral, LateralMode::HeadingHold(_))
+    {
+        plan.engaged = false;
+    }
+}
+
+//Set the autopilot targets from the plan. When the plan is complete it disengages and the autopilot
+//keeps holding the last heading and altitude
+pub fn follow_flight_plan(fdm: &DataFDM, plan: &mut FlightPlan, ap: &mut AutopilotState)
+{
+    if !plan.engaged
+    {
+        return;
+    }
+
+    //Position is latitude and longitude in degrees, the velocities are east, north and up
+    let position = Geodetic::from_degrees(fdm.position.x, fdm.position.y, fdm.position.z);
+    let v_ned = Vector3::new(fdm.q[2], fdm.q[0], -fdm.q[4]);
+    match plan.guide(&position, &v_ned)
+    {
+        Some(guidance) =>
+        {
+            //The altitude state and the geodetic altitude can differ by a constant
+            let altitude = fdm.q[5] + (guidance.altitude - fdm.position.z);
+
+            ap.modes.lateral = LateralMode::HeadingHold(guidance.heading);
+            ap.modes.vertical = VerticalMode::AltitudeHold(altitude);
+            if let Some(airspeed) = guidance.airspeed
+            {
+                ap.modes.speed = SpeedMode::AirspeedHold(airspeed);
+            }
+        }
+        None => plan.engaged = false,
+    }
+}
@@ -0,0 +1,35 @@
+//Functions to burn the fuel of the engine on the airplane
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Engine models
+use crate::engine::PistonEngine;
+use crate::jet::JetEngine;
+
+//Burn the fuel flow of the engine for dt seconds from the tanks in feed order. The mass of the airplane includes the fuel
+//in its tanks, so it drops by the fuel burnt, and once the tanks are empty the engine stops. The airplane is a point mass,
+//so the tank positions do not matter. An airplane without tanks is left alone
+pub fn burn_fuel(fdm: &mut DataFDM, engine: Option<&mut PistonEngine>, jet: Option<&mut JetEngine>, dt: f64)
+{
+    if fdm.fuel.tanks.is_empty()
+    {
+        return;
+    }
+
+    let fuel_flow = engine.as_ref().map_or(0.0, |engine| engine.fuel_flow) + jet.as_ref().map_or(0.0, |jet| jet.fuel_flow);
+    fdm.mass_properties.mass -= fdm.fuel.drain(fuel_flow, dt);
+
+    //Fuel exhaustion
+    if fdm.fuel.exhausted()
+    {
+        if let Some(engine) = engine
+        {
+            engine.running = false;
+        }
+        if let Some(jet) = jet
+        {
+            jet.shutdown();
+        }
+    }
+}
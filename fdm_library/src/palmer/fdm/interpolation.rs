@@ -0,0 +1,33 @@
+//Functions to record and interpolate the airplane pose between physics steps for the output Systems
+
+//Get data needed
+use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::Pose;
+
+//Interpolation helpers
+use crate::scheduler::{lerp, lerp_angle};
+
+//Take a copy of the current pose
+pub fn snapshot(fdm: &DataFDM) -> Pose
+{
+    Pose
+    {
+        position: fdm.position,
+        bank: fdm.bank,
+        alpha: fdm.alpha,
+        heading_angle: fdm.heading_angle,
+    }
+}
+
+//Blend two poses, alpha = 0.0 gives the previous pose and alpha = 1.0 the current pose.
+//The heading is interpolated the short way around the circle
+pub fn interpolate(previous: &Pose, current: &Pose, alpha: f64) -> Pose
+{
+    Pose
+    {
+        position: previous.position + (current.position - previous.position) * alpha,
+        bank: lerp(previous.bank, current.bank, alpha),
+        alpha: lerp(previous.alpha, current.alpha, alpha),
+        heading_angle: lerp_angle(previous.heading_angle, current.heading_angle, alpha),
+    }
+}
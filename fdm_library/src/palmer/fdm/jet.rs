@@ -0,0 +1,25 @@
+//Functions to run a jet engine on the airplane
+
+//DataFDM and KeyboardState structs
+use crate::palmer::fdm::structures::{DataFDM, KeyboardState};
+
+//Engine and the air it runs in
+use crate::jet::JetEngine;
+use crate::atmosphere::Atmosphere;
+
+//Apply the starter key. This runs at the input rate
+pub fn handle_jet_input(engine: &mut JetEngine, keystate: &KeyboardState)
+{
+    if keystate.engine_start && !engine.running
+    {
+        engine.start();
+    }
+}
+
+//Run the engine for dt seconds at the throttle setting, its thrust takes the place of the propeller
+pub fn run_jet(fdm: &mut DataFDM, engine: &mut JetEngine, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.z);
+    engine.update(fdm.throttle, &air, fdm.airspeed, dt);
+    fdm.jet_thrust = Some(engine.thrust);
+}
@@ -2,23 +2,42 @@
 
 //Get data needed for the System to work
 use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::Pose;
 use crate::flightgear::FGNetFDM;
 
+//Pose helper
+use crate::palmer::fdm::interpolation::snapshot;
+
+//Stall warning
+use crate::stall::load_stall_warning;
+
 //System to make packet based on fgnetfdm structure required by FlightGear 
 pub fn load_fgnetfdm(fdm: &DataFDM, fgnet: &mut FGNetFDM)
 {
     //All data passed into the FGNetFDM struct is converted to network byte order
 
+    //Position and attitude
+    load_pose(&snapshot(fdm), fgnet);
+
+    //Other airplane data
+    let fg_net_fdm_version = 24_u32;
+    fgnet.version = u32::from_be_bytes(fg_net_fdm_version.to_ne_bytes());
+    load_stall_warning(fdm.stall_warning, fgnet);
+}
+
+//Load the position and attitude of a pose, this is also used with an interpolated pose
+pub fn load_pose(pose: &Pose, fgnet: &mut FGNetFDM)
+{
     //Set Roll, Pitch, Yaw in radians
     //Negate to compensate for coordinate differences
-    let roll: f32 = fdm.bank.to_radians() as f32;
-    let pitch: f32 = fdm.alpha.to_radians() as f32;
-    let yaw: f32 = 90.0_f32.to_radians() + -fdm.heading_angle as f32; //heading angle is already in radians
+    let roll: f32 = pose.bank.to_radians() as f32;
+    let pitch: f32 = pose.alpha.to_radians() as f32;
+    let yaw: f32 = 90.0_f32.to_radians() + -pose.heading_angle as f32; //heading angle is already in radians
 
     //Lat and lon degrees need to be converted to radians for FlightGear
-    let lat = fdm.position.x.to_radians();
-    let lon = fdm.position.y.to_radians();
-    let alt = fdm.position.z;
+    let lat = pose.position.x.to_radians();
+    let lon = pose.position.y.to_radians();
+    let alt = pose.position.z;
 
     //Set lat, long, alt
     fgnet.latitude = f64::from_be_bytes(lat.to_ne_bytes()); 
@@ -29,8 +48,4 @@ pub fn load_fgnetfdm(fdm: &DataFDM, fgnet: &mut FGNetFDM)
     fgnet.phi = f32::from_be_bytes(roll.to_ne_bytes());
     fgnet.theta = f32::from_be_bytes(pitch.to_ne_bytes());
     fgnet.psi = f32::from_be_bytes(yaw.to_ne_bytes());
-
-    //Other airplane data
-    let fg_net_fdm_version = 24_u32;
-    fgnet.version = u32::from_be_bytes(fg_net_fdm_version.to_ne_bytes());
 }
\ No newline at end of file
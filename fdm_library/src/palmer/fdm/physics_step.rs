@@ -0,0 +1,106 @@
+//Function to advance an airplane and the Components it carries by one physics step
+
+//Get data needed for the physics step
+use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::StateHistory;
+use crate::palmer::fdm::structures::Actuators;
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+use crate::sensors::Sensors;
+use crate::estimator::Estimator;
+use crate::air_data::AirDataComputer;
+use crate::envelope::EnvelopeMonitor;
+use crate::integrator::Integrator;
+
+//Get functions to call
+use crate::palmer::fdm::equations_of_motion::eom;
+use crate::palmer::fdm::interpolation::snapshot;
+use crate::palmer::fdm::actuators::{actuate, restore_commands};
+use crate::palmer::fdm::engine::run_engine;
+use crate::palmer::fdm::propeller::run_propeller;
+use crate::palmer::fdm::jet::run_jet;
+use crate::palmer::fdm::fuel::burn_fuel;
+use crate::palmer::fdm::sensors::sensor_truth;
+use crate::palmer::fdm::air_data::run_air_data;
+use crate::palmer::fdm::envelope::{protect, restore_alpha_command};
+
+//Optional Components of an airplane, each takes part in the physics step when the airplane has it
+pub struct Equipment<'a>
+{
+    pub history: Option<&'a mut StateHistory>,
+    pub actuators: Option<&'a mut Actuators>,
+    pub engine: Option<&'a mut PistonEngine>,
+    pub propeller: Option<&'a mut Propeller>,
+    pub jet: Option<&'a mut JetEngine>,
+    pub sensors: Option<&'a mut Sensors>,
+    pub estimator: Option<&'a mut Estimator>,
+    pub air_data: Option<&'a mut AirDataComputer>,
+    pub monitor: Option<&'a EnvelopeMonitor>,
+}
+
+//Advance the airplane by one physics step of dt seconds, eom calls plane_right_hand_side
+pub fn physics_step(fdm: &mut DataFDM, equipment: &mut Equipment, dt: f64, integrator: &Integrator)
+{
+    if let Some(history) = equipment.history.as_mut()
+    {
+        history.previous = snapshot(fdm);
+    }
+
+    //An EnvelopeMonitor that protects the airplane takes over its angle of attack command for the step
+    let protected = equipment.monitor.and_then(|monitor| protect(fdm, monitor));
+
+    //With Actuators the airplane flies on the actual positions of its controls, which follow the commands
+    if let Some(actuators) = equipment.actuators.as_mut()
+    {
+        actuate(fdm, actuators, dt);
+    }
+
+    //A PistonEngine gives the thrust, through its Propeller when it has one, or a JetEngine does.
+    //The engine burns the fuel in the tanks, which lightens the airplane
+    match (equipment.engine.as_mut(), equipment.propeller.as_mut())
+    {
+        (Some(engine), Some(propeller)) => run_propeller(fdm, engine, propeller, dt),
+        (Some(engine), None) => run_engine(fdm, engine, dt),
+        _ => (),
+    }
+    if let Some(jet) = equipment.jet.as_mut()
+    {
+        run_jet(fdm, jet, dt);
+    }
+    burn_fuel(fdm, equipment.engine.as_deref_mut(), equipment.jet.as_deref_mut(), dt);
+
+    eom(fdm, dt, integrator);
+
+    //The sensors and air data sample the airplane as it flew, before the commands are put back.
+    //The Estimator fuses the sensor outputs and compares the estimate with the state
+    if let Some(sensors) = equipment.sensors.as_mut()
+    {
+        let truth = sensor_truth(fdm);
+        sensors.update(&truth, dt);
+        if let Some(estimator) = equipment.estimator.as_mut()
+        {
+            estimator.process(sensors);
+            estimator.compare(&truth);
+        }
+    }
+    if let Some(air_data) = equipment.air_data.as_mut()
+    {
+        run_air_data(fdm, air_data, dt);
+    }
+
+    //The inputs and the autopilot keep working on the commands
+    if let Some(actuators) = equipment.actuators.as_ref()
+    {
+        restore_commands(fdm, actuators);
+    }
+    if let Some(command) = protected
+    {
+        restore_alpha_command(fdm, command);
+    }
+
+    if let Some(history) = equipment.history.as_mut()
+    {
+        history.current = snapshot(fdm);
+    }
+}
@@ -1,11 +1,32 @@
-//This function is called by the equations of motion function and loads the right-hand sides for the plane ODEs
+//This file loads the right-hand sides for the plane ODEs, it is called by the equations of motion function
 
 use crate::palmer::fdm::structures::DataFDM;
 
+//Palmer's original right-hand side function: loads dq with ds * f(q + q_scale * delta_q),
+//which is the form used by the hand-written Runge-Kutta steps
 pub fn plane_rhs(fdm: &mut DataFDM, q: &mut Vec<f64>, delta_q: &mut Vec<f64>, &ds: & f64, q_scale: f64, dq: &mut Vec<f64>)
 {
     let mut new_q = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0]; // intermediate dependent variable values 
- 
+
+    //  Compute the intermediate values of the 
+    //  dependent variables.
+    for i in 0..6
+    {
+        new_q[i] = q[i] + q_scale * delta_q[i]; 
+    }
+
+    plane_derivatives(fdm, &new_q, dq);
+
+    for dqi in dq.iter_mut().take(6)
+    {
+        *dqi *= ds;
+    }
+}
+
+//Time derivatives of the plane ODEs for the state q = [vx, x, vy, y, vz, z].
+//This is the derivative function handed to the integrator, it also updates the climb and heading angles
+pub fn plane_derivatives(fdm: &mut DataFDM, new_q: &[f64], dq: &mut [f64])
+{
     let negativeone = -1.0_f64;
     let pi = negativeone.acos();
     let g: f64 = -9.81;
@@ -20,13 +41,6 @@ pub fn plane_rhs(fdm: &mut DataFDM, q: &mut Vec<f64>, delta_q: &mut Vec<f64>, &d
     //  Cl-alpha curve is defined in terms of degrees.
     let bank = fdm.bank.to_radians();
 
-    //  Compute the intermediate values of the 
-    //  dependent variables.
-    for i in 0..6
-    {
-        new_q[i] = q[i] + q_scale * delta_q[i]; 
-    }
-
     //  Assign convenenience variables to the intermediate 
     //  values of the locations and velocities.
     let vx: f64 = new_q[0];
@@ -48,9 +62,11 @@ pub fn plane_rhs(fdm: &mut DataFDM, q: &mut Vec<f64>, delta_q: &mut Vec<f64>, &d
     let omega: f64 = density / 1.225;
     let factor: f64 = (omega - 0.12)/  0.88;
 
-    //  Compute thrust 
+    //  Compute thrust, with the power of the engine model when there is one
+    //  and the thrust of the propeller or jet engine model when there is one
+    let power: f64 = fdm.shaft_power.unwrap_or(fdm.throttle * factor * fdm.mass_properties.engine_power);
     let advance_ratio: f64 = vtotal / (fdm.mass_properties.engine_rps * fdm.mass_properties.prop_diameter);
-    let thrust: f64 = fdm.throttle * factor * fdm.mass_properties.engine_power * (fdm.mass_properties.a + fdm.mass_properties.b * advance_ratio * advance_ratio) / (fdm.mass_properties.engine_rps * fdm.mass_properties.prop_diameter);
+    let thrust: f64 = fdm.jet_thrust.or(fdm.propeller_thrust).unwrap_or(power * (fdm.mass_properties.a + fdm.mass_properties.b * advance_ratio * advance_ratio) / (fdm.mass_properties.engine_rps * fdm.mass_properties.prop_diameter));
 
     //  Compute lift coefficient. The Cl curve is 
     //  modeled using two straight lines.
@@ -64,16 +80,10 @@ pub fn plane_rhs(fdm: &mut DataFDM, q: &mut Vec<f64>, delta_q: &mut Vec<f64>, &d
     }
 
     //  Include effects of flaps and ground effects.
-    //  Ground effects are present if the plane is
-    //  within 5 meters of the ground.
-    if fdm.flap == 20.0
-    {
-        cl += 0.25;
-    }
-    if fdm.flap == 40.0
-    {
-        cl += 0.5;
-    }
+    //  The flap lift of the airplane is set at each of
+    //  its flap detents. Ground effects are present if
+    //  the plane is within 5 meters of the ground.
+    cl += fdm.control_limits.flap_lift(fdm.flap);
     if z < 5.0
     {
         cl += 0.25;
@@ -137,12 +147,12 @@ pub fn plane_rhs(fdm: &mut DataFDM, q: &mut Vec<f64>, delta_q: &mut Vec<f64>, &d
     }
 
     //  Load the right-hand sides of the ODE's
-    dq[0] = ds * (fx / fdm.mass_properties.mass);
-    dq[1] = ds * vx;
-    dq[2] = ds * (fy / fdm.mass_properties.mass);
-    dq[3] = ds * vy;
-    dq[4] = ds * (fz / fdm.mass_properties.mass);
-    dq[5] = ds * vz;
+    dq[0] = fx / fdm.mass_properties.mass;
+    dq[1] = vx;
+    dq[2] = fy / fdm.mass_properties.mass;
+    dq[3] = vy;
+    dq[4] = fz / fdm.mass_properties.mass;
+    dq[5] = vz;
 
     //Calculate climb angle and heading angle
     if vh == 0.0 
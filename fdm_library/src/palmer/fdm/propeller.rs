@@ -0,0 +1,24 @@
+//Functions to turn a propeller with the engine on the airplane
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Engine, propeller and the air they run in
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::atmosphere::Atmosphere;
+
+//Run the engine for dt seconds at the throttle setting turning the propeller, which loads the engine with its torque.
+//The airplane is a point mass, so only the thrust of the propeller is used, along the flight path
+pub fn run_propeller(fdm: &mut DataFDM, engine: &mut PistonEngine, propeller: &mut Propeller, dt: f64)
+{
+    let air = Atmosphere::standard(fdm.position.z);
+
+    //The propeller loads the engine at its current speed, then gives its thrust at the new one
+    propeller.update(engine.rpm, fdm.airspeed, air.density);
+    engine.update(fdm.throttle, &air, propeller.engine_load(), dt);
+    propeller.update(engine.rpm, fdm.airspeed, air.density);
+
+    fdm.shaft_power = Some(engine.power);
+    fdm.propeller_thrust = Some(propeller.thrust);
+}
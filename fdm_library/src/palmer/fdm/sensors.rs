@@ -0,0 +1,71 @@
+//Functions to find the truth the sensors measure on the airplane
+
+//nalgebra vector
+use nalgebra::Vector3;
+
+//DataFDM struct
+use crate::palmer::fdm::structures::DataFDM;
+
+//Derivatives of the state, which give the acceleration
+use crate::palmer::fdm::plane_right_hand_side::plane_derivatives;
+
+//Sensor truth and navigation frames
+use crate::sensors::{SensorTruth, ned_to_body, euler_rates_to_body};
+use crate::navigation::{Geodetic, enu_to_ned};
+
+//Gravity (m/s^2)
+const GRAVITY: f64 = 9.81;
+
+//Truth of the airplane's state for the sensors, in aerospace axes and SI units. The airplane is a point mass, so its
+//attitude is the bank, the climb angle plus the angle of attack and the heading, and its body rates are those of the
+//turn and the change of climb angle the acceleration gives, with the rates of the bank and angle of attack over the
+//last physics step. With Actuators it is taken before the commands are put back on the airplane
+pub fn sensor_truth(fdm: &DataFDM) -> SensorTruth
+{
+    //Acceleration from the derivatives of the current state, q = [vx, x, vy, y, vz, z] with x east, y north, z up.
+    //The derivatives update the climb and heading angles on the airplane they are taken on, so they are taken on a copy
+    let q = fdm.q.clone();
+    let mut dq = vec![0.0; q.len()];
+    plane_derivatives(&mut fdm.clone(), &q, &mut dq);
+    let velocity = Vector3::new(q[0], q[2], q[4]);
+    let mut acceleration = Vector3::new(dq[0], dq[2], dq[4]);
+
+    //On the ground it holds the airplane up
+    if fdm.on_ground
+    {
+        acceleration.z = acceleration.z.max(0.0);
+    }
+
+    //Heading is measured clockwise from north, the model's heading angle counterclockwise from east
+    let heading = (0.5 * std::f64::consts::PI - fdm.heading_angle).rem_euclid(2.0 * std::f64::consts::PI);
+    let attitude = Vector3::new(fdm.bank.to_radians(), fdm.climb_angle + fdm.alpha.to_radians(), heading);
+
+    //Rates of the heading and climb angle from the horizontal and vertical acceleration
+    let vh2 = velocity.x * velocity.x + velocity.y * velocity.y;
+    let vh = vh2.sqrt();
+    let (heading_rate, climb_rate) = if vh > 1e-6
+    {
+        let vh_dot = (velocity.x * acceleration.x + velocity.y * acceleration.y) / vh;
+        (-(velocity.x * acceleration.y - velocity.y * acceleration.x) / vh2,
+         (vh * acceleration.z - velocity.z * vh_dot) / (vh2 + velocity.z * velocity.z))
+    }
+    else
+    {
+        (0.0, 0.0)
+    };
+
+    //The accelerometers feel the acceleration less gravity
+    let specific_force = enu_to_ned(&(acceleration + Vector3::new(0.0, 0.0, GRAVITY)));
+
+    let (bank_rate, alpha_rate) = (fdm.bank_rate.to_radians(), fdm.alpha_rate.to_radians());
+
+    SensorTruth
+    {
+        specific_force: ned_to_body(&attitude, &specific_force),
+        angular_rate: euler_rates_to_body(&attitude, &Vector3::new(bank_rate, climb_rate + alpha_rate, heading_rate)),
+        attitude,
+        position: Geodetic::from_degrees(fdm.position.x, fdm.position.y, fdm.position.z),
+        velocity_ned: enu_to_ned(&velocity),
+        airspeed: fdm.airspeed,
+    }
+}
@@ -3,8 +3,23 @@
 //Coordinate transforms/nalgebra vector
 use coord_transforms::prelude::*;
 
+//Autopilot loops and modes
+use crate::autopilot::{Pid, PidGains, AutopilotModes};
+
+//Actuator dynamics
+use crate::actuator::Actuator;
+
+//Fuel tanks
+use crate::fuel::FuelSystem;
+
+//Engines an airplane is spawned with
+use crate::crash::SpawnedEngines;
+
+//Table interpolation
+use crate::propeller::lookup;
+
 //Performance data of the airplane, contains lifting surface data and mass properties. This structure is used in DataFDM Component
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct PerformanceData
 {
     pub wing_area: f64,
@@ -17,7 +32,7 @@ pub struct PerformanceData
     pub alpha_cl_max: f64,  // alpha when Cl=Clmax
     pub cdp: f64,         // parasite drag coefficient
     pub eff: f64,         // induced drag efficiency coefficient
-    pub mass: f64,
+    pub mass: f64,        // includes the fuel in the tanks, it drops as the fuel burns
     pub engine_power: f64,
     pub engine_rps: f64,   // revolutions per second
     pub prop_diameter: f64,
@@ -26,8 +41,54 @@ pub struct PerformanceData
 }
 
 
+//Limits and rates of the controls of an airplane, the keys move the controls at these rates within the limits
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlLimits
+{
+    pub alpha_min: f64, //deg
+    pub alpha_max: f64, //deg
+    pub bank_max: f64, //deg, either way
+    pub flap_detents: Vec<f64>, //deg, flap settings from retracted to fully down, the flap keys step through them
+    pub flap_cl: Vec<f64>, //lift coefficient the flaps add at each detent
+    pub alpha_rate: f64, //deg/s
+    pub bank_rate: f64, //deg/s
+    pub throttle_rate: f64, //throttle fraction per second
+}
+
+impl Default for ControlLimits
+{
+    fn default() -> Self
+    {
+        ControlLimits
+        {
+            alpha_min: -16.0,
+            alpha_max: 20.0,
+            bank_max: 20.0,
+            flap_detents: vec![0.0, 20.0, 40.0],
+            flap_cl: vec![0.0, 0.25, 0.5],
+            alpha_rate: 30.0,
+            bank_rate: 30.0,
+            throttle_rate: 1.5,
+        }
+    }
+}
+
+impl ControlLimits
+{
+    //Lift coefficient the flaps add at a deflection, interpolated between the detents so it builds up
+    //while the flap travels, and held past the last one
+    pub fn flap_lift(&self, flap: f64) -> f64
+    {
+        if self.flap_detents.is_empty()
+        {
+            return 0.0;
+        }
+        lookup(&self.flap_detents, &self.flap_cl, flap)
+    }
+}
+
 //Component containing data of the airplane
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct DataFDM
 {
     pub q: Vec<f64>, //will store ODE results
@@ -41,13 +102,53 @@ pub struct DataFDM
 
     pub bank: f64, //bank angle
     pub alpha: f64, //angle of attack
+    pub bank_rate: f64, //deg/s, change of the bank over the last physics step, from the one flown the step before
+    pub alpha_rate: f64, //deg/s, change of the angle of attack over the last physics step
+    pub flown: Option<Pose>, //pose the last physics step flew with, none before the first one
     pub throttle: f64, //throttle percentage
     pub flap: f64, //flap deflection amount
+    pub shaft_power: Option<f64>, //W from an engine model, which then takes the place of the throttle and power drop-off factor
+    pub propeller_thrust: Option<f64>, //N from a propeller model, which then takes the place of the propeller efficiency curve
+    pub jet_thrust: Option<f64>, //N from a jet engine model, which takes the place of the propeller
+
+    pub stall_margin: f64, //angle of attack (deg) left before the wing stalls at alpha_cl_max, negative once stalled
+    pub stall_warning: f64, //smoothed stall warning (0 - 1) from the stall margin
+
+    pub on_ground: bool, //the airplane has touched the ground and not climbed away from it yet
+    pub touchdown_rate: f64, //vertical speed (m/s) at the last touchdown, negative down
 
     pub mass_properties : PerformanceData,
+    pub control_limits: ControlLimits,
+    pub fuel: FuelSystem, //fuel tanks feeding the engine, their fuel is part of the mass
+
+
 
+}
+
+//Position and attitude of the airplane after a physics step
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Pose
+{
+    pub position: Vector3<f64>,
+    pub bank: f64,
+    pub alpha: f64,
+    pub heading_angle: f64,
+}
 
+//Poses before and after the latest physics step, the output Systems interpolate between them
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StateHistory
+{
+    pub previous: Pose,
+    pub current: Pose,
+}
 
+//State the airplane is spawned with, a crashed airplane is reset to it
+#[derive(Debug, Clone)]
+pub struct InitialConditions
+{
+    pub fdm: DataFDM,
+    pub engines: SpawnedEngines,
 }
 
 
@@ -63,4 +164,93 @@ pub struct KeyboardState
     pub bank_left: bool,
     pub flaps_down: bool,
     pub zero_flaps: bool,
-}
\ No newline at end of file
+    pub ap_altitude_hold: bool, //engage at the current altitude
+    pub ap_vertical_speed: bool, //engage at the current climb rate
+    pub ap_heading_hold: bool, //engage at the current heading
+    pub ap_airspeed_hold: bool, //engage at the current airspeed
+    pub ap_wing_leveler: bool,
+    pub ap_disengage: bool, //disengage every mode
+    pub ap_flight_plan: bool, //let the flight plan drive the autopilot
+    pub ap_approach: bool, //fly the approach to the runway and land
+    pub mixture_rich: bool,
+    pub mixture_lean: bool,
+    pub engine_start: bool,
+}
+
+//Gains of the autopilot loops, set per airplane. The outer loops command the inner ones:
+//altitude (m) -> climb rate (m/s) -> angle of attack (deg), heading (deg) -> bank (deg)
+//and airspeed (m/s) -> throttle (0 - 1)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutopilotGains
+{
+    pub altitude: PidGains,
+    pub vertical_speed: PidGains,
+    pub approach_vertical_speed: PidGains, //vertical speed loop flown by the approach coupler, tighter to track the glide path
+    pub heading: PidGains,
+    pub airspeed: PidGains,
+}
+
+impl Default for AutopilotGains
+{
+    //Tuned for the airplane in the Palmer example
+    fn default() -> Self
+    {
+        AutopilotGains
+        {
+            altitude: PidGains::new(0.2, 0.0, 0.0, -5.0, 5.0),
+            vertical_speed: PidGains::new(0.5, 0.1, 0.0, -10.0, 14.0),
+            approach_vertical_speed: PidGains::new(1.5, 0.5, 0.0, -10.0, 14.0),
+            heading: PidGains::new(1.0, 0.0, 0.0, -20.0, 20.0),
+            airspeed: PidGains::new(0.1, 0.02, 0.0, 0.0, 1.0),
+        }
+    }
+}
+
+//Autopilot of an airplane: the engaged modes and the state of each loop
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AutopilotState
+{
+    pub modes: AutopilotModes,
+    pub engaged: AutopilotModes, //modes the loops last ran with, a change resets the loops
+    pub altitude: Pid,
+    pub vertical_speed: Pid,
+    pub approach_vertical_speed: Pid,
+    pub heading: Pid,
+    pub airspeed: Pid,
+}
+
+impl AutopilotState
+{
+    pub fn new(gains: AutopilotGains) -> AutopilotState
+    {
+        AutopilotState
+        {
+            modes: AutopilotModes::default(),
+            engaged: AutopilotModes::default(),
+            altitude: Pid::new(gains.altitude),
+            vertical_speed: Pid::new(gains.vertical_speed),
+            approach_vertical_speed: Pid::new(gains.approach_vertical_speed),
+            heading: Pid::new(gains.heading),
+            airspeed: Pid::new(gains.airspeed),
+        }
+    }
+}
+
+impl Default for AutopilotState
+{
+    fn default() -> Self
+    {
+        AutopilotState::new(AutopilotGains::default())
+    }
+}
+
+//Actuators of the control channels, set per airplane. The inputs and the autopilot command the controls on DataFDM
+//and the physics flies on the actual positions. Angle of attack, bank and flap are in degrees, the throttle is 0 - 1
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Actuators
+{
+    pub alpha: Actuator,
+    pub bank: Actuator,
+    pub throttle: Actuator,
+    pub flap: Actuator,
+}
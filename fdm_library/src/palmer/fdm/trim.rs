@@ -0,0 +1,63 @@
+//Trim the Palmer airplane for a steady flight condition.
+//The unknowns are the angle of attack (degrees) and throttle, the bank angle comes from the target.
+//The residuals are the accelerations from plane_derivatives along the flight path and normal to it
+//in the vertical plane, which must both be zero for steady flight. In a banked turn the horizontal
+//component of lift is left over and turns the airplane
+
+use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::plane_right_hand_side::plane_derivatives;
+
+//Trim solver
+use crate::trim::{TrimTarget, TrimSettings, TrimReport, solve};
+
+//Order of the unknowns in the TrimReport
+pub const TRIM_ALPHA: usize = 0;
+pub const TRIM_THROTTLE: usize = 1;
+
+//Solve for alpha and throttle at the target condition (m, m/s). On return the airplane is left
+//flying the target condition with the trimmed controls, whether or not the solver converged
+pub fn trim(fdm: &mut DataFDM, target: &TrimTarget, settings: &TrimSettings) -> TrimReport
+{
+    let gamma = target.climb_angle();
+    let heading = target.heading.to_radians();
+
+    //Velocity along the flight path, keep the horizontal position
+    if fdm.q.len() < 6
+    {
+        fdm.q = vec![0.0; 6];
+    }
+    fdm.q[0] = target.airspeed * gamma.cos() * heading.cos();
+    fdm.q[2] = target.airspeed * gamma.cos() * heading.sin();
+    fdm.q[4] = target.airspeed * gamma.sin();
+    fdm.q[5] = target.altitude;
+    fdm.bank = target.bank;
+    fdm.airspeed = target.airspeed;
+
+    //Unit vectors along the flight path and normal to it in the vertical plane
+    let tangent = [gamma.cos() * heading.cos(), gamma.cos() * heading.sin(), gamma.sin()];
+    let normal = [-gamma.sin() * heading.cos(), -gamma.sin() * heading.sin(), gamma.cos()];
+
+    let q = fdm.q.clone();
+    let mut x = vec![fdm.alpha, fdm.throttle];
+    let mut dq = vec![0.0; 6];
+
+    let report = solve(&mut x, &[1.0e-3, 1.0e-4], settings, |x, r|
+    {
+        fdm.alpha = x[TRIM_ALPHA];
+        fdm.throttle = x[TRIM_THROTTLE];
+        plane_derivatives(fdm, &q, &mut dq);
+
+        r[0] = dq[0] * tangent[0] + dq[2] * tangent[1] + dq[4] * tangent[2];
+        r[1] = dq[0] * normal[0] + dq[2] * normal[1] + dq[4] * normal[2];
+    });
+
+    fdm.alpha = report.unknowns[TRIM_ALPHA];
+    fdm.throttle = report.unknowns[TRIM_THROTTLE];
+    report
+}
+
+//Turn rate (degrees per second) of a steady coordinated turn at a bank angle (degrees) and airspeed (m/s)
+pub fn turn_rate(bank: f64, airspeed: f64) -> f64
+{
+    (9.81 * bank.to_radians().tan() / airspeed).to_degrees()
+}
@@ -1,4 +1,9 @@
 pub mod system_flight_control;
 pub mod system_make_packet;
 pub mod system_send_packet;
-pub mod system_equations_of_motion;
\ No newline at end of file
+pub mod system_equations_of_motion;
+pub mod system_autopilot;
+pub mod system_flight_plan;
+pub mod system_approach;
+pub mod system_envelope;
+pub mod system_crash;
\ No newline at end of file
@@ -0,0 +1,52 @@
+//This file contains the Autoland System
+
+//SPECS
+use specs::prelude::*;
+
+//Get Component data needed to perform the System operations
+use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::KeyboardState;
+use crate::palmer::fdm::structures::AutopilotState;
+use crate::approach::Approach;
+use crate::scheduler::Scheduler;
+use crate::estimator::Estimator;
+
+//Functions to call
+use crate::palmer::fdm::approach::{fly_approach, fly_approach_on, handle_approach_input};
+use crate::palmer::fdm::estimator::estimated_state;
+
+//System to fly the approach and landing through the autopilot. It runs after the FlightPlanGuidance System,
+//so an engaged approach takes over from the flight plan, and before the Autopilot System at the input rate
+pub struct Autoland;
+impl<'a> System<'a> for Autoland
+{
+    type SystemData = (
+        Option<Read<'a, Scheduler>>,
+        WriteStorage<'a, DataFDM>,
+        ReadStorage<'a, KeyboardState>,
+        WriteStorage<'a, Approach>,
+        WriteStorage<'a, AutopilotState>,
+        ReadStorage<'a, Estimator>
+    );
+
+    fn run(&mut self, (scheduler, mut datafdm, keyboardstate, mut approach, mut autopilotstate, estimators): Self::SystemData) 
+    {
+        if let Some(scheduler) = scheduler
+        {
+            if !scheduler.input.is_due()
+            {
+                return;
+            }
+        }
+
+        for (fdm, keystate, approach, ap, estimator) in (&mut datafdm, &keyboardstate, &mut approach, &mut autopilotstate, estimators.maybe()).join() 
+        {
+            handle_approach_input(approach, ap, keystate);
+            match estimated_state(fdm, estimator)
+            {
+                Some(state) => fly_approach_on(&state, fdm, approach, ap),
+                None => fly_approach(fdm, approach, ap),
+            }
+        }
+    }
+}
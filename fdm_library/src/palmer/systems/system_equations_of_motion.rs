@@ -6,30 +6,128 @@ use specs::prelude::*;
 //Get data needed to perform the System operations
 use crate::palmer::fdm::structures::KeyboardState;
 use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::StateHistory;
+use crate::palmer::fdm::structures::Actuators;
+use crate::engine::PistonEngine;
+use crate::propeller::Propeller;
+use crate::jet::JetEngine;
+use crate::sensors::Sensors;
+use crate::estimator::Estimator;
+use crate::air_data::AirDataComputer;
+use crate::envelope::EnvelopeMonitor;
+use crate::crash::CrashMonitor;
 use crate::palmer::resources::delta_time::DeltaTime;
+use crate::integrator::Integrator;
+use crate::scheduler::Scheduler;
 
-//Get function to call
-use crate::palmer::fdm::equations_of_motion::eom;
+//Get functions to call
+use crate::palmer::fdm::equations_of_motion::{handle_input, print_state};
+use crate::palmer::fdm::physics_step::{physics_step, Equipment};
+use crate::palmer::fdm::actuators::print_actuators;
+use crate::palmer::fdm::engine::handle_engine_input;
+use crate::palmer::fdm::jet::handle_jet_input;
+use crate::engine::print_engine;
+use crate::propeller::print_propeller;
+use crate::jet::print_jet;
+use crate::fuel::print_fuel;
+use crate::sensors::print_sensors;
+use crate::estimator::print_estimate;
+use crate::air_data::print_air_data;
 
-//System to perform physics calculations using a 4th-order Runge-Kutta solver
+//System to perform physics calculations using the integrator resource (4th-order Runge-Kutta by default).
+//With a Scheduler resource the physics is sub-stepped at the physics rate, otherwise one DeltaTime step is taken
 pub struct EquationsOfMotion;
 impl<'a> System<'a> for EquationsOfMotion
 {
     type SystemData = (
         Read<'a, DeltaTime>,
+        Read<'a, Integrator>,
+        Option<Read<'a, Scheduler>>,
         WriteStorage<'a, DataFDM>,
-        ReadStorage<'a, KeyboardState>
+        ReadStorage<'a, KeyboardState>,
+        WriteStorage<'a, StateHistory>,
+        WriteStorage<'a, Actuators>,
+        WriteStorage<'a, PistonEngine>,
+        WriteStorage<'a, Propeller>,
+        WriteStorage<'a, JetEngine>,
+        WriteStorage<'a, Sensors>,
+        WriteStorage<'a, Estimator>,
+        WriteStorage<'a, AirDataComputer>,
+        ReadStorage<'a, EnvelopeMonitor>,
+        ReadStorage<'a, CrashMonitor>
     );
 
-    fn run(&mut self, (dt, mut datafdm, keyboardstate): Self::SystemData) 
+    fn run(&mut self, (dt, integrator, scheduler, mut datafdm, keyboardstate, mut history, mut actuators, mut engines, mut propellers, mut jets, mut sensors, mut estimators, mut air_data_computers, monitors, crash_monitors): Self::SystemData) 
     {
-        //Get DeltaTime resource
-        let dt = dt.0;
-        for (mut fdm, keystate) in (&mut datafdm, &keyboardstate).join() 
+        //Number of physics steps this frame, their size, whether the inputs are applied and the time since the last inputs
+        let (steps, dt, input_due, input_dt) = match scheduler
         {
-            //Call eom function, which calls plane_right_hand_side function
-            eom(&mut fdm, &keystate, dt);
+            Some(scheduler) => (scheduler.physics.steps, scheduler.physics.dt(), scheduler.input.is_due(), scheduler.input.steps as f64 * scheduler.input.dt()),
+            None => (1, dt.0, true, dt.0),
+        };
+
+        for (fdm, keystate, history, actuators, mut engine, propeller, mut jet, sensors, estimator, air_data, monitor, crash_monitor) in (&mut datafdm, &keyboardstate, (&mut history).maybe(), (&mut actuators).maybe(), (&mut engines).maybe(), (&mut propellers).maybe(), (&mut jets).maybe(), (&mut sensors).maybe(), (&mut estimators).maybe(), (&mut air_data_computers).maybe(), monitors.maybe(), crash_monitors.maybe()).join() 
+        {
+            //A crashed airplane stays where it crashed
+            if crash_monitor.is_some_and(CrashMonitor::is_crashed)
+            {
+                continue;
+            }
+
+            //Apply the control inputs
+            if input_due
+            {
+                handle_input(fdm, keystate, input_dt);
+                if let Some(engine) = engine.as_mut()
+                {
+                    handle_engine_input(engine, keystate, input_dt);
+                }
+                if let Some(jet) = jet.as_mut()
+                {
+                    handle_jet_input(jet, keystate);
+                }
+            }
+
+            //Advance the airplane once for each physics step
+            let mut equipment = Equipment{ history, actuators, engine, propeller, jet, sensors, estimator, air_data, monitor };
+            for _ in 0..steps
+            {
+                physics_step(fdm, &mut equipment, dt, &integrator);
+            }
+
+            if steps > 0
+            {
+                print_state(fdm);
+                print_fuel(&fdm.fuel);
+                if let Some(actuators) = equipment.actuators.as_deref()
+                {
+                    print_actuators(actuators);
+                }
+                if let Some(engine) = equipment.engine.as_deref()
+                {
+                    print_engine(engine);
+                }
+                if let Some(propeller) = equipment.propeller.as_deref()
+                {
+                    print_propeller(propeller);
+                }
+                if let Some(jet) = equipment.jet.as_deref()
+                {
+                    print_jet(jet);
+                }
+                if let Some(sensors) = equipment.sensors.as_deref()
+                {
+                    print_sensors(sensors);
+                }
+                if let Some(estimator) = equipment.estimator.as_deref()
+                {
+                    print_estimate(estimator);
+                }
+                if let Some(air_data) = equipment.air_data.as_deref()
+                {
+                    print_air_data(air_data);
+                }
+            }
         }
     }
 }
-
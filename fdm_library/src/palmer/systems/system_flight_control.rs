@@ -5,18 +5,30 @@ use specs::prelude::*;
 
 //Get data needed
 use crate::palmer::fdm::structures::KeyboardState;
+use crate::scheduler::Scheduler;
 
 //Get function to call
 use crate::palmer::fdm::flight_control::flt_ctrl;
 
-//System to handle user input
+//System to handle user input, it only polls the keyboard when the input rate group is due
 pub struct FlightControl;
 impl<'a> System<'a> for FlightControl
 {
-    type SystemData = WriteStorage<'a, KeyboardState>;
+    type SystemData = (
+        Option<Read<'a, Scheduler>>,
+        WriteStorage<'a, KeyboardState>
+    );
 
-    fn run(&mut self, mut keyboardstate: Self::SystemData) 
+    fn run(&mut self, (scheduler, mut keyboardstate): Self::SystemData) 
     {
+        if let Some(scheduler) = scheduler
+        {
+            if !scheduler.input.is_due()
+            {
+                return;
+            }
+        }
+
         for mut keystate in (&mut keyboardstate).join() 
         {
             //Call flight control function to handle key presses
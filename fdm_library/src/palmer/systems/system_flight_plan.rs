@@ -0,0 +1,48 @@
+//This file contains the FlightPlanGuidance System
+
+//SPECS
+use specs::prelude::*;
+
+//Get Component data needed to perform the System operations
+use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::KeyboardState;
+use crate::palmer::fdm::structures::AutopilotState;
+use crate::flight_plan::FlightPlan;
+use crate::scheduler::Scheduler;
+use crate::estimator::Estimator;
+
+//Functions to call
+use crate::palmer::fdm::flight_plan::{follow_flight_plan, handle_flight_plan_input};
+use crate::palmer::fdm::estimator::estimated_state;
+
+//System to feed the flight plan guidance to the autopilot, it runs before the Autopilot System at the input rate
+pub struct FlightPlanGuidance;
+impl<'a> System<'a> for FlightPlanGuidance
+{
+    type SystemData = (
+        Option<Read<'a, Scheduler>>,
+        ReadStorage<'a, DataFDM>,
+        ReadStorage<'a, KeyboardState>,
+        WriteStorage<'a, FlightPlan>,
+        WriteStorage<'a, AutopilotState>,
+        ReadStorage<'a, Estimator>
+    );
+
+    fn run(&mut self, (scheduler, datafdm, keyboardstate, mut flightplan, mut autopilotstate, estimators): Self::SystemData) 
+    {
+        if let Some(scheduler) = scheduler
+        {
+            if !scheduler.input.is_due()
+            {
+                return;
+            }
+        }
+
+        for (fdm, keystate, plan, ap, estimator) in (&datafdm, &keyboardstate, &mut flightplan, &mut autopilotstate, estimators.maybe()).join() 
+        {
+            handle_flight_plan_input(plan, ap, keystate);
+            let estimated = estimated_state(fdm, estimator);
+            follow_flight_plan(estimated.as_ref().unwrap_or(fdm), plan, ap);
+        }
+    }
+}
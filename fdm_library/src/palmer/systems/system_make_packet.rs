@@ -5,26 +5,70 @@ use specs::prelude::*;
 
 //Get data needed for the System to work
 use crate::palmer::fdm::structures::DataFDM;
+use crate::palmer::fdm::structures::StateHistory;
 use crate::flightgear::FGNetFDM;
+use crate::engine::{PistonEngine, load_engine};
+use crate::jet::{JetEngine, load_jet};
+use crate::fuel::load_fuel;
+use crate::air_data::{AirDataComputer, load_air_data};
+use crate::scheduler::Scheduler;
 
-//Get function to call
-use crate::palmer::fdm::make_packet::load_fgnetfdm;
+//Get functions to call
+use crate::palmer::fdm::make_packet::{load_fgnetfdm, load_pose};
+use crate::palmer::fdm::interpolation::interpolate;
 
-//System to make packet based on fgnetfdm structure required by FlightGear 
+//System to make packet based on fgnetfdm structure required by FlightGear.
+//With a Scheduler resource it runs at the output rate and sends the pose interpolated between physics steps
 pub struct MakePacket;
 impl<'a> System<'a> for MakePacket
 {
     type SystemData = (
+        Option<Read<'a, Scheduler>>,
         ReadStorage<'a, DataFDM>,
+        ReadStorage<'a, StateHistory>,
+        ReadStorage<'a, PistonEngine>,
+        ReadStorage<'a, JetEngine>,
+        ReadStorage<'a, AirDataComputer>,
         WriteStorage<'a, FGNetFDM>,
     );
 
-    fn run(&mut self, (datafdm, mut fgnetfdm): Self::SystemData) 
+    fn run(&mut self, (scheduler, datafdm, history, engines, jets, air_data_computers, mut fgnetfdm): Self::SystemData) 
     {
-        for (fdm, mut fgnet) in (&datafdm, &mut fgnetfdm).join() 
+        if let Some(scheduler) = &scheduler
+        {
+            if !scheduler.output.is_due()
+            {
+                return;
+            }
+        }
+
+        for (fdm, history, engine, jet, air_data, mut fgnet) in (&datafdm, history.maybe(), engines.maybe(), jets.maybe(), air_data_computers.maybe(), &mut fgnetfdm).join() 
         {
             //Call function to load the updated data to the fgnetfdm structure 
             load_fgnetfdm(fdm, &mut fgnet);
+            if let Some(engine) = engine
+            {
+                load_engine(engine, fgnet);
+            }
+            if let Some(jet) = jet
+            {
+                load_jet(jet, fgnet);
+            }
+            if !fdm.fuel.tanks.is_empty()
+            {
+                load_fuel(&fdm.fuel, fgnet);
+            }
+            if let Some(outputs) = air_data.and_then(|computer| computer.outputs)
+            {
+                load_air_data(&outputs, fgnet);
+            }
+
+            //Replace the pose with the one interpolated between the last two physics steps
+            if let (Some(scheduler), Some(history)) = (&scheduler, history)
+            {
+                let pose = interpolate(&history.previous, &history.current, scheduler.physics.alpha());
+                load_pose(&pose, fgnet);
+            }
         }
     }
 }
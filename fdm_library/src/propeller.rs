@@ -0,0 +1,288 @@
+//To run unit tests for the propeller module from the command line:
+//cargo test --lib propeller
+
+//Propeller shared by both models. The thrust and power coefficients are tables over the advance ratio J = V / (n D),
+//giving the thrust T = Ct rho n^2 D^4 and the power P = Cp rho n^3 D^5 it absorbs. The propeller is driven by an engine,
+//through a gear ratio, and loads it with its torque. Besides the thrust it gives the reaction torque on the airframe,
+//its spin for the gyroscopic moment and the P-factor shift of the thrust line. Each model's propeller module turns these
+//into forces and moments in its own axes. SI units: m, N, N m, W and kg m^2, the speed is in RPM
+
+//SPECS
+use specs::prelude::*;
+
+//nalgebra vector type
+use nalgebra::Vector3;
+
+//Data of a propeller, set per airplane
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropellerData
+{
+    pub diameter: f64, //m
+    pub advance_ratio: Vec<f64>, //table of J, increasing
+    pub thrust_coefficient: Vec<f64>, //Ct at each J
+    pub power_coefficient: Vec<f64>, //Cp at each J
+    pub gear_ratio: f64, //propeller RPM over engine RPM
+    pub inertia: f64, //kg m^2, propeller alone, for the gyroscopic moment
+    pub clockwise: bool, //turning clockwise seen from the cockpit, the usual way for a light airplane
+    pub p_factor: f64, //shift of the thrust line as a fraction of the radius, per unit of the inflow across the disc over the airspeed
+    pub position: Vector3<f64>, //hub in the design coordinates and length units of the airframe
+}
+
+impl PropellerData
+{
+    //Two-bladed fixed-pitch propeller of a diameter (m), directly driven, with the coefficients of a typical light airplane propeller
+    pub fn fixed_pitch(diameter: f64, position: Vector3<f64>) -> PropellerData
+    {
+        PropellerData
+        {
+            diameter,
+            advance_ratio: vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.1, 1.2],
+            thrust_coefficient: vec![0.0776, 0.0744, 0.0712, 0.0655, 0.0588, 0.0518, 0.0419, 0.0318, 0.0216, 0.0101, -0.0014, -0.0138, -0.0262],
+            power_coefficient: vec![0.0552, 0.0553, 0.0557, 0.0554, 0.0537, 0.0505, 0.0451, 0.0378, 0.0290, 0.0178, 0.0063, -0.0046, -0.0151],
+            gear_ratio: 1.0,
+            inertia: 2.0,
+            clockwise: true,
+            p_factor: 0.3,
+            position,
+        }
+    }
+
+    //Thrust and power coefficients at an advance ratio, held at the ends of the tables
+    pub fn coefficients(&self, advance_ratio: f64) -> (f64, f64)
+    {
+        (lookup(&self.advance_ratio, &self.thrust_coefficient, advance_ratio), lookup(&self.advance_ratio, &self.power_coefficient, advance_ratio))
+    }
+}
+
+//Linear interpolation in a table, held at its ends
+pub(crate) fn lookup(x: &[f64], y: &[f64], value: f64) -> f64
+{
+    if value <= x[0]
+    {
+        return y[0];
+    }
+    for i in 1..x.len()
+    {
+        if value < x[i]
+        {
+            return y[i - 1] + (value - x[i - 1]) * (y[i] - y[i - 1]) / (x[i] - x[i - 1]);
+        }
+    }
+    y[y.len() - 1]
+}
+
+//Propeller and its operating point: speed, advance ratio, thrust and the torque and power it absorbs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Propeller
+{
+    pub data: PropellerData,
+    pub rpm: f64,
+    pub advance_ratio: f64,
+    pub thrust: f64, //N
+    pub torque: f64, //N m, absorbed at the propeller shaft
+    pub power: f64, //W
+}
+
+impl Component for Propeller
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl Propeller
+{
+    //A propeller standing still
+    pub fn new(data: PropellerData) -> Propeller
+    {
+        Propeller { data, rpm: 0.0, advance_ratio: 0.0, thrust: 0.0, torque: 0.0, power: 0.0 }
+    }
+
+    //Stop the propeller, it turns again with the engine at the next update
+    pub fn reset(&mut self)
+    {
+        *self = Propeller::new(self.data.clone());
+    }
+
+    //Turn the propeller with the engine at an engine RPM, in air coming at it along the shaft (m/s) at a density (kg/m^3)
+    pub fn update(&mut self, engine_rpm: f64, airspeed: f64, density: f64)
+    {
+        self.rpm = engine_rpm * self.data.gear_ratio;
+        let n = self.rpm / 60.0;
+        if n <= 0.0
+        {
+            self.advance_ratio = 0.0;
+            self.thrust = 0.0;
+            self.torque = 0.0;
+            self.power = 0.0;
+            return;
+        }
+
+        let diameter = self.data.diameter;
+        self.advance_ratio = airspeed.max(0.0) / (n * diameter);
+        let (ct, cp) = self.data.coefficients(self.advance_ratio);
+        self.thrust = ct * density * n * n * diameter.powi(4);
+        self.power = cp * density * n * n * n * diameter.powi(5);
+        self.torque = self.power / (2.0 * std::f64::consts::PI * n);
+    }
+
+    //Torque (N m) the propeller loads the engine shaft with, through the gears
+    pub fn engine_load(&self) -> f64
+    {
+        self.torque * self.data.gear_ratio
+    }
+
+    //Direction of the spin about the forward axis by the right-hand rule, 1 for clockwise seen from the cockpit
+    pub fn spin(&self) -> f64
+    {
+        if self.data.clockwise { 1.0 } else { -1.0 }
+    }
+
+    //Angular momentum (kg m^2/s) about the forward axis, by the right-hand rule
+    pub fn angular_momentum(&self) -> f64
+    {
+        self.spin() * self.data.inertia * self.rpm.to_radians() * 6.0
+    }
+
+    //Reaction torque (N m) on the airframe about the forward axis, by the right-hand rule. It turns the airframe against the propeller
+    pub fn reaction_torque(&self) -> f64
+    {
+        -self.spin() * self.torque
+    }
+
+    //P-factor: the blade going down into the inflow at an angle of attack takes a bigger bite, so the thrust line moves to its side.
+    //Given the inflow across the disc over the airspeed as the (up, left) components of the airplane's velocity in the disc plane,
+    //return the (up, left) shift of the thrust line from the hub, in m
+    pub fn p_factor_offset(&self, up: f64, left: f64) -> (f64, f64)
+    {
+        let shift = self.p_factor_shift();
+        (-shift * left, shift * up)
+    }
+
+    //P-factor shift of the thrust line (m) per unit of the inflow across the disc over the airspeed, signed with the spin.
+    //In vector form the thrust line moves by this times the cross product of that inflow and the shaft direction
+    pub fn p_factor_shift(&self) -> f64
+    {
+        self.spin() * self.data.p_factor * 0.5 * self.data.diameter
+    }
+}
+
+//Print the propeller state, this is called once per frame
+pub fn print_propeller(propeller: &Propeller)
+{
+    println!("Propeller RPM:    {}", propeller.rpm);
+    println!("Advance ratio:    {}", propeller.advance_ratio);
+    println!("Prop thrust (N):  {}", propeller.thrust);
+    println!("Prop torque (Nm): {}", propeller.torque);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::engine::{PistonEngine, PistonEngineData};
+    use crate::atmosphere::{Atmosphere, SEA_LEVEL_DENSITY};
+
+    //76 inch propeller on the nose
+    fn test_propeller() -> Propeller
+    {
+        Propeller::new(PropellerData::fixed_pitch(1.93, Vector3::new(20.0, 0.0, 1.5)))
+    }
+
+    #[test]
+    fn coefficients_test()
+    {
+        //Table values, interpolation between them and held at the ends
+        let data = test_propeller().data;
+        assert_eq!(data.coefficients(0.0), (0.0776, 0.0552));
+        let (ct, cp) = data.coefficients(0.65);
+        assert!((ct - 0.03685).abs() < 1e-12 && (cp - 0.04145).abs() < 1e-12);
+        assert_eq!(data.coefficients(-1.0), data.coefficients(0.0));
+        assert_eq!(data.coefficients(2.0), (-0.0262, -0.0151));
+    }
+
+    #[test]
+    fn thrust_test()
+    {
+        //Static thrust and power at 2400 RPM at sea level
+        let mut propeller = test_propeller();
+        propeller.update(2400.0, 0.0, SEA_LEVEL_DENSITY);
+        let n: f64 = 40.0;
+        assert_eq!(propeller.advance_ratio, 0.0);
+        assert!((propeller.thrust - 0.0776 * SEA_LEVEL_DENSITY * n.powi(2) * 1.93_f64.powi(4)).abs() < 1e-6);
+        assert!((propeller.power - 0.0552 * SEA_LEVEL_DENSITY * n.powi(3) * 1.93_f64.powi(5)).abs() < 1e-6);
+        assert!((propeller.torque * propeller.rpm.to_radians() * 6.0 - propeller.power).abs() < 1e-6);
+
+        //Thrust falls off with the airspeed, and a windmilling propeller drags
+        let static_thrust = propeller.thrust;
+        propeller.update(2400.0, 50.0, SEA_LEVEL_DENSITY);
+        assert!((propeller.advance_ratio - 50.0 / (40.0 * 1.93)).abs() < 1e-12);
+        assert!(propeller.thrust > 0.0 && propeller.thrust < static_thrust);
+        propeller.update(1000.0, 50.0, SEA_LEVEL_DENSITY);
+        assert!(propeller.thrust < 0.0 && propeller.torque < 0.0);
+
+        //Standing still it does nothing
+        propeller.update(0.0, 50.0, SEA_LEVEL_DENSITY);
+        assert_eq!((propeller.thrust, propeller.torque, propeller.power), (0.0, 0.0, 0.0));
+
+        //Gears turn it slower than the engine and multiply its load
+        propeller.data.gear_ratio = 0.5;
+        propeller.update(4800.0, 0.0, SEA_LEVEL_DENSITY);
+        assert_eq!(propeller.rpm, 2400.0);
+        assert!((propeller.thrust - static_thrust).abs() < 1e-9);
+        assert_eq!(propeller.engine_load(), 0.5 * propeller.torque);
+    }
+
+    #[test]
+    fn moments_test()
+    {
+        //A clockwise propeller rolls the airframe to the left, has its spin forward, and at an angle of attack
+        //(the airplane moving down through the disc) moves the thrust to the right of the hub
+        let mut propeller = test_propeller();
+        propeller.update(2400.0, 40.0, SEA_LEVEL_DENSITY);
+        assert_eq!(propeller.reaction_torque(), -propeller.torque);
+        assert!((propeller.angular_momentum() - 2.0 * 80.0 * std::f64::consts::PI).abs() < 1e-9);
+        let (up, left) = propeller.p_factor_offset(-0.1, 0.0);
+        assert_eq!(up, 0.0);
+        assert!((left + 0.3 * 0.965 * 0.1).abs() < 1e-12);
+
+        //Sliding to the left, the thrust moves down
+        let (up, left) = propeller.p_factor_offset(0.0, 0.1);
+        assert!(up < 0.0 && left == 0.0);
+
+        //A counter-clockwise propeller mirrors all of them
+        let mut mirrored = propeller.clone();
+        mirrored.data.clockwise = false;
+        assert_eq!(mirrored.reaction_torque(), -propeller.reaction_torque());
+        assert_eq!(mirrored.angular_momentum(), -propeller.angular_momentum());
+        assert_eq!(mirrored.p_factor_offset(-0.1, 0.05), (-propeller.p_factor_offset(-0.1, 0.05).0, -propeller.p_factor_offset(-0.1, 0.05).1));
+    }
+
+    #[test]
+    fn engine_coupling_test()
+    {
+        //A 180 hp engine at full throttle turning the propeller: it settles where the propeller absorbs its power,
+        //below the rated RPM standing still and faster once flying
+        let sea_level = Atmosphere::standard(0.0);
+        let mut engine = PistonEngine::new(PistonEngineData::new(134000.0, 2700.0, 0.0059));
+        let mut propeller = test_propeller();
+        engine.start();
+        let mut settle = |engine: &mut PistonEngine, airspeed: f64|
+        {
+            for _ in 0..240 * 20
+            {
+                propeller.update(engine.rpm, airspeed, sea_level.density);
+                engine.update(1.0, &sea_level, propeller.engine_load(), 1.0 / 240.0);
+            }
+            propeller.update(engine.rpm, airspeed, sea_level.density);
+            (engine.rpm, propeller.thrust, (engine.torque - propeller.engine_load()).abs())
+        };
+
+        let (static_rpm, static_thrust, unbalance) = settle(&mut engine, 0.0);
+        assert!(static_rpm > 2100.0 && static_rpm < 2600.0, "{}", static_rpm);
+        assert!(static_thrust > 1500.0, "{}", static_thrust);
+        assert!(unbalance < 0.1);
+
+        let (cruise_rpm, cruise_thrust, _) = settle(&mut engine, 50.0);
+        assert!(cruise_rpm > static_rpm);
+        assert!(cruise_thrust < static_thrust);
+    }
+}
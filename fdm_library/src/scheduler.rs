@@ -0,0 +1,190 @@
+//To run unit tests for the scheduler module from the command line:
+//cargo test --lib scheduler
+
+//Multi-rate scheduling of the Systems. Each rate group keeps an accumulator of real elapsed time
+//and reports how many fixed steps are due this frame, so physics can be sub-stepped at a high rate
+//while input polling and packet sending run at their own rates
+
+//A group of Systems running at a fixed rate
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RateGroup
+{
+    pub rate: f64, //Hz
+    pub max_steps: u32, //most steps allowed in one frame, stops the simulation from spiralling after a stall
+    pub accumulator: f64, //seconds of real time not yet simulated
+    pub steps: u32, //steps due this frame
+}
+
+impl RateGroup
+{
+    pub fn new(rate: f64) -> RateGroup
+    {
+        RateGroup
+        {
+            rate,
+            max_steps: (rate / 10.0).ceil().max(1.0) as u32, //at most 0.1 seconds of catch-up per frame
+            accumulator: 0.0,
+            steps: 0,
+        }
+    }
+
+    //Fixed time step of the group
+    pub fn dt(&self) -> f64
+    {
+        1.0 / self.rate
+    }
+
+    //Add elapsed real time and work out how many steps are due
+    pub fn advance(&mut self, elapsed: f64) -> u32
+    {
+        self.accumulator += elapsed;
+
+        let dt = self.dt();
+        let mut steps = (self.accumulator / dt).floor() as u32;
+        if steps > self.max_steps
+        {
+            //Too far behind, drop the time that cannot be caught up
+            steps = self.max_steps;
+            self.accumulator = 0.0;
+        }
+        else
+        {
+            self.accumulator -= steps as f64 * dt;
+        }
+
+        self.steps = steps;
+        steps
+    }
+
+    pub fn is_due(&self) -> bool
+    {
+        self.steps > 0
+    }
+
+    //Fraction of a step left in the accumulator, used to interpolate between the last two states
+    pub fn alpha(&self) -> f64
+    {
+        (self.accumulator / self.dt()).clamp(0.0, 1.0)
+    }
+}
+
+//Scheduler SPECS resource with the physics, input and output rate groups.
+//When this resource is not inserted the Systems fall back to one step of DeltaTime per dispatch
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Scheduler
+{
+    pub physics: RateGroup, //EquationsOfMotion sub-steps
+    pub input: RateGroup, //FlightControl keyboard polling
+    pub output: RateGroup, //MakePacket and SendPacket
+    pub time: f64, //simulated physics time in seconds
+}
+
+impl Default for Scheduler
+{
+    fn default() -> Self
+    {
+        Scheduler::new(240.0, 30.0, 30.0)
+    }
+}
+
+impl Scheduler
+{
+    pub fn new(physics_rate: f64, input_rate: f64, output_rate: f64) -> Scheduler
+    {
+        Scheduler
+        {
+            physics: RateGroup::new(physics_rate),
+            input: RateGroup::new(input_rate),
+            output: RateGroup::new(output_rate),
+            time: 0.0,
+        }
+    }
+
+    //Called once per frame from the main loop with the real time elapsed since the last frame
+    pub fn advance(&mut self, elapsed: f64)
+    {
+        let steps = self.physics.advance(elapsed);
+        self.input.advance(elapsed);
+        self.output.advance(elapsed);
+        self.time += steps as f64 * self.physics.dt();
+    }
+}
+
+//Linear interpolation helper for the output systems
+pub fn lerp(a: f64, b: f64, alpha: f64) -> f64
+{
+    a + (b - a) * alpha
+}
+
+//Interpolate an angle in radians along the shortest way around the circle
+pub fn lerp_angle(a: f64, b: f64, alpha: f64) -> f64
+{
+    let two_pi = 2.0 * std::f64::consts::PI;
+    let mut diff = (b - a) % two_pi;
+    if diff > std::f64::consts::PI
+    {
+        diff -= two_pi;
+    }
+    else if diff < -std::f64::consts::PI
+    {
+        diff += two_pi;
+    }
+    a + diff * alpha
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn rate_group_steps_test()
+    {
+        //240 Hz physics with 30 Hz frames runs 8 steps per frame
+        let mut physics = RateGroup::new(240.0);
+        assert_eq!(physics.advance(1.0 / 30.0), 8);
+        assert!(physics.alpha() < 1.0e-9);
+    }
+
+    #[test]
+    fn rate_group_accumulator_test()
+    {
+        //30 Hz input with 60 Hz frames is due every other frame
+        let mut input = RateGroup::new(30.0);
+        assert_eq!(input.advance(1.0 / 60.0), 0);
+        assert!(!input.is_due());
+        assert!((input.alpha() - 0.5).abs() < 1.0e-9);
+        assert_eq!(input.advance(1.0 / 60.0), 1);
+        assert!(input.is_due());
+    }
+
+    #[test]
+    fn rate_group_max_steps_test()
+    {
+        //A long stall is clamped instead of running hundreds of steps
+        let mut physics = RateGroup::new(240.0);
+        assert_eq!(physics.advance(5.0), physics.max_steps);
+        assert_eq!(physics.accumulator, 0.0);
+    }
+
+    #[test]
+    fn scheduler_time_test()
+    {
+        let mut scheduler = Scheduler::new(200.0, 50.0, 25.0);
+        for _ in 0..100
+        {
+            scheduler.advance(0.01);
+        }
+        assert!((scheduler.time - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn lerp_angle_test()
+    {
+        let pi = std::f64::consts::PI;
+        let a = lerp_angle(1.9 * pi, 0.1 * pi, 0.5);
+        assert!((a - 2.0 * pi).abs() < 1.0e-9);
+        assert_eq!(lerp(1.0, 3.0, 0.25), 1.5);
+    }
+}
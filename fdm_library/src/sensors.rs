@@ -0,0 +1,602 @@
+//To run unit tests for the sensors module from the command line:
+//cargo test --lib sensors
+
+//Simulated sensor suite shared by both models, for estimation and control software that should see measurements
+//rather than the truth state. Each model's sensors module finds the truth the sensors measure from its DataFDM, and
+//the Sensors Component samples it each physics step, every sensor at its own rate:
+//an IMU of accelerometers and gyros with bias, bias drift, scale factor, noise and saturation, giving the mean specific
+//force and angular rate since its last sample as an IMU integrating them does, a GPS whose fixes are
+//quantised and arrive after a latency, pitot-static air data giving airspeed and baro altitude, and a magnetometer.
+//The errors come from a random generator seeded in the data, so runs with the same seed are repeatable.
+//Aerospace axes and SI units: the body axes are x forward, y right, z down, velocities are NED, m, m/s, rad, Pa,
+//and the magnetic field is in microtesla
+
+//SPECS
+use specs::prelude::*;
+
+//nalgebra vector and rotation
+use nalgebra::{Rotation3, Vector3};
+
+//Seeded random generator and the normal distribution of the noise
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, StandardNormal};
+
+//GPS fixes waiting out their latency
+use std::collections::VecDeque;
+
+//Standard atmosphere for the pitot-static system
+use crate::atmosphere::{Atmosphere, SEA_LEVEL_PRESSURE, HEAT_CAPACITY_RATIO, pressure_altitude};
+
+//Geodetic position of the GPS
+use crate::navigation::{Geodetic, radii_of_curvature};
+
+//Truth the sensors measure, found from the model's DataFDM
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SensorTruth
+{
+    pub specific_force: Vector3<f64>, //m/s^2 in body axes, the non-gravitational acceleration an accelerometer feels
+    pub angular_rate: Vector3<f64>, //rad/s in body axes
+    pub attitude: Vector3<f64>, //rad, x = roll, y = pitch, z = heading clockwise from north
+    pub position: Geodetic,
+    pub velocity_ned: Vector3<f64>, //m/s
+    pub airspeed: f64, //true airspeed, m/s
+}
+
+//Errors of a three axis inertial sensor, the same on each axis. Standard deviations, in m/s^2 for accelerometers
+//and rad/s for gyros
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InertialSensorData
+{
+    pub noise: f64, //of each sample
+    pub bias: f64, //of the bias at turn-on
+    pub bias_walk: f64, //of the bias drift after one second, it grows with the square root of time
+    pub scale_factor: f64, //of the scale factor error, as a fraction
+    pub range: f64, //the output saturates at +/- range
+}
+
+//IMU of accelerometers and gyros sampled together
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImuData
+{
+    pub rate: f64, //Hz, 0 turns the sensor off
+    pub accelerometer: InertialSensorData,
+    pub gyro: InertialSensorData,
+}
+
+//GPS receiver. The noise is a standard deviation, the resolutions are the steps the fix is quantised to
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GpsData
+{
+    pub rate: f64, //Hz
+    pub latency: f64, //s from the measurement to the fix being output
+    pub position_noise: f64, //m, horizontal
+    pub altitude_noise: f64, //m
+    pub velocity_noise: f64, //m/s
+    pub position_resolution: f64, //deg of latitude and longitude
+    pub altitude_resolution: f64, //m
+    pub velocity_resolution: f64, //m/s
+}
+
+//Pitot-static system with its pressure transducers. Standard deviations in Pa
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PitotStaticData
+{
+    pub rate: f64, //Hz
+    pub static_noise: f64,
+    pub static_bias: f64, //at turn-on
+    pub impact_noise: f64, //of the pitot minus static pressure
+}
+
+//Magnetometer. Standard deviations in microtesla
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MagnetometerData
+{
+    pub rate: f64, //Hz
+    pub noise: f64,
+    pub bias: f64, //of the hard iron bias of the airplane
+    pub field: Vector3<f64>, //earth field in NED, set for the area flown
+}
+
+//Data of a sensor suite, set per airplane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SensorSuiteData
+{
+    pub seed: u64, //of the random generator, the same seed gives the same errors
+    pub imu: ImuData,
+    pub gps: GpsData,
+    pub pitot_static: PitotStaticData,
+    pub magnetometer: MagnetometerData,
+}
+
+impl Default for SensorSuiteData
+{
+    //MEMS sensors and a GPS receiver typical of a small autopilot, and a mid-latitude magnetic field
+    fn default() -> SensorSuiteData
+    {
+        SensorSuiteData
+        {
+            seed: 0,
+            imu: ImuData
+            {
+                rate: 100.0,
+                accelerometer: InertialSensorData { noise: 0.03, bias: 0.05, bias_walk: 0.001, scale_factor: 0.002, range: 16.0 * 9.80665 },
+                gyro: InertialSensorData { noise: 0.002, bias: 0.005, bias_walk: 0.0001, scale_factor: 0.002, range: 2000.0_f64.to_radians() },
+            },
+            gps: GpsData
+            {
+                rate: 5.0,
+                latency: 0.2,
+                position_noise: 1.5,
+                altitude_noise: 3.0,
+                velocity_noise: 0.1,
+                position_resolution: 1e-7,
+                altitude_resolution: 0.01,
+                velocity_resolution: 0.01,
+            },
+            pitot_static: PitotStaticData { rate: 50.0, static_noise: 2.0, static_bias: 10.0, impact_noise: 1.0 },
+            magnetometer: MagnetometerData { rate: 50.0, noise: 0.3, bias: 1.0, field: Vector3::new(20.0, 0.0, 45.0) },
+        }
+    }
+}
+
+//Sample of the IMU
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ImuReading
+{
+    pub time: f64, //s
+    pub specific_force: Vector3<f64>, //m/s^2
+    pub angular_rate: Vector3<f64>, //rad/s
+}
+
+//GPS fix. The time is when it was measured, it is output a latency later
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GpsFix
+{
+    pub time: f64, //s
+    pub position: Geodetic,
+    pub velocity_ned: Vector3<f64>, //m/s
+}
+
+//Sample of the air data
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct AirDataReading
+{
+    pub time: f64, //s
+    pub static_pressure: f64, //Pa
+    pub impact_pressure: f64, //Pa, pitot minus static
+    pub airspeed: f64, //calibrated airspeed, m/s
+    pub altitude: f64, //pressure altitude, m
+}
+
+//Sample of the magnetometer
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct MagnetometerReading
+{
+    pub time: f64, //s
+    pub field: Vector3<f64>, //microtesla in body axes
+}
+
+//Errors of a three axis inertial sensor drawn at turn-on, the bias drifts from there
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+struct InertialErrors
+{
+    bias: Vector3<f64>,
+    scale_factor: Vector3<f64>,
+}
+
+impl InertialErrors
+{
+    fn new(data: &InertialSensorData, rng: &mut ChaCha8Rng) -> InertialErrors
+    {
+        InertialErrors { bias: normal_vector(rng, data.bias), scale_factor: normal_vector(rng, data.scale_factor) }
+    }
+
+    //Measure a truth over a sample period (s), with the drift of the bias since the last sample
+    fn measure(&mut self, data: &InertialSensorData, truth: &Vector3<f64>, period: f64, rng: &mut ChaCha8Rng) -> Vector3<f64>
+    {
+        self.bias += normal_vector(rng, data.bias_walk * period.sqrt());
+        let measured = truth + truth.component_mul(&self.scale_factor) + self.bias + normal_vector(rng, data.noise);
+        measured.map(|x| x.clamp(-data.range, data.range))
+    }
+}
+
+//Sensor suite holding the latest output of each sensor, with the errors and timers that carry over between samples
+#[derive(Debug, Clone)]
+pub struct Sensors
+{
+    pub data: SensorSuiteData,
+    pub time: f64, //s since the sensors were turned on
+    pub imu: Option<ImuReading>,
+    pub gps: Option<GpsFix>,
+    pub air_data: Option<AirDataReading>,
+    pub magnetometer: Option<MagnetometerReading>,
+    rng: ChaCha8Rng,
+    accelerometer_errors: InertialErrors,
+    gyro_errors: InertialErrors,
+    static_bias: f64,
+    magnetometer_bias: Vector3<f64>,
+    timers: [f64; 4], //s since the last sample of the IMU, GPS, air data and magnetometer
+    imu_sums: (Vector3<f64>, Vector3<f64>, f64), //specific force and angular rate integrated since the last IMU sample, and the time
+    gps_pending: VecDeque<GpsFix>, //fixes measured and waiting out the latency
+}
+
+impl Component for Sensors
+{
+    type Storage = VecStorage<Self>;
+}
+
+impl Sensors
+{
+    //Turn the sensors on, drawing their turn-on errors from the seeded generator. There is no output until each sensor samples
+    pub fn new(data: SensorSuiteData) -> Sensors
+    {
+        let mut rng = ChaCha8Rng::seed_from_u64(data.seed);
+        let accelerometer_errors = InertialErrors::new(&data.imu.accelerometer, &mut rng);
+        let gyro_errors = InertialErrors::new(&data.imu.gyro, &mut rng);
+        let static_bias = normal(&mut rng, data.pitot_static.static_bias);
+        let magnetometer_bias = normal_vector(&mut rng, data.magnetometer.bias);
+
+        Sensors
+        {
+            data,
+            time: 0.0,
+            imu: None,
+            gps: None,
+            air_data: None,
+            magnetometer: None,
+            rng,
+            accelerometer_errors,
+            gyro_errors,
+            static_bias,
+            magnetometer_bias,
+            timers: [0.0; 4],
+            imu_sums: (Vector3::zeros(), Vector3::zeros(), 0.0),
+            gps_pending: VecDeque::new(),
+        }
+    }
+
+    //Turn the sensors off and on again when the airplane is put somewhere else, so no sample, fix waiting out its
+    //latency or IMU sum carries over from where it was. The turn-on errors are drawn again from the seed
+    pub fn reset(&mut self)
+    {
+        *self = Sensors::new(self.data);
+    }
+
+    //Advance the sensors dt seconds to the truth at the end of the step. Each sensor that is due samples it, the IMU
+    //the mean of the truths since its last sample, and the GPS outputs the fixes whose latency has passed
+    pub fn update(&mut self, truth: &SensorTruth, dt: f64)
+    {
+        self.time += dt;
+
+        let imu = self.data.imu;
+        self.imu_sums.0 += truth.specific_force * dt;
+        self.imu_sums.1 += truth.angular_rate * dt;
+        self.imu_sums.2 += dt;
+        if sample_due(&mut self.timers[0], imu.rate, dt)
+        {
+            let period = 1.0 / imu.rate;
+            let (specific_force, angular_rate, time) = self.imu_sums;
+            self.imu_sums = (Vector3::zeros(), Vector3::zeros(), 0.0);
+            self.imu = Some(ImuReading
+            {
+                time: self.time,
+                specific_force: self.accelerometer_errors.measure(&imu.accelerometer, &(specific_force / time), period, &mut self.rng),
+                angular_rate: self.gyro_errors.measure(&imu.gyro, &(angular_rate / time), period, &mut self.rng),
+            });
+        }
+
+        let gps = self.data.gps;
+        if sample_due(&mut self.timers[1], gps.rate, dt)
+        {
+            let fix = self.measure_gps(truth);
+            self.gps_pending.push_back(fix);
+        }
+        while self.gps_pending.front().is_some_and(|fix| fix.time + gps.latency <= self.time + 1e-9)
+        {
+            self.gps = self.gps_pending.pop_front();
+        }
+
+        if sample_due(&mut self.timers[2], self.data.pitot_static.rate, dt)
+        {
+            self.air_data = Some(self.measure_air_data(truth));
+        }
+
+        let magnetometer = self.data.magnetometer;
+        if sample_due(&mut self.timers[3], magnetometer.rate, dt)
+        {
+            let field = ned_to_body(&truth.attitude, &magnetometer.field) + self.magnetometer_bias + normal_vector(&mut self.rng, magnetometer.noise);
+            self.magnetometer = Some(MagnetometerReading { time: self.time, field });
+        }
+    }
+
+    //GPS fix of the truth with noise, quantised to the receiver's resolutions
+    fn measure_gps(&mut self, truth: &SensorTruth) -> GpsFix
+    {
+        let gps = self.data.gps;
+        let (r_meridian, r_normal) = radii_of_curvature(truth.position.latitude);
+
+        let north = normal(&mut self.rng, gps.position_noise);
+        let east = normal(&mut self.rng, gps.position_noise);
+        let latitude = truth.position.latitude + north / (r_meridian + truth.position.altitude);
+        let longitude = truth.position.longitude + east / ((r_normal + truth.position.altitude) * latitude.cos());
+        let altitude = truth.position.altitude + normal(&mut self.rng, gps.altitude_noise);
+        let velocity_ned = truth.velocity_ned + normal_vector(&mut self.rng, gps.velocity_noise);
+
+        GpsFix
+        {
+            time: self.time,
+            position: Geodetic::new(quantise(latitude.to_degrees(), gps.position_resolution).to_radians(),
+                                    quantise(longitude.to_degrees(), gps.position_resolution).to_radians(),
+                                    quantise(altitude, gps.altitude_resolution)),
+            velocity_ned: velocity_ned.map(|v| quantise(v, gps.velocity_resolution)),
+        }
+    }
+
+    //Static and impact pressures of the truth with the transducer errors, and the airspeed and altitude they give
+    fn measure_air_data(&mut self, truth: &SensorTruth) -> AirDataReading
+    {
+        let pitot_static = self.data.pitot_static;
+        let air = Atmosphere::standard(truth.position.altitude);
+        let static_pressure = air.pressure + self.static_bias + normal(&mut self.rng, pitot_static.static_noise);
+        let impact_pressure = (impact_pressure(&air, truth.airspeed) + normal(&mut self.rng, pitot_static.impact_noise)).max(0.0);
+
+        AirDataReading
+        {
+            time: self.time,
+            static_pressure,
+            impact_pressure,
+            airspeed: calibrated_airspeed(impact_pressure),
+            altitude: pressure_altitude(static_pressure),
+        }
+    }
+}
+
+//Whether a sensor at a rate (Hz) samples this step of dt seconds, counting its timer on. A rate of 0 never samples
+fn sample_due(timer: &mut f64, rate: f64, dt: f64) -> bool
+{
+    if rate <= 0.0
+    {
+        return false;
+    }
+
+    let period = 1.0 / rate;
+    *timer += dt;
+    if *timer + 1e-9 < period
+    {
+        return false;
+    }
+
+    //A step longer than the period samples once
+    *timer = (*timer - period).max(0.0) % period;
+    true
+}
+
+//Normally distributed sample with a standard deviation
+fn normal(rng: &mut ChaCha8Rng, sigma: f64) -> f64
+{
+    let sample: f64 = StandardNormal.sample(rng);
+    sigma * sample
+}
+
+fn normal_vector(rng: &mut ChaCha8Rng, sigma: f64) -> Vector3<f64>
+{
+    Vector3::new(normal(rng, sigma), normal(rng, sigma), normal(rng, sigma))
+}
+
+//Round a value to the nearest step of a resolution, a resolution of 0 leaves it alone
+fn quantise(value: f64, resolution: f64) -> f64
+{
+    if resolution > 0.0
+    {
+        (value / resolution).round() * resolution
+    }
+    else
+    {
+        value
+    }
+}
+
+//Pitot minus static pressure (Pa) at a true airspeed (m/s) in subsonic compressible flow
+pub fn impact_pressure(air: &Atmosphere, airspeed: f64) -> f64
+{
+    let mach = airspeed / air.speed_of_sound();
+    let exponent = HEAT_CAPACITY_RATIO / (HEAT_CAPACITY_RATIO - 1.0);
+    air.pressure * ((1.0 + 0.5 * (HEAT_CAPACITY_RATIO - 1.0) * mach * mach).powf(exponent) - 1.0)
+}
+
+//Calibrated airspeed (m/s) of an impact pressure (Pa), the true airspeed that gives it at sea level
+pub fn calibrated_airspeed(impact_pressure: f64) -> f64
+{
+    let sea_level = Atmosphere::standard(0.0);
+    let exponent = (HEAT_CAPACITY_RATIO - 1.0) / HEAT_CAPACITY_RATIO;
+    sea_level.speed_of_sound() * (2.0 / (HEAT_CAPACITY_RATIO - 1.0) * ((impact_pressure / SEA_LEVEL_PRESSURE + 1.0).powf(exponent) - 1.0)).sqrt()
+}
+
+//NED ---> body axes at an attitude (roll, pitch, heading), with the yaw-pitch-roll sequence
+pub fn ned_to_body(attitude: &Vector3<f64>, v_ned: &Vector3<f64>) -> Vector3<f64>
+{
+    Rotation3::from_euler_angles(attitude.x, attitude.y, attitude.z).inverse() * v_ned
+}
+
+//Body angular rates of the rates of change of the attitude (roll, pitch, heading)
+pub fn euler_rates_to_body(attitude: &Vector3<f64>, euler_rates: &Vector3<f64>) -> Vector3<f64>
+{
+    let (sin_roll, cos_roll) = attitude.x.sin_cos();
+    let (sin_pitch, cos_pitch) = attitude.y.sin_cos();
+    Vector3::new(euler_rates.x - euler_rates.z * sin_pitch,
+                 euler_rates.y * cos_roll + euler_rates.z * sin_roll * cos_pitch,
+                 -euler_rates.y * sin_roll + euler_rates.z * cos_roll * cos_pitch)
+}
+
+//Print the latest output of each sensor, this is called once per frame
+pub fn print_sensors(sensors: &Sensors)
+{
+    if let Some(imu) = sensors.imu
+    {
+        println!("IMU accel (m/s^2):  {:?}", imu.specific_force.as_slice());
+        println!("IMU gyro (rad/s):   {:?}", imu.angular_rate.as_slice());
+    }
+    if let Some(gps) = sensors.gps
+    {
+        println!("GPS lat/lon (deg):  {} {}", gps.position.latitude_deg(), gps.position.longitude_deg());
+        println!("GPS altitude (m):   {}", gps.position.altitude);
+    }
+    if let Some(air_data) = sensors.air_data
+    {
+        println!("CAS (m/s):          {}", air_data.airspeed);
+        println!("Baro altitude (m):  {}", air_data.altitude);
+    }
+    if let Some(magnetometer) = sensors.magnetometer
+    {
+        println!("Mag field (uT):     {:?}", magnetometer.field.as_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    //Level flight due north at 50 m/s and 1000 m
+    fn test_truth() -> SensorTruth
+    {
+        SensorTruth
+        {
+            specific_force: Vector3::new(0.0, 0.0, -9.80665),
+            angular_rate: Vector3::new(0.0, 0.01, 0.0),
+            attitude: Vector3::new(0.0, 0.0, 0.0),
+            position: Geodetic::from_degrees(39.8, -84.0, 1000.0),
+            velocity_ned: Vector3::new(50.0, 0.0, 0.0),
+            airspeed: 50.0,
+        }
+    }
+
+    //Run the sensors for a time at 240 Hz and collect the IMU samples
+    fn run(sensors: &mut Sensors, truth: &SensorTruth, time: f64) -> Vec<ImuReading>
+    {
+        let mut samples = Vec::new();
+        for _ in 0..(time * 240.0).round() as usize
+        {
+            let last = sensors.imu;
+            sensors.update(truth, 1.0 / 240.0);
+            if sensors.imu != last
+            {
+                samples.push(sensors.imu.unwrap());
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn repeatability_test()
+    {
+        //The same seed gives the same outputs, another seed other ones
+        let truth = test_truth();
+        let mut first = Sensors::new(SensorSuiteData::default());
+        let mut second = Sensors::new(SensorSuiteData::default());
+        let mut other = Sensors::new(SensorSuiteData { seed: 7, ..SensorSuiteData::default() });
+        assert_eq!(run(&mut first, &truth, 2.0), run(&mut second, &truth, 2.0));
+        assert_eq!((first.gps, first.air_data, first.magnetometer), (second.gps, second.air_data, second.magnetometer));
+        run(&mut other, &truth, 2.0);
+        assert_ne!(first.imu.unwrap().specific_force, other.imu.unwrap().specific_force);
+    }
+
+    #[test]
+    fn imu_test()
+    {
+        //100 samples a second, averaging to the truth within the errors
+        let truth = test_truth();
+        let mut sensors = Sensors::new(SensorSuiteData::default());
+        let samples = run(&mut sensors, &truth, 10.0);
+        assert!((samples.len() as i64 - 1000).abs() <= 1, "{}", samples.len());
+        let mean = samples.iter().fold(Vector3::zeros(), |sum, sample| sum + sample.specific_force) / samples.len() as f64;
+        assert!((mean - truth.specific_force).norm() < 0.3, "{:?}", mean);
+        assert!(samples.iter().any(|sample| sample.specific_force != samples[0].specific_force));
+
+        //Without errors the IMU is the truth, and it saturates at its range
+        let mut data = SensorSuiteData::default();
+        data.imu.accelerometer = InertialSensorData { noise: 0.0, bias: 0.0, bias_walk: 0.0, scale_factor: 0.0, range: 20.0 };
+        data.imu.gyro = data.imu.accelerometer;
+        let mut sensors = Sensors::new(data);
+        let hard_landing = SensorTruth { specific_force: Vector3::new(0.0, 0.0, -40.0), ..truth };
+        run(&mut sensors, &hard_landing, 0.1);
+        let imu = sensors.imu.unwrap();
+        assert_eq!(imu.specific_force, Vector3::new(0.0, 0.0, -20.0));
+        assert_eq!(imu.angular_rate, truth.angular_rate);
+    }
+
+    #[test]
+    fn gps_test()
+    {
+        //Without noise the fix is the truth quantised, measured a latency before it is output
+        let gps = GpsData { rate: 5.0, latency: 0.2, position_noise: 0.0, altitude_noise: 0.0, velocity_noise: 0.0,
+                            position_resolution: 1e-5, altitude_resolution: 1.0, velocity_resolution: 0.5 };
+        let mut sensors = Sensors::new(SensorSuiteData { gps, ..SensorSuiteData::default() });
+        let mut truth = test_truth();
+        truth.position.altitude = 1000.3;
+        truth.velocity_ned = Vector3::new(50.2, 0.3, -0.1);
+
+        run(&mut sensors, &truth, 0.3);
+        assert_eq!(sensors.gps, None);
+        run(&mut sensors, &truth, 0.11);
+        let fix = sensors.gps.unwrap();
+        assert!((fix.time - 0.2).abs() < 1e-9);
+        assert!((fix.position.latitude_deg() - 39.8).abs() < 1e-9);
+        assert_eq!(fix.position.altitude, 1000.0);
+        assert_eq!(fix.velocity_ned, Vector3::new(50.0, 0.5, -0.0));
+
+        //With noise the fix scatters by a few meters around the truth
+        let mut sensors = Sensors::new(SensorSuiteData::default());
+        run(&mut sensors, &truth, 1.0);
+        let fix = sensors.gps.unwrap();
+        assert!(crate::navigation::distance(&fix.position, &truth.position) < 10.0);
+        assert_ne!(fix.position, truth.position);
+    }
+
+    #[test]
+    fn air_data_test()
+    {
+        //At sea level the calibrated airspeed is the true airspeed, higher up it reads low.
+        //Without errors the baro altitude is the altitude
+        let pitot_static = PitotStaticData { rate: 50.0, static_noise: 0.0, static_bias: 0.0, impact_noise: 0.0 };
+        let mut sensors = Sensors::new(SensorSuiteData { pitot_static, ..SensorSuiteData::default() });
+        let sea_level = SensorTruth { position: Geodetic::from_degrees(39.8, -84.0, 0.0), ..test_truth() };
+        run(&mut sensors, &sea_level, 0.1);
+        assert!((sensors.air_data.unwrap().airspeed - 50.0).abs() < 1e-6);
+
+        run(&mut sensors, &test_truth(), 0.1);
+        let air_data = sensors.air_data.unwrap();
+        assert!((air_data.altitude - 1000.0).abs() < 1e-6);
+        assert!((air_data.airspeed - 50.0 * (Atmosphere::standard(1000.0).density / Atmosphere::standard(0.0).density).sqrt()).abs() < 0.1);
+
+        //The errors move the altitude by meters
+        let mut sensors = Sensors::new(SensorSuiteData::default());
+        run(&mut sensors, &test_truth(), 0.1);
+        let air_data = sensors.air_data.unwrap();
+        assert!((air_data.altitude - 1000.0).abs() < 10.0 && air_data.altitude != 1000.0);
+    }
+
+    #[test]
+    fn magnetometer_test()
+    {
+        //Heading east the north component of the field lies along the left wing
+        let mut data = SensorSuiteData::default();
+        data.magnetometer.noise = 0.0;
+        data.magnetometer.bias = 0.0;
+        let mut sensors = Sensors::new(data);
+        let truth = SensorTruth { attitude: Vector3::new(0.0, 0.0, 90.0_f64.to_radians()), ..test_truth() };
+        run(&mut sensors, &truth, 0.1);
+        assert!((sensors.magnetometer.unwrap().field - Vector3::new(0.0, -20.0, 45.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn euler_rates_test()
+    {
+        //A level turn is all yaw rate, banked it splits between pitch and yaw rate
+        let level = euler_rates_to_body(&Vector3::zeros(), &Vector3::new(0.0, 0.0, 0.2));
+        assert!((level - Vector3::new(0.0, 0.0, 0.2)).norm() < 1e-12);
+        let banked = euler_rates_to_body(&Vector3::new(30.0_f64.to_radians(), 0.0, 0.0), &Vector3::new(0.0, 0.0, 0.2));
+        assert!((banked - Vector3::new(0.0, 0.1, 0.2 * 30.0_f64.to_radians().cos())).norm() < 1e-12);
+    }
+}
@@ -0,0 +1,84 @@
+//To run unit tests for the stall module from the command line:
+//cargo test --lib stall
+
+//Stall warning shared by both models. Each model finds the stall margin of its wing, the angle of attack (deg) left
+//before it stalls, negative once stalled. The warning rises from 0 as the margin drops below WARNING_MARGIN to 1 at
+//the stall, and is smoothed with a first-order lag like a stall warning vane, so gusts and sub-steps do not make it flicker
+
+//FGNetFDM packet structure
+use crate::flightgear::FGNetFDM;
+
+//Stall margin (deg) at which the warning starts
+pub const WARNING_MARGIN: f64 = 5.0;
+
+//Time constant (s) of the warning
+pub const WARNING_TIME_CONSTANT: f64 = 0.3;
+
+//Warning (0 - 1) at a stall margin, before smoothing
+pub fn warning_level(margin: f64) -> f64
+{
+    ((WARNING_MARGIN - margin) / WARNING_MARGIN).clamp(0.0, 1.0)
+}
+
+//Move the smoothed warning dt seconds towards the warning at a stall margin
+pub fn smooth_warning(warning: f64, margin: f64, dt: f64) -> f64
+{
+    if dt <= 0.0
+    {
+        return warning;
+    }
+    warning + (warning_level(margin) - warning) * (1.0 - (-dt / WARNING_TIME_CONSTANT).exp())
+}
+
+//Load the warning into the FlightGear packet, in network byte order
+pub fn load_stall_warning(warning: f64, fgnet: &mut FGNetFDM)
+{
+    fgnet.stall_warning = f32::from_be_bytes((warning as f32).to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn level_test()
+    {
+        //Nothing well below the stall, half way into the band, full at the stall and beyond
+        assert_eq!(warning_level(10.0), 0.0);
+        assert_eq!(warning_level(WARNING_MARGIN), 0.0);
+        assert!((warning_level(0.5 * WARNING_MARGIN) - 0.5).abs() < 1e-12);
+        assert_eq!(warning_level(0.0), 1.0);
+        assert_eq!(warning_level(-3.0), 1.0);
+    }
+
+    #[test]
+    fn smoothing_test()
+    {
+        //After one time constant at the stall the warning has made 63% of its way up
+        let mut warning = 0.0;
+        for _ in 0..(WARNING_TIME_CONSTANT * 240.0).round() as usize
+        {
+            warning = smooth_warning(warning, 0.0, 1.0 / 240.0);
+        }
+        assert!((warning - (1.0 - (-1.0_f64).exp())).abs() < 1e-3, "{}", warning);
+
+        //A single sub-step of a gust barely moves it, and it settles back to nothing
+        let gust = smooth_warning(0.0, -5.0, 1.0 / 240.0);
+        assert!(gust < 0.02);
+        for _ in 0..240 * 5
+        {
+            warning = smooth_warning(warning, 20.0, 1.0 / 240.0);
+        }
+        assert!(warning < 1e-6);
+        assert_eq!(smooth_warning(0.4, 0.0, 0.0), 0.4);
+    }
+
+    #[test]
+    fn packet_test()
+    {
+        let mut fgnet = FGNetFDM::default();
+        load_stall_warning(0.25, &mut fgnet);
+        assert_eq!(f32::from_be_bytes(fgnet.stall_warning.to_ne_bytes()), 0.25);
+    }
+}
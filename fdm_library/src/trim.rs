@@ -0,0 +1,293 @@
+//To run unit tests for the trim module from the command line:
+//cargo test --lib trim
+
+//Trim solver shared by both models. A model supplies a residual function of its trim unknowns
+//(controls and attitude) that returns the accelerations left over at the target condition,
+//and the solver drives them to zero with Newton's method and a finite difference Jacobian
+
+//Target flight condition, in the units of the model being trimmed (Bourg: ft and ft/s, Palmer: m and m/s)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrimTarget
+{
+    pub airspeed: f64,
+    pub altitude: f64,
+    pub climb_rate: f64, //vertical speed, positive up
+    pub bank: f64, //degrees, positive right wing down, gives a steady coordinated turn
+    pub heading: f64, //degrees, direction of flight from the earth x axis toward y
+}
+
+impl TrimTarget
+{
+    //Straight and level flight
+    pub fn level(airspeed: f64, altitude: f64) -> TrimTarget
+    {
+        TrimTarget { airspeed, altitude, climb_rate: 0.0, bank: 0.0, heading: 0.0 }
+    }
+
+    //Steady straight climb (or descent with a negative rate)
+    pub fn climb(airspeed: f64, altitude: f64, climb_rate: f64) -> TrimTarget
+    {
+        TrimTarget { airspeed, altitude, climb_rate, bank: 0.0, heading: 0.0 }
+    }
+
+    //Steady level coordinated turn
+    pub fn turn(airspeed: f64, altitude: f64, bank: f64) -> TrimTarget
+    {
+        TrimTarget { airspeed, altitude, climb_rate: 0.0, bank, heading: 0.0 }
+    }
+
+    pub fn with_heading(mut self, heading: f64) -> TrimTarget
+    {
+        self.heading = heading;
+        self
+    }
+
+    //Flight path angle in radians
+    pub fn climb_angle(&self) -> f64
+    {
+        if self.airspeed == 0.0
+        {
+            return 0.0;
+        }
+        (self.climb_rate / self.airspeed).clamp(-1.0, 1.0).asin()
+    }
+}
+
+//Solver settings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrimSettings
+{
+    pub tolerance: f64, //converged when the largest residual is below this, accelerations in the model's units
+    pub max_iterations: usize,
+}
+
+impl Default for TrimSettings
+{
+    fn default() -> Self
+    {
+        TrimSettings { tolerance: 1.0e-4, max_iterations: 100 }
+    }
+}
+
+//Outcome of a trim
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrimReport
+{
+    pub converged: bool,
+    pub iterations: usize,
+    pub unknowns: Vec<f64>, //solution, in the order the model defines
+    pub residuals: Vec<f64>, //remaining accelerations at the solution
+    pub residual_norm: f64, //largest absolute residual
+    pub untrimmed: Vec<f64>, //accelerations the model has no control to zero, filled in by the model's trim
+}
+
+//Largest absolute value
+fn max_abs(v: &[f64]) -> f64
+{
+    v.iter().fold(0.0, |m: f64, r| m.max(r.abs()))
+}
+
+//Newton's method for as many residuals as unknowns.
+//x: initial guess, overwritten with the solution
+//perturbation: finite difference step for each unknown, also used to limit how far one Newton step may move it
+//residual: fills r with the residuals for the unknowns x
+pub fn solve<F>(x: &mut [f64], perturbation: &[f64], settings: &TrimSettings, mut residual: F) -> TrimReport
+    where F: FnMut(&[f64], &mut [f64])
+{
+    let n = x.len();
+    let mut r = vec![0.0; n];
+    residual(x, &mut r);
+    let mut norm = max_abs(&r);
+    let mut iterations = 0;
+
+    while norm > settings.tolerance && iterations < settings.max_iterations
+    {
+        iterations += 1;
+
+        //Finite difference Jacobian, central differences
+        let mut jacobian = vec![vec![0.0; n]; n];
+        let mut r_plus = vec![0.0; n];
+        let mut r_minus = vec![0.0; n];
+        let mut xp = x.to_vec();
+        for j in 0..n
+        {
+            let h = perturbation[j];
+            xp[j] = x[j] + h;
+            residual(&xp, &mut r_plus);
+            xp[j] = x[j] - h;
+            residual(&xp, &mut r_minus);
+            xp[j] = x[j];
+            for i in 0..n
+            {
+                jacobian[i][j] = (r_plus[i] - r_minus[i]) / (2.0 * h);
+            }
+        }
+
+        //Newton step, J dx = -r
+        let minus_r: Vec<f64> = r.iter().map(|v| -v).collect();
+        let mut dx = match solve_linear(&jacobian, &minus_r)
+        {
+            Some(dx) => dx,
+            None => break, //singular Jacobian, report what we have
+        };
+
+        //Limit each unknown to move at most 1000 perturbations in one step
+        let mut scale: f64 = 1.0;
+        for j in 0..n
+        {
+            let limit = 1000.0 * perturbation[j];
+            if dx[j].abs() * scale > limit
+            {
+                scale = limit / dx[j].abs();
+            }
+        }
+        for d in dx.iter_mut()
+        {
+            *d *= scale;
+        }
+
+        //Backtracking line search on the largest residual
+        let mut step = 1.0;
+        let mut trial = x.to_vec();
+        let mut r_trial = vec![0.0; n];
+        loop
+        {
+            for j in 0..n
+            {
+                trial[j] = x[j] + step * dx[j];
+            }
+            residual(&trial, &mut r_trial);
+            let trial_norm = max_abs(&r_trial);
+            if trial_norm < norm || step < 1.0e-4
+            {
+                x.copy_from_slice(&trial);
+                r.copy_from_slice(&r_trial);
+                norm = trial_norm;
+                break;
+            }
+            step *= 0.5;
+        }
+    }
+
+    //Leave the model at the solution
+    residual(x, &mut r);
+    norm = max_abs(&r);
+
+    TrimReport
+    {
+        converged: norm <= settings.tolerance,
+        iterations,
+        unknowns: x.to_vec(),
+        residuals: r,
+        residual_norm: norm,
+        untrimmed: Vec::new(),
+    }
+}
+
+//Solve a x = b by Gaussian elimination with partial pivoting, None if a is singular or has a NaN pivot
+pub fn solve_linear(a: &[Vec<f64>], b: &[f64]) -> Option<Vec<f64>>
+{
+    let n = b.len();
+    let mut m: Vec<Vec<f64>> = a.to_vec();
+    let mut x = b.to_vec();
+
+    for col in 0..n
+    {
+        //Pivot on the largest element in the column
+        let pivot = (col..n).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))?;
+        if m[pivot][col].is_nan() || m[pivot][col].abs() < 1.0e-300
+        {
+            return None;
+        }
+        m.swap(col, pivot);
+        x.swap(col, pivot);
+
+        for row in (col + 1)..n
+        {
+            let factor = m[row][col] / m[col][col];
+            let pivot_row = m[col].clone();
+            for (a, p) in m[row].iter_mut().zip(pivot_row.iter()).skip(col)
+            {
+                *a -= factor * p;
+            }
+            x[row] -= factor * x[col];
+        }
+    }
+
+    //Back substitution
+    for row in (0..n).rev()
+    {
+        let mut sum = x[row];
+        for k in (row + 1)..n
+        {
+            sum -= m[row][k] * x[k];
+        }
+        x[row] = sum / m[row][row];
+    }
+
+    Some(x)
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn solve_linear_test()
+    {
+        let a = vec![vec![0.0, 2.0, 1.0], vec![1.0, 1.0, 0.0], vec![3.0, 0.0, 1.0]];
+        let x = solve_linear(&a, &[7.0, 3.0, 6.0]).unwrap();
+        assert!((x[0] - 1.0).abs() < 1.0e-12);
+        assert!((x[1] - 2.0).abs() < 1.0e-12);
+        assert!((x[2] - 3.0).abs() < 1.0e-12);
+
+        let singular = vec![vec![1.0, 2.0], vec![2.0, 4.0]];
+        assert_eq!(solve_linear(&singular, &[1.0, 2.0]), None);
+
+        //A Jacobian from a diverged state may hold NaNs
+        let diverged = vec![vec![1.0, 2.0], vec![f64::NAN, 4.0]];
+        assert_eq!(solve_linear(&diverged, &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn newton_test()
+    {
+        //x^2 + y^2 = 4, x = y, root at (sqrt 2, sqrt 2)
+        let mut x = vec![1.0, 0.5];
+        let report = solve(&mut x, &[1.0e-3, 1.0e-3], &TrimSettings { tolerance: 1.0e-6, max_iterations: 100 }, |x, r|
+        {
+            r[0] = x[0] * x[0] + x[1] * x[1] - 4.0;
+            r[1] = x[0] - x[1];
+        });
+
+        assert!(report.converged);
+        assert!(report.iterations < 10);
+        assert!((x[0] - 2.0_f64.sqrt()).abs() < 1.0e-6);
+        assert_eq!(report.unknowns, x);
+        assert!(report.residual_norm <= 1.0e-6);
+    }
+
+    #[test]
+    fn no_solution_test()
+    {
+        //x^2 + 1 = 0 has no real root, the solver reports it did not converge
+        let mut x = vec![3.0];
+        let report = solve(&mut x, &[1.0e-3], &TrimSettings { tolerance: 1.0e-9, max_iterations: 20 }, |x, r|
+        {
+            r[0] = x[0] * x[0] + 1.0;
+        });
+
+        assert!(!report.converged);
+        assert!(report.residual_norm >= 1.0);
+    }
+
+    #[test]
+    fn target_test()
+    {
+        let climb = TrimTarget::climb(100.0, 1000.0, 50.0);
+        assert!((climb.climb_angle() - 30.0_f64.to_radians()).abs() < 1.0e-12);
+        assert_eq!(TrimTarget::turn(100.0, 1000.0, 30.0).with_heading(90.0).heading, 90.0);
+    }
+}
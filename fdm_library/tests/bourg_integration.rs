@@ -8,11 +8,55 @@ use float_cmp::*;
 //Bring into scope the functions and data as necessary for testing
 use fdm_library::bourg::common::vector::Vector;
 use fdm_library::bourg::common::quaternion::Quaternion;
+use fdm_library::bourg::common::real::{Float, Real};
 use fdm_library::bourg::fdm::structures::DataFDM;
-use fdm_library::bourg::fdm::structures::PointMass;
+use fdm_library::bourg::fdm::structures::{PointMass, LandingGear};
 use fdm_library::bourg::fdm::calc_loads::calc_airplane_loads;
 use fdm_library::bourg::fdm::mass_properties::calc_airplane_mass_properties;
 use fdm_library::bourg::fdm::keypresses::*;
+use fdm_library::bourg::fdm::equations_of_motion::{eom, handle_input};
+use fdm_library::bourg::fdm::frames::{earth_to_ned, attitude_ned, FEET_PER_METER};
+use fdm_library::integrator::{Integrator, IntegrationMethod};
+use fdm_library::navigation::{Geodetic, geodetic_to_ecef, ecef_to_ned};
+use fdm_library::bourg::fdm::trim::{trim, turn_rate, TRIM_THRUST};
+use fdm_library::trim::{TrimTarget, TrimSettings};
+use fdm_library::bourg::fdm::trim::SurfaceControls;
+use fdm_library::bourg::fdm::linearize::{linearize, save_state, state_derivatives};
+use fdm_library::bourg::fdm::structures::{KeyboardState, AutopilotState, Actuators};
+use fdm_library::bourg::fdm::actuators::{actuate, restore_commands};
+use fdm_library::actuator::{Actuator, ActuatorDynamics};
+use fdm_library::engine::{PistonEngine, PistonEngineData};
+use fdm_library::bourg::fdm::engine::{run_engine, handle_engine_input};
+use fdm_library::propeller::{Propeller, PropellerData};
+use fdm_library::bourg::fdm::propeller::{run_propeller, NEWTONS_PER_POUND};
+use nalgebra::Vector3;
+use fdm_library::jet::{JetEngine, JetEngineData};
+use fdm_library::bourg::fdm::jet::{run_jet, handle_jet_input};
+use fdm_library::bourg::fdm::structures::{EngineMount, Powerplant};
+use fdm_library::bourg::fdm::powerplant::{run_engines, load_engines};
+use fdm_library::flightgear::FGNetFDM;
+use fdm_library::fuel::{FuelSystem, FuelTank};
+use fdm_library::bourg::fdm::fuel::{burn_fuel, KILOGRAMS_PER_SLUG};
+use fdm_library::stall::{smooth_warning, WARNING_MARGIN};
+use fdm_library::bourg::fdm::autopilot::{autopilot, autopilot_on, handle_autopilot_input};
+use fdm_library::autopilot::{VerticalMode, LateralMode, SpeedMode};
+use fdm_library::bourg::fdm::flight_plan::{follow_flight_plan, handle_flight_plan_input};
+use fdm_library::flight_plan::{FlightPlan, Waypoint};
+use fdm_library::bourg::fdm::approach::{fly_approach, handle_approach_input, wheel_height};
+use fdm_library::approach::{Approach, ApproachPhase, Runway};
+use fdm_library::navigation::destination;
+use fdm_library::sensors::{Sensors, SensorSuiteData, ImuData};
+use fdm_library::estimator::{Estimator, EstimatorData};
+use fdm_library::bourg::fdm::estimator::estimated_state;
+use fdm_library::bourg::fdm::sensors::sensor_truth;
+use fdm_library::air_data::{AirDataComputer, AirDataComputerData, load_air_data, METERS_PER_SECOND_PER_KNOT};
+use fdm_library::bourg::fdm::air_data::run_air_data;
+use fdm_library::atmosphere::Atmosphere;
+use fdm_library::envelope::{EnvelopeMonitor, EnvelopeLimits, EnvelopeEvent, Limit, PROTECTION_MARGIN};
+use fdm_library::bourg::fdm::envelope::{envelope_state, protect, restore_pitch_command};
+use fdm_library::crash::{CrashMonitor, CrashLimits, CrashCause, CrashEvent, SpawnedEngines};
+use fdm_library::bourg::fdm::crash::{ground_contact, reset_airplane};
+use fdm_library::bourg::fdm::structures::{InitialConditions, StateHistory};
 
 #[test]
 fn fdm_test() 
@@ -217,13 +261,13 @@ fn fdm_test()
     println!("C++ Benchmark         : {:?}", benchmark_data);
    
     //7 Variables to check for each of the 6 tests
-    let cmp1 = approx_eq!(f32, flight_test_data[0], benchmark_data[0], epsilon = 0.01); //Pos x
-    let cmp2 = approx_eq!(f32, flight_test_data[1], benchmark_data[1], epsilon = 0.01); //Pos y
-    let cmp3 = approx_eq!(f32, flight_test_data[2], benchmark_data[2], epsilon = 0.01); //Pos z
-    let cmp4 = approx_eq!(f32, flight_test_data[3], benchmark_data[3], epsilon = 0.01); //Roll
-    let cmp5 = approx_eq!(f32, flight_test_data[4], benchmark_data[4], epsilon = 0.01); //Pitch
-    let cmp6 = approx_eq!(f32, flight_test_data[5], benchmark_data[5], epsilon = 0.01); //Yaw
-    let cmp7 = approx_eq!(f32, flight_test_data[6], benchmark_data[6], epsilon = 0.01); //Airspeed
+    let cmp1 = approx_eq!(Float, flight_test_data[0], benchmark_data[0], epsilon = 0.01); //Pos x
+    let cmp2 = approx_eq!(Float, flight_test_data[1], benchmark_data[1], epsilon = 0.01); //Pos y
+    let cmp3 = approx_eq!(Float, flight_test_data[2], benchmark_data[2], epsilon = 0.01); //Pos z
+    let cmp4 = approx_eq!(Float, flight_test_data[3], benchmark_data[3], epsilon = 0.01); //Roll
+    let cmp5 = approx_eq!(Float, flight_test_data[4], benchmark_data[4], epsilon = 0.01); //Pitch
+    let cmp6 = approx_eq!(Float, flight_test_data[5], benchmark_data[5], epsilon = 0.01); //Yaw
+    let cmp7 = approx_eq!(Float, flight_test_data[6], benchmark_data[6], epsilon = 0.01); //Airspeed
 
     //If all comparisons are within the epsilon, return true
     if cmp1 == true && cmp2 == true  && cmp3 == true && cmp4 == true && cmp5 == true && cmp6 == true && cmp7 == true
@@ -238,6 +282,1273 @@ fn fdm_test()
         
 }
 
+//Airplane used by the geodetic position test, same as the benchmark airplane above
+fn test_airplane() -> DataFDM
+{
+    let mut fdm = DataFDM{ 
+        element : vec![
+            PointMass{f_mass: 6.56, v_d_coords: Vector::new(14.5, 12.0, 2.5), v_local_inertia: Vector::new(13.92, 10.50, 24.00), f_incidence: -3.5, f_dihedral: 0.0, f_area: 31.2, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 7.31, v_d_coords: Vector::new(14.5, 5.5, 2.5), v_local_inertia: Vector::new(21.95, 12.22, 33.67), f_incidence: -3.5, f_dihedral: 0.0, f_area: 36.4, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 7.31, v_d_coords: Vector::new(14.5, -5.5, 2.5), v_local_inertia: Vector::new(21.95, 12.22, 33.67), f_incidence: -3.5, f_dihedral: 0.0, f_area: 36.4, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 6.56, v_d_coords: Vector::new(14.5, -12.0, 2.5), v_local_inertia: Vector::new(13.92, 10.50, 24.00), f_incidence: -3.5, f_dihedral: 0.0, f_area: 31.2, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 2.62, v_d_coords: Vector::new(3.03, 2.5, 3.0), v_local_inertia: Vector::new(0.837, 0.385, 1.206), f_incidence: 0.0, f_dihedral: 0.0, f_area: 10.8, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 2.62, v_d_coords: Vector::new(3.03, -2.5, 3.0), v_local_inertia: Vector::new(0.837, 0.385, 1.206), f_incidence: 0.0, f_dihedral: 0.0, f_area: 10.8, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 2.93, v_d_coords: Vector::new(2.25, 0.0, 5.0), v_local_inertia: Vector::new(1.262, 1.942, 0.718), f_incidence: 0.0, f_dihedral: 90.0, f_area: 12.0, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) },
+            PointMass{f_mass: 31.8, v_d_coords: Vector::new(15.25, 0.0, 1.5), v_local_inertia: Vector::new(66.30, 861.9, 861.9), f_incidence: 0.0, f_dihedral: 0.0, f_area: 84.0, i_flap: 0, v_normal: Vector::new(0.0, 0.0, 0.0), v_cg_coords: Vector::new(0.0, 0.0, 0.0) }
+        ], 
+
+    //Define initial flight parameters, Wpafb at 2000 ft
+    position: Geodetic::from_degrees(39.826, -84.045, 2000.0 / FEET_PER_METER),
+    v_position: Vector{x: 0.0, y: 0.0, z: 2000.0},
+    v_velocity: Vector{x: 60.0, y: 0.0, z: 0.0},
+    f_speed: 60.0,
+    v_forces: Vector{x: 500.0, y: 0.0, z: 0.0},
+    thrustforce: 500.0,
+    q_orientation: Quaternion::make_q_from_euler(0.0, 0.0, 0.0),
+
+    //Everything else is zero to begin
+    ..Default::default()
+    };
+
+    calc_airplane_mass_properties(&mut fdm);
+    fdm
+}
+
+#[test]
+fn geodetic_position_test()
+{
+    //Fly for 30 seconds at 240 Hz, then check the geodetic position against the earth space position
+    let mut fdm = test_airplane();
+    let start = fdm.position;
+    let start_position = fdm.v_position;
+    let integrator = Integrator::new(IntegrationMethod::SemiImplicitEuler);
+
+    for _ in 0..7200
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+
+    //Local NED offset from the geodetic positions, through ECEF
+    let offset = ecef_to_ned(&start, &(geodetic_to_ecef(&fdm.position) - geodetic_to_ecef(&start)));
+
+    //Same offset from the flat earth space position
+    let flat = earth_to_ned(&(fdm.v_position - start_position));
+
+    println!("Geodetic offset (m): {:?}", offset);
+    println!("Flat offset (m)    : {:?}", flat);
+
+    //The airplane flies roughly east and must have moved well over a kilometer
+    assert!(offset.y > 1000.0);
+    assert!((offset.x - flat.x).abs() < 1.0);
+    assert!((offset.y - flat.y).abs() < 1.0);
+    assert!((offset.z - flat.z).abs() < 1.0);
+}
+
+#[test]
+fn attitude_convention_test()
+{
+    //The NED attitude must match Bourg's Euler angles with the old FlightGear sign flips:
+    //roll unchanged, pitch negated and heading = 90 - yaw
+    for (roll, pitch, yaw) in [(0.0, 0.0, 0.0), (10.0, 20.0, 30.0), (-35.0, -10.0, 170.0), (5.0, 60.0, -95.0)].iter()
+    {
+        let q = Quaternion::make_q_from_euler(*roll, *pitch, *yaw);
+        let euler = Quaternion::make_euler_from_q(&q);
+        let attitude = attitude_ned(&q);
+
+        let heading = (attitude.z.to_degrees() - (90.0 - euler.z) + 540.0) % 360.0 - 180.0;
+        assert!((attitude.x.to_degrees() - euler.x).abs() < 0.01);
+        assert!((attitude.y.to_degrees() + euler.y).abs() < 0.01);
+        assert!(heading.abs() < 0.01);
+    }
+}
+
+#[test]
+fn unit_orientation_test()
+{
+    //The exponential map keeps the orientation a unit quaternion without renormalizing
+    let mut fdm = test_airplane();
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+
+    for _ in 0..7200
+    {
+        roll_left(&mut fdm);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+
+    assert!((fdm.q_orientation.magnitude() - 1.0).abs() < 1.0e-5);
+    assert!(fdm.v_angular_velocity.magnitude() > 0.0);
+}
+
+
+#[test]
+fn trim_level_test()
+{
+    //Level flight at 150 ft/s, then fly for 2 seconds with the trimmed controls
+    let mut fdm = test_airplane();
+    let report = trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+
+    println!("{:?}", report);
+    assert!(report.converged);
+    assert!(report.residual_norm <= TrimSettings::default().tolerance);
+    assert_eq!(report.untrimmed.len(), 2);
+    assert!(report.unknowns[TRIM_THRUST] > 0.0);
+    assert!((fdm.f_speed - 150.0).abs() < 1.0e-3);
+
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    for _ in 0..480
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+
+    //The untrimmed rudder side force slowly yaws the airplane, but speed and height hold
+    assert!((fdm.v_position.z - 2000.0).abs() < 1.0);
+    assert!((fdm.f_speed - 150.0).abs() < 0.5);
+}
+
+#[test]
+fn trim_climb_turn_test()
+{
+    //Steady climb: the velocity follows the climb rate and more thrust is needed than in level flight
+    let mut level = test_airplane();
+    let level_report = trim(&mut level, &TrimTarget::level(180.0, 2000.0), &TrimSettings::default());
+    let mut climb = test_airplane();
+    let climb_report = trim(&mut climb, &TrimTarget::climb(180.0, 2000.0, 15.0), &TrimSettings::default());
+
+    assert!(level_report.converged && climb_report.converged);
+    assert!((climb.v_velocity.z - 15.0).abs() < 1.0e-3);
+    assert!(climb_report.unknowns[TRIM_THRUST] > level_report.unknowns[TRIM_THRUST]);
+
+    //Coordinated turn to the right at 30 degrees of bank, heading north
+    let mut turn = test_airplane();
+    let report = trim(&mut turn, &TrimTarget::turn(200.0, 2000.0, 30.0).with_heading(90.0), &TrimSettings::default());
+    let attitude = attitude_ned(&turn.q_orientation);
+
+    println!("{:?}", report);
+    assert!(report.converged);
+    println!("Roll, pitch, heading (deg): {}, {}, {}", attitude.x.to_degrees(), attitude.y.to_degrees(), attitude.z.to_degrees());
+
+    //The bank is about the flight path, so the roll and heading differ a little by the angle of attack
+    assert!((attitude.x.to_degrees() - 30.0).abs() < 0.5);
+    assert!(attitude.z.to_degrees().abs() < 1.0);
+    assert!(turn.v_velocity.x.abs() < 1.0e-3 && (turn.v_velocity.y - 200.0).abs() < 1.0e-3);
+
+    //The body rates give the turn rate about the vertical
+    let rate = Quaternion::qvrotate(&turn.q_orientation, &turn.v_angular_velocity);
+    assert!((rate.z.to_degrees() + turn_rate(30.0, 200.0) as Float).abs() < 1.0e-3);
+}
+
+#[test]
+fn linearize_test()
+{
+    //Linearize about level flight at 150 ft/s
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    let x0 = save_state(&fdm);
+    let model = linearize(&mut fdm);
+
+    assert_eq!(model.a.len(), 12);
+    assert_eq!(model.b[0].len(), 4);
+    for (x, x_trim) in save_state(&fdm).iter().zip(x0.iter())
+    {
+        assert!((x - x_trim).abs() < 1.0e-6);
+    }
+
+    //Kinematics: theta_dot = q in level flight, and the airplane flies east at 150 ft/s
+    assert!((model.a[7][4] - 1.0).abs() < 1.0e-6);
+    assert!((model.x0[0] * model.a[10][0] - 150.0).abs() < 0.1);
+
+    //The linear model predicts the nonlinear response to a small pitch rate and elevator change
+    let controls = SurfaceControls::from_fdm(&fdm);
+    let f0 = state_derivatives(&mut fdm, &x0, &controls);
+    let mut x = x0.clone();
+    x[4] += 0.005;
+    let f1 = state_derivatives(&mut fdm, &x, &SurfaceControls { elevator: controls.elevator + 0.1, ..controls });
+    for i in 0..12
+    {
+        let predicted = f0[i] + model.a[i][4] * 0.005 + model.b[i][1] * 0.1;
+        assert!((f1[i] - predicted).abs() < 1.0e-2 * (1.0 + f1[i].abs()));
+    }
+
+    //Classic modes
+    for mode in model.modes.iter()
+    {
+        println!("{:<16} frequency {:>8.4} rad/s, damping {:>7.4}", mode.name, mode.frequency, mode.damping);
+    }
+    let mode = |name: &str| model.modes.iter().find(|m| m.name == name).unwrap().clone();
+    let short_period = mode("short period");
+    let phugoid = mode("phugoid");
+    let roll = mode("roll subsidence");
+    assert!(short_period.frequency > phugoid.frequency);
+    assert!(short_period.damping > 0.0 && short_period.period().is_some());
+    assert!(roll.real < 0.0 && roll.imag == 0.0);
+    assert!(mode("dutch roll").imag > 0.0);
+    assert!(mode("spiral").frequency < roll.frequency);
+
+    //Export
+    let csv = model.to_csv();
+    assert!(csv.starts_with("A,u,v,w,p,q,r,phi,theta,psi,north,east,down\n"));
+    assert!(csv.contains("\nB,thrust,elevator,aileron,rudder\n"));
+    assert!(csv.contains("\nshort period,"));
+    let json = model.to_json().unwrap();
+    assert!(json.contains("\"modes\"") && json.contains("\"dutch roll\""));
+}
+
+//Keyboard with no key pressed
+fn no_keys() -> KeyboardState
+{
+    KeyboardState{
+        thrust_up: false,
+        thrust_down: false,
+        left_rudder: false,
+        right_rudder: false,
+        roll_left: false,
+        roll_right: false,
+        pitch_up: false,
+        pitch_down: false,
+        flaps_down: false,
+        zero_flaps: false,
+        ap_altitude_hold: false,
+        ap_vertical_speed: false,
+        ap_heading_hold: false,
+        ap_airspeed_hold: false,
+        ap_wing_leveler: false,
+        ap_disengage: false,
+        ap_flight_plan: false,
+        ap_approach: false,
+        mixture_rich: false,
+        mixture_lean: false,
+        engine_start: false,
+    }
+}
+
+#[test]
+fn autopilot_test()
+{
+    //Trimmed level flight heading east, then the autopilot is engaged at the current values
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+
+    let mut ap = AutopilotState::default();
+    let keys = KeyboardState{ ap_altitude_hold: true, ap_heading_hold: true, ap_airspeed_hold: true, ..no_keys() };
+    handle_autopilot_input(&fdm, &mut ap, &keys);
+    assert!(matches!(ap.modes.vertical, VerticalMode::AltitudeHold(a) if (a - 2000.0).abs() < 1.0e-3));
+    assert!(matches!(ap.modes.lateral, LateralMode::HeadingHold(h) if (h - 90.0).abs() < 0.1));
+    assert!(matches!(ap.modes.speed, SpeedMode::AirspeedHold(v) if (v - 150.0).abs() < 1.0e-3));
 
+    //New targets: climb 100 ft, turn 30 degrees right and speed up
+    ap.modes.vertical = VerticalMode::AltitudeHold(2100.0);
+    ap.modes.lateral = LateralMode::HeadingHold(120.0);
+    ap.modes.speed = SpeedMode::AirspeedHold(160.0);
 
+    //Five minutes unattended, the autopilot runs at 30 Hz and the physics at 240 Hz.
+    //After the first two minutes the airplane must stay on the targets
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut worst = [0.0_f64; 3];
+    for frame in 0..9000
+    {
+        autopilot(&mut fdm, &mut ap, 1.0 / 30.0, 3000.0);
+        for _ in 0..8
+        {
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+        }
 
+        if frame >= 3600
+        {
+            let heading = attitude_ned(&fdm.q_orientation).z.to_f64().to_degrees();
+            worst[0] = worst[0].max((fdm.v_position.z.to_f64() - 2100.0).abs());
+            worst[1] = worst[1].max((heading - 120.0).abs());
+            worst[2] = worst[2].max((fdm.f_speed.to_f64() - 160.0).abs());
+        }
+    }
+
+    println!("Largest altitude, heading and airspeed errors: {:?}", worst);
+    assert!(worst[0] < 1.0);
+    assert!(worst[1] < 0.5);
+    assert!(worst[2] < 0.5);
+    assert!(fdm.thrustforce > 0.0 && fdm.thrustforce < 3000.0);
+
+    //Flying the pitch axis by hand disengages only that axis, the disengage key drops the rest
+    handle_autopilot_input(&fdm, &mut ap, &KeyboardState{ pitch_up: true, ..no_keys() });
+    assert_eq!(ap.modes.vertical, VerticalMode::Off);
+    assert!(ap.modes.lateral != LateralMode::Off && ap.modes.speed != SpeedMode::Off);
+    handle_autopilot_input(&fdm, &mut ap, &KeyboardState{ ap_disengage: true, ..no_keys() });
+    assert!(!ap.modes.is_engaged());
+}
+
+#[test]
+fn flight_plan_test()
+{
+    //Racetrack east of Wright-Patterson at 2000 ft, flown twice hands-off from trimmed level flight heading east
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+
+    let altitude = 2000.0 / FEET_PER_METER;
+    let mut plan = FlightPlan{
+        repeat: true,
+        ..FlightPlan::new(vec![
+            Waypoint::from_degrees(39.826, -84.0, altitude),
+            Waypoint::from_degrees(39.8125, -84.0, altitude),
+            Waypoint::from_degrees(39.8125, -84.045, altitude).with_airspeed(50.0),
+            Waypoint::from_degrees(39.826, -84.045, altitude),
+        ])
+    };
+
+    //The flight plan key engages the plan, which sets the autopilot modes
+    let mut ap = AutopilotState::default();
+    handle_flight_plan_input(&mut plan, &ap, &KeyboardState{ ap_flight_plan: true, ..no_keys() });
+    follow_flight_plan(&fdm, &mut plan, &mut ap);
+    assert!(plan.engaged);
+    assert!(matches!(ap.modes.lateral, LateralMode::HeadingHold(_)));
+    assert!(matches!(ap.modes.vertical, VerticalMode::AltitudeHold(a) if (a - 2000.0).abs() < 0.01));
+
+    //Ten minutes, guidance and autopilot at 30 Hz and physics at 240 Hz
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut legs = 0;
+    let mut active = plan.active;
+    let mut worst_cross_track = 0.0_f64;
+    let mut worst_altitude = 0.0_f64;
+    for _ in 0..18000
+    {
+        follow_flight_plan(&fdm, &mut plan, &mut ap);
+        autopilot(&mut fdm, &mut ap, 1.0 / 30.0, 3000.0);
+        for _ in 0..8
+        {
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+        }
+
+        if plan.active != active
+        {
+            legs += 1;
+            active = plan.active;
+        }
+
+        //The long legs east and west settle onto the track, the altitude holds throughout
+        if legs > 0 && matches!(active, 0 | 2) && plan.guidance.distance_to_waypoint < 1500.0
+        {
+            worst_cross_track = worst_cross_track.max(plan.guidance.cross_track.abs());
+        }
+        worst_altitude = worst_altitude.max((fdm.v_position.z.to_f64() - 2000.0).abs());
+    }
+
+    println!("Legs flown: {}, largest cross track (m): {}, largest altitude error (ft): {}", legs, worst_cross_track, worst_altitude);
+    assert!(legs >= 8);
+    assert!(plan.engaged && !plan.is_complete());
+    assert!(worst_cross_track < 10.0);
+    assert!(worst_altitude < 10.0);
+
+    //The speed constraint of the third waypoint is held from then on
+    assert!((fdm.f_speed.to_f64() - 50.0 * FEET_PER_METER).abs() < 0.5);
+
+    //Rolling by hand disengages the heading hold, and with it the flight plan
+    handle_autopilot_input(&fdm, &mut ap, &KeyboardState{ roll_left: true, ..no_keys() });
+    handle_flight_plan_input(&mut plan, &ap, &no_keys());
+    assert!(!plan.engaged);
+}
+
+//Test airplane with a tricycle landing gear, the mains just behind the CG and the nose wheel well ahead of it
+fn test_airplane_with_gear() -> DataFDM
+{
+    let mut fdm = DataFDM{
+        gear: vec![
+            LandingGear{v_d_coords: Vector::new(12.0, 4.0, -2.0), f_stiffness: 5000.0, f_damping: 800.0, f_rolling_friction: 0.02, f_brake_friction: 0.5, f_side_friction: 0.8, ..Default::default()},
+            LandingGear{v_d_coords: Vector::new(12.0, -4.0, -2.0), f_stiffness: 5000.0, f_damping: 800.0, f_rolling_friction: 0.02, f_brake_friction: 0.5, f_side_friction: 0.8, ..Default::default()},
+            LandingGear{v_d_coords: Vector::new(18.0, 0.0, -2.0), f_stiffness: 3000.0, f_damping: 250.0, f_rolling_friction: 0.02, f_brake_friction: 0.0, f_side_friction: 0.8, ..Default::default()},
+        ],
+        ..test_airplane()
+    };
+
+    calc_airplane_mass_properties(&mut fdm);
+    fdm
+}
+
+#[test]
+fn autoland_test()
+{
+    //Runway heading east at 248 m elevation, 10 km ahead and 150 m left of an airplane trimmed level at 2000 ft,
+    //below the glide path, so the approach captures the localizer first and then the glide slope from below
+    let mut fdm = test_airplane_with_gear();
+    fdm.f_ground_elevation = Float::from_f64(248.0 * FEET_PER_METER);
+    trim(&mut fdm, &TrimTarget::level(40.0 * FEET_PER_METER, 2000.0), &TrimSettings::default());
+
+    let threshold = destination(&destination(&fdm.position, 0.0, 150.0), 90.0_f64.to_radians(), 10000.0);
+    let mut approach = Approach{
+        flare_height: 15.0,
+        ..Approach::new(Runway::new(Geodetic { altitude: 248.0, ..threshold }, 90.0, 2500.0), 40.0)
+    };
+
+    let mut ap = AutopilotState::default();
+    handle_approach_input(&mut approach, &ap, &KeyboardState{ ap_approach: true, ..no_keys() });
+    fly_approach(&mut fdm, &mut approach, &mut ap);
+    assert_eq!(approach.phase, ApproachPhase::Approach);
+    assert!(matches!(ap.modes.vertical, VerticalMode::VerticalSpeed(v) if v == 0.0));
+
+    //Guidance and autopilot at 30 Hz and physics at 240 Hz until the airplane has stopped on the runway
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut worst_localizer = 0.0_f64;
+    let mut worst_glide_slope = 0.0_f64;
+    let mut time = 0.0;
+    while approach.phase != ApproachPhase::Stopped && time < 600.0
+    {
+        fly_approach(&mut fdm, &mut approach, &mut ap);
+        autopilot(&mut fdm, &mut ap, 1.0 / 30.0, 3000.0);
+        for _ in 0..8
+        {
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+        }
+        time += 1.0 / 30.0;
+
+        //Once established, the last 3 km of the approach down to the flare
+        let d = approach.deviations;
+        if approach.phase == ApproachPhase::Approach && d.distance_from_threshold > -3000.0
+        {
+            worst_localizer = worst_localizer.max(d.localizer.abs());
+            worst_glide_slope = worst_glide_slope.max(d.glide_slope.abs());
+        }
+    }
+
+    let touchdown = approach.touchdown.unwrap();
+    println!("Time {} s, largest localizer deviation (m): {}, largest glide slope deviation (m): {}", time, worst_localizer, worst_glide_slope);
+    println!("{:?}", touchdown);
+    assert_eq!(approach.phase, ApproachPhase::Stopped);
+    assert!(worst_localizer < 5.0);
+    assert!(worst_glide_slope < 5.0);
+
+    //Gentle touchdown in the touchdown zone on the centreline, then stopped on the runway on its wheels
+    assert!(touchdown.vertical_speed < 0.0 && touchdown.vertical_speed > -1.0);
+    assert!(touchdown.distance_from_threshold > 150.0 && touchdown.distance_from_threshold < 900.0);
+    assert!(touchdown.centreline_deviation.abs() < 3.0);
+    assert!(approach.deviations.distance_from_threshold < 2500.0);
+    assert!(approach.deviations.localizer.abs() < 5.0);
+    assert!(fdm.on_ground && wheel_height(&fdm) < 0.5 && fdm.f_speed.to_f64() < FEET_PER_METER);
+}
+
+#[test]
+fn actuator_test()
+{
+    //Trimmed level flight heading east, with servos on the surfaces and a lag on the thrust
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    let trimmed = SurfaceControls::from_fdm(&fdm);
+
+    let mut actuators = Actuators{
+        elevator: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -20.0, 20.0, 60.0),
+        aileron: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -20.0, 20.0, 60.0),
+        rudder: Actuator::new(ActuatorDynamics::SecondOrder(15.0, 0.7), -20.0, 20.0, 40.0),
+        thrust: Actuator::new(ActuatorDynamics::FirstOrder(1.0), 0.0, 3000.0, f64::INFINITY),
+        elevator_flap: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -1.0, 1.0, 4.0),
+        aileron_flap: Actuator::new(ActuatorDynamics::SecondOrder(20.0, 0.7), -1.0, 1.0, 4.0),
+    };
+    actuators.reset(&fdm);
+    assert_eq!(actuators.thrust.position, trimmed.thrust);
+
+    //Physics at 240 Hz on the actual positions, the commands stay on the airplane between the steps
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let step = |fdm: &mut DataFDM, actuators: &mut Actuators|
+    {
+        for _ in 0..8
+        {
+            actuate(fdm, actuators, 1.0 / 240.0);
+            eom(fdm, 1.0 / 240.0, &integrator);
+            restore_commands(fdm, actuators);
+        }
+    };
+
+    //Full rudder: the command is there at once, the rudder travels at no more than 40 deg/s
+    yaw_left(&mut fdm);
+    step(&mut fdm, &mut actuators);
+    assert_eq!(fdm.element[6].f_incidence, 16.0);
+    assert_eq!(actuators.rudder.command, 16.0);
+    assert!(actuators.rudder.position > 0.0 && actuators.rudder.position <= 40.0 / 30.0 + 1e-9);
+    for _ in 0..30
+    {
+        step(&mut fdm, &mut actuators);
+    }
+    assert!((actuators.rudder.position - 16.0).abs() < 0.01);
+
+    //The pitch and roll keys move the flaps, which switch on the airfoil tables once they are half way there
+    zero_rudder(&mut fdm);
+    pitch_up(&mut fdm);
+    roll_left(&mut fdm);
+    actuate(&mut fdm, &mut actuators, 1.0 / 240.0);
+    assert!(fdm.element[4].i_flap == 0 && fdm.element[0].i_flap == 0 && fdm.element[3].i_flap == 0);
+    restore_commands(&mut fdm, &actuators);
+    assert!(fdm.element[4].i_flap == 1 && fdm.element[0].i_flap == 1 && fdm.element[3].i_flap == -1);
+    for _ in 0..60
+    {
+        restore_commands(&mut fdm, &actuators);
+        actuate(&mut fdm, &mut actuators, 1.0 / 240.0);
+    }
+    assert!(fdm.element[4].i_flap == 1 && fdm.element[5].i_flap == 1 && fdm.element[0].i_flap == 1 && fdm.element[3].i_flap == -1);
+    restore_commands(&mut fdm, &actuators);
+    zero_elevators(&mut fdm);
+    zero_ailerons(&mut fdm);
+    for _ in 0..6
+    {
+        step(&mut fdm, &mut actuators);
+    }
+    assert!(actuators.elevator_flap.position.abs() < 0.5 && actuators.aileron_flap.position.abs() < 0.5);
+
+    //A thrust step is a first-order lag: 63% of the way there after a second
+    thrust_up(&mut fdm, 500.0);
+    for _ in 0..30
+    {
+        step(&mut fdm, &mut actuators);
+    }
+    let lag = (actuators.thrust.position - (trimmed.thrust + 500.0 * (1.0 - (-1.0_f64).exp()))).abs();
+    assert!(lag < 1.0, "{}", lag);
+
+    //The autopilot flies through the actuators: back to the trimmed thrust, climb 100 ft, turn 30 degrees right and speed up
+    fdm.thrustforce = Float::from_f64(trimmed.thrust);
+    let mut ap = AutopilotState::default();
+    ap.modes.vertical = VerticalMode::AltitudeHold(2100.0);
+    ap.modes.lateral = LateralMode::HeadingHold(120.0);
+    ap.modes.speed = SpeedMode::AirspeedHold(160.0);
+
+    //After the first two minutes the airplane must stay on the targets
+    let mut worst = [0.0_f64; 3];
+    for frame in 0..9000
+    {
+        autopilot(&mut fdm, &mut ap, 1.0 / 30.0, 3000.0);
+        step(&mut fdm, &mut actuators);
+
+        if frame >= 3600
+        {
+            let heading = attitude_ned(&fdm.q_orientation).z.to_f64().to_degrees();
+            worst[0] = worst[0].max((fdm.v_position.z.to_f64() - 2100.0).abs());
+            worst[1] = worst[1].max((heading - 120.0).abs());
+            worst[2] = worst[2].max((fdm.f_speed.to_f64() - 160.0).abs());
+        }
+    }
+
+    println!("Largest altitude, heading and airspeed errors with actuators: {:?}", worst);
+    assert!(worst[0] < 1.0);
+    assert!(worst[1] < 0.5);
+    assert!(worst[2] < 0.5);
+}
+
+#[test]
+fn control_rate_test()
+{
+    //Holding the thrust key for half a second adds the same thrust at any input rate, 3000 lb/s up to 3000 lb
+    for input_rate in [30.0, 60.0, 144.0]
+    {
+        let mut fdm = test_airplane();
+        fdm.thrustforce = 0.0;
+        for _ in 0..(input_rate as usize) / 2
+        {
+            handle_input(&mut fdm, &KeyboardState{ thrust_up: true, ..no_keys() }, 3000.0, 3000.0, 1.0 / input_rate);
+        }
+        assert!((fdm.thrustforce.to_f64() - 1500.0).abs() < 0.1);
+
+        //The thrust stays within 0 and the max thrust
+        for _ in 0..2 * input_rate as usize
+        {
+            handle_input(&mut fdm, &KeyboardState{ thrust_up: true, ..no_keys() }, 3000.0, 3000.0, 1.0 / input_rate);
+        }
+        assert_eq!(fdm.thrustforce, 3000.0);
+        for _ in 0..2 * input_rate as usize
+        {
+            handle_input(&mut fdm, &KeyboardState{ thrust_down: true, ..no_keys() }, 3000.0, 3000.0, 1.0 / input_rate);
+        }
+        assert_eq!(fdm.thrustforce, 0.0);
+    }
+}
+
+#[test]
+fn engine_test()
+{
+    //Trimmed level flight at 2000 ft with a running 180 hp engine, the thrust lever at half of the max thrust
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    fdm.thrustforce = 1500.0;
+    let mut engine = PistonEngine::new(PistonEngineData::new(134000.0, 2700.0, 0.0059));
+    engine.start();
+
+    //The engine settles and its thrust takes the place of the lever
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    for _ in 0..240 * 10
+    {
+        run_engine(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    let thrust = fdm.engine_thrust.unwrap().to_f64();
+    assert!((thrust - 3000.0 * engine.power / 134000.0).abs() < 0.01);
+    assert!(thrust > 500.0 && thrust < 3000.0);
+    assert!(engine.running && engine.rpm > 1500.0 && engine.rpm < 2700.0);
+    assert_eq!(fdm.thrustforce, 1500.0);
+
+    //The same lever gives less thrust at 10000 ft
+    let low = engine;
+    fdm.position.altitude = 10000.0 / FEET_PER_METER;
+    for _ in 0..240 * 10
+    {
+        run_engine(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+    }
+    assert!(engine.power < 0.8 * low.power);
+    assert!(engine.manifold_pressure < low.manifold_pressure);
+
+    //The mixture keys move the mixture at a rate, cut-off stops the engine and the thrust goes
+    for _ in 0..60
+    {
+        handle_engine_input(&mut engine, &KeyboardState{ mixture_lean: true, ..no_keys() }, 1.0 / 30.0);
+    }
+    assert_eq!(engine.mixture, 0.0);
+    for _ in 0..240 * 10
+    {
+        run_engine(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+    }
+    assert!(!engine.running);
+    assert_eq!(fdm.engine_thrust, Some(0.0));
+}
+
+//600 hp radial engine turning a 2.7 m propeller on the nose, sized for the drag of the test airplane
+fn test_powerplant(clockwise: bool) -> (PistonEngine, Propeller)
+{
+    let mut engine = PistonEngine::new(PistonEngineData::new(447000.0, 2250.0, 0.0221));
+    engine.start();
+    let propeller = Propeller::new(PropellerData{ clockwise, ..PropellerData::fixed_pitch(2.7, Vector3::new(26.0, 0.0, 1.5)) });
+    (engine, propeller)
+}
+
+#[test]
+fn propeller_test()
+{
+    //Trimmed level flight at 100 ft/s, at a high angle of attack, with the throttle wide open
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(100.0, 2000.0), &TrimSettings::default());
+    fdm.thrustforce = 3000.0;
+    let (mut engine, mut propeller) = test_powerplant(true);
+
+    //The engine settles where the propeller absorbs its power, and the propeller's thrust takes the place of the lever
+    for _ in 0..240 * 10
+    {
+        run_propeller(&mut fdm, &mut engine, &mut propeller, 3000.0, 1.0 / 240.0);
+    }
+    assert!(engine.running && engine.rpm > 1800.0 && engine.rpm < 2400.0, "{}", engine.rpm);
+    assert!((engine.torque - propeller.engine_load()).abs() < 1.0);
+    assert!((fdm.engine_thrust.unwrap().to_f64() - propeller.thrust / NEWTONS_PER_POUND).abs() < 1e-3);
+    assert!(fdm.engine_thrust.unwrap() > 1000.0);
+
+    //The reaction torque rolls the airplane left, and the P-factor at the angle of attack yaws it left
+    let foot_pounds = FEET_PER_METER / NEWTONS_PER_POUND;
+    let moment = fdm.v_propeller_moment;
+    assert!((moment.x.to_f64() - propeller.reaction_torque() * foot_pounds).abs() < 0.1);
+    assert!(moment.x < -500.0);
+    assert!(moment.z > 100.0);
+
+    //Pitching up, the gyroscopic moment yaws the airplane right
+    fdm.v_angular_velocity = Vector::new(0.0, -0.5, 0.0);
+    run_propeller(&mut fdm, &mut engine, &mut propeller, 3000.0, 0.0);
+    let gyroscopic = fdm.v_propeller_moment.z - moment.z;
+    assert!((gyroscopic.to_f64() + 0.5 * propeller.angular_momentum() * foot_pounds).abs() < 0.5);
+    assert!(gyroscopic < 0.0);
+    fdm.v_angular_velocity = Vector::new(0.0, 0.0, 0.0);
+
+    //A counter-clockwise propeller turns the other way, the thrust line pitches the same
+    let (mut engine, mut mirrored) = test_powerplant(false);
+    for _ in 0..240 * 10
+    {
+        run_propeller(&mut fdm, &mut engine, &mut mirrored, 3000.0, 1.0 / 240.0);
+    }
+    assert!(fdm.v_propeller_moment.x > 500.0 && fdm.v_propeller_moment.z < -100.0);
+    assert!((fdm.v_propeller_moment.y - moment.y).abs() < 0.1);
+    assert!((fdm.v_propeller_moment.x + moment.x).abs() < 0.1);
+
+    //Flying on, without a hand on the controls, the clockwise propeller rolls and yaws the airplane left and the other one right
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut rates = Vec::new();
+    for clockwise in [true, false]
+    {
+        let mut fdm = test_airplane();
+        trim(&mut fdm, &TrimTarget::level(100.0, 2000.0), &TrimSettings::default());
+        fdm.thrustforce = 3000.0;
+        let (mut engine, mut propeller) = test_powerplant(clockwise);
+        for _ in 0..240 * 2
+        {
+            run_propeller(&mut fdm, &mut engine, &mut propeller, 3000.0, 1.0 / 240.0);
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+        }
+        rates.push(fdm.v_angular_velocity);
+    }
+    assert!(rates[0].x < 0.0 && rates[0].z > 0.0);
+    assert!(rates[1].x > 0.0 && rates[1].z < 0.0);
+}
+
+#[test]
+fn jet_test()
+{
+    //Trimmed level flight at 150 ft/s with a 14 kN turbofan idling, then the thrust lever slammed full forward
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    let mut engine = JetEngine::new(JetEngineData::new(14000.0, 16000.0));
+    handle_jet_input(&mut engine, &KeyboardState{ engine_start: true, ..no_keys() });
+    fdm.thrustforce = 0.0;
+    for _ in 0..240 * 20
+    {
+        run_jet(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+    }
+    assert!(engine.running && (engine.n1 - 0.6).abs() < 1e-3);
+    let idle = fdm.engine_thrust.unwrap();
+    fdm.thrustforce = 3000.0;
+
+    //The spool lags, one second in the thrust is still well short of full
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    for _ in 0..240
+    {
+        run_jet(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!(fdm.engine_thrust.unwrap() > idle && engine.n1 < 0.8);
+
+    //Spooled up, the jet's thrust takes the place of the lever and the airplane speeds up
+    for _ in 0..240 * 9
+    {
+        run_jet(&mut fdm, &mut engine, 3000.0, 1.0 / 240.0);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!((fdm.engine_thrust.unwrap().to_f64() - engine.thrust / NEWTONS_PER_POUND).abs() < 1e-3);
+    assert!(fdm.engine_thrust.unwrap() > 2000.0 && engine.mach > 0.1);
+    assert!(fdm.f_speed > 170.0);
+    assert_eq!(fdm.thrustforce, 3000.0);
+
+    //The same lever gives less thrust higher up
+    let low = engine.thrust;
+    fdm.position.altitude = 20000.0 / FEET_PER_METER;
+    run_jet(&mut fdm, &mut engine, 3000.0, 0.0);
+    assert!(engine.thrust < 0.7 * low);
+}
+
+//Test airplane trimmed in level flight at an airspeed (ft/s) with two engines mounted 6 ft either side of the CG, ahead of it.
+//Also return the forces and moments of the trimmed airplane before the engines are mounted, the trim leaves the lateral axes alone
+fn test_twin(airspeed: Float, left: Powerplant, right: Powerplant) -> (DataFDM, Vector, Vector)
+{
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(airspeed.to_f64(), 2000.0), &TrimSettings::default());
+    calc_airplane_loads(&mut fdm);
+    let (v_forces, v_moments) = (fdm.v_forces, fdm.v_moments);
+    let cg = fdm.v_cg;
+    fdm.engines = vec![
+        EngineMount::new(left, Vector::new(16.0, cg.y + 6.0, cg.z)),
+        EngineMount::new(right, Vector::new(16.0, cg.y - 6.0, cg.z)),
+    ];
+    calc_airplane_mass_properties(&mut fdm);
+    (fdm, v_forces, v_moments)
+}
+
+#[test]
+fn multi_engine_test()
+{
+    //Two 1500 lb engines following the thrust lever share the trimmed thrust, and the loads stay those of the trimmed airplane
+    let (mut fdm, v_forces, v_moments) = test_twin(150.0, Powerplant::Thrust(1500.0), Powerplant::Thrust(1500.0));
+    let trimmed_thrust = fdm.thrustforce;
+    run_engines(&mut fdm, 3000.0, 1.0 / 240.0);
+    assert!((fdm.engines[0].f_thrust - trimmed_thrust / 2.0).abs() < 0.01);
+    assert_eq!(fdm.engines[0].f_thrust, fdm.engines[1].f_thrust);
+    calc_airplane_loads(&mut fdm);
+    assert!((fdm.v_moments - v_moments).magnitude() < 1.0, "{:?}", fdm.v_moments);
+    assert!((fdm.v_forces - v_forces).magnitude() < 1.0, "{:?}", fdm.v_forces);
+
+    //With the right engine out the left one yaws the airplane right by its thrust times its arm, and its own throttle
+    //does not follow the lever
+    fdm.engines[1].throttle = Some(0.0);
+    fdm.engines[0].throttle = Some(1.0);
+    run_engines(&mut fdm, 3000.0, 1.0 / 240.0);
+    assert_eq!((fdm.engines[0].f_thrust, fdm.engines[1].f_thrust), (1500.0, 0.0));
+    calc_airplane_loads(&mut fdm);
+    assert!((fdm.v_moments.z - v_moments.z + 6.0 * 1500.0).abs() < 1.0, "{:?}", fdm.v_moments);
+
+    //Flying on for a second, the airplane yaws and rolls towards the dead engine
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    for _ in 0..240
+    {
+        run_engines(&mut fdm, 3000.0, 1.0 / 240.0);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!(fdm.v_angular_velocity.z < 0.0 && fdm.v_angular_velocity.x > 0.0, "{:?}", fdm.v_angular_velocity);
+
+    //Both engines go in the packet
+    let mut fgnet = FGNetFDM::default();
+    load_engines(&fdm, &mut fgnet);
+    assert_eq!(u32::from_be_bytes(fgnet.num_engines.to_ne_bytes()), 2);
+    assert_eq!(f32::from_be_bytes(fgnet.eng_state[1].to_ne_bytes()), 2.0);
+}
+
+#[test]
+fn twin_propeller_test()
+{
+    //Twin with 600 hp radials at full throttle, trimmed slow at a high angle of attack
+    let twin = |left: bool, right: bool|
+    {
+        let (left_engine, left_propeller) = test_powerplant(left);
+        let (right_engine, right_propeller) = test_powerplant(right);
+        let (mut fdm, _, v_moments) = test_twin(100.0, Powerplant::Piston(Box::new(left_engine), Box::new(left_propeller)), Powerplant::Piston(Box::new(right_engine), Box::new(right_propeller)));
+        fdm.thrustforce = 3000.0;
+        (fdm, v_moments)
+    };
+    let settle = |fdm: &mut DataFDM|
+    {
+        for _ in 0..240 * 10
+        {
+            run_engines(fdm, 3000.0, 1.0 / 240.0);
+        }
+        calc_airplane_loads(fdm);
+    };
+    let propeller_moment = |fdm: &DataFDM| fdm.engines[0].v_propeller_moment + fdm.engines[1].v_propeller_moment;
+
+    //Counter-rotating propellers cancel each other's torque and P-factor
+    let (mut fdm, _) = twin(true, false);
+    settle(&mut fdm);
+    assert!(fdm.engines[0].f_thrust > 500.0);
+    let moment = propeller_moment(&fdm);
+    assert!(moment.x.abs() < 0.5 && moment.z.abs() < 0.5, "{:?}", moment);
+
+    //With both turning clockwise the torques add up, and the P-factor moves both thrust lines to the right, so losing
+    //the left engine (the critical engine) yaws the airplane left harder than losing the right one yaws it right
+    let (mut fdm, _) = twin(true, true);
+    settle(&mut fdm);
+    let both = propeller_moment(&fdm);
+    assert!(both.x < -1000.0);
+
+    //The moments of a propeller on the single engine add to those of the mounted ones
+    let v_moments = fdm.v_moments;
+    fdm.v_propeller_moment = Vector::new(100.0, 0.0, 0.0);
+    calc_airplane_loads(&mut fdm);
+    assert!((fdm.v_moments.x - v_moments.x - 100.0).abs() < 0.01, "{:?}", fdm.v_moments);
+
+    let mut yaw = Vec::new();
+    for out in 0..2
+    {
+        let (mut fdm, v_moments) = twin(true, true);
+        fdm.engines[out].throttle = Some(0.0);
+        if let Powerplant::Piston(engine, _) = &mut fdm.engines[out].powerplant
+        {
+            engine.mixture = 0.0;
+        }
+        settle(&mut fdm);
+        assert_eq!(fdm.engines[out].f_thrust, 0.0);
+        yaw.push(fdm.v_moments.z - v_moments.z);
+    }
+    assert!(yaw[0] > 0.0 && yaw[1] < 0.0, "{:?}", yaw);
+    assert!(yaw[0].abs() > yaw[1].abs(), "{:?}", yaw);
+}
+
+#[test]
+fn fuel_test()
+{
+    //Test airplane with a 10 kg tank in the right wing feeding first and a 5 kg tank in the nose
+    let mut fdm = test_airplane();
+    let (empty_mass, empty_cg, empty_inertia) = (fdm.mass, fdm.v_cg, fdm.m_inertia);
+    fdm.fuel = FuelSystem::new(vec![
+        FuelTank::new(Vector3::new(14.5, -8.0, 2.5), 10.0, 0),
+        FuelTank::new(Vector3::new(24.0, 0.0, 1.5), 5.0, 1),
+    ]);
+    calc_airplane_mass_properties(&mut fdm);
+
+    //The fuel adds to the mass, moves the CG towards the right wing and adds to the roll inertia
+    let full_cg = fdm.v_cg;
+    assert!((fdm.mass - empty_mass - Float::from_f64(15.0 / KILOGRAMS_PER_SLUG)).abs() < 1e-4);
+    assert!(full_cg.y < empty_cg.y && full_cg.x > empty_cg.x);
+    assert!(fdm.m_inertia.e11 > empty_inertia.e11);
+
+    //The engine at full throttle burns the wing tank first, and the mass properties follow the fuel
+    fdm.thrustforce = 3000.0;
+    let (mut engine, mut propeller) = test_powerplant(true);
+    let run = |fdm: &mut DataFDM, engine: &mut PistonEngine, propeller: &mut Propeller, time: usize|
+    {
+        for _ in 0..240 * time
+        {
+            run_propeller(fdm, engine, propeller, 3000.0, 1.0 / 240.0);
+            burn_fuel(fdm, Some(&mut *engine), None, 1.0 / 240.0);
+        }
+    };
+    run(&mut fdm, &mut engine, &mut propeller, 60);
+    let burnt = 15.0 - fdm.fuel.quantity();
+    assert!(burnt > 1.0 && fdm.fuel.tanks[0].quantity < 10.0 && fdm.fuel.tanks[1].quantity == 5.0, "{}", burnt);
+    assert!((fdm.mass - empty_mass - Float::from_f64(fdm.fuel.quantity() / KILOGRAMS_PER_SLUG)).abs() < 1e-4);
+    assert!(fdm.v_cg.y > full_cg.y);
+    let identity = fdm.m_inertia * fdm.m_inertia_inverse;
+    assert!((identity.e11 - 1.0).abs() < 1e-4 && (identity.e22 - 1.0).abs() < 1e-4 && identity.e13.abs() < 1e-4);
+
+    //Once both tanks run dry the engine stops, and the airplane is back to its empty mass properties
+    run(&mut fdm, &mut engine, &mut propeller, 600);
+    assert!(fdm.fuel.exhausted());
+    assert!(!engine.running && engine.rpm == 0.0);
+    assert!((fdm.mass - empty_mass).abs() < 1e-4);
+    assert!((fdm.v_cg - empty_cg).magnitude() < 1e-4);
+    assert!(fdm.engine_thrust.unwrap() < 1.0);
+
+    //Mounted engines are stopped too
+    fdm.engines = vec![EngineMount::new(Powerplant::Jet(JetEngine::new(JetEngineData::new(14000.0, 16000.0))), Vector::new(16.0, 0.0, 1.5))];
+    if let Powerplant::Jet(engine) = &mut fdm.engines[0].powerplant
+    {
+        engine.start();
+    }
+    run_engines(&mut fdm, 3000.0, 1.0 / 240.0);
+    burn_fuel(&mut fdm, None, None, 1.0 / 240.0);
+    assert!(matches!(&fdm.engines[0].powerplant, Powerplant::Jet(engine) if !engine.running));
+}
+
+#[test]
+fn stall_test()
+{
+    //Trimmed level flight at 150 ft/s, each lifting element well below its stall angle and no warning
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    for _ in 0..240
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!(fdm.f_stall_margins.iter().all(|margin| *margin > 5.0), "{:?}", fdm.f_stall_margins);
+    assert!(!fdm.stalling && fdm.f_stall_warning == 0.0);
+
+    //Air coming from below at a growing angle eats into the wing's stall margin, and past the stall angle the wing stalls
+    let speed = fdm.f_speed;
+    fdm.v_angular_velocity = Vector::new(0.0, 0.0, 0.0);
+    let loads_at = |fdm: &mut DataFDM, alpha: Float|
+    {
+        let alpha = alpha.to_f64().to_radians();
+        fdm.v_velocity_body = Vector::new(speed * Float::from_f64(alpha.cos()), 0.0, -speed * Float::from_f64(alpha.sin()));
+        calc_airplane_loads(fdm);
+    };
+    loads_at(&mut fdm, 10.0);
+    let margin = fdm.f_stall_margins[1];
+    assert!(margin > 0.0 && margin < 10.0, "{}", margin);
+    assert!((fdm.f_stall_margins[1] - fdm.f_stall_margins[2]).abs() < 1e-3, "{:?}", fdm.f_stall_margins);
+    loads_at(&mut fdm, 12.0);
+    assert!((fdm.f_stall_margins[1] - (margin - 2.0)).abs() < 0.1);
+    loads_at(&mut fdm, 25.0);
+    assert!(fdm.stalling && fdm.f_stall_margins[0] < 0.0);
+
+    //Holding a stall margin in the warning band, the smoothed warning follows it after a lag
+    let alpha = 10.0 + margin - 0.5 * Float::from_f64(WARNING_MARGIN);
+    for _ in 0..240 * 2
+    {
+        loads_at(&mut fdm, alpha);
+        fdm.f_stall_warning = Float::from_f64(smooth_warning(fdm.f_stall_warning.to_f64(), fdm.f_stall_margins[1].to_f64(), 1.0 / 240.0));
+    }
+    assert!((fdm.f_stall_warning - 0.5).abs() < 0.01, "{}", fdm.f_stall_warning);
+
+    //The right wing stalling 3 degrees early loses lift first past its early stall angle and rolls the airplane right
+    loads_at(&mut fdm, 10.0 + margin - 2.0);
+    let symmetric = fdm.v_moments;
+    fdm.f_stall_asymmetry = 3.0;
+    loads_at(&mut fdm, 10.0 + margin - 2.0);
+    assert!(fdm.f_stall_margins[2] < 0.0 && fdm.f_stall_margins[1] > 0.0 && fdm.stalling);
+    assert!(fdm.v_moments.x > symmetric.x + 100.0, "{:?} {:?}", fdm.v_moments, symmetric);
+
+    //Trimmed slow and pulling up into the stall, the airplane drops its right wing, but stays wings level without the asymmetry
+    let roll_rate = |asymmetry: Float|
+    {
+        let mut fdm = test_airplane();
+        trim(&mut fdm, &TrimTarget::level(100.0, 2000.0), &TrimSettings::default());
+        fdm.f_stall_asymmetry = asymmetry;
+        let mut roll_rate: Float = 0.0;
+        for _ in 0..240 * 2
+        {
+            pitch_up(&mut fdm);
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+            roll_rate = roll_rate.max(fdm.v_angular_velocity.x.abs());
+        }
+        assert!(fdm.stalling);
+        roll_rate * fdm.v_angular_velocity.x.signum()
+    };
+    assert!(roll_rate(3.0) > 0.5);
+    assert!(roll_rate(0.0).abs() < 0.1);
+}
+
+#[test]
+fn sensors_test()
+{
+    //Trimmed level flight heading east at 150 ft/s: the accelerometers feel 1 g up, the airplane barely rotates
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    eom(&mut fdm, 1.0 / 240.0, &integrator);
+    let truth = sensor_truth(&fdm);
+    assert!((truth.specific_force.norm() - 9.81).abs() < 0.2, "{:?}", truth.specific_force);
+    assert!(truth.specific_force.z < -9.5);
+    assert!(truth.angular_rate.norm() < 0.05);
+    assert!((truth.attitude.z - 90.0_f64.to_radians()).abs() < 0.01);
+    assert!((truth.velocity_ned.y - 150.0 / FEET_PER_METER).abs() < 0.1);
+    assert!((truth.airspeed - 150.0 / FEET_PER_METER).abs() < 1e-3);
+
+    //Two sensor suites with the same seed give the same outputs, another seed other ones
+    let mut sensors = Sensors::new(SensorSuiteData::default());
+    let mut same_seed = Sensors::new(SensorSuiteData::default());
+    let mut other_seed = Sensors::new(SensorSuiteData{ seed: 42, ..SensorSuiteData::default() });
+    for _ in 0..240 * 5
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+        let truth = sensor_truth(&fdm);
+        sensors.update(&truth, 1.0 / 240.0);
+        same_seed.update(&truth, 1.0 / 240.0);
+        other_seed.update(&truth, 1.0 / 240.0);
+        assert_eq!((sensors.imu, sensors.gps, sensors.air_data, sensors.magnetometer), (same_seed.imu, same_seed.gps, same_seed.air_data, same_seed.magnetometer));
+    }
+    assert_ne!(sensors.imu, other_seed.imu);
+
+    //The measurements are close to the truth but not the truth
+    let truth = sensor_truth(&fdm);
+    let imu = sensors.imu.unwrap();
+    assert!((imu.specific_force - truth.specific_force).norm() < 0.5 && imu.specific_force != truth.specific_force);
+    assert!((imu.angular_rate - truth.angular_rate).norm() < 0.05);
+    let gps = sensors.gps.unwrap();
+    assert!(gps.time < sensors.time - 0.1);
+    assert!(fdm_library::navigation::distance(&gps.position, &fdm.position) < 50.0);
+    let air_data = sensors.air_data.unwrap();
+    assert!((air_data.altitude - fdm.position.altitude).abs() < 15.0);
+    assert!(air_data.airspeed < truth.airspeed && air_data.airspeed > truth.airspeed - 3.0);
+}
+
+#[test]
+fn estimator_test()
+{
+    //Trimmed level flight heading east, the autopilot engaged at the current values. The IMU samples at 50 Hz.
+    //The geodetic altitude is 248 m above the earth space height, as when the airplane is spawned over Wright-Patterson
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+    fdm.position.altitude += 248.0;
+    let mut ap = AutopilotState::default();
+    handle_autopilot_input(&fdm, &mut ap, &KeyboardState{ ap_altitude_hold: true, ap_heading_hold: true, ap_airspeed_hold: true, ..no_keys() });
+
+    let data = SensorSuiteData{ imu: ImuData{ rate: 50.0, ..SensorSuiteData::default().imu }, ..SensorSuiteData::default() };
+    let mut sensors = Sensors::new(data);
+    let mut estimator = Estimator::new(EstimatorData::from_sensors(&data));
+    estimator.guidance_on_estimate = true;
+
+    //A minute and a half with the autopilot flying on the estimate once the filter has aligned,
+    //the statistics are kept after the first half minute
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut worst_altitude: f64 = 0.0;
+    for frame in 0..30 * 90
+    {
+        let position = fdm.position;
+        match estimated_state(&fdm, Some(&estimator))
+        {
+            Some(state) => autopilot_on(&state, &mut fdm, &mut ap, 1.0 / 30.0, 3000.0),
+            None => autopilot(&mut fdm, &mut ap, 1.0 / 30.0, 3000.0),
+        }
+        assert_eq!(fdm.position, position);
+
+        for _ in 0..8
+        {
+            eom(&mut fdm, 1.0 / 240.0, &integrator);
+            let truth = sensor_truth(&fdm);
+            sensors.update(&truth, 1.0 / 240.0);
+            estimator.process(&sensors);
+            estimator.compare(&truth);
+        }
+
+        if frame == 30 * 30
+        {
+            estimator.statistics = Default::default();
+        }
+        if frame >= 30 * 30
+        {
+            worst_altitude = worst_altitude.max((fdm.v_position.z.to_f64() - 2000.0).abs());
+        }
+    }
+
+    //The estimate is close to the truth and better than the GPS alone
+    let statistics = estimator.statistics;
+    println!("RMS position, velocity and attitude errors: {:?} {:?} {:?}", statistics.rms_position(), statistics.rms_velocity(), statistics.rms_attitude());
+    assert!(statistics.rms_position().x.hypot(statistics.rms_position().y) < 1.5 * data.gps.position_noise);
+    assert!(statistics.rms_position().z < 2.0);
+    assert!(statistics.rms_velocity().norm() < 0.3);
+    assert!(statistics.rms_attitude().x < 0.01 && statistics.rms_attitude().y < 0.01 && statistics.rms_attitude().z < 0.05);
+
+    //Flying on the estimate costs some altitude keeping, but the airplane still holds it
+    println!("Largest altitude error (ft): {}", worst_altitude);
+    assert!(worst_altitude < 15.0);
+}
+
+#[test]
+fn air_data_test()
+{
+    //Trimmed climb at 150 ft/s and 15 ft/s from 2000 ft, the air data computer runs each physics step
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let mut fdm = test_airplane();
+    trim(&mut fdm, &TrimTarget::climb(150.0, 2000.0, 15.0), &TrimSettings::default());
+    let mut computer = AirDataComputer::new(AirDataComputerData::default());
+    for _ in 0..240 * 10
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+        run_air_data(&fdm, &mut computer, 1.0 / 240.0);
+    }
+
+    //The true airspeed is the speed through the air, the calibrated airspeed reads lower by about the square root
+    //of the density ratio, and the indicated airspeed a little lower again with the static error
+    let air_data = computer.outputs.unwrap();
+    let true_airspeed = fdm.v_velocity_body.magnitude().to_f64() / FEET_PER_METER;
+    let density_ratio = Atmosphere::standard(fdm.position.altitude).density / Atmosphere::standard(0.0).density;
+    println!("IAS, CAS, TAS (m/s): {} {} {}", air_data.indicated_airspeed, air_data.calibrated_airspeed, air_data.true_airspeed);
+    assert!((air_data.true_airspeed - true_airspeed).abs() < 1e-3);
+    assert!((air_data.calibrated_airspeed - true_airspeed * density_ratio.sqrt()).abs() < 0.1);
+    assert!(air_data.indicated_airspeed < air_data.calibrated_airspeed);
+    assert!((air_data.mach - true_airspeed / 340.0).abs() < 0.01);
+
+    //In a standard atmosphere the pressure and density altitudes are the altitude, and the VSI follows the climb
+    //a couple of seconds behind as it slowly changes
+    println!("Pressure altitude, altimeter (m): {} {}, VSI (ft/s): {}", air_data.pressure_altitude, air_data.indicated_altitude, air_data.vertical_speed * FEET_PER_METER);
+    assert!((air_data.pressure_altitude - fdm.position.altitude).abs() < 0.01);
+    assert!((air_data.density_altitude - fdm.position.altitude).abs() < 0.01);
+    assert!(air_data.indicated_altitude < air_data.pressure_altitude && air_data.indicated_altitude > air_data.pressure_altitude - 5.0);
+    assert!((air_data.vertical_speed * FEET_PER_METER - fdm.v_velocity.z.to_f64()).abs() < 0.5, "{}", fdm.v_velocity.z);
+    assert!((air_data.vertical_speed * FEET_PER_METER - 15.0).abs() < 1.0);
+
+    //Flying straight the wind comes from below the nose without sideslip
+    assert!(air_data.alpha > 0.0 && air_data.alpha < 10.0_f64.to_radians(), "{}", air_data.alpha);
+    assert!(air_data.beta.abs() < 0.01, "{}", air_data.beta);
+
+    //FlightGear gets the calibrated airspeed in knots and the climb rate in ft/s
+    let mut fgnet = FGNetFDM::default();
+    load_air_data(&air_data, &mut fgnet);
+    let read = |x: f32| f32::from_be_bytes(x.to_ne_bytes()) as f64;
+    assert!((read(fgnet.vcas) - air_data.calibrated_airspeed / METERS_PER_SECOND_PER_KNOT).abs() < 1e-3);
+    assert!((read(fgnet.climb_rate) - 15.0).abs() < 1.0);
+    assert!((read(fgnet.alpha) - air_data.alpha).abs() < 1e-6);
+}
+
+#[test]
+fn envelope_test()
+{
+    //Trimmed level flight at 150 ft/s, then the elevator key held for 8 s. The limits are set low enough for the pull
+    //to exceed the angle of attack and load factor limits
+    let limits = EnvelopeLimits{ max_alpha: 8.0, max_load_factor: 1.8, ..EnvelopeLimits::new(25.0, 90.0) };
+    let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+    let fly_frame = |fdm: &mut DataFDM, monitor: &mut EnvelopeMonitor, keys: &KeyboardState, max_alpha: &mut f64|
+    {
+        handle_input(fdm, keys, 100.0, 3000.0, 1.0 / 30.0);
+        for _ in 0..8
+        {
+            let protected = protect(fdm, monitor, 1.0 / 240.0);
+            eom(fdm, 1.0 / 240.0, &integrator);
+            if let Some(command) = protected
+            {
+                restore_pitch_command(fdm, &command);
+            }
+            let state = envelope_state(fdm);
+            *max_alpha = max_alpha.max(state.alpha);
+            monitor.check(&state, 1.0 / 240.0);
+        }
+    };
+    let fly = |protection: bool|
+    {
+        let mut fdm = test_airplane();
+        trim(&mut fdm, &TrimTarget::level(150.0, 2000.0), &TrimSettings::default());
+        let trimmed = SurfaceControls::from_fdm(&fdm);
+        let mut monitor = EnvelopeMonitor::new(limits);
+        monitor.protection = protection;
+        let mut max_alpha: f64 = 0.0;
+        let mut max_protection: f64 = 0.0;
+        for frame in 0..30 * 10
+        {
+            let keys = if frame < 30 * 2 { no_keys() } else { KeyboardState{ pitch_up: true, ..no_keys() } };
+            fly_frame(&mut fdm, &mut monitor, &keys, &mut max_alpha);
+            max_protection = max_protection.max(fdm.f_alpha_protection.to_f64());
+
+            //The protection takes over the elevator for the physics steps only, the command stays where it was
+            assert_eq!(SurfaceControls::from_fdm(&fdm).elevator, trimmed.elevator);
+
+            //Level flight is within the envelope, at 1 g
+            if frame == 30 * 2 - 1
+            {
+                let state = envelope_state(&fdm);
+                assert!(monitor.events.is_empty(), "{:?}", monitor.events);
+                assert!((state.load_factor - 1.0).abs() < 0.1 && state.alpha.abs() < 3.0, "{:?}", state);
+            }
+        }
+        (fdm, monitor, max_alpha, max_protection)
+    };
+
+    //Unprotected the pull raises the load factor past its limit, which it recovers from as the airplane slows,
+    //the nose goes past the pitch limit and the angle of attack past its limit
+    let (_, monitor, max_alpha, max_protection) = fly(false);
+    for event in &monitor.events
+    {
+        println!("{}", event);
+    }
+    let exceeded = |monitor: &EnvelopeMonitor, limit: Limit| monitor.events.iter().any(|event| matches!(event, EnvelopeEvent::Exceeded{ limit: l, .. } if *l == limit));
+    assert!(exceeded(&monitor, Limit::PositiveLoadFactor) && exceeded(&monitor, Limit::Pitch) && exceeded(&monitor, Limit::AngleOfAttack));
+    assert!(monitor.events.iter().any(|event| matches!(event, EnvelopeEvent::Recovered{ limit: Limit::PositiveLoadFactor, peak, .. } if *peak > 1.8)));
+    assert!(!monitor.is_exceeded(Limit::PositiveLoadFactor) && monitor.is_exceeded(Limit::AngleOfAttack));
+    assert!(max_alpha > 8.0 && max_protection == 0.0);
+
+    //With protection the elevator is eased before the angle of attack reaches the limit
+    let (mut fdm, mut monitor, mut max_alpha, max_protection) = fly(true);
+    println!("Largest angle of attack protected (deg): {}, elevator taken off (deg): {}", max_alpha, max_protection);
+    assert!(!exceeded(&monitor, Limit::AngleOfAttack));
+    assert!(max_alpha < 8.0 - 0.5 * PROTECTION_MARGIN);
+    assert!(max_protection > 0.0);
+
+    //Letting go of the elevator key the airplane regains the margin, and the protection lets go
+    for _ in 0..30 * 5
+    {
+        fly_frame(&mut fdm, &mut monitor, &no_keys(), &mut max_alpha);
+    }
+    assert_eq!(fdm.f_alpha_protection, 0.0);
+    assert_eq!(protect(&mut fdm, &monitor, 1.0 / 240.0), None);
+}
+
+#[test]
+fn crash_test()
+{
+    //Trimmed descents from 30 ft onto the ground at 0 ft, until the airplane crashes or 20 s have gone by
+    let descend = |mut fdm: DataFDM, climb_rate: f64|
+    {
+        let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+        trim(&mut fdm, &TrimTarget::climb(150.0, 30.0, climb_rate), &TrimSettings::default());
+        let initial = InitialConditions { fdm: fdm.clone(), engines: SpawnedEngines::default() };
+        let mut monitor = CrashMonitor::new(CrashLimits::default());
+        let mut frame = 0;
+        while !monitor.is_crashed() && frame < 30 * 20
+        {
+            for _ in 0..8
+            {
+                eom(&mut fdm, 1.0 / 240.0, &integrator);
+            }
+            monitor.check(ground_contact(&fdm).as_ref(), 1.0 / 30.0);
+            frame += 1;
+        }
+        (fdm, initial, monitor)
+    };
+
+    //The gear takes a gentle touchdown descending at 3 ft/s, and the airplane rolls on without the airframe touching the ground
+    let (fdm, _, monitor) = descend(test_airplane_with_gear(), -3.0);
+    assert!(monitor.events.is_empty(), "{:?}", monitor.events);
+    assert!(fdm.on_ground && !ground_contact(&fdm).unwrap().airframe);
+
+    //Descending at 20 ft/s it is a hard landing at the touchdown rate
+    let (fdm, _, monitor) = descend(test_airplane_with_gear(), -20.0);
+    println!("{}", monitor.events[0]);
+    match monitor.crashed
+    {
+        Some((_, CrashCause::HardLanding { sink_rate })) => assert!((sink_rate + fdm.f_touchdown_rate.to_f64() / FEET_PER_METER).abs() < 1e-9 && sink_rate > 3.0),
+        crashed => panic!("{:?}", crashed),
+    }
+
+    //Without gear the airframe flies into the ground, and it is reset to where it started
+    let (mut fdm, initial, mut monitor) = descend(test_airplane(), -3.0);
+    println!("{}", monitor.events[0]);
+    assert!(matches!(monitor.crashed, Some((_, CrashCause::TerrainImpact { .. }))));
+    assert!(fdm.v_position.z < 5.0);
+
+    let mut history = StateHistory::default();
+    let mut actuators = Actuators::default();
+    reset_airplane(&mut fdm, &initial, Some(&mut history), Some(&mut actuators), None);
+    monitor.reset();
+    assert!(matches!(monitor.events[..], [CrashEvent::Crashed { .. }, CrashEvent::Reset { .. }]));
+    assert!(fdm.v_position == initial.fdm.v_position && fdm.v_velocity == initial.fdm.v_velocity);
+    assert!(history.previous.position == fdm.position && history.current.position == fdm.position);
+    assert_eq!(ground_contact(&fdm), None);
+
+    //An autopilot that was holding the altitude starts its loops again from the initial controls, still engaged
+    let mut ap = AutopilotState::default();
+    ap.modes.vertical = VerticalMode::AltitudeHold(30.0);
+    ap.engaged = ap.modes;
+    ap.pitch.integral = 8.0;
+    ap.reset(&fdm);
+    assert_eq!(ap.engaged, ap.modes);
+    assert_eq!(ap.pitch.integral, SurfaceControls::from_fdm(&fdm).elevator.clamp(-10.0, 10.0));
+}
@@ -9,6 +9,47 @@ use float_cmp::*;
 use fdm_library::palmer::fdm::plane_right_hand_side::plane_rhs;
 use fdm_library::palmer::fdm::structures::DataFDM;
 use fdm_library::palmer::fdm::structures::PerformanceData;
+use fdm_library::palmer::fdm::plane_right_hand_side::plane_derivatives;
+use fdm_library::palmer::fdm::equations_of_motion::VELOCITY_STATES;
+use fdm_library::integrator::{Integrator, IntegrationMethod};
+use nalgebra::Vector3;
+use fdm_library::palmer::fdm::trim::{trim, turn_rate, TRIM_ALPHA, TRIM_THROTTLE};
+use fdm_library::trim::{TrimTarget, TrimSettings};
+
+//SPECS and scheduler for the multi-rate test
+use specs::prelude::*;
+use fdm_library::palmer::fdm::structures::{KeyboardState, StateHistory, AutopilotState, Actuators, ControlLimits, InitialConditions};
+use fdm_library::palmer::fdm::equations_of_motion::handle_input;
+use fdm_library::palmer::fdm::equations_of_motion::eom;
+use fdm_library::palmer::systems::system_equations_of_motion::EquationsOfMotion;
+use fdm_library::palmer::systems::system_autopilot::Autopilot;
+use fdm_library::palmer::systems::system_flight_plan::FlightPlanGuidance;
+use fdm_library::flight_plan::{FlightPlan, Waypoint};
+use fdm_library::palmer::systems::system_approach::Autoland;
+use fdm_library::approach::{Approach, ApproachPhase, Runway};
+use fdm_library::navigation::{Geodetic, distance, destination, FEET_PER_METER};
+use fdm_library::autopilot::{VerticalMode, LateralMode, SpeedMode};
+use fdm_library::scheduler::Scheduler;
+use fdm_library::actuator::{Actuator, ActuatorDynamics};
+use fdm_library::palmer::fdm::actuators::actuate;
+use fdm_library::engine::{PistonEngine, PistonEngineData};
+use fdm_library::propeller::{Propeller, PropellerData};
+use fdm_library::palmer::fdm::propeller::run_propeller;
+use fdm_library::jet::{JetEngine, JetEngineData};
+use fdm_library::palmer::systems::system_make_packet::MakePacket;
+use fdm_library::flightgear::FGNetFDM;
+use fdm_library::fuel::{FuelSystem, FuelTank};
+use fdm_library::sensors::{Sensors, SensorSuiteData, ImuData};
+use fdm_library::estimator::{Estimator, EstimatorData};
+use fdm_library::palmer::fdm::sensors::sensor_truth;
+use fdm_library::palmer::fdm::make_packet::load_fgnetfdm;
+use fdm_library::air_data::{AirDataComputer, AirDataComputerData, METERS_PER_SECOND_PER_KNOT};
+use fdm_library::atmosphere::Atmosphere;
+use fdm_library::envelope::{EnvelopeMonitor, EnvelopeLimits, EnvelopeEvent, Limit, PROTECTION_MARGIN};
+use fdm_library::palmer::systems::system_envelope::EnvelopeMonitoring;
+use fdm_library::crash::{CrashMonitor, CrashLimits, CrashCause, CrashEvent, SpawnedEngines};
+use fdm_library::palmer::systems::system_crash::CrashDetection;
+use fdm_library::palmer::fdm::crash::ground_contact;
 
 #[test]
 fn fdm_test()
@@ -136,7 +177,1259 @@ fn fdm_test()
     }
 
 }
-   
 
-   
 
+//Create the airplane used by the equivalency tests
+fn test_airplane() -> DataFDM
+{
+    DataFDM{
+        throttle: 1.0,
+        alpha: 4.0,
+        bank: 5.0,
+        flap: 20.0,
+        q: vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+
+        mass_properties: PerformanceData{
+            wing_area: 16.2,
+            wing_span: 10.9,
+            tail_area: 2.0,
+            cl_slope0: 0.0889,
+            cl0: 0.178,
+            cl_slope1: -0.1,
+            cl1: 3.2,
+            alpha_cl_max: 16.0,
+            cdp: 0.034,
+            eff: 0.77,
+            mass: 1114.0,
+            engine_power: 119310.0,
+            engine_rps: 40.0,
+            prop_diameter: 1.905,
+            a: 1.83,
+            b:-1.32,
+        },
+        ..Default::default()
+    }
+}
+
+//Fly the test airplane for 60 seconds and return x, y, z
+fn fly(integrator: &Integrator, fps: f64) -> Vec<f64>
+{
+    let mut fdm = test_airplane();
+    let dt = 1.0 / fps;
+    let frames = (60.0 * fps).round() as usize;
+
+    for _ in 0..frames
+    {
+        let mut q = fdm.q.clone();
+        integrator.step(0.0, &mut q, dt, &VELOCITY_STATES, |_, y, dydt| plane_derivatives(&mut fdm, y, dydt));
+        fdm.q = q;
+    }
+    vec![fdm.q[1], fdm.q[3], fdm.q[5]]
+}
+
+#[test]
+fn integrator_rk4_test()
+{
+    //The shared Runge-Kutta integrator must reproduce the C benchmark (TEST 4 EVERYTHING)
+    let flight_test_data = fly(&Integrator::new(IntegrationMethod::RungeKutta4), 10.0);
+    let benchmark_data = [1428.695336, -1109.084685, 186.708746];
+
+    for i in 0..3
+    {
+        assert!(approx_eq!(f64, flight_test_data[i], benchmark_data[i], epsilon = 0.000001));
+    }
+}
+
+#[test]
+fn integrator_accuracy_test()
+{
+    //Compare each method at 10 Hz against a 100 Hz Runge-Kutta reference
+    let reference = fly(&Integrator::new(IntegrationMethod::RungeKutta4), 100.0);
+    let error = |method: IntegrationMethod| -> f64
+    {
+        let result = fly(&Integrator::new(method), 10.0);
+        (0..3).map(|i| (result[i] - reference[i]).abs()).fold(0.0, f64::max)
+    };
+
+    let euler = error(IntegrationMethod::Euler);
+    let semi_implicit = error(IntegrationMethod::SemiImplicitEuler);
+    let rk4 = error(IntegrationMethod::RungeKutta4);
+    let adaptive = error(IntegrationMethod::DormandPrince45);
+
+    println!("Euler: {}, Semi-implicit Euler: {}, RK4: {}, Dormand-Prince: {}", euler, semi_implicit, rk4, adaptive);
+    assert!(rk4 < euler && rk4 < semi_implicit);
+    assert!(adaptive < 0.01);
+}
+
+//Keyboard with no key pressed
+fn no_keys() -> KeyboardState
+{
+    KeyboardState{
+        throttle_up: false,
+        throttle_down: false,
+        aoa_up: false,
+        aoa_down: false,
+        bank_right: false,
+        bank_left: false,
+        flaps_down: false,
+        zero_flaps: false,
+        ap_altitude_hold: false,
+        ap_vertical_speed: false,
+        ap_heading_hold: false,
+        ap_airspeed_hold: false,
+        ap_wing_leveler: false,
+        ap_disengage: false,
+        ap_flight_plan: false,
+        ap_approach: false,
+        mixture_rich: false,
+        mixture_lean: false,
+        engine_start: false,
+    }
+}
+
+//Test airplane with the flaps up, in trimmed level flight heading east at 50 m/s, 300 m above Wright-Patterson
+fn trimmed_airplane() -> DataFDM
+{
+    trimmed_for(&TrimTarget::level(50.0, 300.0))
+}
+
+//Test airplane with the flaps up, trimmed for a flight condition. The altitude of the target is the height above Wright-Patterson
+fn trimmed_for(target: &TrimTarget) -> DataFDM
+{
+    let mut fdm = test_airplane();
+    fdm.flap = 0.0;
+    fdm.position = Vector3::new(39.826, -84.045, 248.0 + target.altitude);
+    fdm.lla_origin = Vector3::new(39.826, -84.045, 248.0);
+    trim(&mut fdm, target, &TrimSettings::default());
+    fdm
+}
+
+//World with 240 Hz physics and 30 Hz input and output, running the Systems of the dispatcher
+fn world_with(dispatcher: DispatcherBuilder<'static, 'static>) -> (World, Dispatcher<'static, 'static>)
+{
+    let mut world = World::new();
+    world.insert(Integrator::default());
+    world.insert(Scheduler::new(240.0, 30.0, 30.0));
+
+    let mut dispatcher = dispatcher.build();
+    dispatcher.setup(&mut world);
+    (world, dispatcher)
+}
+
+//Run the Systems for a number of seconds of 30 Hz frames
+fn run(world: &mut World, dispatcher: &mut Dispatcher, seconds: f64)
+{
+    for _ in 0..(30.0 * seconds).round() as usize
+    {
+        world.write_resource::<Scheduler>().advance(1.0 / 30.0);
+        dispatcher.dispatch(world);
+        world.maintain();
+    }
+}
+
+#[test]
+fn scheduler_substep_test()
+{
+    //One 30 Hz frame with 240 Hz physics must match eight direct 240 Hz eom steps
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[]));
+
+    let plane = world.create_entity()
+    .with(test_airplane())
+    .with(no_keys())
+    .with(StateHistory{ ..Default::default() })
+    .build();
+
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+    let mut reference = test_airplane();
+    for _ in 0..8
+    {
+        eom(&mut reference, 1.0 / 240.0, &Integrator::default());
+    }
+
+    let storage = world.read_storage::<DataFDM>();
+    let fdm = storage.get(plane).unwrap();
+    assert_eq!(fdm.q, reference.q);
+
+    //The history holds the last two physics steps
+    let history = world.read_storage::<StateHistory>();
+    let history = history.get(plane).unwrap();
+    assert_eq!(history.current.position, reference.position);
+    assert!(history.previous.position != history.current.position);
+}
+
+#[test]
+fn trim_test()
+{
+    //Level flight, a climb and a turn at 50 m/s and 1000 m, flaps up
+    let targets = [TrimTarget::level(50.0, 1000.0), TrimTarget::climb(50.0, 1000.0, 1.0), TrimTarget::turn(50.0, 1000.0, 20.0)];
+    let mut throttle = Vec::new();
+
+    for target in targets.iter()
+    {
+        let mut fdm = test_airplane();
+        fdm.flap = 0.0;
+        let report = trim(&mut fdm, target, &TrimSettings::default());
+
+        println!("{:?}", report);
+        assert!(report.converged);
+        assert!(report.residual_norm <= TrimSettings::default().tolerance);
+        assert!(report.unknowns[TRIM_THROTTLE] > 0.0 && report.unknowns[TRIM_THROTTLE] < 1.0);
+        assert_eq!(fdm.alpha, report.unknowns[TRIM_ALPHA]);
+        throttle.push(fdm.throttle);
+
+        //Ten seconds with the trimmed controls holds the speed and climb rate, the climb drifts a little as the air thins
+        let integrator = Integrator::new(IntegrationMethod::RungeKutta4);
+        for _ in 0..100
+        {
+            eom(&mut fdm, 0.1, &integrator);
+        }
+        assert!((fdm.airspeed - 50.0).abs() < 0.05);
+        assert!((fdm.q[5] - (1000.0 + 10.0 * target.climb_rate)).abs() < 0.2);
+
+        //In the turn the heading changes at the turn rate
+        if target.bank != 0.0
+        {
+            let heading = fdm.q[2].atan2(fdm.q[0]).to_degrees();
+            assert!((heading + 10.0 * turn_rate(20.0, 50.0)).abs() < 0.1);
+        }
+    }
+
+    //Climbing and turning take more power than level flight
+    assert!(throttle[1] > throttle[0] && throttle[2] > throttle[0]);
+}
+
+#[test]
+fn autopilot_test()
+{
+    //Autopilot System between the input and the physics
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(Autopilot, "autopilot", &[])
+    .with(EquationsOfMotion, "EOM", &["autopilot"]));
+
+    //Trimmed level flight heading east
+    let mut fdm = test_airplane();
+    fdm.flap = 0.0;
+    trim(&mut fdm, &TrimTarget::level(50.0, 1000.0), &TrimSettings::default());
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ ap_altitude_hold: true, ap_heading_hold: true, ap_airspeed_hold: true, ..no_keys() })
+    .with(AutopilotState::default())
+    .build();
+
+    //The keys engage the modes at the current values
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    {
+        let mut autopilots = world.write_storage::<AutopilotState>();
+        let ap = autopilots.get_mut(plane).unwrap();
+        assert!(matches!(ap.modes.vertical, VerticalMode::AltitudeHold(a) if (a - 1000.0).abs() < 0.01));
+        assert!(matches!(ap.modes.lateral, LateralMode::HeadingHold(h) if (h - 90.0).abs() < 0.1));
+        assert!(matches!(ap.modes.speed, SpeedMode::AirspeedHold(v) if (v - 50.0).abs() < 0.01));
+
+        //New targets: climb 50 m, turn 30 degrees left and slow down
+        ap.modes.vertical = VerticalMode::AltitudeHold(1050.0);
+        ap.modes.lateral = LateralMode::HeadingHold(60.0);
+        ap.modes.speed = SpeedMode::AirspeedHold(45.0);
+    }
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = no_keys();
+
+    //Five minutes unattended, after the first two minutes the airplane must stay on the targets
+    let mut worst = [0.0_f64; 3];
+    for frame in 0..9000
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        if frame >= 3600
+        {
+            let storage = world.read_storage::<DataFDM>();
+            let fdm = storage.get(plane).unwrap();
+            let heading = 90.0 - fdm.q[2].atan2(fdm.q[0]).to_degrees();
+            worst[0] = worst[0].max((fdm.q[5] - 1050.0).abs());
+            worst[1] = worst[1].max((heading - 60.0).abs());
+            worst[2] = worst[2].max((fdm.airspeed - 45.0).abs());
+        }
+    }
+
+    println!("Largest altitude, heading and airspeed errors: {:?}", worst);
+    assert!(worst[0] < 0.5);
+    assert!(worst[1] < 0.1);
+    assert!(worst[2] < 0.1);
+
+    //Banking by hand disengages the heading hold, the wing leveler then rolls the wings level
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = KeyboardState{ bank_right: true, ..no_keys() };
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    assert_eq!(world.read_storage::<AutopilotState>().get(plane).unwrap().modes.lateral, LateralMode::Off);
+    assert!(world.read_storage::<DataFDM>().get(plane).unwrap().bank > 0.0);
+
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = KeyboardState{ ap_wing_leveler: true, ..no_keys() };
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    assert_eq!(world.read_storage::<AutopilotState>().get(plane).unwrap().modes.lateral, LateralMode::WingLeveler);
+    assert_eq!(world.read_storage::<DataFDM>().get(plane).unwrap().bank, 0.0);
+
+    //The disengage key drops every mode
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = KeyboardState{ ap_disengage: true, ..no_keys() };
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    assert!(!world.read_storage::<AutopilotState>().get(plane).unwrap().modes.is_engaged());
+}
+
+#[test]
+fn flight_plan_test()
+{
+    //Flight plan, autopilot and physics Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(FlightPlanGuidance, "flightplan", &[])
+    .with(Autopilot, "autopilot", &["flightplan"])
+    .with(EquationsOfMotion, "EOM", &["autopilot"]));
+
+    //Trimmed level flight heading east from Wright-Patterson at 1000 m
+    let mut fdm = test_airplane();
+    fdm.flap = 0.0;
+    fdm.position = Vector3::new(39.826, -84.045, 1000.0);
+    fdm.lla_origin = fdm.position;
+    trim(&mut fdm, &TrimTarget::level(50.0, 1000.0), &TrimSettings::default());
+
+    //Survey pattern: north, east, south, then climb on the last leg east and slow down
+    let waypoints = vec![
+        Waypoint::from_degrees(39.85, -84.045, 1000.0),
+        Waypoint::from_degrees(39.85, -84.02, 1000.0),
+        Waypoint::from_degrees(39.826, -84.02, 1000.0),
+        Waypoint::from_degrees(39.826, -83.995, 1050.0).with_airspeed(45.0),
+    ];
+    let last = waypoints[3].position;
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ ap_flight_plan: true, ..no_keys() })
+    .with(AutopilotState::default())
+    .with(FlightPlan::new(waypoints))
+    .build();
+
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = no_keys();
+    assert!(world.read_storage::<FlightPlan>().get(plane).unwrap().engaged);
+
+    //Fly until the plan is complete, the time to go keeps the distance to go over the ground speed
+    let mut captured = vec![0.0];
+    let mut worst_cross_track = 0.0_f64;
+    for frame in 1..30 * 900
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        let plans = world.read_storage::<FlightPlan>();
+        let plan = plans.get(plane).unwrap();
+        if plan.active >= captured.len()
+        {
+            captured.push(frame as f64 / 30.0);
+        }
+        if plan.is_complete()
+        {
+            break;
+        }
+
+        let guidance = plan.guidance;
+        assert!((guidance.time_to_go - guidance.distance_to_go / 50.0).abs() < 0.2 * guidance.time_to_go);
+        if plan.active > 0 && guidance.distance_to_waypoint < 1500.0
+        {
+            worst_cross_track = worst_cross_track.max(guidance.cross_track.abs());
+        }
+    }
+
+    println!("Capture times: {:?}, largest cross track: {}", captured, worst_cross_track);
+    assert_eq!(captured.len(), 5);
+    assert!(worst_cross_track < 200.0);
+
+    //The plan lets go at the last waypoint, at its altitude and speed
+    let plans = world.read_storage::<FlightPlan>();
+    let plan = plans.get(plane).unwrap();
+    assert!(plan.is_complete() && !plan.engaged);
+
+    let storage = world.read_storage::<DataFDM>();
+    let fdm = storage.get(plane).unwrap();
+    let position = Geodetic::from_degrees(fdm.position.x, fdm.position.y, fdm.position.z);
+    assert!(distance(&position, &last) < 250.0);
+    assert!((fdm.position.z - 1050.0).abs() < 2.0);
+    assert!((fdm.airspeed - 45.0).abs() < 1.0);
+}
+
+#[test]
+fn autoland_test()
+{
+    //Autoland, autopilot and physics Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(Autoland, "approach", &[])
+    .with(Autopilot, "autopilot", &["approach"])
+    .with(EquationsOfMotion, "EOM", &["autopilot"]));
+
+    //Trimmed level flight heading east 300 m above Wright-Patterson, with the runway 10 km ahead and 150 m to the left.
+    //The airplane is below the glide path, so the approach captures the localizer first and then the glide slope from below
+    let fdm = trimmed_for(&TrimTarget::level(40.0, 300.0));
+
+    let start = Geodetic::from_degrees(39.826, -84.045, 248.0);
+    let threshold = destination(&destination(&start, 0.0, 150.0), 90.0_f64.to_radians(), 10000.0);
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ ap_approach: true, ..no_keys() })
+    .with(AutopilotState::default())
+    .with(Approach::new(Runway::new(threshold, 90.0, 2500.0), 40.0))
+    .build();
+
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = no_keys();
+    assert_eq!(world.read_storage::<Approach>().get(plane).unwrap().phase, ApproachPhase::Approach);
+
+    //Fly down to the runway and 20 seconds of rollout
+    let mut worst_localizer = 0.0_f64;
+    let mut worst_glide_slope = 0.0_f64;
+    let mut rollout = 0;
+    for _ in 0..30 * 600
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        //Once established, the last 3 km of the approach down to the flare
+        let approaches = world.read_storage::<Approach>();
+        let approach = approaches.get(plane).unwrap();
+        let d = approach.deviations;
+        if approach.phase == ApproachPhase::Approach && d.distance_from_threshold > -3000.0
+        {
+            worst_localizer = worst_localizer.max(d.localizer.abs());
+            worst_glide_slope = worst_glide_slope.max(d.glide_slope.abs());
+        }
+        if approach.touchdown.is_some()
+        {
+            rollout += 1;
+            if rollout == 30 * 20
+            {
+                break;
+            }
+        }
+    }
+
+    let approach = *world.read_storage::<Approach>().get(plane).unwrap();
+    let touchdown = approach.touchdown.unwrap();
+    println!("Largest localizer deviation (m): {}, largest glide slope deviation (m): {}", worst_localizer, worst_glide_slope);
+    println!("{:?}", touchdown);
+    assert!(worst_localizer < 5.0);
+    assert!(worst_glide_slope < 5.0);
+
+    //Gentle touchdown in the touchdown zone on the centreline
+    assert!(touchdown.vertical_speed < 0.0 && touchdown.vertical_speed > -1.0);
+    assert!(touchdown.distance_from_threshold > 150.0 && touchdown.distance_from_threshold < 900.0);
+    assert!(touchdown.centreline_deviation.abs() < 3.0);
+
+    //Rolling out on the runway with the throttle closed and the autopilot off
+    let storage = world.read_storage::<DataFDM>();
+    let fdm = storage.get(plane).unwrap();
+    assert!(matches!(approach.phase, ApproachPhase::Rollout | ApproachPhase::Stopped));
+    assert!(fdm.on_ground && fdm.q[5] == 0.0 && fdm.throttle == 0.0);
+    assert!(approach.deviations.localizer.abs() < 5.0);
+    assert!(!world.read_storage::<AutopilotState>().get(plane).unwrap().modes.is_engaged());
+}
+
+#[test]
+fn actuator_test()
+{
+    //Autopilot and physics Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(Autopilot, "autopilot", &[])
+    .with(EquationsOfMotion, "EOM", &["autopilot"]));
+
+    //Two airplanes in trimmed level flight, one with instant controls and one with actuators
+    let trimmed = ||
+    {
+        let mut fdm = test_airplane();
+        fdm.flap = 0.0;
+        trim(&mut fdm, &TrimTarget::level(50.0, 1000.0), &TrimSettings::default());
+        fdm
+    };
+    let fdm = trimmed();
+    let trim_alpha = fdm.alpha;
+
+    let mut actuators = Actuators{
+        alpha: Actuator::new(ActuatorDynamics::SecondOrder(6.0, 0.8), -16.0, 20.0, 10.0),
+        bank: Actuator::new(ActuatorDynamics::FirstOrder(0.3), -45.0, 45.0, 15.0),
+        throttle: Actuator::new(ActuatorDynamics::FirstOrder(0.5), 0.0, 1.0, f64::INFINITY),
+        flap: Actuator::new(ActuatorDynamics::Instant, 0.0, 40.0, 5.0),
+    };
+    actuators.reset(&fdm);
+
+    let instant = world.create_entity()
+    .with(trimmed())
+    .with(KeyboardState{ aoa_up: true, ..no_keys() })
+    .build();
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ aoa_up: true, ..no_keys() })
+    .with(AutopilotState::default())
+    .with(actuators)
+    .build();
+
+    //One frame with the nose up key held: the command steps by a degree, the actual angle of attack follows it
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    {
+        let storage = world.read_storage::<DataFDM>();
+        let actuators = *world.read_storage::<Actuators>().get(plane).unwrap();
+        assert!((storage.get(plane).unwrap().alpha - (trim_alpha + 1.0)).abs() < 1e-9);
+        assert_eq!(actuators.alpha.command, storage.get(plane).unwrap().alpha);
+        assert!(actuators.alpha.position > trim_alpha && actuators.alpha.position < trim_alpha + 1.0 / 3.0);
+
+        //The lagging airplane has not pulled up as much yet
+        assert!(storage.get(plane).unwrap().q[4] < storage.get(instant).unwrap().q[4]);
+    }
+    for (keystate, _) in (&mut world.write_storage::<KeyboardState>(), &world.entities()).join()
+    {
+        *keystate = no_keys();
+    }
+
+    //The actual position settles on the command
+    run(&mut world, &mut dispatcher, 2.0);
+    let actuators = *world.read_storage::<Actuators>().get(plane).unwrap();
+    assert!((actuators.alpha.position - (trim_alpha + 1.0)).abs() < 1e-3);
+
+    //The autopilot flies through the actuators: climb 50 m, turn 30 degrees left and slow down
+    {
+        let mut autopilots = world.write_storage::<AutopilotState>();
+        let ap = autopilots.get_mut(plane).unwrap();
+        ap.modes.vertical = VerticalMode::AltitudeHold(1050.0);
+        ap.modes.lateral = LateralMode::HeadingHold(60.0);
+        ap.modes.speed = SpeedMode::AirspeedHold(45.0);
+    }
+
+    //After two minutes the airplane must stay on the targets, and the bank never moves faster than its actuator
+    let mut worst = [0.0_f64; 3];
+    let mut bank = world.read_storage::<Actuators>().get(plane).unwrap().bank.position;
+    for frame in 0..9000
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        let actuators = *world.read_storage::<Actuators>().get(plane).unwrap();
+        assert!((actuators.bank.position - bank).abs() <= 15.0 / 30.0 + 1e-9);
+        bank = actuators.bank.position;
+
+        if frame >= 3600
+        {
+            let storage = world.read_storage::<DataFDM>();
+            let fdm = storage.get(plane).unwrap();
+            let heading = 90.0 - fdm.q[2].atan2(fdm.q[0]).to_degrees();
+            worst[0] = worst[0].max((fdm.q[5] - 1050.0).abs());
+            worst[1] = worst[1].max((heading - 60.0).abs());
+            worst[2] = worst[2].max((fdm.airspeed - 45.0).abs());
+        }
+    }
+    println!("Largest altitude (m), heading (deg) and airspeed (m/s) errors with actuators: {:?}", worst);
+    assert!(worst[0] < 0.5);
+    assert!(worst[1] < 0.1);
+    assert!(worst[2] < 0.1);
+}
+
+#[test]
+fn flap_transit_test()
+{
+    //Vertical acceleration of the airplane in level flight at 50 m/s with the flap at its actual position
+    let lift = |flap: f64| -> f64
+    {
+        let mut fdm = test_airplane();
+        fdm.alpha = 0.0;
+        fdm.bank = 0.0;
+        fdm.flap = flap;
+        let mut dq = vec![0.0; 6];
+        plane_derivatives(&mut fdm, &[50.0, 0.0, 0.0, 0.0, 0.0, 1000.0], &mut dq);
+        dq[4]
+    };
+
+    //A rate-limited flap going down to 20 degrees adds lift all the way, not only once it gets there
+    let mut fdm = test_airplane();
+    fdm.flap = 0.0;
+    let mut actuators = Actuators{ flap: Actuator::new(ActuatorDynamics::Instant, 0.0, 40.0, 5.0), ..Actuators::default() };
+    actuators.reset(&fdm);
+    let mut previous = lift(0.0);
+    for _ in 0..4
+    {
+        fdm.flap = 20.0;
+        actuate(&mut fdm, &mut actuators, 1.0);
+        assert!(lift(fdm.flap) > previous);
+        previous = lift(fdm.flap);
+    }
+    assert!((lift(10.0) - (lift(0.0) + lift(20.0)) / 2.0).abs() < 1e-9);
+
+    //A lagging flap never quite reaches the detent, but its lift gets there
+    let mut actuators = Actuators{ flap: Actuator::new(ActuatorDynamics::FirstOrder(0.5), 0.0, 40.0, f64::INFINITY), ..Actuators::default() };
+    fdm.flap = 0.0;
+    actuators.reset(&fdm);
+    for _ in 0..10
+    {
+        fdm.flap = 20.0;
+        actuate(&mut fdm, &mut actuators, 0.5);
+    }
+    assert!(fdm.flap != 20.0);
+    assert!((lift(fdm.flap) - lift(20.0)).abs() < 1e-3);
+}
+
+#[test]
+fn control_rate_test()
+{
+    //Holding the keys for a second moves the controls just as far at any input rate
+    let held = KeyboardState{ throttle_up: true, aoa_up: true, bank_left: true, ..no_keys() };
+    let mut controls = Vec::new();
+    for input_rate in [30.0, 60.0, 144.0]
+    {
+        let mut fdm = test_airplane();
+        fdm.throttle = 0.0;
+        fdm.alpha = 0.0;
+        fdm.bank = 0.0;
+        for _ in 0..(input_rate as usize) / 3
+        {
+            handle_input(&mut fdm, &held, 1.0 / input_rate);
+        }
+        controls.push((fdm.throttle, fdm.alpha, fdm.bank));
+    }
+    for (throttle, alpha, bank) in controls
+    {
+        assert!((throttle - 0.5).abs() < 1e-9);
+        assert!((alpha - 10.0).abs() < 1e-9);
+        assert!((bank + 10.0).abs() < 1e-9);
+    }
+
+    //The controls stop at the limits of the airplane, and the flaps step through its detents
+    let mut fdm = test_airplane();
+    fdm.control_limits = ControlLimits{ alpha_max: 12.0, bank_max: 30.0, flap_detents: vec![0.0, 10.0, 25.0], flap_cl: vec![0.0, 0.2, 0.6], ..ControlLimits::default() };
+    fdm.flap = 0.0;
+    for _ in 0..60
+    {
+        handle_input(&mut fdm, &KeyboardState{ throttle_up: true, aoa_up: true, bank_right: true, ..no_keys() }, 1.0 / 30.0);
+    }
+    assert_eq!((fdm.throttle, fdm.alpha, fdm.bank), (1.0, 12.0, 30.0));
+
+    //Each detent adds the lift coefficient set for it: the vertical acceleration in level flight at 50 m/s,
+    //where the whole lift is vertical, grows in proportion to it
+    let vertical_acceleration = |fdm: &mut DataFDM| -> f64
+    {
+        let mut dq = vec![0.0; 6];
+        plane_derivatives(fdm, &[50.0, 0.0, 0.0, 0.0, 0.0, 1000.0], &mut dq);
+        dq[4]
+    };
+    fdm.bank = 0.0;
+    let retracted = vertical_acceleration(&mut fdm);
+
+    let mut flaps = Vec::new();
+    let mut lift = Vec::new();
+    for _ in 0..3
+    {
+        handle_input(&mut fdm, &KeyboardState{ flaps_down: true, ..no_keys() }, 1.0 / 30.0);
+        flaps.push(fdm.flap);
+        lift.push(vertical_acceleration(&mut fdm) - retracted);
+    }
+    handle_input(&mut fdm, &KeyboardState{ zero_flaps: true, ..no_keys() }, 1.0 / 30.0);
+    flaps.push(fdm.flap);
+    lift.push(vertical_acceleration(&mut fdm) - retracted);
+    assert_eq!(flaps, vec![10.0, 25.0, 25.0, 0.0]);
+
+    println!("Vertical acceleration (m/s^2) added by the flaps: {:?}", lift);
+    assert!(lift[0] > 0.0);
+    assert!((lift[1] / lift[0] - 3.0).abs() < 1e-9);
+    assert_eq!(lift[2], lift[1]);
+    assert_eq!(lift[3], 0.0);
+}
+
+#[test]
+fn engine_test()
+{
+    //Physics and packet Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(MakePacket, "makepacket", &["EOM"]));
+
+    //Level flight heading east 300 m above Wright-Patterson at full throttle, with a running 160 hp engine
+    let mut fdm = trimmed_airplane();
+    fdm.throttle = 1.0;
+
+    let mut engine = PistonEngine::new(PistonEngineData::new(119310.0, 2400.0, 0.0059));
+    engine.start();
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(no_keys())
+    .with(engine)
+    .with(FGNetFDM::default())
+    .build();
+
+    //The engine spins up to about its rated speed, its power drives the propeller and the airplane climbs
+    run(&mut world, &mut dispatcher, 20.0);
+    let engine = *world.read_storage::<PistonEngine>().get(plane).unwrap();
+    {
+        let storage = world.read_storage::<DataFDM>();
+        let fdm = storage.get(plane).unwrap();
+        assert!(engine.running);
+        assert!((engine.rpm - 2400.0).abs() < 100.0, "{}", engine.rpm);
+        assert_eq!(fdm.shaft_power, Some(engine.power));
+        assert!(engine.power > 0.8 * 119310.0 && engine.power < 119310.0);
+        assert!(fdm.q[4] > 1.0);
+        assert!(engine.egt > engine.cht && engine.cht > 300.0);
+    }
+
+    //The packet carries the engine state in FlightGear's units
+    {
+        let packets = world.read_storage::<FGNetFDM>();
+        let fgnet = packets.get(plane).unwrap();
+        let value = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+        assert_eq!(u32::from_be_bytes(fgnet.num_engines.to_ne_bytes()), 1);
+        assert_eq!(value(fgnet.rpm[0]), engine.rpm as f32);
+        assert!(value(fgnet.fuel_flow[0]) > 5.0 && value(fgnet.fuel_flow[0]) < 20.0);
+        assert!(value(fgnet.mp_osi[0]) > 25.0 && value(fgnet.mp_osi[0]) < 30.0);
+    }
+
+    //Higher up the full throttle power drops
+    run(&mut world, &mut dispatcher, 120.0);
+    {
+        let engine_high = *world.read_storage::<PistonEngine>().get(plane).unwrap();
+        let storage = world.read_storage::<DataFDM>();
+        assert!(storage.get(plane).unwrap().position.z > 700.0);
+        assert!(engine_high.manifold_pressure < engine.manifold_pressure);
+        assert!(engine_high.power < engine.power);
+    }
+
+    //Leaning to cut-off stops the engine, with no power the airplane sinks
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = KeyboardState{ mixture_lean: true, ..no_keys() };
+    run(&mut world, &mut dispatcher, 10.0);
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = no_keys();
+    run(&mut world, &mut dispatcher, 20.0);
+    {
+        let engine = *world.read_storage::<PistonEngine>().get(plane).unwrap();
+        let storage = world.read_storage::<DataFDM>();
+        assert!(!engine.running);
+        assert_eq!((engine.rpm, engine.fuel_flow, engine.power), (0.0, 0.0, 0.0));
+        assert!(storage.get(plane).unwrap().q[4] < 0.0);
+    }
+
+    //The starter brings it back
+    *world.write_storage::<KeyboardState>().get_mut(plane).unwrap() = KeyboardState{ mixture_rich: true, engine_start: true, ..no_keys() };
+    run(&mut world, &mut dispatcher, 2.0);
+    assert!(world.read_storage::<PistonEngine>().get(plane).unwrap().running);
+}
+
+#[test]
+fn propeller_test()
+{
+    //Level flight 300 m above Wright-Patterson at full throttle, the 160 hp engine turning a 1.905 m propeller
+    let mut fdm = trimmed_airplane();
+    fdm.throttle = 1.0;
+    let mut engine = PistonEngine::new(PistonEngineData::new(119310.0, 2400.0, 0.0059));
+    engine.start();
+    let mut propeller = Propeller::new(PropellerData::fixed_pitch(1.905, Vector3::new(0.0, 0.0, 0.0)));
+
+    //The engine settles where the propeller absorbs its power, and the propeller's thrust climbs the airplane
+    let integrator = Integrator::default();
+    for _ in 0..240 * 20
+    {
+        run_propeller(&mut fdm, &mut engine, &mut propeller, 1.0 / 240.0);
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!(engine.running && engine.rpm > 2200.0 && engine.rpm < 2800.0, "{}", engine.rpm);
+    assert!((engine.torque - propeller.engine_load()).abs() < 1.0);
+    assert_eq!(fdm.shaft_power, Some(engine.power));
+    assert_eq!(fdm.propeller_thrust, Some(propeller.thrust));
+    assert!(propeller.thrust > 1000.0 && propeller.advance_ratio > 0.5);
+    assert!(fdm.q[4] > 1.0);
+
+    //The propeller's thrust takes the place of the efficiency curve, along the flight path
+    let q = fdm.q.clone();
+    let mut dq = vec![0.0; 6];
+    let mut dq_more = vec![0.0; 6];
+    plane_derivatives(&mut fdm, &q, &mut dq);
+    fdm.propeller_thrust = Some(propeller.thrust + 1000.0);
+    plane_derivatives(&mut fdm, &q, &mut dq_more);
+    let more = Vector3::new(dq_more[0] - dq[0], dq_more[2] - dq[2], dq_more[4] - dq[4]);
+    let velocity = Vector3::new(q[0], q[2], q[4]);
+    assert!((more.norm() - 1000.0 / 1114.0).abs() < 1e-9);
+    assert!((more.normalize().dot(&velocity.normalize()) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn jet_test()
+{
+    //Physics and packet Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(MakePacket, "makepacket", &["EOM"]));
+
+    //Level flight 300 m above Wright-Patterson at full throttle, with a 14 kN turbofan that is not running
+    let mut fdm = trimmed_airplane();
+    fdm.throttle = 1.0;
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ engine_start: true, ..no_keys() })
+    .with(JetEngine::new(JetEngineData::new(14000.0, 16000.0)))
+    .with(FGNetFDM::default())
+    .build();
+
+    //The starter key lights the engine, it spools up and its thrust takes the place of the propeller
+    run(&mut world, &mut dispatcher, 20.0);
+    let engine = *world.read_storage::<JetEngine>().get(plane).unwrap();
+    assert!(engine.running && engine.n1 > 0.99);
+    {
+        let storage = world.read_storage::<DataFDM>();
+        let fdm = storage.get(plane).unwrap();
+        assert_eq!(fdm.jet_thrust, Some(engine.thrust));
+        assert_eq!(fdm.shaft_power, None);
+        assert!(engine.thrust > 10000.0 && engine.mach > 0.15);
+        assert!(fdm.q[4] > 5.0);
+    }
+
+    //The packet carries the fan speed and fuel flow
+    let packets = world.read_storage::<FGNetFDM>();
+    let fgnet = packets.get(plane).unwrap();
+    let value = |x: f32| f32::from_be_bytes(x.to_ne_bytes());
+    assert_eq!(value(fgnet.eng_state[0]), 2.0);
+    assert!((value(fgnet.rpm[0]) - engine.n1 as f32 * 16000.0).abs() < 200.0);
+    assert!(value(fgnet.fuel_flow[0]) > 200.0);
+}
+
+#[test]
+fn fuel_test()
+{
+    //Physics and packet Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(MakePacket, "makepacket", &["EOM"]));
+
+    //Level flight at full throttle with a 14 kN turbofan fed by two 2 kg wing tanks, then a 3 kg fuselage tank
+    let mut fdm = trimmed_airplane();
+    fdm.throttle = 1.0;
+    fdm.fuel = FuelSystem::new(vec![
+        FuelTank::new(Vector3::new(0.0, 2.5, 0.0), 2.0, 0),
+        FuelTank::new(Vector3::new(0.0, -2.5, 0.0), 2.0, 0),
+        FuelTank::new(Vector3::new(1.0, 0.0, 0.0), 3.0, 1),
+    ]);
+    let full_mass = fdm.mass_properties.mass;
+
+    let plane = world.create_entity()
+    .with(fdm)
+    .with(KeyboardState{ engine_start: true, ..no_keys() })
+    .with(JetEngine::new(JetEngineData::new(14000.0, 16000.0)))
+    .with(FGNetFDM::default())
+    .build();
+
+    //The engine burns the wing tanks evenly first, and the airplane gets lighter by the fuel burnt
+    run(&mut world, &mut dispatcher, 10.0);
+    {
+        let storage = world.read_storage::<DataFDM>();
+        let fdm = storage.get(plane).unwrap();
+        let tanks = &fdm.fuel.tanks;
+        assert!(tanks[0].quantity < 2.0 && tanks[0].quantity == tanks[1].quantity && tanks[2].quantity == 3.0);
+        assert!((fdm.mass_properties.mass - (full_mass - 7.0 + fdm.fuel.quantity())).abs() < 1e-9);
+    }
+
+    //Once the tanks run dry the engine stops
+    world.write_storage::<KeyboardState>().insert(plane, no_keys()).unwrap();
+    run(&mut world, &mut dispatcher, 60.0);
+    let engine = *world.read_storage::<JetEngine>().get(plane).unwrap();
+    assert!(!engine.running && engine.thrust == 0.0 && engine.fuel_flow == 0.0);
+    {
+        let storage = world.read_storage::<DataFDM>();
+        let fdm = storage.get(plane).unwrap();
+        assert!(fdm.fuel.exhausted());
+        assert!((fdm.mass_properties.mass - (full_mass - 7.0)).abs() < 1e-9);
+        assert_eq!(fdm.jet_thrust, Some(0.0));
+    }
+
+    //The packet carries the empty tanks
+    let packets = world.read_storage::<FGNetFDM>();
+    let fgnet = packets.get(plane).unwrap();
+    assert_eq!(u32::from_be_bytes(fgnet.num_tanks.to_ne_bytes()), 3);
+    assert_eq!(f32::from_be_bytes(fgnet.fuel_quantity[2].to_ne_bytes()), 0.0);
+}
+
+#[test]
+fn stall_warning_test()
+{
+    //Trimmed level flight well below the stall, there is no warning
+    let integrator = Integrator::default();
+    let mut fdm = trimmed_airplane();
+    eom(&mut fdm, 1.0 / 240.0, &integrator);
+    assert!((fdm.stall_margin - (fdm.mass_properties.alpha_cl_max - fdm.alpha)).abs() < 1e-12);
+    assert!(fdm.stall_margin > 5.0 && fdm.stall_warning == 0.0);
+
+    //Half way into the warning band the smoothed warning settles at a half, and it goes to the packet
+    fdm.alpha = fdm.mass_properties.alpha_cl_max - 2.5;
+    for _ in 0..240 * 2
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!((fdm.stall_warning - 0.5).abs() < 0.01, "{}", fdm.stall_warning);
+    let mut fgnet = FGNetFDM::default();
+    load_fgnetfdm(&fdm, &mut fgnet);
+    assert_eq!(f32::from_be_bytes(fgnet.stall_warning.to_ne_bytes()), fdm.stall_warning as f32);
+
+    //Past the stall it is full
+    fdm.alpha = fdm.mass_properties.alpha_cl_max + 2.0;
+    for _ in 0..240 * 3
+    {
+        eom(&mut fdm, 1.0 / 240.0, &integrator);
+    }
+    assert!(fdm.stall_margin < 0.0 && fdm.stall_warning > 0.99);
+}
+
+#[test]
+fn sensors_test()
+{
+    //Physics System
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[]));
+
+    //Trimmed level flight at 50 m/s: the accelerometers feel 1 g up and the airplane does not rotate
+    let truth = sensor_truth(&trimmed_airplane());
+    assert!((truth.specific_force.norm() - 9.81).abs() < 0.05, "{:?}", truth.specific_force);
+    assert!(truth.specific_force.z < -9.5);
+    assert!(truth.angular_rate.norm() < 1e-3);
+    assert!((truth.airspeed - 50.0).abs() < 0.1);
+
+    //Three airplanes flying the same, two of them with sensors seeded the same
+    let mut plane = |seed: u64|
+    {
+        world.create_entity()
+        .with(trimmed_airplane())
+        .with(no_keys())
+        .with(Sensors::new(SensorSuiteData{ seed, ..SensorSuiteData::default() }))
+        .build()
+    };
+    let planes = [plane(1), plane(1), plane(2)];
+    run(&mut world, &mut dispatcher, 5.0);
+
+    //The same seed gives the same outputs, another seed other ones
+    let storage = world.read_storage::<Sensors>();
+    let sensors: Vec<&Sensors> = planes.iter().map(|plane| storage.get(*plane).unwrap()).collect();
+    let outputs = |sensors: &Sensors| (sensors.imu, sensors.gps, sensors.air_data, sensors.magnetometer);
+    assert_eq!(outputs(sensors[0]), outputs(sensors[1]));
+    assert_ne!(sensors[0].imu, sensors[2].imu);
+    assert!((sensors[0].time - 5.0).abs() < 1e-6);
+
+    //The measurements are close to the truth
+    let fdms = world.read_storage::<DataFDM>();
+    let fdm = fdms.get(planes[0]).unwrap();
+    let truth = sensor_truth(fdm);
+    let imu = sensors[0].imu.unwrap();
+    assert!((imu.specific_force - truth.specific_force).norm() < 0.5 && imu.specific_force != truth.specific_force);
+    assert!(imu.angular_rate.norm() < 0.05);
+    assert!(distance(&sensors[0].gps.unwrap().position, &truth.position) < 30.0);
+    let air_data = sensors[0].air_data.unwrap();
+    assert!((air_data.altitude - fdm.position.z).abs() < 15.0);
+    assert!(air_data.airspeed < 50.0 && air_data.airspeed > 47.0, "{}", air_data.airspeed);
+}
+
+#[test]
+fn estimator_test()
+{
+    //Autopilot and physics Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(Autopilot, "autopilot", &[])
+    .with(EquationsOfMotion, "EOM", &["autopilot"]));
+
+    //Two airplanes in trimmed level flight heading east with the autopilot holding the current values,
+    //one flying on the truth and the other on the estimate. The IMU samples at 50 Hz
+    let data = SensorSuiteData{ imu: ImuData{ rate: 50.0, ..SensorSuiteData::default().imu }, ..SensorSuiteData::default() };
+    let mut plane = |guidance_on_estimate: bool|
+    {
+        let mut estimator = Estimator::new(EstimatorData::from_sensors(&data));
+        estimator.guidance_on_estimate = guidance_on_estimate;
+        world.create_entity()
+        .with(trimmed_for(&TrimTarget::level(50.0, 1000.0)))
+        .with(KeyboardState{ ap_altitude_hold: true, ap_heading_hold: true, ap_airspeed_hold: true, ..no_keys() })
+        .with(AutopilotState::default())
+        .with(Sensors::new(data))
+        .with(estimator)
+        .build()
+    };
+    let planes = [plane(false), plane(true)];
+
+    run(&mut world, &mut dispatcher, 1.0 / 30.0);
+    for plane in planes.iter()
+    {
+        *world.write_storage::<KeyboardState>().get_mut(*plane).unwrap() = no_keys();
+    }
+
+    //A minute and a half, turning 30 degrees left after half a minute.
+    //The statistics are kept and the altitudes watched once the turn has settled
+    let mut worst = [0.0_f64; 2];
+    for frame in 0..30 * 90
+    {
+        if frame == 30 * 30
+        {
+            let mut autopilots = world.write_storage::<AutopilotState>();
+            for plane in planes.iter()
+            {
+                autopilots.get_mut(*plane).unwrap().modes.lateral = LateralMode::HeadingHold(60.0);
+            }
+        }
+        if frame == 30 * 60
+        {
+            let mut estimators = world.write_storage::<Estimator>();
+            for plane in planes.iter()
+            {
+                estimators.get_mut(*plane).unwrap().statistics = Default::default();
+            }
+        }
+
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        if frame >= 30 * 60
+        {
+            let storage = world.read_storage::<DataFDM>();
+            for (worst, plane) in worst.iter_mut().zip(planes.iter())
+            {
+                *worst = worst.max((storage.get(*plane).unwrap().q[5] - 1000.0).abs());
+            }
+        }
+    }
+
+    //Both estimates are close to the truth
+    let estimators = world.read_storage::<Estimator>();
+    for plane in planes.iter()
+    {
+        let statistics = estimators.get(*plane).unwrap().statistics;
+        println!("RMS position, velocity and attitude errors: {:?} {:?} {:?}", statistics.rms_position(), statistics.rms_velocity(), statistics.rms_attitude());
+        assert!(statistics.samples > 0);
+        assert!(statistics.rms_position().x.hypot(statistics.rms_position().y) < 1.5 * data.gps.position_noise);
+        assert!(statistics.rms_position().z < 2.0);
+        assert!(statistics.rms_velocity().norm() < 0.3);
+        assert!(statistics.rms_attitude().x < 0.01 && statistics.rms_attitude().y < 0.01 && statistics.rms_attitude().z < 0.05);
+    }
+
+    //The airplane flying on the estimate holds the altitude nearly as well as the one flying on the truth
+    println!("Largest altitude errors (m): {:?}", worst);
+    assert!(worst[0] < 1.0);
+    assert!(worst[1] < 5.0);
+
+    //The headings both come round to the new one
+    let storage = world.read_storage::<DataFDM>();
+    for plane in planes.iter()
+    {
+        let fdm = storage.get(*plane).unwrap();
+        let heading = 90.0 - fdm.q[2].atan2(fdm.q[0]).to_degrees();
+        assert!((heading - 60.0).abs() < 2.0, "{}", heading);
+    }
+}
+
+#[test]
+fn air_data_test()
+{
+    //Physics and packet Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(MakePacket, "makepacket", &["EOM"]));
+
+    //Trimmed climb at 50 m/s and 2 m/s with the air data computer on board
+    let plane = world.create_entity()
+    .with(trimmed_for(&TrimTarget::climb(50.0, 300.0, 2.0)))
+    .with(no_keys())
+    .with(AirDataComputer::new(AirDataComputerData::default()))
+    .with(FGNetFDM::default())
+    .build();
+    run(&mut world, &mut dispatcher, 10.0);
+
+    //The true airspeed is the airspeed, the calibrated airspeed reads lower higher up, and the angle of attack is the airplane's
+    let storage = world.read_storage::<DataFDM>();
+    let fdm = storage.get(plane).unwrap();
+    let air_data = world.read_storage::<AirDataComputer>().get(plane).unwrap().outputs.unwrap();
+    let density_ratio = Atmosphere::standard(fdm.position.z).density / Atmosphere::standard(0.0).density;
+    println!("IAS, CAS, TAS (m/s): {} {} {}, VSI (m/s): {}", air_data.indicated_airspeed, air_data.calibrated_airspeed, air_data.true_airspeed, air_data.vertical_speed);
+    assert!((air_data.true_airspeed - fdm.airspeed).abs() < 1e-9);
+    assert!((air_data.calibrated_airspeed - fdm.airspeed * density_ratio.sqrt()).abs() < 0.1);
+    assert!(air_data.indicated_airspeed < air_data.calibrated_airspeed);
+    assert!((air_data.alpha - fdm.alpha.to_radians()).abs() < 1e-9);
+    assert_eq!(air_data.beta, 0.0);
+    assert!((air_data.pressure_altitude - fdm.position.z).abs() < 0.01);
+
+    //The VSI shows the climb
+    assert!((air_data.vertical_speed - fdm.q[4]).abs() < 0.2, "{}", fdm.q[4]);
+    assert!((air_data.vertical_speed - 2.0).abs() < 0.5);
+
+    //And the packet carries the air data in FlightGear's units
+    let packets = world.read_storage::<FGNetFDM>();
+    let fgnet = packets.get(plane).unwrap();
+    let read = |x: f32| f32::from_be_bytes(x.to_ne_bytes()) as f64;
+    assert!((read(fgnet.vcas) - air_data.calibrated_airspeed / METERS_PER_SECOND_PER_KNOT).abs() < 1e-3);
+    assert!((read(fgnet.climb_rate) - air_data.vertical_speed * FEET_PER_METER).abs() < 1e-3);
+    assert!((read(fgnet.alpha) - fdm.alpha.to_radians()).abs() < 1e-6);
+    assert_eq!(read(fgnet.beta), 0.0);
+}
+
+#[test]
+fn envelope_test()
+{
+    //Physics and monitoring Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(EnvelopeMonitoring, "envelope", &["EOM"]));
+
+    //Two airplanes in trimmed level flight at 50 m/s, the angle of attack limit at Cl(max), one of them protected
+    let limits = EnvelopeLimits{ max_alpha: 16.0, ..EnvelopeLimits::new(25.0, 80.0) };
+    let mut plane = |protection: bool|
+    {
+        let mut monitor = EnvelopeMonitor::new(limits);
+        monitor.protection = protection;
+        world.create_entity()
+        .with(trimmed_for(&TrimTarget::level(50.0, 800.0)))
+        .with(no_keys())
+        .with(monitor)
+        .build()
+    };
+    let planes = [plane(false), plane(true)];
+
+    //Level flight is within the envelope
+    run(&mut world, &mut dispatcher, 2.0);
+    assert!(world.read_storage::<EnvelopeMonitor>().join().all(|monitor| monitor.events.is_empty()));
+
+    //Pulling the angle of attack up to its stop for a second and a half
+    for plane in planes
+    {
+        world.write_storage::<KeyboardState>().insert(plane, KeyboardState{ aoa_up: true, ..no_keys() }).unwrap();
+    }
+    let mut max_alpha = [0.0_f64; 2];
+    for _ in 0..45
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+        let fdms = world.read_storage::<DataFDM>();
+        for (i, plane) in planes.iter().enumerate()
+        {
+            max_alpha[i] = max_alpha[i].max(fdms.get(*plane).unwrap().flown.unwrap().alpha);
+        }
+    }
+
+    //The protection only takes over the command for each physics step, the pilot's angle of attack is back after it
+    let fdms = world.read_storage::<DataFDM>();
+    let alpha_max = fdms.get(planes[1]).unwrap().control_limits.alpha_max;
+    assert!(planes.iter().all(|plane| fdms.get(*plane).unwrap().alpha == alpha_max));
+
+    //Unprotected the airplane goes past the angle of attack limit, protected the angle of attack it flies is clipped
+    //a margin below it. Both zoom past the pitch limit
+    let monitors = world.read_storage::<EnvelopeMonitor>();
+    let unprotected = monitors.get(planes[0]).unwrap();
+    let protected = monitors.get(planes[1]).unwrap();
+    for event in &unprotected.events
+    {
+        println!("{}", event);
+    }
+    let exceeded = |monitor: &EnvelopeMonitor, limit: Limit| monitor.events.iter().any(|event| matches!(event, EnvelopeEvent::Exceeded{ limit: l, .. } if *l == limit));
+    assert!(max_alpha[0] > 16.0 && exceeded(unprotected, Limit::AngleOfAttack));
+    assert!(exceeded(unprotected, Limit::Pitch) && exceeded(protected, Limit::Pitch));
+    assert!((max_alpha[1] - (16.0 - PROTECTION_MARGIN)).abs() < 1e-9, "{}", max_alpha[1]);
+    assert!(!exceeded(protected, Limit::AngleOfAttack));
+    assert!((unprotected.time - 3.5).abs() < 1e-6);
+}
+
+#[test]
+fn crash_test()
+{
+    //Physics and crash detection Systems
+    let (mut world, mut dispatcher) = world_with(DispatcherBuilder::new()
+    .with(EquationsOfMotion, "EOM", &[])
+    .with(CrashDetection, "crash", &["EOM"]));
+
+    //Airplanes trimmed at 50 m/s with the flaps down, one set down level on the runway with the throttle closed,
+    //and three in descents from 20 m with the angle of attack pushed 3 degrees below the trim, two of them reset 2 s
+    //after they crash
+    let airplane = |height: f64, climb_rate: f64, pitch_down: f64|
+    {
+        let mut fdm = test_airplane();
+        fdm.flap = 40.0;
+        fdm.position = Vector3::new(39.826, -84.045, 248.0 + height);
+        fdm.lla_origin = Vector3::new(39.826, -84.045, 248.0);
+        trim(&mut fdm, &TrimTarget::climb(50.0, height, climb_rate), &TrimSettings::default());
+        fdm.alpha -= pitch_down;
+        fdm
+    };
+    let mut plane = |fdm: DataFDM, reset_delay: Option<f64>|
+    {
+        let mut monitor = CrashMonitor::new(CrashLimits::default());
+        monitor.reset_delay = reset_delay;
+        world.create_entity()
+        .with(InitialConditions { fdm: fdm.clone(), engines: SpawnedEngines::default() })
+        .with(fdm)
+        .with(no_keys())
+        .with(monitor)
+        .build()
+    };
+    let mut on_runway = airplane(0.0, 0.0, 0.0);
+    on_runway.throttle = 0.0;
+    let gentle = plane(on_runway, None);
+    let hard = plane(airplane(20.0, -4.5, 3.0), None);
+    let reset = plane(airplane(20.0, -4.5, 3.0), Some(2.0));
+
+    //The other reset one is spawned with its engine running on nearly empty tanks, which run dry before it crashes
+    let mut low_fuel = airplane(20.0, -4.5, 3.0);
+    low_fuel.fuel = FuelSystem::new(vec![FuelTank::new(Vector3::new(0.0, 0.0, 0.0), 0.002, 0)]);
+    let mut engine = PistonEngine::new(PistonEngineData::new(119310.0, 2400.0, 0.0059));
+    engine.start();
+    let dry = plane(low_fuel, Some(2.0));
+    world.write_storage::<PistonEngine>().insert(dry, engine).unwrap();
+    world.write_storage::<InitialConditions>().get_mut(dry).unwrap().engines.engine = Some(engine);
+
+    //The reset one carries sensors and an estimator, which are turned on again with it
+    world.write_storage::<Sensors>().insert(reset, Sensors::new(SensorSuiteData::default())).unwrap();
+    world.write_storage::<Estimator>().insert(reset, Estimator::new(EstimatorData::from_sensors(&SensorSuiteData::default()))).unwrap();
+
+    let mut frozen = None;
+    let mut dry_engine = Vec::new();
+    for frame in 0..30 * 7
+    {
+        run(&mut world, &mut dispatcher, 1.0 / 30.0);
+
+        //Whether the engine on nearly empty tanks runs and the tanks are dry as it crashes and as it is reset
+        if world.read_storage::<CrashMonitor>().get(dry).unwrap().events.len() > dry_engine.len()
+        {
+            let running = world.read_storage::<PistonEngine>().get(dry).unwrap().running;
+            dry_engine.push((running, world.read_storage::<DataFDM>().get(dry).unwrap().fuel.exhausted()));
+        }
+
+        //The crashed airplane stays where it crashed
+        let fdms = world.read_storage::<DataFDM>();
+        if world.read_storage::<CrashMonitor>().get(hard).unwrap().is_crashed()
+        {
+            let q = fdms.get(hard).unwrap().q.clone();
+            assert!(frozen.as_ref().is_none_or(|frozen| *frozen == q), "frame {}", frame);
+            frozen = Some(q);
+        }
+    }
+
+    let monitors = world.read_storage::<CrashMonitor>();
+    let fdms = world.read_storage::<DataFDM>();
+    for event in &monitors.get(reset).unwrap().events
+    {
+        println!("{}", event);
+    }
+
+    //The airplane set down on the runway is in contact with the ground without crashing
+    assert!(monitors.get(gentle).unwrap().events.is_empty() && ground_contact(fdms.get(gentle).unwrap()).is_some());
+
+    //The hard one crashes at its touchdown rate and stays crashed
+    let monitor = monitors.get(hard).unwrap();
+    match monitor.events[..]
+    {
+        [CrashEvent::Crashed { cause: CrashCause::HardLanding { sink_rate }, .. }] => assert!((sink_rate + fdms.get(hard).unwrap().touchdown_rate).abs() < 1e-9 && sink_rate > 5.0),
+        ref events => panic!("{:?}", events),
+    }
+    assert!(monitor.is_crashed() && frozen.is_some());
+
+    //The reset one is flown again from its initial conditions 2 s after it crashes, and crashes the same way again.
+    //Its sensors and estimator have only run since the reset
+    let sensors_time = world.read_storage::<Sensors>().get(reset).unwrap().time;
+    assert_eq!(world.read_storage::<Estimator>().get(reset).unwrap().time, sensors_time);
+    match monitors.get(reset).unwrap().events[..]
+    {
+        [CrashEvent::Crashed { time: first, cause }, CrashEvent::Reset { time: reset }, CrashEvent::Crashed { time: second, cause: again }] =>
+        {
+            assert!(reset - first >= 2.0 && reset - first < 2.0 + 1.0 / 30.0 + 1e-9);
+            assert!((second - reset - first).abs() < 1.0 / 30.0 + 1e-9, "{} {} {}", first, reset, second);
+            assert_eq!(cause, again);
+            assert!((sensors_time - (second - reset)).abs() < 1e-6, "{} {} {}", sensors_time, reset, second);
+        }
+        ref events => panic!("{:?}", events),
+    }
+
+    //The one on nearly empty tanks crashes with its engine stopped and its tanks dry, and is reset with its engine
+    //running on the fuel it was spawned with
+    assert_eq!(dry_engine[..2], [(false, true), (true, false)], "{:?}", monitors.get(dry).unwrap().events);
+}